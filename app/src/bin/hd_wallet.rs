@@ -0,0 +1,69 @@
+//! Command line entry point for the HD wallet master seed (SEP-0005).
+//!
+//! Usage:
+//! * `hd_wallet init` - generate a new mnemonic, store its seed and print the words once
+//! * `hd_wallet restore <env_var>` - store the seed of the mnemonic held in `env_var`
+//! * `hd_wallet migrate [limit]` - hand active random-key accounts over to derived keys
+//!
+//! The optional BIP-39 passphrase is read from `HD_WALLET_PASSPHRASE`.
+use helpers::hd_wallet::generate_mnemonic;
+use helpers::secret::{redact_secrets, SecretString};
+use services::hd_wallet::hd_wallet::{migrate_random_key_accounts, store_master_seed};
+
+/// Number of accounts migrated per run when no limit is given.
+const DEFAULT_MIGRATION_LIMIT: i64 = 100;
+
+fn read_passphrase() -> SecretString {
+    SecretString::new(std::env::var("HD_WALLET_PASSPHRASE").unwrap_or_default())
+}
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("init") => match generate_mnemonic() {
+            Ok(mnemonic) => store_master_seed(&mnemonic, &read_passphrase())
+                .await
+                .map(|_| {
+                    println!("Master seed stored. Write down these words and keep them offline;");
+                    println!("they are shown only once and recover every derived account:");
+                    println!("{}", mnemonic.expose_secret());
+                }),
+            Err(error) => Err(error),
+        },
+        Some("restore") => match args.get(1).map(std::env::var) {
+            Some(Ok(mnemonic)) => {
+                store_master_seed(&SecretString::new(mnemonic), &read_passphrase())
+                    .await
+                    .map(|_| println!("Master seed restored"))
+            }
+            Some(Err(_)) => Err(anyhow::anyhow!("Mnemonic environment variable is not set")),
+            None => Err(anyhow::anyhow!("Usage: hd_wallet restore <env_var>")),
+        },
+        Some("migrate") => {
+            let limit = match args.get(1) {
+                Some(limit) => limit.parse::<i64>().unwrap_or(DEFAULT_MIGRATION_LIMIT),
+                None => DEFAULT_MIGRATION_LIMIT,
+            };
+
+            migrate_random_key_accounts(limit).await.map(|summary| {
+                println!(
+                    "Migrated {} accounts to derived keys, {} failed",
+                    summary.migrated, summary.failed
+                );
+            })
+        }
+        _ => Err(anyhow::anyhow!(
+            "Usage: hd_wallet <init | restore <env_var> | migrate [limit]>"
+        )),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", redact_secrets(&error.to_string()));
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,38 @@
+//! Command line entry point for moving anchor deposits and withdrawals along.
+//!
+//! Usage: `process_anchor [limit]`
+//!
+//! Run it periodically: received deposits are paid out, withdrawal payments to the
+//! distributors are matched by memo and paid out in fiat, settled payouts complete and
+//! transactions whose funds never came expire.
+use helpers::secret::redact_secrets;
+use services::anchor::anchor::process_anchor_transactions;
+
+/// Number of transactions handled per step when no limit is given.
+const DEFAULT_ANCHOR_LIMIT: i64 = 100;
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let limit = match std::env::args().nth(1) {
+        Some(limit) => limit.parse::<i64>().unwrap_or(DEFAULT_ANCHOR_LIMIT),
+        None => DEFAULT_ANCHOR_LIMIT,
+    };
+
+    match process_anchor_transactions(limit).await {
+        Ok(summary) => println!(
+            "Processed anchor transactions: {} received, {} completed, {} waiting for trust, {} expired, {} failed",
+            summary.received,
+            summary.completed,
+            summary.waiting_for_trust,
+            summary.expired,
+            summary.failed
+        ),
+        Err(error) => {
+            eprintln!("{}", redact_secrets(&error.to_string()));
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Command line entry point for the chain outbox worker.
+//!
+//! Usage: `process_outbox [limit]`
+//!
+//! Run it periodically: outbox entries left pending by a crash or an unknown
+//! submission outcome are submitted again and their effects applied once they land.
+use helpers::secret::redact_secrets;
+use services::outbox::outbox::process_pending_entries;
+
+/// Number of outbox entries processed per run when no limit is given.
+const DEFAULT_OUTBOX_LIMIT: i64 = 100;
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let limit = match std::env::args().nth(1) {
+        Some(limit) => limit.parse::<i64>().unwrap_or(DEFAULT_OUTBOX_LIMIT),
+        None => DEFAULT_OUTBOX_LIMIT,
+    };
+
+    match process_pending_entries(limit).await {
+        Ok(summary) => println!(
+            "Processed outbox: {} completed, {} failed, {} retrying",
+            summary.completed, summary.failed, summary.retrying
+        ),
+        Err(error) => {
+            eprintln!("{}", redact_secrets(&error.to_string()));
+            std::process::exit(1);
+        }
+    }
+}
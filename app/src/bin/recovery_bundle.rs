@@ -0,0 +1,141 @@
+//! Command line entry point for exporting and restoring the offline recovery bundle.
+//!
+//! Usage:
+//! * `recovery_bundle export <bundle> passphrase <env_var>` - protect with the passphrase in `env_var`
+//! * `recovery_bundle export <bundle> recipient <public_key.pem>` - protect for an RSA recipient
+//! * `recovery_bundle export <bundle> shamir <shares> <threshold>` - split the bundle key into
+//!   custodian shares, written next to the bundle as `<bundle>.share-<n>`
+//! * `recovery_bundle verify <bundle> <unlock>` - check the bundle can be opened and is intact
+//! * `recovery_bundle import <bundle> <unlock>` - restore missing accounts and keys
+//!
+//! `<unlock>` is one of `passphrase <env_var>`, `recipient <private_key.pem>` or
+//! `shamir <share_file>...`.
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+
+use anyhow::Error;
+use helpers::recovery::{BundleProtection, BundleUnlock, RecoveryBundle};
+use helpers::secret::{SecretBytes, SecretString};
+use services::recovery::recovery::{
+    export_recovery_bundle, import_recovery_bundle, verify_recovery_bundle,
+};
+
+/// Writes a file readable only by the current user.
+fn write_private_file(path: &str, contents: &[u8]) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+fn read_passphrase(env_var: &str) -> Result<SecretString, Error> {
+    Ok(SecretString::new(std::env::var(env_var).map_err(|_| {
+        anyhow::anyhow!("Passphrase environment variable {} is not set", env_var)
+    })?))
+}
+
+fn parse_protection(args: &[String]) -> Result<BundleProtection, Error> {
+    match args.first().map(String::as_str) {
+        Some("passphrase") if args.len() == 2 => {
+            Ok(BundleProtection::Passphrase(read_passphrase(&args[1])?))
+        }
+        Some("recipient") if args.len() == 2 => {
+            Ok(BundleProtection::Recipient(std::fs::read(&args[1])?))
+        }
+        Some("shamir") if args.len() == 3 => Ok(BundleProtection::Shamir {
+            shares: args[1].parse()?,
+            threshold: args[2].parse()?,
+        }),
+        _ => Err(anyhow::anyhow!(
+            "Expected passphrase <env_var>, recipient <public_key.pem> or shamir <shares> <threshold>"
+        )),
+    }
+}
+
+fn parse_unlock(args: &[String]) -> Result<BundleUnlock, Error> {
+    match args.first().map(String::as_str) {
+        Some("passphrase") if args.len() == 2 => {
+            Ok(BundleUnlock::Passphrase(read_passphrase(&args[1])?))
+        }
+        Some("recipient") if args.len() == 2 => Ok(BundleUnlock::RecipientKey(SecretBytes::new(
+            std::fs::read(&args[1])?,
+        ))),
+        Some("shamir") if args.len() >= 2 => {
+            let shares = args[1..]
+                .iter()
+                .map(|path| {
+                    Ok(SecretString::new(
+                        std::fs::read_to_string(path)?.trim().to_string(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(BundleUnlock::Shares(shares))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Expected passphrase <env_var>, recipient <private_key.pem> or shamir <share_file>..."
+        )),
+    }
+}
+
+fn read_bundle(path: &str) -> Result<RecoveryBundle, Error> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+async fn run(args: &[String]) -> Result<(), Error> {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("export"), Some(bundle_path)) => {
+            let sealed = export_recovery_bundle(parse_protection(&args[2..])?).await?;
+
+            write_private_file(bundle_path, &serde_json::to_vec_pretty(&sealed.bundle)?)?;
+            for (index, share) in sealed.shares.iter().enumerate() {
+                let share_path = format!("{}.share-{}", bundle_path, index + 1);
+                write_private_file(&share_path, share.expose_secret().as_bytes())?;
+                println!("Wrote custodian share {} to {}", index + 1, share_path);
+            }
+
+            println!(
+                "Exported {} accounts to {}",
+                sealed.bundle.account_count, bundle_path
+            );
+            Ok(())
+        }
+        (Some("verify"), Some(bundle_path)) => {
+            let bundle = read_bundle(bundle_path)?;
+            let account_count = verify_recovery_bundle(&bundle, parse_unlock(&args[2..])?)?;
+            println!(
+                "Recovery bundle {} is intact and holds {} accounts",
+                bundle_path, account_count
+            );
+            Ok(())
+        }
+        (Some("import"), Some(bundle_path)) => {
+            let bundle = read_bundle(bundle_path)?;
+            let summary = import_recovery_bundle(&bundle, parse_unlock(&args[2..])?).await?;
+            println!(
+                "Restored {} accounts and {} keys, {} keys already present",
+                summary.restored_accounts, summary.restored_keys, summary.skipped
+            );
+            Ok(())
+        }
+        _ => Err(anyhow::anyhow!(
+            "Usage: recovery_bundle <export | verify | import> <bundle> <protection | unlock>"
+        )),
+    }
+}
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Err(error) = run(&args).await {
+        eprintln!("{}", helpers::secret::redact_secrets(&error.to_string()));
+        std::process::exit(1);
+    }
+}
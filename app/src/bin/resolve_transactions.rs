@@ -0,0 +1,33 @@
+//! Command line entry point for settling transactions whose outcome is unknown.
+//!
+//! Usage: `resolve_transactions [limit]`
+//!
+//! Run it periodically: pending transactions are looked up once their max time has
+//! passed and marked `completed` or `failed`.
+use helpers::secret::redact_secrets;
+use services::resolver::resolver::resolve_expired_transactions;
+
+/// Number of transactions looked up per run when no limit is given.
+const DEFAULT_RESOLVE_LIMIT: i64 = 100;
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let limit = match std::env::args().nth(1) {
+        Some(limit) => limit.parse::<i64>().unwrap_or(DEFAULT_RESOLVE_LIMIT),
+        None => DEFAULT_RESOLVE_LIMIT,
+    };
+
+    match resolve_expired_transactions(limit).await {
+        Ok(summary) => println!(
+            "Resolved transactions: {} completed, {} failed, {} unresolved; {} envelopes expired",
+            summary.completed, summary.failed, summary.unresolved, summary.expired_envelopes
+        ),
+        Err(error) => {
+            eprintln!("{}", redact_secrets(&error.to_string()));
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Command line entry point for rotating the key-encryption key of stored secrets.
+//!
+//! Usage:
+//! * `rotate_keys run [batch_size]` - re-encrypt all keys under the newest key version
+//! * `rotate_keys status` - show how many stored keys use each key version
+//! * `rotate_keys retire <version>` - check that a key version is no longer referenced
+use helpers::secret::redact_secrets;
+use services::key_rotation::key_rotation::{
+    get_key_version_usage, retire_key_version, rotate_encrypted_keys, DEFAULT_BATCH_SIZE,
+};
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("run") => {
+            let batch_size = match args.get(1) {
+                Some(batch_size) => batch_size.parse::<i64>().unwrap_or(DEFAULT_BATCH_SIZE),
+                None => DEFAULT_BATCH_SIZE,
+            };
+
+            rotate_encrypted_keys(batch_size).await.map(|job| {
+                println!(
+                    "Key rotation {} {} for version {}: {} re-encrypted, {} failed",
+                    job.id,
+                    job.status,
+                    job.target_key_version,
+                    job.processed_count,
+                    job.failed_count
+                );
+            })
+        }
+        Some("status") => get_key_version_usage().await.map(|usage| {
+            for (key_version, count) in usage {
+                println!("Key version {}: {} stored keys", key_version, count);
+            }
+        }),
+        Some("retire") => match args.get(1).and_then(|version| version.parse::<i32>().ok()) {
+            Some(key_version) => retire_key_version(key_version).await.map(|_| {
+                println!(
+                    "Key version {} is no longer referenced and can be removed from ENCRYPTION_KEYS",
+                    key_version
+                );
+            }),
+            None => Err(anyhow::anyhow!("Usage: rotate_keys retire <version>")),
+        },
+        _ => Err(anyhow::anyhow!(
+            "Usage: rotate_keys <run [batch_size] | status | retire <version>>"
+        )),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", redact_secrets(&error.to_string()));
+        std::process::exit(1);
+    }
+}
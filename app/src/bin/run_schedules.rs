@@ -0,0 +1,33 @@
+//! Command line entry point for the payment scheduler.
+//!
+//! Usage: `run_schedules [limit]`
+//!
+//! Run it every minute: schedules that are due are paid and moved on to their next
+//! occurrence.
+use helpers::secret::redact_secrets;
+use services::scheduler::scheduler::run_due_schedules;
+
+/// Number of schedules run per invocation when no limit is given.
+const DEFAULT_SCHEDULE_LIMIT: i64 = 100;
+
+#[rocket::main]
+async fn main() {
+    // Load env
+    dotenv::dotenv().ok();
+
+    let limit = match std::env::args().nth(1) {
+        Some(limit) => limit.parse::<i64>().unwrap_or(DEFAULT_SCHEDULE_LIMIT),
+        None => DEFAULT_SCHEDULE_LIMIT,
+    };
+
+    match run_due_schedules(limit).await {
+        Ok(summary) => println!(
+            "Ran schedules: {} completed, {} failed, {} submitted; {} schedules paused",
+            summary.completed, summary.failed, summary.submitted, summary.paused
+        ),
+        Err(error) => {
+            eprintln!("{}", redact_secrets(&error.to_string()));
+            std::process::exit(1);
+        }
+    }
+}
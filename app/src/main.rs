@@ -1,6 +1,11 @@
 #[macro_use]
 extern crate rocket;
-use app::routes::{account::account, payment::payment};
+use app::routes::{
+    account::account, anchor::anchor, asset::asset, auth::auth, customer::customer,
+    envelope::envelope, escrow::escrow, federation::federation, multisig::multisig,
+    payment::payment, payment_request::payment_request, remittance::remittance,
+    schedule::schedule, stellar_toml::stellar_toml,
+};
 // use helpers::{asset_issuer::AssetIssuer, stellar_chain::StellarChain};
 // use stellar_base::Network;
 // use stellar_sdk::Keypair;
@@ -29,12 +34,13 @@ async fn rocket() -> _ {
 
     // let account = stellar_chain.create_new_account().unwrap();
 
-    // let account_keypair = Keypair::from_secret_key(account.secret_key.as_str()).unwrap();
+    // let account_keypair = Keypair::from_secret_key(account.secret_key.expose_secret()).unwrap();
 
     // stellar_chain.activate_account(account_keypair).await.unwrap();
 
-    // Generate encryption key and iv. Use when generating a new key.
-    // helpers::common::generate_encryption_key_and_iv();
+    // Generate encryption key and iv. Use when generating a new key, and store the
+    // returned values straight into the secret manager rather than printing them.
+    // let (encryption_key, encryption_iv) = helpers::common::generate_encryption_key_and_iv();
 
     // Launch application
     rocket::build()
@@ -44,6 +50,7 @@ async fn rocket() -> _ {
                 account::get_single_account,
                 account::create_account,
                 account::activate_account,
+                account::prepare_account_activation,
                 account::update_account,
                 account::soft_delete_account,
                 account::get_account_by_stellar_address
@@ -54,7 +61,132 @@ async fn rocket() -> _ {
             routes![
                 payment::establish_trustline_for_non_native_asset,
                 payment::send_native_payment,
-                payment::send_non_native_payment
+                payment::send_non_native_payment,
+                payment::send_batch_payment
+            ],
+        )
+        .mount("/v1/envelopes", routes![envelope::submit_signed_envelope])
+        .mount(
+            "/v1/multisig",
+            routes![
+                multisig::apply_signer_policy,
+                multisig::get_pending_transaction,
+                multisig::sign_pending_transaction,
+                multisig::add_pending_signature,
+                multisig::add_pending_envelope
+            ],
+        )
+        .mount(
+            "/v1/schedules",
+            routes![
+                schedule::create_payment_schedule,
+                schedule::get_payment_schedules,
+                schedule::get_payment_schedule_runs,
+                schedule::pause_payment_schedule,
+                schedule::resume_payment_schedule,
+                schedule::cancel_payment_schedule
+            ],
+        )
+        .mount(
+            "/v1/escrows",
+            routes![
+                escrow::create_escrow,
+                escrow::get_escrows,
+                escrow::claim_escrow,
+                escrow::reclaim_escrow
+            ],
+        )
+        .mount(
+            "/v1/assets",
+            routes![
+                asset::register_asset,
+                asset::get_assets,
+                asset::update_asset_metadata,
+                asset::set_issuer_home_domain,
+                asset::mint_asset,
+                asset::burn_asset,
+                asset::get_asset_supply,
+                asset::get_trustlines,
+                asset::authorize_trustline,
+                asset::freeze_trustline,
+                asset::revoke_trustline,
+                asset::clawback,
+                asset::clawback_claimable_balance,
+                asset::get_clawbacks
+            ],
+        )
+        .mount("/.well-known", routes![stellar_toml::get_stellar_toml])
+        .mount("/federation", routes![federation::lookup])
+        .mount("/v1/federation", routes![federation::register_handle])
+        .mount(
+            "/auth",
+            routes![
+                auth::get_challenge,
+                auth::authenticate,
+                auth::authenticate_json
+            ],
+        )
+        .mount(
+            "/sep6",
+            routes![
+                anchor::sep6_info,
+                anchor::deposit,
+                anchor::withdraw,
+                anchor::transaction,
+                anchor::transactions
+            ],
+        )
+        .mount(
+            "/sep24",
+            routes![
+                anchor::sep24_info,
+                anchor::interactive_deposit,
+                anchor::interactive_withdraw,
+                anchor::transaction,
+                anchor::transactions
+            ],
+        )
+        .mount(
+            "/sep31",
+            routes![
+                remittance::info,
+                remittance::create_transaction,
+                remittance::get_transaction,
+                remittance::set_callback
+            ],
+        )
+        .mount(
+            "/kyc",
+            routes![
+                customer::get_customer,
+                customer::put_customer,
+                customer::delete_customer
+            ],
+        )
+        .mount(
+            "/v1/customers",
+            routes![
+                customer::get_customers,
+                customer::get_customer_review,
+                customer::get_customer_document,
+                customer::review_customer
+            ],
+        )
+        .mount(
+            "/v1/anchor",
+            routes![
+                anchor::complete_interactive,
+                anchor::funds_received,
+                anchor::payout_completed
+            ],
+        )
+        .mount(
+            "/v1/payment-requests",
+            routes![
+                payment_request::create_payment_request,
+                payment_request::get_payment_request,
+                payment_request::get_payment_request_qr_code,
+                payment_request::payment_request_callback
             ],
         )
 }
@@ -1,3 +1,5 @@
+#![allow(clippy::module_inception)]
+
 pub mod account {
     use controllers::{
         account::form::form::{
@@ -6,7 +8,8 @@ pub mod account {
         },
         api::api::{failure, success, ApiResponse},
     };
-    use models::models::Account;
+    use helpers::secret::redact_secrets;
+    use models::models::{Account, PendingTransaction};
     use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
 
     #[get("/<account_id>")]
@@ -62,6 +65,33 @@ pub mod account {
         ))
     }
 
+    #[post("/activate/prepare", data = "<form>")]
+    pub async fn prepare_account_activation<'r>(
+        form: Form<ActivateAccountForm<'r>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<PendingTransaction>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let prepared_envelope = controllers::account::prepare_account_activation_controller(form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error preparing account activation: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure(
+                    "Failed to prepare account activation",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(success(
+            "Account activation prepared successfully",
+            prepared_envelope,
+            Status::Ok,
+        ))
+    }
+
     #[post("/update", data = "<form>")]
     pub async fn update_account<'r>(
         form: Form<UpdateAccountForm<'r>>,
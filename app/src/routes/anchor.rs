@@ -0,0 +1,252 @@
+#![allow(clippy::module_inception)]
+
+pub mod anchor {
+    use controllers::{
+        admin::admin::Admin,
+        anchor::form::form::{
+            DepositQuery, FundsReceivedForm, InteractiveForm, InteractiveInfoForm,
+            TransactionQuery, TransactionsQuery, WithdrawQuery,
+        },
+        anchor::{
+            anchor_info_controller, complete_interactive_controller, deposit_controller,
+            funds_received_controller, interactive_controller, payout_completed_controller,
+            transaction_controller, transactions_controller, withdraw_controller,
+        },
+        api::api::{failure, success, ApiResponse},
+        web_auth::web_auth::WebAuth,
+    };
+    use helpers::secret::redact_secrets;
+    use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
+    use serde_json::{json, Value};
+    use services::anchor::anchor::{
+        AnchorTransactionView, DepositInstructions, InteractiveResponse, WithdrawalInstructions,
+    };
+
+    use crate::routes::auth::auth::{error, ErrorResponse};
+
+    type AnchorResult<T> = Result<Json<T>, status::Custom<Json<ErrorResponse>>>;
+
+    /// Rejects a request made for another account than the authenticated one
+    fn check_account(
+        web_auth: &WebAuth,
+        account: Option<&str>,
+    ) -> Result<(), status::Custom<Json<ErrorResponse>>> {
+        if account.is_some_and(|account| account != web_auth.account) {
+            return Err(error(
+                "Account does not match the authenticated account",
+                Status::Forbidden,
+            ));
+        }
+        Ok(())
+    }
+
+    async fn info(protocol: &str) -> AnchorResult<Value> {
+        let result = anchor_info_controller(protocol).await.map_err(|e| {
+            eprintln!(
+                "Error listing anchored assets: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            error(
+                "Failed to list anchored assets",
+                Status::InternalServerError,
+            )
+        })?;
+
+        Ok(Json(result))
+    }
+
+    #[get("/info")]
+    pub async fn sep6_info() -> AnchorResult<Value> {
+        info("sep6").await
+    }
+
+    #[get("/info")]
+    pub async fn sep24_info() -> AnchorResult<Value> {
+        info("sep24").await
+    }
+
+    #[get("/deposit?<query..>")]
+    pub async fn deposit(
+        web_auth: WebAuth,
+        query: DepositQuery<'_>,
+    ) -> AnchorResult<DepositInstructions> {
+        check_account(&web_auth, query.account)?;
+
+        let result = deposit_controller(&web_auth.account, query)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error starting deposit: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to start deposit", Status::BadRequest)
+            })?;
+
+        Ok(Json(result))
+    }
+
+    #[get("/withdraw?<query..>")]
+    pub async fn withdraw(
+        web_auth: WebAuth,
+        query: WithdrawQuery<'_>,
+    ) -> AnchorResult<WithdrawalInstructions> {
+        check_account(&web_auth, query.account)?;
+
+        let result = withdraw_controller(&web_auth.account, query)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error starting withdrawal: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to start withdrawal", Status::BadRequest)
+            })?;
+
+        Ok(Json(result))
+    }
+
+    #[post("/transactions/deposit/interactive", data = "<form>")]
+    pub async fn interactive_deposit(
+        web_auth: WebAuth,
+        form: Form<InteractiveForm<'_>>,
+    ) -> AnchorResult<InteractiveResponse> {
+        check_account(&web_auth, form.account)?;
+
+        let result = interactive_controller("deposit", &web_auth.account, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error starting interactive deposit: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to start deposit", Status::BadRequest)
+            })?;
+
+        Ok(Json(result))
+    }
+
+    #[post("/transactions/withdraw/interactive", data = "<form>")]
+    pub async fn interactive_withdraw(
+        web_auth: WebAuth,
+        form: Form<InteractiveForm<'_>>,
+    ) -> AnchorResult<InteractiveResponse> {
+        check_account(&web_auth, form.account)?;
+
+        let result = interactive_controller("withdrawal", &web_auth.account, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error starting interactive withdrawal: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to start withdrawal", Status::BadRequest)
+            })?;
+
+        Ok(Json(result))
+    }
+
+    #[get("/transaction?<query..>")]
+    pub async fn transaction(
+        web_auth: WebAuth,
+        query: TransactionQuery<'_>,
+    ) -> AnchorResult<Value> {
+        let result = transaction_controller(&web_auth.account, query)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting anchor transaction: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Transaction not found", Status::NotFound)
+            })?;
+
+        Ok(Json(json!({ "transaction": result })))
+    }
+
+    #[get("/transactions?<query..>")]
+    pub async fn transactions(
+        web_auth: WebAuth,
+        query: TransactionsQuery<'_>,
+    ) -> AnchorResult<Value> {
+        let result = transactions_controller(&web_auth.account, query)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error listing anchor transactions: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to list transactions", Status::BadRequest)
+            })?;
+
+        Ok(Json(json!({ "transactions": result })))
+    }
+
+    #[post("/transactions/<transaction_id>/interactive", data = "<form>")]
+    pub async fn complete_interactive(
+        _admin: Admin,
+        transaction_id: &str,
+        form: Form<InteractiveInfoForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<AnchorTransactionView>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = complete_interactive_controller(transaction_id, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error completing interactive flow: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to complete interactive flow", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Interactive flow completed successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/transactions/<transaction_id>/funds-received", data = "<form>")]
+    pub async fn funds_received(
+        _admin: Admin,
+        transaction_id: &str,
+        form: Form<FundsReceivedForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<AnchorTransactionView>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = funds_received_controller(transaction_id, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error recording received deposit: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to record received deposit", Status::BadRequest)
+            })?;
+
+        Ok(success("Deposit recorded successfully", result, Status::Ok))
+    }
+
+    #[post("/transactions/<transaction_id>/payout-completed")]
+    pub async fn payout_completed(
+        _admin: Admin,
+        transaction_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<AnchorTransactionView>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = payout_completed_controller(transaction_id)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error completing payout: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to complete payout", Status::BadRequest)
+            })?;
+
+        Ok(success("Payout completed successfully", result, Status::Ok))
+    }
+}
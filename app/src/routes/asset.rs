@@ -0,0 +1,279 @@
+#![allow(clippy::module_inception)]
+
+pub mod asset {
+    use controllers::{
+        admin::admin::Admin,
+        api::api::{failure, success, ApiResponse},
+        asset::form::form::{
+            AssetMetadataForm, ChangeSupplyForm, ClawbackClaimableBalanceForm, ClawbackForm,
+            HomeDomainForm, RegisterAssetForm, TrustlineForm,
+        },
+        asset::{
+            authorize_trustline_controller, burn_asset_controller,
+            clawback_claimable_balance_controller, clawback_controller,
+            freeze_trustline_controller, get_asset_supply_controller, get_assets_controller,
+            get_clawbacks_controller, get_trustlines_controller, mint_asset_controller,
+            register_asset_controller, revoke_trustline_controller,
+            set_issuer_home_domain_controller, update_asset_metadata_controller,
+        },
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::{Clawback, Token, Trustline};
+    use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
+    use services::asset::asset::AssetSupply;
+
+    type AssetResponse =
+        Result<status::Custom<Json<ApiResponse<Token>>>, status::Custom<Json<ApiResponse<()>>>>;
+
+    #[post("/", data = "<form>")]
+    pub async fn register_asset(_admin: Admin, form: Form<RegisterAssetForm<'_>>) -> AssetResponse {
+        let result = register_asset_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error registering asset: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to register asset", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Asset registered successfully",
+            result,
+            Status::Created,
+        ))
+    }
+
+    #[get("/")]
+    pub async fn get_assets(
+    ) -> Result<status::Custom<Json<ApiResponse<Vec<Token>>>>, status::Custom<Json<ApiResponse<()>>>>
+    {
+        let result = get_assets_controller()
+            .await
+            .map_err(|_| failure("Failed to get assets", Status::InternalServerError))?;
+
+        Ok(success("Assets fetched successfully", result, Status::Ok))
+    }
+
+    #[post("/metadata", data = "<form>")]
+    pub async fn update_asset_metadata(
+        _admin: Admin,
+        form: Form<AssetMetadataForm<'_>>,
+    ) -> AssetResponse {
+        let result = update_asset_metadata_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error updating asset metadata: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to update asset metadata", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Asset metadata updated successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/home-domain", data = "<form>")]
+    pub async fn set_issuer_home_domain(
+        _admin: Admin,
+        form: Form<HomeDomainForm<'_>>,
+    ) -> AssetResponse {
+        let result = set_issuer_home_domain_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error setting issuer home domain: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to set issuer home domain", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Issuer home domain set successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/mint", data = "<form>")]
+    pub async fn mint_asset(_admin: Admin, form: Form<ChangeSupplyForm<'_>>) -> AssetResponse {
+        let result = mint_asset_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error minting asset: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to mint asset", Status::BadRequest)
+        })?;
+
+        Ok(success("Asset minted successfully", result, Status::Ok))
+    }
+
+    #[post("/burn", data = "<form>")]
+    pub async fn burn_asset(_admin: Admin, form: Form<ChangeSupplyForm<'_>>) -> AssetResponse {
+        let result = burn_asset_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error burning asset: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to burn asset", Status::BadRequest)
+        })?;
+
+        Ok(success("Asset burned successfully", result, Status::Ok))
+    }
+
+    #[get("/<token_id>/supply")]
+    pub async fn get_asset_supply(
+        token_id: &str,
+    ) -> Result<status::Custom<Json<ApiResponse<AssetSupply>>>, status::Custom<Json<ApiResponse<()>>>>
+    {
+        let result = get_asset_supply_controller(token_id).await.map_err(|e| {
+            eprintln!(
+                "Error getting asset supply: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to get asset supply", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Asset supply fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    type TrustlineResponse =
+        Result<status::Custom<Json<ApiResponse<Trustline>>>, status::Custom<Json<ApiResponse<()>>>>;
+
+    #[get("/<token_id>/trustlines")]
+    pub async fn get_trustlines(
+        token_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<Vec<Trustline>>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = get_trustlines_controller(token_id)
+            .await
+            .map_err(|_| failure("Failed to get trustlines", Status::InternalServerError))?;
+
+        Ok(success(
+            "Trustlines fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/trustlines/authorize", data = "<form>")]
+    pub async fn authorize_trustline(
+        _admin: Admin,
+        form: Form<TrustlineForm<'_>>,
+    ) -> TrustlineResponse {
+        let result = authorize_trustline_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error authorizing trustline: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to authorize trustline", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Trustline authorized successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/trustlines/freeze", data = "<form>")]
+    pub async fn freeze_trustline(
+        _admin: Admin,
+        form: Form<TrustlineForm<'_>>,
+    ) -> TrustlineResponse {
+        let result = freeze_trustline_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error freezing trustline: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to freeze trustline", Status::BadRequest)
+        })?;
+
+        Ok(success("Trustline frozen successfully", result, Status::Ok))
+    }
+
+    #[post("/trustlines/revoke", data = "<form>")]
+    pub async fn revoke_trustline(
+        _admin: Admin,
+        form: Form<TrustlineForm<'_>>,
+    ) -> TrustlineResponse {
+        let result = revoke_trustline_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error revoking trustline: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to revoke trustline", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Trustline revoked successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    type ClawbackResponse =
+        Result<status::Custom<Json<ApiResponse<Clawback>>>, status::Custom<Json<ApiResponse<()>>>>;
+
+    #[post("/clawback", data = "<form>")]
+    pub async fn clawback(_admin: Admin, form: Form<ClawbackForm<'_>>) -> ClawbackResponse {
+        let result = clawback_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error clawing back asset: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to claw back asset", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Asset clawed back successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/clawback/claimable-balance", data = "<form>")]
+    pub async fn clawback_claimable_balance(
+        _admin: Admin,
+        form: Form<ClawbackClaimableBalanceForm<'_>>,
+    ) -> ClawbackResponse {
+        let result = clawback_claimable_balance_controller(form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error clawing back claimable balance: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to claw back claimable balance", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Claimable balance clawed back successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[get("/<token_id>/clawbacks")]
+    pub async fn get_clawbacks(
+        _admin: Admin,
+        token_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<Vec<Clawback>>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = get_clawbacks_controller(token_id)
+            .await
+            .map_err(|_| failure("Failed to get clawbacks", Status::InternalServerError))?;
+
+        Ok(success(
+            "Clawbacks fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
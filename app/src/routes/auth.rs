@@ -0,0 +1,76 @@
+#![allow(clippy::module_inception)]
+
+pub mod auth {
+    use controllers::auth::form::form::{ChallengeForm, TokenForm};
+    use controllers::auth::{authenticate_controller, get_challenge_controller};
+    use helpers::secret::redact_secrets;
+    use rocket::serde::{json::Json, Serialize};
+    use rocket::{form::Form, get, http::Status, post, response::status};
+    use services::web_auth::web_auth::ChallengeResponse;
+
+    /// A token for an authenticated account, in the shape SEP-0010 defines
+    #[derive(Serialize)]
+    #[serde(crate = "rocket::serde")]
+    pub struct TokenResponse {
+        pub token: String,
+    }
+
+    /// An error, in the shape SEP-0010 defines
+    #[derive(Serialize)]
+    #[serde(crate = "rocket::serde")]
+    pub struct ErrorResponse {
+        pub error: String,
+    }
+
+    pub fn error(message: &str, status: Status) -> status::Custom<Json<ErrorResponse>> {
+        status::Custom(
+            status,
+            Json(ErrorResponse {
+                error: message.to_string(),
+            }),
+        )
+    }
+
+    #[get("/?<query..>")]
+    pub async fn get_challenge(
+        query: ChallengeForm<'_>,
+    ) -> Result<Json<ChallengeResponse>, status::Custom<Json<ErrorResponse>>> {
+        let result = get_challenge_controller(query).map_err(|e| {
+            eprintln!(
+                "Error building challenge: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            error("Failed to build challenge", Status::BadRequest)
+        })?;
+
+        Ok(Json(result))
+    }
+
+    #[post("/", format = "form", data = "<form>")]
+    pub async fn authenticate(
+        form: Form<TokenForm>,
+    ) -> Result<Json<TokenResponse>, status::Custom<Json<ErrorResponse>>> {
+        issue_token(&form).await
+    }
+
+    #[post("/", format = "json", data = "<body>")]
+    pub async fn authenticate_json(
+        body: Json<TokenForm>,
+    ) -> Result<Json<TokenResponse>, status::Custom<Json<ErrorResponse>>> {
+        issue_token(&body).await
+    }
+
+    async fn issue_token(
+        data: &TokenForm,
+    ) -> Result<Json<TokenResponse>, status::Custom<Json<ErrorResponse>>> {
+        let token = authenticate_controller(data).await.map_err(|e| {
+            eprintln!(
+                "Error verifying challenge: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            error("Failed to verify challenge", Status::Unauthorized)
+        })?;
+
+        Ok(Json(TokenResponse { token }))
+    }
+}
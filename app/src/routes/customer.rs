@@ -0,0 +1,193 @@
+#![allow(clippy::module_inception)]
+
+pub mod customer {
+    use controllers::{
+        admin::admin::Admin,
+        api::api::{failure, success, ApiResponse},
+        customer::form::form::{CustomerForm, CustomerQueryForm, DeleteCustomerForm, ReviewForm},
+        customer::{
+            delete_customer_controller, get_customer_controller, get_customer_document_controller,
+            get_customer_review_controller, get_customers_controller, put_customer_controller,
+            review_customer_controller,
+        },
+        web_auth::web_auth::WebAuth,
+    };
+    use helpers::secret::redact_secrets;
+    use rocket::{
+        delete, form::Form, get, http::ContentType, http::Status, post, put, response::status,
+        serde::json::Json,
+    };
+    use serde_json::{json, Value};
+    use services::customer::customer::{CustomerReview, CustomerSummary, CustomerView};
+
+    use crate::routes::auth::auth::{error, ErrorResponse};
+
+    type CustomerResult<T> = Result<T, status::Custom<Json<ErrorResponse>>>;
+
+    /// Rejects a request made for another account than the authenticated one
+    fn check_account(web_auth: &WebAuth, account: Option<&str>) -> CustomerResult<()> {
+        if account.is_some_and(|account| account != web_auth.account) {
+            return Err(error(
+                "Account does not match the authenticated account",
+                Status::Forbidden,
+            ));
+        }
+        Ok(())
+    }
+
+    #[get("/customer?<query..>")]
+    pub async fn get_customer(
+        web_auth: WebAuth,
+        query: CustomerQueryForm<'_>,
+    ) -> CustomerResult<Json<CustomerView>> {
+        check_account(&web_auth, query.account)?;
+
+        let result = get_customer_controller(&web_auth.account, query)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting customer: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Customer not found", Status::NotFound)
+            })?;
+
+        Ok(Json(result))
+    }
+
+    #[put("/customer", data = "<form>")]
+    pub async fn put_customer(
+        web_auth: WebAuth,
+        form: Form<CustomerForm<'_>>,
+    ) -> CustomerResult<status::Custom<Json<Value>>> {
+        check_account(&web_auth, form.account)?;
+
+        let result = put_customer_controller(&web_auth.account, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error putting customer: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to put customer", Status::BadRequest)
+            })?;
+
+        Ok(status::Custom(
+            Status::Accepted,
+            Json(json!({ "id": result })),
+        ))
+    }
+
+    #[delete("/customer/<account>", data = "<form>")]
+    pub async fn delete_customer(
+        web_auth: WebAuth,
+        account: &str,
+        form: Option<Form<DeleteCustomerForm<'_>>>,
+    ) -> CustomerResult<Status> {
+        check_account(&web_auth, Some(account))?;
+
+        delete_customer_controller(account, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error deleting customer: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Customer not found", Status::NotFound)
+            })?;
+
+        Ok(Status::Ok)
+    }
+
+    #[get("/?<status>")]
+    pub async fn get_customers(
+        _admin: Admin,
+        status: Option<&str>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<Vec<CustomerSummary>>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = get_customers_controller(status).await.map_err(|e| {
+            eprintln!(
+                "Error listing customers: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to get customers", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Customers fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[get("/<customer_id>")]
+    pub async fn get_customer_review(
+        _admin: Admin,
+        customer_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<CustomerReview>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = get_customer_review_controller(customer_id)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting customer: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Customer not found", Status::NotFound)
+            })?;
+
+        Ok(success("Customer fetched successfully", result, Status::Ok))
+    }
+
+    #[get("/<customer_id>/documents/<field>")]
+    pub async fn get_customer_document(
+        _admin: Admin,
+        customer_id: &str,
+        field: &str,
+    ) -> Result<(ContentType, Vec<u8>), status::Custom<Json<ApiResponse<()>>>> {
+        let (content_type, content) = get_customer_document_controller(customer_id, field)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting customer document: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Document not found", Status::NotFound)
+            })?;
+
+        let content_type =
+            ContentType::parse_flexible(&content_type).unwrap_or(ContentType::Binary);
+
+        Ok((content_type, content))
+    }
+
+    #[post("/<customer_id>/review", data = "<form>")]
+    pub async fn review_customer(
+        _admin: Admin,
+        customer_id: &str,
+        form: Form<ReviewForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<CustomerSummary>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = review_customer_controller(customer_id, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error reviewing customer: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to review customer", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Customer reviewed successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
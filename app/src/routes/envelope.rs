@@ -0,0 +1,34 @@
+#![allow(clippy::module_inception)]
+
+pub mod envelope {
+    use controllers::{
+        api::api::{failure, success, ApiResponse},
+        envelope::form::form::SubmitSignedEnvelopeForm,
+        envelope::submit_signed_envelope_controller,
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::PendingTransaction;
+    use rocket::{form::Form, http::Status, post, response::status, serde::json::Json};
+
+    #[post("/submit", data = "<form>")]
+    pub async fn submit_signed_envelope(
+        form: Form<SubmitSignedEnvelopeForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<PendingTransaction>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = submit_signed_envelope_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error submitting signed envelope: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to submit signed envelope", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Signed envelope submitted successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
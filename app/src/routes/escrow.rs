@@ -0,0 +1,75 @@
+#![allow(clippy::module_inception)]
+
+pub mod escrow {
+    use controllers::{
+        api::api::{failure, success, ApiResponse},
+        escrow::form::form::{CreateEscrowForm, EscrowForm, GetEscrowsForm},
+        escrow::{
+            claim_escrow_controller, create_escrow_controller, get_escrows_controller,
+            reclaim_escrow_controller,
+        },
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::Escrow;
+    use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
+
+    type EscrowResponse =
+        Result<status::Custom<Json<ApiResponse<Escrow>>>, status::Custom<Json<ApiResponse<()>>>>;
+
+    #[post("/", data = "<form>")]
+    pub async fn create_escrow(form: Form<CreateEscrowForm<'_>>) -> EscrowResponse {
+        let result = create_escrow_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error creating escrow: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to create escrow", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Escrow created successfully",
+            result,
+            Status::Created,
+        ))
+    }
+
+    #[get("/?<account_id>")]
+    pub async fn get_escrows(
+        account_id: &str,
+    ) -> Result<status::Custom<Json<ApiResponse<Vec<Escrow>>>>, status::Custom<Json<ApiResponse<()>>>>
+    {
+        let form = GetEscrowsForm { account_id };
+
+        let result = get_escrows_controller(Form::from(form))
+            .await
+            .map_err(|_| failure("Failed to get escrows", Status::InternalServerError))?;
+
+        Ok(success("Escrows fetched successfully", result, Status::Ok))
+    }
+
+    #[post("/claim", data = "<form>")]
+    pub async fn claim_escrow(form: Form<EscrowForm<'_>>) -> EscrowResponse {
+        let result = claim_escrow_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error claiming escrow: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to claim escrow", Status::BadRequest)
+        })?;
+
+        Ok(success("Escrow claimed successfully", result, Status::Ok))
+    }
+
+    #[post("/reclaim", data = "<form>")]
+    pub async fn reclaim_escrow(form: Form<EscrowForm<'_>>) -> EscrowResponse {
+        let result = reclaim_escrow_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error reclaiming escrow: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to reclaim escrow", Status::BadRequest)
+        })?;
+
+        Ok(success("Escrow reclaimed successfully", result, Status::Ok))
+    }
+}
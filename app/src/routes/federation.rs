@@ -0,0 +1,76 @@
+#![allow(clippy::module_inception)]
+
+pub mod federation {
+    use controllers::{
+        admin::admin::Admin,
+        api::api::{failure, success, ApiResponse},
+        federation::form::form::{FederationQuery, RegisterHandleForm},
+        federation::{federation_lookup_controller, register_handle_controller},
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::FederationHandle;
+    use rocket::{
+        form::Form, get, http::Header, http::Status, post, response::status, serde::json::Json,
+        Responder,
+    };
+    use serde_json::{json, Value};
+
+    /// A federation response, readable by wallets on any origin as SEP-0002 requires
+    #[derive(Responder)]
+    pub struct FederationResponse {
+        body: status::Custom<Json<Value>>,
+        cors: Header<'static>,
+    }
+
+    fn federation_response(body: Value, status: Status) -> FederationResponse {
+        FederationResponse {
+            body: status::Custom(status, Json(body)),
+            cors: Header::new("Access-Control-Allow-Origin", "*"),
+        }
+    }
+
+    #[get("/?<query..>")]
+    pub async fn lookup(query: FederationQuery<'_>) -> FederationResponse {
+        if matches!(query.query_type, "txid" | "forward") {
+            return federation_response(
+                json!({ "detail": "Lookup type not supported" }),
+                Status::NotImplemented,
+            );
+        }
+
+        match federation_lookup_controller(query).await {
+            Ok(Some(record)) => federation_response(json!(record), Status::Ok),
+            Ok(None) => federation_response(json!({ "detail": "Not found" }), Status::NotFound),
+            Err(e) => {
+                eprintln!(
+                    "Error looking up federation record: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                federation_response(json!({ "detail": "Invalid query" }), Status::BadRequest)
+            }
+        }
+    }
+
+    #[post("/handles", data = "<form>")]
+    pub async fn register_handle(
+        _admin: Admin,
+        form: Form<RegisterHandleForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<FederationHandle>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = register_handle_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error registering federation handle: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to register federation handle", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Federation handle registered successfully",
+            result,
+            Status::Created,
+        ))
+    }
+}
@@ -1,2 +1,14 @@
 pub mod account;
+pub mod anchor;
+pub mod asset;
+pub mod auth;
+pub mod customer;
+pub mod envelope;
+pub mod escrow;
+pub mod federation;
+pub mod multisig;
 pub mod payment;
+pub mod payment_request;
+pub mod remittance;
+pub mod schedule;
+pub mod stellar_toml;
@@ -0,0 +1,132 @@
+#![allow(clippy::module_inception)]
+
+pub mod multisig {
+    use controllers::{
+        admin::admin::Admin,
+        api::api::{failure, success, ApiResponse},
+        multisig::form::form::{
+            AddPendingEnvelopeForm, AddPendingSignatureForm, ApplySignerPolicyForm,
+            GetPendingTransactionForm, SignPendingTransactionForm,
+        },
+        multisig::{
+            add_pending_envelope_controller, add_pending_signature_controller,
+            apply_signer_policy_controller, get_pending_transaction_controller,
+            sign_pending_transaction_controller,
+        },
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::PendingTransaction;
+    use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
+
+    type PendingTransactionResponse = Result<
+        status::Custom<Json<ApiResponse<PendingTransaction>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    >;
+
+    #[post("/policy", data = "<form>")]
+    pub async fn apply_signer_policy(
+        admin: Admin,
+        form: Form<ApplySignerPolicyForm<'_>>,
+    ) -> PendingTransactionResponse {
+        let result = apply_signer_policy_controller(admin, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error applying signer policy: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to apply signer policy", Status::InternalServerError)
+            })?;
+
+        Ok(success(
+            "Signer policy transaction created successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[get("/pending/<pending_transaction_id>")]
+    pub async fn get_pending_transaction(
+        pending_transaction_id: &str,
+    ) -> PendingTransactionResponse {
+        let form = GetPendingTransactionForm {
+            pending_transaction_id,
+        };
+
+        let result = get_pending_transaction_controller(Form::from(form))
+            .await
+            .map_err(|_| {
+                failure(
+                    "Failed to get pending transaction",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(success(
+            "Pending transaction fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/pending/sign", data = "<form>")]
+    pub async fn sign_pending_transaction(
+        admin: Admin,
+        form: Form<SignPendingTransactionForm<'_>>,
+    ) -> PendingTransactionResponse {
+        let result = sign_pending_transaction_controller(admin, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error signing pending transaction: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure(
+                    "Failed to sign pending transaction",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(success(
+            "Pending transaction signed successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/pending/signature", data = "<form>")]
+    pub async fn add_pending_signature(
+        _admin: Admin,
+        form: Form<AddPendingSignatureForm<'_>>,
+    ) -> PendingTransactionResponse {
+        let result = add_pending_signature_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error adding signature: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to add signature", Status::BadRequest)
+        })?;
+
+        Ok(success("Signature added successfully", result, Status::Ok))
+    }
+
+    #[post("/pending/envelope", data = "<form>")]
+    pub async fn add_pending_envelope(
+        _admin: Admin,
+        form: Form<AddPendingEnvelopeForm<'_>>,
+    ) -> PendingTransactionResponse {
+        let result = add_pending_envelope_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error adding envelope signatures: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to add envelope signatures", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Envelope signatures added successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
@@ -1,15 +1,49 @@
+#![allow(clippy::module_inception)]
+
 pub mod payment {
     use controllers::{
-        api::api::{failure, success, ApiResponse},
+        api::api::{failure, success, validation_fail, ApiResponse},
         payment::form::form::{
-            EstablishTrustlineForm, SendNativePaymentForm, SendNonNativePaymentForm,
+            EstablishTrustlineForm, SendBatchPaymentForm, SendNativePaymentForm,
+            SendNonNativePaymentForm,
         },
         payment::{
-            establish_trustline_for_non_native_asset_controller, send_native_payment_controller,
-            send_non_native_payment_controller,
+            establish_trustline_for_non_native_asset_controller, send_batch_payment_controller,
+            send_native_payment_controller, send_non_native_payment_controller,
         },
     };
-    use rocket::{form::Form, http::Status, post, response::status, serde::json::Json};
+    use helpers::secret::redact_secrets;
+    use rocket::{
+        form::Form,
+        http::Status,
+        post,
+        response::{self, status, Responder},
+        serde::json::Json,
+        Request,
+    };
+    use services::payment::payment::{BatchValidationError, PaymentBatchResult, PaymentResult};
+
+    /// A payment response with the `bool` data clients of `/native` and `/non-native`
+    /// read, and what was recorded for the payment in the `X-Transaction-Id`,
+    /// `X-Escrow-Id` and `X-Balance-Id` headers
+    pub struct PaymentResponse {
+        response: status::Custom<Json<ApiResponse<bool>>>,
+        result: PaymentResult,
+    }
+
+    impl<'r> Responder<'r, 'static> for PaymentResponse {
+        fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+            let mut response = self.response.respond_to(request)?;
+            response.set_raw_header("X-Transaction-Id", self.result.transaction_id.to_string());
+            if let Some(escrow_id) = self.result.escrow_id {
+                response.set_raw_header("X-Escrow-Id", escrow_id.to_string());
+            }
+            if let Some(balance_id) = self.result.balance_id {
+                response.set_raw_header("X-Balance-Id", balance_id);
+            }
+            Ok(response)
+        }
+    }
 
     #[post("/trustline", data = "<form>")]
     pub async fn establish_trustline_for_non_native_asset(
@@ -19,7 +53,10 @@ pub mod payment {
         let result = establish_trustline_for_non_native_asset_controller(form)
             .await
             .map_err(|e| {
-                eprintln!("Error establishing trustline: {:?}", e);
+                eprintln!(
+                    "Error establishing trustline: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
                 failure("Failed to establish trustline", Status::InternalServerError)
             })?;
 
@@ -33,39 +70,64 @@ pub mod payment {
     #[post("/native", data = "<form>")]
     pub async fn send_native_payment(
         form: Form<SendNativePaymentForm<'_>>,
-    ) -> Result<status::Custom<Json<ApiResponse<bool>>>, status::Custom<Json<ApiResponse<()>>>>
-    {
+    ) -> Result<PaymentResponse, status::Custom<Json<ApiResponse<()>>>> {
         let result = send_native_payment_controller(form).await.map_err(|e| {
-            eprintln!("Error sending native payment: {:?}", e);
+            eprintln!(
+                "Error sending native payment: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
             failure("Failed to send native payment", Status::InternalServerError)
         })?;
 
-        Ok(success(
-            "Native payment sent successfully",
+        Ok(PaymentResponse {
+            response: success("Native payment sent successfully", true, Status::Ok),
             result,
-            Status::Ok,
-        ))
+        })
     }
 
     #[post("/non-native", data = "<form>")]
     pub async fn send_non_native_payment(
         form: Form<SendNonNativePaymentForm<'_>>,
-    ) -> Result<status::Custom<Json<ApiResponse<bool>>>, status::Custom<Json<ApiResponse<()>>>>
-    {
+    ) -> Result<PaymentResponse, status::Custom<Json<ApiResponse<()>>>> {
         let result = send_non_native_payment_controller(form)
             .await
             .map_err(|e| {
-                eprintln!("Error sending non-native payment: {:?}", e);
+                eprintln!(
+                    "Error sending non-native payment: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
                 failure(
                     "Failed to send non-native payment",
                     Status::InternalServerError,
                 )
             })?;
 
-        Ok(success(
-            "Non-native payment sent successfully",
+        Ok(PaymentResponse {
+            response: success("Non-native payment sent successfully", true, Status::Ok),
             result,
-            Status::Ok,
-        ))
+        })
+    }
+
+    #[post("/batch", data = "<form>")]
+    pub async fn send_batch_payment(
+        form: Form<SendBatchPaymentForm<'_>>,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<PaymentBatchResult>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let result = send_batch_payment_controller(form).await.map_err(|e| {
+            // Invalid items are reported back, nothing was signed or submitted
+            if let Some(validation_error) = e.downcast_ref::<BatchValidationError>() {
+                return validation_fail(validation_error.errors.clone(), Status::BadRequest);
+            }
+
+            eprintln!(
+                "Error sending batch payment: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to send batch payment", Status::InternalServerError)
+        })?;
+
+        Ok(success("Batch payment processed", result, Status::Ok))
     }
 }
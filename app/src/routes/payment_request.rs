@@ -0,0 +1,108 @@
+#![allow(clippy::module_inception)]
+
+pub mod payment_request {
+    use controllers::{
+        admin::admin::Admin,
+        api::api::{failure, success, ApiResponse},
+        payment_request::form::form::{PaymentRequestCallbackForm, PaymentRequestForm},
+        payment_request::{
+            create_payment_request_controller, get_payment_request_controller,
+            get_payment_request_qr_code_controller, submit_payment_request_controller,
+        },
+    };
+    use helpers::secret::redact_secrets;
+    use rocket::{
+        form::Form, get, http::ContentType, http::Status, post, response::status, serde::json::Json,
+    };
+    use services::payment_request::payment_request::PaymentRequestView;
+
+    type PaymentRequestResponse = Result<
+        status::Custom<Json<ApiResponse<PaymentRequestView>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    >;
+
+    #[post("/", data = "<form>")]
+    pub async fn create_payment_request(
+        _admin: Admin,
+        form: Form<PaymentRequestForm<'_>>,
+    ) -> PaymentRequestResponse {
+        let result = create_payment_request_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error creating payment request: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to create payment request", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Payment request created successfully",
+            result,
+            Status::Created,
+        ))
+    }
+
+    #[get("/<request_id>")]
+    pub async fn get_payment_request(_admin: Admin, request_id: &str) -> PaymentRequestResponse {
+        let result = get_payment_request_controller(request_id)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting payment request: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Payment request not found", Status::NotFound)
+            })?;
+
+        Ok(success(
+            "Payment request fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[get("/<request_id>/qr?<format>")]
+    pub async fn get_payment_request_qr_code(
+        _admin: Admin,
+        request_id: &str,
+        format: Option<&str>,
+    ) -> Result<(ContentType, Vec<u8>), status::Custom<Json<ApiResponse<()>>>> {
+        let (content_type, image) = get_payment_request_qr_code_controller(request_id, format)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error rendering payment request QR code: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to render QR code", Status::BadRequest)
+            })?;
+
+        let content_type =
+            ContentType::parse_flexible(&content_type).unwrap_or(ContentType::Binary);
+
+        Ok((content_type, image))
+    }
+
+    // Wallets post here without our API key, the request id and the transaction
+    // checks stand in for it
+    #[post("/<request_id>/callback", data = "<form>")]
+    pub async fn payment_request_callback(
+        request_id: &str,
+        form: Form<PaymentRequestCallbackForm<'_>>,
+    ) -> PaymentRequestResponse {
+        let result = submit_payment_request_controller(request_id, form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error submitting payment request transaction: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to submit signed transaction", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Signed transaction submitted successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
@@ -0,0 +1,109 @@
+#![allow(clippy::module_inception)]
+
+pub mod remittance {
+    use controllers::{
+        remittance::form::form::{CallbackForm, ReceiveForm},
+        remittance::{
+            create_receive_controller, receive_transaction_controller, remittance_info_controller,
+            set_callback_controller,
+        },
+        web_auth::web_auth::WebAuth,
+    };
+    use helpers::secret::redact_secrets;
+    use rocket::{get, http::Status, post, put, response::status, serde::json::Json};
+    use serde_json::{json, Value};
+    use services::remittance::remittance::CustomerInfoNeeded;
+
+    type RemittanceResult<T> = Result<T, status::Custom<Json<Value>>>;
+
+    /// An error, in the shape SEP-0031 defines
+    fn error(message: &str, status: Status) -> status::Custom<Json<Value>> {
+        status::Custom(status, Json(json!({ "error": message })))
+    }
+
+    #[get("/info")]
+    pub async fn info() -> RemittanceResult<Json<Value>> {
+        let result = remittance_info_controller().await.map_err(|e| {
+            eprintln!(
+                "Error listing received assets: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            error(
+                "Failed to list received assets",
+                Status::InternalServerError,
+            )
+        })?;
+
+        Ok(Json(result))
+    }
+
+    #[post("/transactions", format = "json", data = "<body>")]
+    pub async fn create_transaction(
+        web_auth: WebAuth,
+        body: Json<ReceiveForm>,
+    ) -> RemittanceResult<status::Custom<Json<Value>>> {
+        let result = create_receive_controller(&web_auth.account, &body)
+            .await
+            .map_err(|e| {
+                // The sending anchor registers the customer with SEP-0012 and retries
+                if let Some(customer_info_needed) = e.downcast_ref::<CustomerInfoNeeded>() {
+                    return status::Custom(
+                        Status::BadRequest,
+                        Json(json!({
+                            "error": "customer_info_needed",
+                            "type": customer_info_needed.customer_type,
+                        })),
+                    );
+                }
+
+                eprintln!(
+                    "Error creating received payment: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to create transaction", Status::BadRequest)
+            })?;
+
+        Ok(status::Custom(Status::Created, Json(json!(result))))
+    }
+
+    #[get("/transactions/<transaction_id>")]
+    pub async fn get_transaction(
+        web_auth: WebAuth,
+        transaction_id: &str,
+    ) -> RemittanceResult<Json<Value>> {
+        let result = receive_transaction_controller(&web_auth.account, transaction_id)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error getting received payment: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Transaction not found", Status::NotFound)
+            })?;
+
+        Ok(Json(json!({ "transaction": result })))
+    }
+
+    #[put(
+        "/transactions/<transaction_id>/callback",
+        format = "json",
+        data = "<body>"
+    )]
+    pub async fn set_callback(
+        web_auth: WebAuth,
+        transaction_id: &str,
+        body: Json<CallbackForm>,
+    ) -> RemittanceResult<Status> {
+        set_callback_controller(&web_auth.account, transaction_id, &body)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error registering callback: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                error("Failed to register callback", Status::BadRequest)
+            })?;
+
+        Ok(Status::NoContent)
+    }
+}
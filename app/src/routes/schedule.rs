@@ -0,0 +1,155 @@
+#![allow(clippy::module_inception)]
+
+pub mod schedule {
+    use controllers::{
+        api::api::{failure, success, ApiResponse},
+        schedule::form::form::{
+            CreatePaymentScheduleForm, GetPaymentSchedulesForm, PaymentScheduleForm,
+        },
+        schedule::{
+            cancel_payment_schedule_controller, create_payment_schedule_controller,
+            get_payment_schedule_runs_controller, get_payment_schedules_controller,
+            pause_payment_schedule_controller, resume_payment_schedule_controller,
+        },
+    };
+    use helpers::secret::redact_secrets;
+    use models::models::{PaymentSchedule, PaymentScheduleRun};
+    use rocket::{form::Form, get, http::Status, post, response::status, serde::json::Json};
+
+    type PaymentScheduleResponse = Result<
+        status::Custom<Json<ApiResponse<PaymentSchedule>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    >;
+
+    #[post("/", data = "<form>")]
+    pub async fn create_payment_schedule(
+        form: Form<CreatePaymentScheduleForm<'_>>,
+    ) -> PaymentScheduleResponse {
+        let result = create_payment_schedule_controller(form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error creating payment schedule: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to create payment schedule", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Payment schedule created successfully",
+            result,
+            Status::Created,
+        ))
+    }
+
+    #[get("/?<account_id>")]
+    pub async fn get_payment_schedules(
+        account_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<Vec<PaymentSchedule>>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let form = GetPaymentSchedulesForm { account_id };
+
+        let result = get_payment_schedules_controller(Form::from(form))
+            .await
+            .map_err(|_| {
+                failure(
+                    "Failed to get payment schedules",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(success(
+            "Payment schedules fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[get("/<schedule_id>/runs")]
+    pub async fn get_payment_schedule_runs(
+        schedule_id: &str,
+    ) -> Result<
+        status::Custom<Json<ApiResponse<Vec<PaymentScheduleRun>>>>,
+        status::Custom<Json<ApiResponse<()>>>,
+    > {
+        let form = PaymentScheduleForm { schedule_id };
+
+        let result = get_payment_schedule_runs_controller(Form::from(form))
+            .await
+            .map_err(|_| {
+                failure(
+                    "Failed to get payment schedule runs",
+                    Status::InternalServerError,
+                )
+            })?;
+
+        Ok(success(
+            "Payment schedule runs fetched successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/pause", data = "<form>")]
+    pub async fn pause_payment_schedule(
+        form: Form<PaymentScheduleForm<'_>>,
+    ) -> PaymentScheduleResponse {
+        let result = pause_payment_schedule_controller(form).await.map_err(|e| {
+            eprintln!(
+                "Error pausing payment schedule: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            failure("Failed to pause payment schedule", Status::BadRequest)
+        })?;
+
+        Ok(success(
+            "Payment schedule paused successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/resume", data = "<form>")]
+    pub async fn resume_payment_schedule(
+        form: Form<PaymentScheduleForm<'_>>,
+    ) -> PaymentScheduleResponse {
+        let result = resume_payment_schedule_controller(form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error resuming payment schedule: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to resume payment schedule", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Payment schedule resumed successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+
+    #[post("/cancel", data = "<form>")]
+    pub async fn cancel_payment_schedule(
+        form: Form<PaymentScheduleForm<'_>>,
+    ) -> PaymentScheduleResponse {
+        let result = cancel_payment_schedule_controller(form)
+            .await
+            .map_err(|e| {
+                eprintln!(
+                    "Error cancelling payment schedule: {}",
+                    redact_secrets(&format!("{:?}", e))
+                );
+                failure("Failed to cancel payment schedule", Status::BadRequest)
+            })?;
+
+        Ok(success(
+            "Payment schedule cancelled successfully",
+            result,
+            Status::Ok,
+        ))
+    }
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::module_inception)]
+
+pub mod stellar_toml {
+    use controllers::stellar_toml::get_stellar_toml_controller;
+    use helpers::secret::redact_secrets;
+    use rocket::{get, http::Header, http::Status, Responder};
+
+    /// The stellar.toml file, readable by wallets on any origin as SEP-0001 requires
+    #[derive(Responder)]
+    #[response(content_type = "text/plain")]
+    pub struct StellarTomlResponse {
+        body: String,
+        cors: Header<'static>,
+    }
+
+    #[get("/stellar.toml")]
+    pub async fn get_stellar_toml() -> Result<StellarTomlResponse, Status> {
+        let body = get_stellar_toml_controller().await.map_err(|e| {
+            eprintln!(
+                "Error generating stellar.toml: {}",
+                redact_secrets(&format!("{:?}", e))
+            );
+            Status::InternalServerError
+        })?;
+
+        Ok(StellarTomlResponse {
+            body,
+            cors: Header::new("Access-Control-Allow-Origin", "*"),
+        })
+    }
+}
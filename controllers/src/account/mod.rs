@@ -3,11 +3,11 @@ use crate::account::form::form::{
     GetSingleAccountForm, SoftDeleteAccountForm, UpdateAccountForm,
 };
 use models::common::Pagination;
-use models::models::Account;
+use models::models::{Account, PendingTransaction};
 use rocket::form::Form;
 use services::account::account::{
     activate_account, create_account, get_account, get_account_by_stellar_address,
-    get_many_accounts, soft_delete_account, update_account,
+    get_many_accounts, prepare_account_activation, soft_delete_account, update_account,
 };
 
 pub mod form;
@@ -33,6 +33,13 @@ pub async fn activate_account_controller(
     Ok(activate_account(data.account_id).await?)
 }
 
+// Prepare account activation for offline signing
+pub async fn prepare_account_activation_controller(
+    data: Form<ActivateAccountForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(prepare_account_activation(data.account_id).await?)
+}
+
 // Update account
 pub async fn update_account_controller(
     data: Form<UpdateAccountForm<'_>>,
@@ -0,0 +1,43 @@
+#![allow(clippy::module_inception)]
+
+pub mod admin {
+    use rocket::http::Status;
+    use rocket::request::{FromRequest, Outcome, Request};
+
+    /// Header carrying the admin API key
+    pub const ADMIN_KEY_HEADER: &str = "X-Admin-Key";
+
+    /// Request guard for admin-only endpoints.
+    ///
+    /// The request must carry the `ADMIN_API_KEY` environment variable in its
+    /// `X-Admin-Key` header. Without `ADMIN_API_KEY` set every request is refused.
+    pub struct Admin;
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for Admin {
+        type Error = &'static str;
+
+        async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            let admin_key = match std::env::var("ADMIN_API_KEY") {
+                Ok(admin_key) if !admin_key.is_empty() => admin_key,
+                _ => return Outcome::Error((Status::Forbidden, "Admin endpoints are disabled")),
+            };
+
+            match request.headers().get_one(ADMIN_KEY_HEADER) {
+                Some(key) if constant_time_eq(key.as_bytes(), admin_key.as_bytes()) => {
+                    Outcome::Success(Admin)
+                }
+                _ => Outcome::Error((Status::Unauthorized, "Invalid admin key")),
+            }
+        }
+    }
+
+    /// Compares two byte strings without returning early on the first difference
+    fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+
+        a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+}
@@ -0,0 +1,62 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    /// A SEP-0006 deposit
+    #[derive(FromForm)]
+    pub struct DepositQuery<'r> {
+        pub asset_code: &'r str,
+        /// Defaults to the authenticated account
+        pub account: Option<&'r str>,
+        pub amount: Option<&'r str>,
+    }
+
+    /// A SEP-0006 withdrawal
+    #[derive(FromForm)]
+    pub struct WithdrawQuery<'r> {
+        pub asset_code: &'r str,
+        #[field(name = "type")]
+        pub withdraw_type: Option<&'r str>,
+        /// Where the fiat goes, e.g. a bank account number
+        pub dest: Option<&'r str>,
+        pub amount: Option<&'r str>,
+        /// Defaults to the authenticated account
+        pub account: Option<&'r str>,
+    }
+
+    /// A SEP-0024 deposit or withdrawal
+    #[derive(FromForm)]
+    pub struct InteractiveForm<'r> {
+        pub asset_code: &'r str,
+        /// Defaults to the authenticated account
+        pub account: Option<&'r str>,
+        pub amount: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct TransactionQuery<'r> {
+        pub id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct TransactionsQuery<'r> {
+        pub asset_code: &'r str,
+        pub limit: Option<i64>,
+    }
+
+    /// What the user entered in the interactive flow
+    #[derive(FromForm)]
+    pub struct InteractiveInfoForm<'r> {
+        pub amount: &'r str,
+        /// Where the fiat goes, required for a withdrawal
+        pub dest: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct FundsReceivedForm<'r> {
+        pub amount: &'r str,
+        /// The fiat rail's reference for the transfer
+        pub external_reference: Option<&'r str>,
+    }
+}
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use crate::anchor::form::form::{
+    DepositQuery, FundsReceivedForm, InteractiveForm, InteractiveInfoForm, TransactionQuery,
+    TransactionsQuery, WithdrawQuery,
+};
+use bigdecimal::BigDecimal;
+use rocket::form::Form;
+use rocket::serde::json::Value;
+use services::anchor::anchor::{
+    complete_interactive, confirm_deposit_received, confirm_payout_completed, get_info,
+    get_transaction, get_transactions, start_deposit, start_interactive, start_withdrawal,
+    AnchorRequest, AnchorTransactionView, DepositInstructions, InteractiveResponse,
+    WithdrawalInstructions, WITHDRAW_TYPE,
+};
+
+pub mod form;
+
+// List the assets we anchor for a protocol
+pub async fn anchor_info_controller(protocol: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    Ok(get_info(protocol).await?)
+}
+
+// Start a SEP-6 deposit for the authenticated account
+pub async fn deposit_controller(
+    account: &str,
+    query: DepositQuery<'_>,
+) -> Result<DepositInstructions, Box<dyn std::error::Error>> {
+    let request = AnchorRequest {
+        account: query.account.unwrap_or(account).to_string(),
+        asset_code: query.asset_code.to_string(),
+        amount: query.amount.map(BigDecimal::from_str).transpose()?,
+        destination: None,
+    };
+
+    Ok(start_deposit(request).await?)
+}
+
+// Start a SEP-6 withdrawal for the authenticated account
+pub async fn withdraw_controller(
+    account: &str,
+    query: WithdrawQuery<'_>,
+) -> Result<WithdrawalInstructions, Box<dyn std::error::Error>> {
+    if query
+        .withdraw_type
+        .is_some_and(|withdraw_type| withdraw_type != WITHDRAW_TYPE)
+    {
+        return Err(format!("Withdrawal type must be {}", WITHDRAW_TYPE).into());
+    }
+
+    let request = AnchorRequest {
+        account: query.account.unwrap_or(account).to_string(),
+        asset_code: query.asset_code.to_string(),
+        amount: query.amount.map(BigDecimal::from_str).transpose()?,
+        destination: query.dest.map(str::to_string),
+    };
+
+    Ok(start_withdrawal(request).await?)
+}
+
+// Start a SEP-24 deposit or withdrawal for the authenticated account
+pub async fn interactive_controller(
+    kind: &str,
+    account: &str,
+    data: Form<InteractiveForm<'_>>,
+) -> Result<InteractiveResponse, Box<dyn std::error::Error>> {
+    let request = AnchorRequest {
+        account: data.account.unwrap_or(account).to_string(),
+        asset_code: data.asset_code.to_string(),
+        amount: data.amount.map(BigDecimal::from_str).transpose()?,
+        destination: None,
+    };
+
+    Ok(start_interactive(kind, request).await?)
+}
+
+// Get one of the authenticated account's anchor transactions
+pub async fn transaction_controller(
+    account: &str,
+    query: TransactionQuery<'_>,
+) -> Result<AnchorTransactionView, Box<dyn std::error::Error>> {
+    Ok(get_transaction(account, query.id).await?)
+}
+
+// List the authenticated account's anchor transactions for an asset
+pub async fn transactions_controller(
+    account: &str,
+    query: TransactionsQuery<'_>,
+) -> Result<Vec<AnchorTransactionView>, Box<dyn std::error::Error>> {
+    Ok(get_transactions(account, query.asset_code, query.limit).await?)
+}
+
+// Record what the user entered in the interactive flow
+pub async fn complete_interactive_controller(
+    transaction_id: &str,
+    data: Form<InteractiveInfoForm<'_>>,
+) -> Result<AnchorTransactionView, Box<dyn std::error::Error>> {
+    let amount = BigDecimal::from_str(data.amount)?;
+
+    Ok(complete_interactive(transaction_id, amount, data.dest).await?)
+}
+
+// Record the fiat received for a deposit and pay the asset out
+pub async fn funds_received_controller(
+    transaction_id: &str,
+    data: Form<FundsReceivedForm<'_>>,
+) -> Result<AnchorTransactionView, Box<dyn std::error::Error>> {
+    let amount = BigDecimal::from_str(data.amount)?;
+
+    Ok(confirm_deposit_received(transaction_id, amount, data.external_reference).await?)
+}
+
+// Record that the fiat payout of a withdrawal settled
+pub async fn payout_completed_controller(
+    transaction_id: &str,
+) -> Result<AnchorTransactionView, Box<dyn std::error::Error>> {
+    Ok(confirm_payout_completed(transaction_id).await?)
+}
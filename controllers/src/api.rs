@@ -1,3 +1,5 @@
+#![allow(clippy::module_inception)]
+
 pub mod api {
     use rocket::http::Status;
     use rocket::response::status;
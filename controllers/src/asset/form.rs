@@ -0,0 +1,76 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    #[derive(FromForm)]
+    pub struct RegisterAssetForm<'r> {
+        pub issuer_account_id: &'r str,
+        pub distributor_account_id: &'r str,
+        pub asset_code: &'r str,
+        /// Most the distributor trusts the issuer for, in units of the asset
+        pub trust_limit: Option<&'r str>,
+        /// Issuer flags, all off unless given
+        pub auth_required: Option<bool>,
+        pub auth_revocable: Option<bool>,
+        pub auth_clawback_enabled: Option<bool>,
+        pub auth_immutable: Option<bool>,
+        /// Metadata published in stellar.toml
+        pub name: Option<&'r str>,
+        pub description: Option<&'r str>,
+        pub image: Option<&'r str>,
+        pub display_decimals: Option<i32>,
+        pub anchor_asset_type: Option<&'r str>,
+        pub anchor_asset: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct AssetMetadataForm<'r> {
+        pub token_id: &'r str,
+        pub name: Option<&'r str>,
+        pub description: Option<&'r str>,
+        pub image: Option<&'r str>,
+        pub display_decimals: Option<i32>,
+        pub anchor_asset_type: Option<&'r str>,
+        pub anchor_asset: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct HomeDomainForm<'r> {
+        pub token_id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct ChangeSupplyForm<'r> {
+        pub token_id: &'r str,
+        /// Units of the asset, with at most 7 decimals
+        pub amount: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct TrustlineForm<'r> {
+        pub token_id: &'r str,
+        /// Stellar address of the holder
+        pub stellar_address: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct ClawbackForm<'r> {
+        pub token_id: &'r str,
+        /// Stellar address of the holder
+        pub holder_address: &'r str,
+        /// Units of the asset, with at most 7 decimals
+        pub amount: &'r str,
+        /// The transaction being reversed
+        pub original_transaction_id: &'r str,
+        pub reason_code: &'r str,
+        pub note: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct ClawbackClaimableBalanceForm<'r> {
+        pub escrow_id: &'r str,
+        pub reason_code: &'r str,
+        pub note: Option<&'r str>,
+    }
+}
@@ -0,0 +1,171 @@
+use crate::asset::form::form::{
+    AssetMetadataForm, ChangeSupplyForm, ClawbackClaimableBalanceForm, ClawbackForm,
+    HomeDomainForm, RegisterAssetForm, TrustlineForm,
+};
+use bigdecimal::BigDecimal;
+use helpers::raw_transaction::{IssuerFlags, TrustlineAuthorization};
+use models::models::{Clawback, Token, Trustline};
+use rocket::form::Form;
+use services::asset::asset::{
+    burn_asset, get_asset_supply, get_assets, mint_asset, register_asset, set_issuer_home_domain,
+    update_asset_metadata, AssetMetadata, AssetRegistration, AssetSupply,
+};
+use services::clawback::clawback::{
+    clawback, clawback_claimable_balance, get_clawbacks, ClawbackRequest,
+};
+use services::trustline::trustline::{get_trustlines, set_trustline_authorization};
+use std::str::FromStr;
+
+pub mod form;
+
+// Register asset
+pub async fn register_asset_controller(
+    data: Form<RegisterAssetForm<'_>>,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    let registration = AssetRegistration {
+        issuer_account_id: data.issuer_account_id.to_string(),
+        distributor_account_id: data.distributor_account_id.to_string(),
+        asset_code: data.asset_code.to_string(),
+        trust_limit: data.trust_limit.map(BigDecimal::from_str).transpose()?,
+        issuer_flags: IssuerFlags {
+            auth_required: data.auth_required.unwrap_or(false),
+            auth_revocable: data.auth_revocable.unwrap_or(false),
+            auth_clawback_enabled: data.auth_clawback_enabled.unwrap_or(false),
+            auth_immutable: data.auth_immutable.unwrap_or(false),
+        },
+        metadata: AssetMetadata {
+            name: data.name.map(str::to_string),
+            description: data.description.map(str::to_string),
+            image: data.image.map(str::to_string),
+            display_decimals: data.display_decimals,
+            anchor_asset_type: data.anchor_asset_type.map(str::to_string),
+            anchor_asset: data.anchor_asset.map(str::to_string),
+        },
+    };
+
+    Ok(register_asset(registration).await?)
+}
+
+// Get registered assets
+pub async fn get_assets_controller() -> Result<Vec<Token>, Box<dyn std::error::Error>> {
+    Ok(get_assets().await?)
+}
+
+// Update asset metadata
+pub async fn update_asset_metadata_controller(
+    data: Form<AssetMetadataForm<'_>>,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    let metadata = AssetMetadata {
+        name: data.name.map(str::to_string),
+        description: data.description.map(str::to_string),
+        image: data.image.map(str::to_string),
+        display_decimals: data.display_decimals,
+        anchor_asset_type: data.anchor_asset_type.map(str::to_string),
+        anchor_asset: data.anchor_asset.map(str::to_string),
+    };
+
+    Ok(update_asset_metadata(data.token_id, metadata).await?)
+}
+
+// Set the home domain on the issuer of an asset
+pub async fn set_issuer_home_domain_controller(
+    data: Form<HomeDomainForm<'_>>,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    Ok(set_issuer_home_domain(data.token_id).await?)
+}
+
+// Mint asset to its distributor
+pub async fn mint_asset_controller(
+    data: Form<ChangeSupplyForm<'_>>,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    let amount = BigDecimal::from_str(data.amount)?;
+    Ok(mint_asset(data.token_id, amount).await?)
+}
+
+// Burn asset held by its distributor
+pub async fn burn_asset_controller(
+    data: Form<ChangeSupplyForm<'_>>,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    let amount = BigDecimal::from_str(data.amount)?;
+    Ok(burn_asset(data.token_id, amount).await?)
+}
+
+// Get asset supply
+pub async fn get_asset_supply_controller(
+    token_id: &str,
+) -> Result<AssetSupply, Box<dyn std::error::Error>> {
+    Ok(get_asset_supply(token_id).await?)
+}
+
+// Get trustlines of an asset
+pub async fn get_trustlines_controller(
+    token_id: &str,
+) -> Result<Vec<Trustline>, Box<dyn std::error::Error>> {
+    Ok(get_trustlines(token_id).await?)
+}
+
+// Authorize a holder after the KYC check
+pub async fn authorize_trustline_controller(
+    data: Form<TrustlineForm<'_>>,
+) -> Result<Trustline, Box<dyn std::error::Error>> {
+    Ok(set_trustline_authorization(
+        data.token_id,
+        data.stellar_address,
+        TrustlineAuthorization::Authorized,
+    )
+    .await?)
+}
+
+// Freeze a holder
+pub async fn freeze_trustline_controller(
+    data: Form<TrustlineForm<'_>>,
+) -> Result<Trustline, Box<dyn std::error::Error>> {
+    Ok(set_trustline_authorization(
+        data.token_id,
+        data.stellar_address,
+        TrustlineAuthorization::MaintainLiabilities,
+    )
+    .await?)
+}
+
+// Revoke a holder
+pub async fn revoke_trustline_controller(
+    data: Form<TrustlineForm<'_>>,
+) -> Result<Trustline, Box<dyn std::error::Error>> {
+    Ok(set_trustline_authorization(
+        data.token_id,
+        data.stellar_address,
+        TrustlineAuthorization::Deauthorized,
+    )
+    .await?)
+}
+
+// Claw back an amount from a holder
+pub async fn clawback_controller(
+    data: Form<ClawbackForm<'_>>,
+) -> Result<Clawback, Box<dyn std::error::Error>> {
+    let request = ClawbackRequest {
+        token_id: data.token_id.to_string(),
+        holder_address: data.holder_address.to_string(),
+        amount: BigDecimal::from_str(data.amount)?,
+        original_transaction_id: data.original_transaction_id.to_string(),
+        reason_code: data.reason_code.to_string(),
+        note: data.note.map(str::to_string),
+    };
+
+    Ok(clawback(request).await?)
+}
+
+// Claw back the claimable balance of an escrow
+pub async fn clawback_claimable_balance_controller(
+    data: Form<ClawbackClaimableBalanceForm<'_>>,
+) -> Result<Clawback, Box<dyn std::error::Error>> {
+    Ok(clawback_claimable_balance(data.escrow_id, data.reason_code, data.note).await?)
+}
+
+// Get clawbacks of an asset
+pub async fn get_clawbacks_controller(
+    token_id: &str,
+) -> Result<Vec<Clawback>, Box<dyn std::error::Error>> {
+    Ok(get_clawbacks(token_id).await?)
+}
@@ -0,0 +1,17 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+    use serde::Deserialize;
+
+    #[derive(FromForm)]
+    pub struct ChallengeForm<'r> {
+        pub account: &'r str,
+        pub home_domain: Option<&'r str>,
+    }
+
+    #[derive(FromForm, Deserialize)]
+    pub struct TokenForm {
+        pub transaction: String,
+    }
+}
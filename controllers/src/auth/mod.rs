@@ -0,0 +1,18 @@
+use crate::auth::form::form::{ChallengeForm, TokenForm};
+use services::web_auth::web_auth::{authenticate, get_challenge, ChallengeResponse};
+
+pub mod form;
+
+// Get a challenge transaction for an account
+pub fn get_challenge_controller(
+    query: ChallengeForm<'_>,
+) -> Result<ChallengeResponse, Box<dyn std::error::Error>> {
+    Ok(get_challenge(query.account, query.home_domain)?)
+}
+
+// Exchange a signed challenge transaction for a token
+pub async fn authenticate_controller(
+    data: &TokenForm,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(authenticate(&data.transaction).await?)
+}
@@ -0,0 +1,63 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+    use rocket::fs::TempFile;
+
+    /// Identifies a customer in SEP-0012 `GET /customer`
+    #[derive(FromForm)]
+    pub struct CustomerQueryForm<'r> {
+        pub id: Option<&'r str>,
+        /// Defaults to the authenticated account
+        pub account: Option<&'r str>,
+        pub memo: Option<&'r str>,
+        pub memo_type: Option<&'r str>,
+        #[field(name = "type")]
+        pub customer_type: Option<&'r str>,
+    }
+
+    /// SEP-0012 `PUT /customer`, as a multipart or URL encoded form. Documents need
+    /// a multipart form.
+    #[derive(FromForm)]
+    pub struct CustomerForm<'r> {
+        pub id: Option<&'r str>,
+        /// Defaults to the authenticated account
+        pub account: Option<&'r str>,
+        pub memo: Option<&'r str>,
+        pub memo_type: Option<&'r str>,
+        #[field(name = "type")]
+        pub customer_type: Option<&'r str>,
+        pub first_name: Option<&'r str>,
+        pub last_name: Option<&'r str>,
+        pub email_address: Option<&'r str>,
+        pub mobile_number: Option<&'r str>,
+        pub birth_date: Option<&'r str>,
+        pub address: Option<&'r str>,
+        pub city: Option<&'r str>,
+        pub postal_code: Option<&'r str>,
+        pub country_code: Option<&'r str>,
+        pub id_type: Option<&'r str>,
+        pub id_number: Option<&'r str>,
+        pub bank_account_number: Option<&'r str>,
+        pub bank_number: Option<&'r str>,
+        pub photo_id_front: Option<TempFile<'r>>,
+        pub photo_id_back: Option<TempFile<'r>>,
+        pub proof_of_address: Option<TempFile<'r>>,
+    }
+
+    /// SEP-0012 `DELETE /customer/:account`
+    #[derive(FromForm)]
+    pub struct DeleteCustomerForm<'r> {
+        pub memo: Option<&'r str>,
+        pub memo_type: Option<&'r str>,
+    }
+
+    /// An operator's decision on a customer
+    #[derive(FromForm)]
+    pub struct ReviewForm<'r> {
+        /// `accepted` or `rejected`
+        pub status: &'r str,
+        /// Why, shown to the client
+        pub message: Option<&'r str>,
+    }
+}
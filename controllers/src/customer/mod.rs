@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use crate::customer::form::form::{
+    CustomerForm, CustomerQueryForm, DeleteCustomerForm, ReviewForm,
+};
+use rocket::form::Form;
+use rocket::fs::TempFile;
+use rocket::tokio::io::AsyncReadExt;
+use services::customer::customer::{
+    delete_customer, get_customer, get_customer_document, get_customer_review, get_customers,
+    put_customer, review_customer, CustomerQuery, CustomerReview, CustomerSummary, CustomerUpdate,
+    CustomerView, UploadedDocument,
+};
+use uuid::Uuid;
+
+pub mod form;
+
+// Get the status of a customer of the authenticated account
+pub async fn get_customer_controller(
+    account: &str,
+    query: CustomerQueryForm<'_>,
+) -> Result<CustomerView, Box<dyn std::error::Error>> {
+    check_memo_type(query.memo_type)?;
+
+    let query = CustomerQuery {
+        account: query.account.unwrap_or(account).to_string(),
+        id: query.id.map(str::to_string),
+        memo: query.memo.map(str::to_string),
+        customer_type: query.customer_type.map(str::to_string),
+    };
+
+    Ok(get_customer(query).await?)
+}
+
+// Put the fields and documents of a customer of the authenticated account
+pub async fn put_customer_controller(
+    account: &str,
+    data: Form<CustomerForm<'_>>,
+) -> Result<Uuid, Box<dyn std::error::Error>> {
+    check_memo_type(data.memo_type)?;
+
+    let fields = [
+        ("first_name", data.first_name),
+        ("last_name", data.last_name),
+        ("email_address", data.email_address),
+        ("mobile_number", data.mobile_number),
+        ("birth_date", data.birth_date),
+        ("address", data.address),
+        ("city", data.city),
+        ("postal_code", data.postal_code),
+        ("country_code", data.country_code),
+        ("id_type", data.id_type),
+        ("id_number", data.id_number),
+        ("bank_account_number", data.bank_account_number),
+        ("bank_number", data.bank_number),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| (name.to_string(), value.to_string())))
+    .collect::<BTreeMap<_, _>>();
+
+    let mut documents = Vec::new();
+    for (name, file) in [
+        ("photo_id_front", &data.photo_id_front),
+        ("photo_id_back", &data.photo_id_back),
+        ("proof_of_address", &data.proof_of_address),
+    ] {
+        if let Some(file) = file {
+            documents.push(read_document(name, file).await?);
+        }
+    }
+
+    let update = CustomerUpdate {
+        customer: CustomerQuery {
+            account: data.account.unwrap_or(account).to_string(),
+            id: data.id.map(str::to_string),
+            memo: data.memo.map(str::to_string),
+            customer_type: data.customer_type.map(str::to_string),
+        },
+        fields,
+        documents,
+    };
+
+    Ok(put_customer(update).await?)
+}
+
+// Delete a customer of the authenticated account
+pub async fn delete_customer_controller(
+    account: &str,
+    data: Option<Form<DeleteCustomerForm<'_>>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let memo = match &data {
+        Some(data) => {
+            check_memo_type(data.memo_type)?;
+            data.memo
+        }
+        None => None,
+    };
+
+    Ok(delete_customer(account, memo).await?)
+}
+
+// List customers, e.g. those waiting for review
+pub async fn get_customers_controller(
+    status: Option<&str>,
+) -> Result<Vec<CustomerSummary>, Box<dyn std::error::Error>> {
+    Ok(get_customers(status).await?)
+}
+
+// Get a customer with its fields, for review
+pub async fn get_customer_review_controller(
+    customer_id: &str,
+) -> Result<CustomerReview, Box<dyn std::error::Error>> {
+    Ok(get_customer_review(customer_id).await?)
+}
+
+// Get a document of a customer, for review
+pub async fn get_customer_document_controller(
+    customer_id: &str,
+    field: &str,
+) -> Result<(String, Vec<u8>), Box<dyn std::error::Error>> {
+    Ok(get_customer_document(customer_id, field).await?)
+}
+
+// Accept or reject a customer
+pub async fn review_customer_controller(
+    customer_id: &str,
+    data: Form<ReviewForm<'_>>,
+) -> Result<CustomerSummary, Box<dyn std::error::Error>> {
+    Ok(review_customer(customer_id, data.status, data.message).await?)
+}
+
+// Customers are told apart by id, text or hash memos
+fn check_memo_type(memo_type: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    match memo_type {
+        None | Some("id") | Some("text") | Some("hash") => Ok(()),
+        Some(memo_type) => Err(format!("Unknown memo type {}", memo_type).into()),
+    }
+}
+
+async fn read_document(
+    field: &str,
+    file: &TempFile<'_>,
+) -> Result<UploadedDocument, Box<dyn std::error::Error>> {
+    let content_type = file
+        .content_type()
+        .map(|content_type| format!("{}/{}", content_type.top(), content_type.sub()))
+        .unwrap_or_default();
+
+    let mut content = Vec::new();
+    file.open().await?.read_to_end(&mut content).await?;
+
+    Ok(UploadedDocument {
+        field: field.to_string(),
+        content_type,
+        content,
+    })
+}
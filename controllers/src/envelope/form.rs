@@ -0,0 +1,10 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    #[derive(FromForm)]
+    pub struct SubmitSignedEnvelopeForm<'r> {
+        pub envelope_xdr: &'r str,
+    }
+}
@@ -0,0 +1,13 @@
+use crate::envelope::form::form::SubmitSignedEnvelopeForm;
+use models::models::PendingTransaction;
+use rocket::form::Form;
+use services::envelope::envelope::submit_signed_envelope;
+
+pub mod form;
+
+// Submit an externally signed envelope
+pub async fn submit_signed_envelope_controller(
+    data: Form<SubmitSignedEnvelopeForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(submit_signed_envelope(data.envelope_xdr).await?)
+}
@@ -0,0 +1,26 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    #[derive(FromForm)]
+    pub struct CreateEscrowForm<'r> {
+        pub sender_account_id: &'r str,
+        pub recipient_public_key: &'r str,
+        pub asset_code: &'r str,
+        pub asset_issuer: Option<&'r str>,
+        pub amount: u64,
+        /// RFC 3339 timestamp after which the sender can reclaim the funds
+        pub deadline: Option<&'r str>,
+    }
+
+    #[derive(FromForm)]
+    pub struct GetEscrowsForm<'r> {
+        pub account_id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct EscrowForm<'r> {
+        pub escrow_id: &'r str,
+    }
+}
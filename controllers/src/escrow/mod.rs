@@ -0,0 +1,46 @@
+use crate::escrow::form::form::{CreateEscrowForm, EscrowForm, GetEscrowsForm};
+use crate::schedule::parse_timestamp;
+use models::models::Escrow;
+use rocket::form::Form;
+use services::escrow::escrow::{
+    claim_escrow, create_escrow, get_escrows, reclaim_escrow, EscrowRequest,
+};
+
+pub mod form;
+
+// Create escrow
+pub async fn create_escrow_controller(
+    data: Form<CreateEscrowForm<'_>>,
+) -> Result<Escrow, Box<dyn std::error::Error>> {
+    let request = EscrowRequest {
+        sender_account_id: data.sender_account_id.to_string(),
+        recipient_public_key: data.recipient_public_key.to_string(),
+        asset_code: data.asset_code.to_string(),
+        asset_issuer: data.asset_issuer.map(str::to_string),
+        amount: data.amount,
+        deadline: parse_timestamp(data.deadline)?,
+    };
+
+    Ok(create_escrow(request).await?)
+}
+
+// Get escrows of an account
+pub async fn get_escrows_controller(
+    data: Form<GetEscrowsForm<'_>>,
+) -> Result<Vec<Escrow>, Box<dyn std::error::Error>> {
+    Ok(get_escrows(data.account_id).await?)
+}
+
+// Release escrow to its recipient
+pub async fn claim_escrow_controller(
+    data: Form<EscrowForm<'_>>,
+) -> Result<Escrow, Box<dyn std::error::Error>> {
+    Ok(claim_escrow(data.escrow_id).await?)
+}
+
+// Return expired escrow to its sender
+pub async fn reclaim_escrow_controller(
+    data: Form<EscrowForm<'_>>,
+) -> Result<Escrow, Box<dyn std::error::Error>> {
+    Ok(reclaim_escrow(data.escrow_id).await?)
+}
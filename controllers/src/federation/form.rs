@@ -0,0 +1,23 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    /// A SEP-0002 lookup: `q` is a federation address for `type=name`, or a public key
+    /// for `type=id`
+    #[derive(FromForm)]
+    pub struct FederationQuery<'r> {
+        pub q: &'r str,
+        #[field(name = "type")]
+        pub query_type: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct RegisterHandleForm<'r> {
+        pub account_id: &'r str,
+        pub handle: &'r str,
+        /// `text`, `id` or `hash` when payments to the handle must carry a memo
+        pub memo_type: Option<&'r str>,
+        pub memo: Option<&'r str>,
+    }
+}
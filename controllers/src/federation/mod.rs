@@ -0,0 +1,34 @@
+use crate::federation::form::form::{FederationQuery, RegisterHandleForm};
+use helpers::federation::FederationRecord;
+use models::models::FederationHandle;
+use rocket::form::Form;
+use services::federation::federation::{
+    register_handle, resolve_account_id, resolve_name, HandleRegistration,
+};
+
+pub mod form;
+
+// Look up a federation name or the name of an account
+pub async fn federation_lookup_controller(
+    query: FederationQuery<'_>,
+) -> Result<Option<FederationRecord>, Box<dyn std::error::Error>> {
+    match query.query_type {
+        "name" => Ok(resolve_name(query.q).await?),
+        "id" => Ok(resolve_account_id(query.q).await?),
+        query_type => Err(format!("Unsupported lookup type {}", query_type).into()),
+    }
+}
+
+// Give an account a federation name
+pub async fn register_handle_controller(
+    data: Form<RegisterHandleForm<'_>>,
+) -> Result<FederationHandle, Box<dyn std::error::Error>> {
+    let registration = HandleRegistration {
+        account_id: data.account_id.to_string(),
+        handle: data.handle.to_string(),
+        memo_type: data.memo_type.map(str::to_string),
+        memo: data.memo.map(str::to_string),
+    };
+
+    Ok(register_handle(registration).await?)
+}
@@ -1,3 +1,17 @@
 pub mod account;
+pub mod admin;
+pub mod anchor;
+pub mod asset;
+pub mod auth;
 pub mod api;
+pub mod customer;
+pub mod envelope;
+pub mod escrow;
+pub mod federation;
+pub mod multisig;
 pub mod payment;
+pub mod payment_request;
+pub mod remittance;
+pub mod schedule;
+pub mod stellar_toml;
+pub mod web_auth;
@@ -0,0 +1,46 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    #[derive(FromForm)]
+    pub struct PolicySignerForm<'r> {
+        pub public_key: &'r str,
+        pub weight: u32,
+    }
+
+    #[derive(FromForm)]
+    pub struct ApplySignerPolicyForm<'r> {
+        pub account_id: &'r str,
+        pub master_weight: u32,
+        pub low_threshold: u32,
+        pub medium_threshold: u32,
+        pub high_threshold: u32,
+        pub home_domain: Option<&'r str>,
+        pub signers: Vec<PolicySignerForm<'r>>,
+    }
+
+    #[derive(FromForm)]
+    pub struct GetPendingTransactionForm<'r> {
+        pub pending_transaction_id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct SignPendingTransactionForm<'r> {
+        pub pending_transaction_id: &'r str,
+        pub account_id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct AddPendingSignatureForm<'r> {
+        pub pending_transaction_id: &'r str,
+        pub public_key: &'r str,
+        pub signature: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct AddPendingEnvelopeForm<'r> {
+        pub pending_transaction_id: &'r str,
+        pub envelope_xdr: &'r str,
+    }
+}
@@ -0,0 +1,69 @@
+use crate::admin::admin::Admin;
+use crate::multisig::form::form::{
+    AddPendingEnvelopeForm, AddPendingSignatureForm, ApplySignerPolicyForm,
+    GetPendingTransactionForm, SignPendingTransactionForm,
+};
+use models::models::PendingTransaction;
+use rocket::form::Form;
+use services::multisig::multisig::{
+    add_pending_envelope, add_pending_signature, apply_signer_policy, get_pending_transaction,
+    sign_pending_transaction, Caller, PolicySigner, SignerPolicy,
+};
+
+pub mod form;
+
+// Apply signer policy, as an admin
+pub async fn apply_signer_policy_controller(
+    _admin: Admin,
+    data: Form<ApplySignerPolicyForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    let policy = SignerPolicy {
+        master_weight: data.master_weight,
+        low_threshold: data.low_threshold,
+        medium_threshold: data.medium_threshold,
+        high_threshold: data.high_threshold,
+        home_domain: data.home_domain.map(str::to_string),
+        signers: data
+            .signers
+            .iter()
+            .map(|signer| PolicySigner {
+                public_key: signer.public_key.to_string(),
+                weight: signer.weight,
+            })
+            .collect(),
+    };
+
+    Ok(apply_signer_policy(Caller::Admin, data.account_id, policy).await?)
+}
+
+// Get pending transaction
+pub async fn get_pending_transaction_controller(
+    data: Form<GetPendingTransactionForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(get_pending_transaction(data.pending_transaction_id).await?)
+}
+
+// Sign pending transaction with a key the service holds, as an admin
+pub async fn sign_pending_transaction_controller(
+    _admin: Admin,
+    data: Form<SignPendingTransactionForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(
+        sign_pending_transaction(Caller::Admin, data.pending_transaction_id, data.account_id)
+            .await?,
+    )
+}
+
+// Add a co-signer's signature
+pub async fn add_pending_signature_controller(
+    data: Form<AddPendingSignatureForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(add_pending_signature(data.pending_transaction_id, data.public_key, data.signature).await?)
+}
+
+// Add the signatures of a co-signed envelope
+pub async fn add_pending_envelope_controller(
+    data: Form<AddPendingEnvelopeForm<'_>>,
+) -> Result<PendingTransaction, Box<dyn std::error::Error>> {
+    Ok(add_pending_envelope(data.pending_transaction_id, data.envelope_xdr).await?)
+}
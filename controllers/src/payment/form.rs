@@ -1,3 +1,5 @@
+#![allow(clippy::module_inception)]
+
 pub mod form {
     use rocket::form::FromForm;
 
@@ -13,6 +15,8 @@ pub mod form {
         pub sender_account_id: &'r str,
         pub receiver_public_key: &'r str,
         pub amount: u64,
+        /// Hold the payment in a claimable balance if the receiver has no account yet
+        pub claimable_fallback: Option<bool>,
     }
 
     #[derive(FromForm)]
@@ -22,5 +26,22 @@ pub mod form {
         pub asset_code: &'r str,
         pub asset_issuer: &'r str,
         pub amount: u64,
+        /// Hold the payment in a claimable balance if the receiver has no account or
+        /// no trustline yet
+        pub claimable_fallback: Option<bool>,
+    }
+
+    #[derive(FromForm)]
+    pub struct BatchPaymentItemForm<'r> {
+        pub receiver_public_key: &'r str,
+        pub asset_code: &'r str,
+        pub asset_issuer: Option<&'r str>,
+        pub amount: u64,
+    }
+
+    #[derive(FromForm)]
+    pub struct SendBatchPaymentForm<'r> {
+        pub sender_account_id: &'r str,
+        pub items: Vec<BatchPaymentItemForm<'r>>,
     }
 }
@@ -1,9 +1,11 @@
 use crate::payment::form::form::{
-    EstablishTrustlineForm, SendNativePaymentForm, SendNonNativePaymentForm,
+    EstablishTrustlineForm, SendBatchPaymentForm, SendNativePaymentForm, SendNonNativePaymentForm,
 };
 use rocket::form::Form;
 use services::payment::payment::{
-    establish_trustline_for_non_native_asset, send_native_payment, send_non_native_payment,
+    establish_trustline_for_non_native_asset, send_batch_payment, send_native_payment,
+    send_non_native_payment, BatchPaymentItem, BatchValidationError, PaymentBatchResult,
+    PaymentResult,
 };
 
 pub mod form;
@@ -23,11 +25,12 @@ pub async fn establish_trustline_for_non_native_asset_controller<'r>(
 /// Send a native payment (XLM).
 pub async fn send_native_payment_controller<'r>(
     form: Form<SendNativePaymentForm<'r>>,
-) -> Result<bool, Box<dyn std::error::Error>> {
+) -> Result<PaymentResult, Box<dyn std::error::Error>> {
     Ok(send_native_payment(
         form.sender_account_id.to_string(),
         form.receiver_public_key,
         form.amount,
+        form.claimable_fallback.unwrap_or(false),
     )
     .await?)
 }
@@ -35,13 +38,38 @@ pub async fn send_native_payment_controller<'r>(
 /// Send a non-native payment.
 pub async fn send_non_native_payment_controller<'r>(
     form: Form<SendNonNativePaymentForm<'r>>,
-) -> Result<bool, Box<dyn std::error::Error>> {
+) -> Result<PaymentResult, Box<dyn std::error::Error>> {
     Ok(send_non_native_payment(
         form.sender_account_id.to_string(),
         form.receiver_public_key,
         form.asset_code,
         form.asset_issuer,
         form.amount,
+        form.claimable_fallback.unwrap_or(false),
     )
     .await?)
 }
+
+/// Send a batch of payments.
+pub async fn send_batch_payment_controller<'r>(
+    form: Form<SendBatchPaymentForm<'r>>,
+) -> Result<PaymentBatchResult, Box<dyn std::error::Error>> {
+    let items = form
+        .items
+        .iter()
+        .map(|item| BatchPaymentItem {
+            receiver_public_key: item.receiver_public_key.to_string(),
+            asset_code: item.asset_code.to_string(),
+            asset_issuer: item.asset_issuer.map(str::to_string),
+            amount: item.amount,
+        })
+        .collect();
+
+    // Validation errors are passed on as themselves, so the route can report each item
+    send_batch_payment(form.sender_account_id.to_string(), items)
+        .await
+        .map_err(|error| match error.downcast::<BatchValidationError>() {
+            Ok(validation_error) => validation_error.into(),
+            Err(error) => error.into(),
+        })
+}
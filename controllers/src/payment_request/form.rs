@@ -0,0 +1,27 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    /// A payment a merchant requests from a customer's wallet
+    #[derive(FromForm)]
+    pub struct PaymentRequestForm<'r> {
+        /// The merchant's account, which is paid
+        pub account_id: &'r str,
+        /// `pay` lets the wallet build the payment, `tx` hands it one built for `payer`
+        pub kind: &'r str,
+        pub asset_code: &'r str,
+        pub asset_issuer: Option<&'r str>,
+        /// In units of the asset, required for `tx`
+        pub amount: Option<&'r str>,
+        pub payer: Option<&'r str>,
+        /// Shown to the customer by the wallet
+        pub message: Option<&'r str>,
+    }
+
+    /// The signed transaction a wallet posts to the callback of a SEP-0007 URI
+    #[derive(FromForm)]
+    pub struct PaymentRequestCallbackForm<'r> {
+        pub xdr: &'r str,
+    }
+}
@@ -0,0 +1,50 @@
+use crate::payment_request::form::form::{PaymentRequestCallbackForm, PaymentRequestForm};
+use bigdecimal::BigDecimal;
+use rocket::form::Form;
+use services::payment_request::payment_request::{
+    create_payment_request, get_payment_request, get_payment_request_qr_code,
+    submit_payment_request, NewPaymentRequestInput, PaymentRequestView,
+};
+use std::str::FromStr;
+
+pub mod form;
+
+// Create a payment request and its signed URI
+pub async fn create_payment_request_controller(
+    data: Form<PaymentRequestForm<'_>>,
+) -> Result<PaymentRequestView, Box<dyn std::error::Error>> {
+    let input = NewPaymentRequestInput {
+        account_id: data.account_id.to_string(),
+        kind: data.kind.to_string(),
+        asset_code: data.asset_code.to_string(),
+        asset_issuer: data.asset_issuer.map(str::to_string),
+        amount: data.amount.map(BigDecimal::from_str).transpose()?,
+        payer: data.payer.map(str::to_string),
+        message: data.message.map(str::to_string),
+    };
+
+    Ok(create_payment_request(input).await?)
+}
+
+// Get a payment request
+pub async fn get_payment_request_controller(
+    request_id: &str,
+) -> Result<PaymentRequestView, Box<dyn std::error::Error>> {
+    Ok(get_payment_request(request_id).await?)
+}
+
+// Get the QR code of a payment request, as PNG unless SVG is asked for
+pub async fn get_payment_request_qr_code_controller(
+    request_id: &str,
+    format: Option<&str>,
+) -> Result<(String, Vec<u8>), Box<dyn std::error::Error>> {
+    Ok(get_payment_request_qr_code(request_id, format.unwrap_or("png")).await?)
+}
+
+// Submit the transaction a wallet signed for a payment request
+pub async fn submit_payment_request_controller(
+    request_id: &str,
+    data: Form<PaymentRequestCallbackForm<'_>>,
+) -> Result<PaymentRequestView, Box<dyn std::error::Error>> {
+    Ok(submit_payment_request(request_id, data.xdr).await?)
+}
@@ -0,0 +1,20 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use serde::Deserialize;
+
+    /// A SEP-0031 payment a sending anchor wants to make
+    #[derive(Deserialize)]
+    pub struct ReceiveForm {
+        /// The amount in units, as a string
+        pub amount: String,
+        pub asset_code: String,
+        pub sender_id: Option<String>,
+        pub receiver_id: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct CallbackForm {
+        pub url: String,
+    }
+}
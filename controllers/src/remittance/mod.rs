@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+use crate::remittance::form::form::{CallbackForm, ReceiveForm};
+use bigdecimal::BigDecimal;
+use rocket::serde::json::Value;
+use services::anchor::anchor::AnchorTransactionView;
+use services::remittance::remittance::{
+    get_info, get_receive_transaction, set_callback, start_receive, CustomerInfoNeeded,
+    ReceiveInstructions, ReceiveRequest,
+};
+
+pub mod form;
+
+// List the assets we receive from sending anchors
+pub async fn remittance_info_controller() -> Result<Value, Box<dyn std::error::Error>> {
+    Ok(get_info().await?)
+}
+
+// Create a payment for the authenticated sending anchor to make
+pub async fn create_receive_controller(
+    account: &str,
+    data: &ReceiveForm,
+) -> Result<ReceiveInstructions, Box<dyn std::error::Error>> {
+    let request = ReceiveRequest {
+        account: account.to_string(),
+        asset_code: data.asset_code.clone(),
+        amount: BigDecimal::from_str(&data.amount)?,
+        sender_id: data.sender_id.clone(),
+        receiver_id: data.receiver_id.clone(),
+    };
+
+    // A missing customer is passed on as itself, so the route can name its type
+    start_receive(request)
+        .await
+        .map_err(|error| match error.downcast::<CustomerInfoNeeded>() {
+            Ok(customer_info_needed) => customer_info_needed.into(),
+            Err(error) => error.into(),
+        })
+}
+
+// Get a payment the authenticated sending anchor created
+pub async fn receive_transaction_controller(
+    account: &str,
+    transaction_id: &str,
+) -> Result<AnchorTransactionView, Box<dyn std::error::Error>> {
+    Ok(get_receive_transaction(account, transaction_id).await?)
+}
+
+// Register where the status of a payment is posted
+pub async fn set_callback_controller(
+    account: &str,
+    transaction_id: &str,
+    data: &CallbackForm,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(set_callback(account, transaction_id, &data.url).await?)
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::module_inception)]
+
+pub mod form {
+    use rocket::form::FromForm;
+
+    #[derive(FromForm)]
+    pub struct CreatePaymentScheduleForm<'r> {
+        pub sender_account_id: &'r str,
+        pub receiver_public_key: &'r str,
+        pub asset_code: &'r str,
+        pub asset_issuer: Option<&'r str>,
+        pub amount: u64,
+        /// RFC 3339 timestamp of the first payment
+        pub start_at: Option<&'r str>,
+        /// Cron expression in UTC, e.g. `0 9 * * 1`
+        pub recurrence: Option<&'r str>,
+        /// RFC 3339 timestamp after which no payment is made
+        pub end_at: Option<&'r str>,
+        pub max_runs: Option<u32>,
+    }
+
+    #[derive(FromForm)]
+    pub struct GetPaymentSchedulesForm<'r> {
+        pub account_id: &'r str,
+    }
+
+    #[derive(FromForm)]
+    pub struct PaymentScheduleForm<'r> {
+        pub schedule_id: &'r str,
+    }
+}
@@ -0,0 +1,76 @@
+use crate::schedule::form::form::{
+    CreatePaymentScheduleForm, GetPaymentSchedulesForm, PaymentScheduleForm,
+};
+use chrono::{DateTime, NaiveDateTime};
+use models::models::{PaymentSchedule, PaymentScheduleRun};
+use rocket::form::Form;
+use services::scheduler::scheduler::{
+    cancel_payment_schedule, create_payment_schedule, get_payment_schedule_runs,
+    get_payment_schedules, pause_payment_schedule, resume_payment_schedule, PaymentScheduleRequest,
+};
+
+pub mod form;
+
+// Parse an optional RFC 3339 timestamp into UTC
+pub(crate) fn parse_timestamp(
+    timestamp: Option<&str>,
+) -> Result<Option<NaiveDateTime>, Box<dyn std::error::Error>> {
+    Ok(timestamp
+        .map(DateTime::parse_from_rfc3339)
+        .transpose()?
+        .map(|timestamp| timestamp.naive_utc()))
+}
+
+// Create payment schedule
+pub async fn create_payment_schedule_controller(
+    data: Form<CreatePaymentScheduleForm<'_>>,
+) -> Result<PaymentSchedule, Box<dyn std::error::Error>> {
+    let request = PaymentScheduleRequest {
+        sender_account_id: data.sender_account_id.to_string(),
+        receiver_public_key: data.receiver_public_key.to_string(),
+        asset_code: data.asset_code.to_string(),
+        asset_issuer: data.asset_issuer.map(str::to_string),
+        amount: data.amount,
+        start_at: parse_timestamp(data.start_at)?,
+        recurrence: data.recurrence.map(str::to_string),
+        end_at: parse_timestamp(data.end_at)?,
+        max_runs: data.max_runs,
+    };
+
+    Ok(create_payment_schedule(request).await?)
+}
+
+// Get payment schedules of an account
+pub async fn get_payment_schedules_controller(
+    data: Form<GetPaymentSchedulesForm<'_>>,
+) -> Result<Vec<PaymentSchedule>, Box<dyn std::error::Error>> {
+    Ok(get_payment_schedules(data.account_id).await?)
+}
+
+// Get runs of a payment schedule
+pub async fn get_payment_schedule_runs_controller(
+    data: Form<PaymentScheduleForm<'_>>,
+) -> Result<Vec<PaymentScheduleRun>, Box<dyn std::error::Error>> {
+    Ok(get_payment_schedule_runs(data.schedule_id).await?)
+}
+
+// Pause payment schedule
+pub async fn pause_payment_schedule_controller(
+    data: Form<PaymentScheduleForm<'_>>,
+) -> Result<PaymentSchedule, Box<dyn std::error::Error>> {
+    Ok(pause_payment_schedule(data.schedule_id).await?)
+}
+
+// Resume payment schedule
+pub async fn resume_payment_schedule_controller(
+    data: Form<PaymentScheduleForm<'_>>,
+) -> Result<PaymentSchedule, Box<dyn std::error::Error>> {
+    Ok(resume_payment_schedule(data.schedule_id).await?)
+}
+
+// Cancel payment schedule
+pub async fn cancel_payment_schedule_controller(
+    data: Form<PaymentScheduleForm<'_>>,
+) -> Result<PaymentSchedule, Box<dyn std::error::Error>> {
+    Ok(cancel_payment_schedule(data.schedule_id).await?)
+}
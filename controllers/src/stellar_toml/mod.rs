@@ -0,0 +1,6 @@
+use services::stellar_toml::stellar_toml::get_stellar_toml;
+
+// Get stellar.toml
+pub async fn get_stellar_toml_controller() -> Result<String, Box<dyn std::error::Error>> {
+    Ok(get_stellar_toml().await?)
+}
@@ -0,0 +1,38 @@
+#![allow(clippy::module_inception)]
+
+pub mod web_auth {
+    use rocket::http::Status;
+    use rocket::request::{FromRequest, Outcome, Request};
+    use services::web_auth::web_auth::verify_token;
+
+    /// Request guard for endpoints that need a SEP-0010 token.
+    ///
+    /// The request must carry a token from `POST /auth` in its `Authorization` header
+    /// as `Bearer <token>`. The guard holds the Stellar account the token was issued to.
+    pub struct WebAuth {
+        pub account: String,
+    }
+
+    #[rocket::async_trait]
+    impl<'r> FromRequest<'r> for WebAuth {
+        type Error = &'static str;
+
+        async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+            let token = match request
+                .headers()
+                .get_one("Authorization")
+                .and_then(|header| header.strip_prefix("Bearer "))
+            {
+                Some(token) => token.trim(),
+                None => return Outcome::Error((Status::Unauthorized, "Missing bearer token")),
+            };
+
+            match verify_token(token) {
+                Ok(claims) => Outcome::Success(WebAuth {
+                    account: claims.sub,
+                }),
+                Err(_) => Outcome::Error((Status::Unauthorized, "Invalid bearer token")),
+            }
+        }
+    }
+}
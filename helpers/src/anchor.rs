@@ -0,0 +1,436 @@
+//! Anchor transactions (SEP-0006, SEP-0024 and SEP-0031): the statuses a deposit,
+//! withdrawal or received payment moves through, and the fiat rail that moves money
+//! outside the Stellar network.
+//!
+//! A deposit waits for the user's fiat transfer, then pays the asset out on chain. A
+//! withdrawal waits for the user's payment of the asset, tagged with the memo we
+//! handed out, then pays fiat out through the rail. A received payment does the same
+//! for a payment a sending anchor makes on behalf of its customer.
+
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+
+use anyhow::Error;
+use bigdecimal::BigDecimal;
+use stellar_base::KeyPair;
+
+/// The status of an anchor transaction, as SEP-0006, SEP-0024 and SEP-0031 name them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorStatus {
+    /// The user has not finished the interactive flow yet
+    Incomplete,
+    /// Waiting for the user to send the fiat deposit or the withdrawal payment
+    PendingUserTransferStart,
+    /// The user's transfer was seen but has not settled yet
+    PendingUserTransferComplete,
+    /// Waiting for the fiat rail to settle our payout
+    PendingExternal,
+    /// Being processed by us
+    PendingAnchor,
+    /// Waiting for the payment to land on the Stellar network
+    PendingStellar,
+    /// Waiting for the user to add a trustline for the asset
+    PendingTrust,
+    /// Waiting for the user to take action, e.g. provide more information
+    PendingUser,
+    /// Waiting for the sending anchor's payment
+    PendingSender,
+    /// Being processed by us as the receiving anchor
+    PendingReceiver,
+    /// Waiting for the sending anchor to correct the customer information
+    PendingCustomerInfoUpdate,
+    Completed,
+    Refunded,
+    /// The user never sent funds in time
+    Expired,
+    /// Failed and needs an operator to look at it
+    Error,
+}
+
+impl AnchorStatus {
+    /// Every status, in the order the SEPs list them
+    pub const ALL: [AnchorStatus; 15] = [
+        AnchorStatus::Incomplete,
+        AnchorStatus::PendingUserTransferStart,
+        AnchorStatus::PendingUserTransferComplete,
+        AnchorStatus::PendingExternal,
+        AnchorStatus::PendingAnchor,
+        AnchorStatus::PendingStellar,
+        AnchorStatus::PendingTrust,
+        AnchorStatus::PendingUser,
+        AnchorStatus::PendingSender,
+        AnchorStatus::PendingReceiver,
+        AnchorStatus::PendingCustomerInfoUpdate,
+        AnchorStatus::Completed,
+        AnchorStatus::Refunded,
+        AnchorStatus::Expired,
+        AnchorStatus::Error,
+    ];
+
+    /// Returns the name used in SEP responses and stored with the transaction
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnchorStatus::Incomplete => "incomplete",
+            AnchorStatus::PendingUserTransferStart => "pending_user_transfer_start",
+            AnchorStatus::PendingUserTransferComplete => "pending_user_transfer_complete",
+            AnchorStatus::PendingExternal => "pending_external",
+            AnchorStatus::PendingAnchor => "pending_anchor",
+            AnchorStatus::PendingStellar => "pending_stellar",
+            AnchorStatus::PendingTrust => "pending_trust",
+            AnchorStatus::PendingUser => "pending_user",
+            AnchorStatus::PendingSender => "pending_sender",
+            AnchorStatus::PendingReceiver => "pending_receiver",
+            AnchorStatus::PendingCustomerInfoUpdate => "pending_customer_info_update",
+            AnchorStatus::Completed => "completed",
+            AnchorStatus::Refunded => "refunded",
+            AnchorStatus::Expired => "expired",
+            AnchorStatus::Error => "error",
+        }
+    }
+
+    /// Returns whether the transaction is over and will not change again
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            AnchorStatus::Completed
+                | AnchorStatus::Refunded
+                | AnchorStatus::Expired
+                | AnchorStatus::Error
+        )
+    }
+
+    /// Returns whether a transaction may move from this status to another
+    ///
+    /// Any unfinished transaction can fail. Only transactions still waiting for the
+    /// user can expire, as nothing was received for them yet.
+    pub fn can_transition_to(&self, next: AnchorStatus) -> bool {
+        use AnchorStatus::*;
+
+        if self.is_final() {
+            return false;
+        }
+
+        match next {
+            Error => true,
+            Expired => matches!(self, Incomplete | PendingUserTransferStart | PendingSender),
+            PendingUserTransferStart => matches!(self, Incomplete),
+            PendingUserTransferComplete => matches!(self, PendingUserTransferStart),
+            PendingAnchor => matches!(
+                self,
+                PendingUserTransferStart | PendingUserTransferComplete | PendingUser
+            ),
+            PendingUser => matches!(self, PendingAnchor),
+            PendingTrust => matches!(self, PendingAnchor),
+            PendingExternal | Refunded => matches!(self, PendingAnchor | PendingReceiver),
+            PendingReceiver => matches!(self, PendingSender | PendingCustomerInfoUpdate),
+            PendingCustomerInfoUpdate => matches!(self, PendingReceiver),
+            PendingStellar => matches!(self, PendingAnchor | PendingTrust),
+            Completed => matches!(self, PendingAnchor | PendingStellar | PendingExternal),
+            Incomplete | PendingSender => false,
+        }
+    }
+
+    /// Moves to another status
+    ///
+    /// # Errors
+    /// Fails if the move is not allowed from this status
+    pub fn transition(&self, next: AnchorStatus) -> Result<AnchorStatus, Error> {
+        if !self.can_transition_to(next) {
+            return Err(anyhow::anyhow!(
+                "Anchor transaction cannot move from {} to {}",
+                self,
+                next
+            ));
+        }
+
+        Ok(next)
+    }
+}
+
+impl fmt::Display for AnchorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AnchorStatus {
+    type Err = Error;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        AnchorStatus::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == status)
+            .ok_or_else(|| anyhow::anyhow!("Unknown anchor transaction status {}", status))
+    }
+}
+
+/// A fiat transfer the user makes to us for a deposit.
+pub struct FiatDeposit<'a> {
+    /// The reference the user quotes, so the transfer can be matched
+    pub reference: &'a str,
+    pub asset_code: &'a str,
+    pub amount: Option<&'a BigDecimal>,
+}
+
+/// A fiat payout we make to the user for a withdrawal.
+pub struct FiatPayout<'a> {
+    pub reference: &'a str,
+    pub asset_code: &'a str,
+    pub amount: &'a BigDecimal,
+    /// Where the user wants the fiat, e.g. a bank account number
+    pub destination: &'a str,
+}
+
+/// Moves fiat in and out for anchor transactions.
+///
+/// The rail is told about every deposit and withdrawal, and is polled for the
+/// transfers it has seen settle.
+pub trait FiatRail: Send + Sync {
+    /// Returns the instructions the user follows to send the fiat for a deposit
+    fn deposit_instructions(
+        &self,
+        deposit: &FiatDeposit<'_>,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    /// Returns the amount received for a deposit, once the user's transfer settled
+    fn received_deposit(
+        &self,
+        reference: &str,
+    ) -> impl Future<Output = Result<Option<BigDecimal>, Error>> + Send;
+
+    /// Starts a payout for a withdrawal and returns the rail's reference for it
+    fn send_payout(
+        &self,
+        payout: &FiatPayout<'_>,
+    ) -> impl Future<Output = Result<String, Error>> + Send;
+
+    /// Returns whether a payout started with [`FiatRail::send_payout`] has settled
+    fn payout_completed(
+        &self,
+        payout_reference: &str,
+    ) -> impl Future<Output = Result<bool, Error>> + Send;
+}
+
+/// A fiat rail run by hand until a bank integration exists.
+///
+/// Deposit instructions come from `ANCHOR_DEPOSIT_INSTRUCTIONS`. Operators confirm
+/// received deposits and settled payouts themselves, so this rail never reports
+/// either.
+#[derive(Default)]
+pub struct ManualFiatRail {
+    deposit_instructions: Option<String>,
+}
+
+impl ManualFiatRail {
+    /// Creates a manual rail with the deposit instructions configured in the environment
+    pub fn from_env() -> Self {
+        Self {
+            deposit_instructions: std::env::var("ANCHOR_DEPOSIT_INSTRUCTIONS")
+                .ok()
+                .filter(|instructions| !instructions.is_empty()),
+        }
+    }
+}
+
+impl FiatRail for ManualFiatRail {
+    async fn deposit_instructions(&self, deposit: &FiatDeposit<'_>) -> Result<String, Error> {
+        let instructions = self
+            .deposit_instructions
+            .as_deref()
+            .unwrap_or("Transfer the funds to our bank account");
+
+        Ok(format!(
+            "{}, quoting reference {}",
+            instructions, deposit.reference
+        ))
+    }
+
+    async fn received_deposit(&self, _reference: &str) -> Result<Option<BigDecimal>, Error> {
+        Ok(None)
+    }
+
+    async fn send_payout(&self, payout: &FiatPayout<'_>) -> Result<String, Error> {
+        Ok(format!("manual-{}", payout.reference))
+    }
+
+    async fn payout_completed(&self, _payout_reference: &str) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// Signs a status callback the way SEP-0031 asks
+///
+/// The payload is the timestamp, the host of the callback URL and the body, joined with
+/// dots, signed with our `SIGNING_KEY`.
+///
+/// # Arguments
+/// * `signing_key` - The key published as `SIGNING_KEY` in our `stellar.toml`
+/// * `timestamp` - The Unix time of the callback
+/// * `host` - The host of the callback URL
+/// * `body` - The JSON body of the callback
+///
+/// # Returns
+/// * `String` - The value of the `Signature` header, `t=<timestamp>, s=<base64 signature>`
+pub fn callback_signature(signing_key: &KeyPair, timestamp: i64, host: &str, body: &str) -> String {
+    let payload = format!("{}.{}.{}", timestamp, host, body);
+    let signature = signing_key.sign(payload.as_bytes());
+
+    format!(
+        "t={}, s={}",
+        timestamp,
+        openssl::base64::encode_block(signature.as_bytes())
+    )
+}
+
+/// Returns the host of a callback URL, which must use HTTPS
+///
+/// # Errors
+/// Fails if the URL is not a valid HTTPS URL with a host
+pub fn callback_host(url: &str) -> Result<String, Error> {
+    let url = reqwest::Url::parse(url).map_err(|_| anyhow::anyhow!("Callback URL is invalid"))?;
+    if url.scheme() != "https" {
+        return Err(anyhow::anyhow!("Callback URL must use https"));
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("Callback URL has no host"))?;
+
+    Ok(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names_round_trip() {
+        for status in AnchorStatus::ALL {
+            assert_eq!(status.as_str().parse::<AnchorStatus>().unwrap(), status);
+        }
+        assert!("pending".parse::<AnchorStatus>().is_err());
+    }
+
+    #[test]
+    fn test_deposit_and_withdrawal_paths() {
+        use AnchorStatus::*;
+
+        let deposit = [
+            Incomplete,
+            PendingUserTransferStart,
+            PendingAnchor,
+            PendingTrust,
+            PendingStellar,
+            Completed,
+        ];
+        let withdrawal = [
+            PendingUserTransferStart,
+            PendingAnchor,
+            PendingExternal,
+            Completed,
+        ];
+        let received = [
+            PendingSender,
+            PendingReceiver,
+            PendingCustomerInfoUpdate,
+            PendingReceiver,
+            PendingExternal,
+            Completed,
+        ];
+
+        for path in [&deposit[..], &withdrawal[..], &received[..]] {
+            for step in path.windows(2) {
+                assert_eq!(step[0].transition(step[1]).unwrap(), step[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_transitions() {
+        use AnchorStatus::*;
+
+        // Nothing changes once a transaction is over
+        for status in [Completed, Refunded, Expired, Error] {
+            assert!(!status.can_transition_to(PendingAnchor));
+            assert!(!status.can_transition_to(Error));
+        }
+
+        // Funds were received, so the transaction can no longer expire
+        assert!(PendingAnchor.transition(Expired).is_err());
+        assert!(PendingTrust.transition(Expired).is_err());
+        assert!(PendingUserTransferStart.transition(Completed).is_err());
+        assert!(PendingAnchor.transition(Incomplete).is_err());
+        assert!(PendingStellar.can_transition_to(Error));
+        assert!(PendingReceiver.transition(Expired).is_err());
+        assert!(PendingSender.transition(Completed).is_err());
+    }
+
+    #[test]
+    fn test_callback_signature() {
+        let signing_key = KeyPair::random().unwrap();
+        let body = r#"{"transaction":{"id":"abc"}}"#;
+
+        let header = callback_signature(&signing_key, 1_700_000_000, "partner.example", body);
+        let (timestamp, signature) = header
+            .strip_prefix("t=")
+            .and_then(|header| header.split_once(", s="))
+            .unwrap();
+        assert_eq!(timestamp, "1700000000");
+
+        let signature = stellar_base::signature::Signature::from_slice(
+            &openssl::base64::decode_block(signature).unwrap(),
+        )
+        .unwrap();
+        let payload = format!("1700000000.partner.example.{}", body);
+        assert!(signing_key.verify(payload.as_bytes(), &signature));
+        assert!(!signing_key.verify(b"1700000000.other.example.{}", &signature));
+    }
+
+    #[test]
+    fn test_callback_host() {
+        assert_eq!(
+            callback_host("https://partner.example/callbacks/sep31").unwrap(),
+            "partner.example"
+        );
+        assert_eq!(
+            callback_host("https://partner.example:8443/cb").unwrap(),
+            "partner.example:8443"
+        );
+        assert!(callback_host("http://partner.example/cb").is_err());
+        assert!(callback_host("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_manual_fiat_rail() {
+        let rail = ManualFiatRail {
+            deposit_instructions: Some("Pay IBAN DE00 1234".to_string()),
+        };
+        let amount = BigDecimal::from(10);
+
+        let instructions = rail
+            .deposit_instructions(&FiatDeposit {
+                reference: "abc",
+                asset_code: "GRP",
+                amount: Some(&amount),
+            })
+            .await
+            .unwrap();
+        assert_eq!(instructions, "Pay IBAN DE00 1234, quoting reference abc");
+
+        assert_eq!(rail.received_deposit("abc").await.unwrap(), None);
+        let payout_reference = rail
+            .send_payout(&FiatPayout {
+                reference: "abc",
+                asset_code: "GRP",
+                amount: &amount,
+                destination: "DE00 1234",
+            })
+            .await
+            .unwrap();
+        assert!(!rail.payout_completed(&payout_reference).await.unwrap());
+    }
+}
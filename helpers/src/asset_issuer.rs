@@ -1,26 +1,34 @@
-use std::str::FromStr;
-
 use anyhow::Error;
+use chrono::Duration;
 use reqwest::Response;
 use stellar_base::{
-    amount::Stroops, asset::CreditAsset, operations::{ChangeTrustOperationBuilder, PaymentOperationBuilder}, time_bounds::TimeBounds, transaction::TransactionBuilder, xdr::XDRSerialize, Asset, KeyPair, Network, Transaction
+    amount::Stroops, asset::CreditAsset, operations::{ChangeTrustOperationBuilder, PaymentOperationBuilder}, time_bounds::TimeBounds, transaction::TransactionBuilder, xdr::XDRSerialize, Asset, Network, PublicKey, Transaction
 };
-use stellar_sdk::{Keypair, Server};
+use stellar_sdk::Server;
+
+use crate::raw_transaction::{IssuerFlags, RawOperation, RawTransaction, TrustlineAuthorization};
+use crate::signer::Signer;
+use crate::stellar_chain::{set_home_domain_operation, DEFAULT_TRANSACTION_TIMEOUT_SECONDS};
+use crate::submitter::TransactionSubmitter;
 
 
 
 /// A struct that handles the creation and issuance of custom assets on the Stellar blockchain
 ///
 /// This struct manages the relationship between an asset issuer and receiver, including
-/// creating trustlines and issuing assets.
+/// creating trustlines and issuing assets. Transactions are signed through a [`Signer`],
+/// so the issuer and receiver keys never have to be held by the issuer itself.
 pub struct AssetIssuer {
     client: Server,
-    issuer_keypair: Option<Keypair>,
-    receiver_keypair: Option<Keypair>,
+    issuer_account_id: String,
+    receiver_account_id: String,
+    issuer_public_key: PublicKey,
+    receiver_public_key: PublicKey,
     asset: Option<CreditAsset>,
-    issuer_secret: String,
-    receiver_secret: String,
+    network: Network,
     server_url: String,
+    transaction_timeout: Duration,
+    trust_limit: Option<Stroops>,
 }
 
 impl AssetIssuer {
@@ -28,49 +36,71 @@ impl AssetIssuer {
     ///
     /// # Arguments
     /// * `server_url` - The URL of the Stellar server to connect to
-    /// * `issuer_secret` - The secret key of the asset issuer
-    /// * `receiver_secret` - The secret key of the asset receiver
+    /// * `network` - The Stellar network to sign transactions for
+    /// * `signer` - The signer holding the issuer and receiver keys
+    /// * `issuer_account_id` - The id of the asset issuer account
+    /// * `receiver_account_id` - The id of the asset receiver account
     /// * `asset_code` - The code/name of the asset to be created
-    pub fn new(
+    pub async fn new<S: Signer>(
         server_url: String,
-        issuer_secret: String,
-        receiver_secret: String,
+        network: Network,
+        signer: &S,
+        issuer_account_id: String,
+        receiver_account_id: String,
         asset_code: String,
-    ) -> Self {
+    ) -> Result<Self, Error> {
+        let issuer_public_key = signer.public_key(&issuer_account_id).await?;
+        let receiver_public_key = signer.public_key(&receiver_account_id).await?;
+
         let mut asset_issuer = Self {
             client: Server::new(server_url.clone(), None).unwrap(),
-            issuer_keypair: None,
-            receiver_keypair: None,
+            issuer_account_id,
+            receiver_account_id,
+            issuer_public_key,
+            receiver_public_key,
             asset: None,
-            issuer_secret: issuer_secret.clone(),
-            receiver_secret: receiver_secret.clone(),
-            server_url: server_url,
+            network,
+            server_url,
+            transaction_timeout: Duration::seconds(DEFAULT_TRANSACTION_TIMEOUT_SECONDS),
+            trust_limit: None,
         };
 
-        asset_issuer
-            .set_keypair(issuer_secret, receiver_secret)
-            .unwrap();
-        asset_issuer.define_asset(asset_code).unwrap();
-        asset_issuer
+        asset_issuer.define_asset(asset_code)?;
+        Ok(asset_issuer)
     }
 
-
-    /// Sets the keypairs for both the issuer and receiver
+    /// Sets how long the transactions it signs and submits stay valid
     ///
     /// # Arguments
-    /// * `issuer_secret` - The secret key of the asset issuer
-    /// * `receiver_secret` - The secret key of the asset receiver
+    /// * `transaction_timeout` - The time between building a transaction and its max time
+    pub fn with_transaction_timeout(mut self, transaction_timeout: Duration) -> Self {
+        self.transaction_timeout = transaction_timeout;
+        self
+    }
+
+    /// Sets the most the receiver trusts the issuer for. Without a limit the trustline
+    /// takes the largest amount the network allows.
     ///
-    /// # Returns
-    /// * `Result<(), Error>` - Ok if keypairs are set successfully, Error otherwise
-    fn set_keypair(&mut self, issuer_secret: String, receiver_secret: String) -> Result<(), Error> {
-        let issuer_keypair = Keypair::from_secret_key(&issuer_secret)?;
-        let receiver_keypair = Keypair::from_secret_key(&receiver_secret)?;
+    /// # Arguments
+    /// * `trust_limit` - The trust limit in stroops, or `None` for no limit
+    pub fn with_trust_limit(mut self, trust_limit: Option<Stroops>) -> Self {
+        self.trust_limit = trust_limit;
+        self
+    }
 
-        self.issuer_keypair = Some(issuer_keypair);
-        self.receiver_keypair = Some(receiver_keypair);
+    /// Returns the defined asset
+    pub fn asset(&self) -> Asset {
+        Asset::Credit(self.asset.as_ref().unwrap().clone())
+    }
 
-        Ok(())
+    /// Returns the public key of the issuer account
+    pub fn issuer_public_key(&self) -> &PublicKey {
+        &self.issuer_public_key
+    }
+
+    /// Returns the public key of the receiver account
+    pub fn receiver_public_key(&self) -> &PublicKey {
+        &self.receiver_public_key
     }
 
     /// Defines a new custom asset with the given code
@@ -81,46 +111,53 @@ impl AssetIssuer {
     /// # Returns
     /// * `Result<(), Error>` - Ok if asset is defined successfully, Error otherwise
     fn define_asset(&mut self, asset_code: String) -> Result<(), Error> {
-        let issuer_key = stellar_base::PublicKey::from_account_id(
-            self.issuer_keypair.as_ref().unwrap().public_key().as_str(),
-        )?;
-        let new_asset = CreditAsset::new(asset_code, issuer_key).unwrap();
+        let new_asset = CreditAsset::new(asset_code, self.issuer_public_key.clone())?;
         self.asset = Some(new_asset);
         Ok(())
     }
 
-    /// Creates a trustline between the issuer and receiver for the defined asset
+    /// Signs a transaction through the signer and submits it to Horizon
+    async fn sign_and_submit<S: Signer>(
+        &self,
+        mut transaction: Transaction,
+        signer: &S,
+        account_id: &str,
+    ) -> Result<Response, Error> {
+        let hash = transaction.hash(&self.network)?;
+        let signature = signer.sign_hash(account_id, &hash).await?;
+        transaction.signatures_mut().push(signature);
+
+        let base64_transaction = transaction.into_envelope().xdr_base64()?;
+
+        // Submit the transaction to Horizon over the shared client
+        TransactionSubmitter::new(self.server_url.clone()).post(&base64_transaction).await
+    }
+
+    /// Builds the unsigned transaction that creates a trustline between the issuer and
+    /// receiver for the defined asset, for signing outside the service
     ///
-    /// This operation allows the receiver to hold the custom asset by establishing
-    /// trust with the issuer.
+    /// # Arguments
+    /// * `time_bounds` - The period the transaction is valid for
     ///
     /// # Returns
-    /// * `Result<(), Error>` - Ok if trustline is created successfully, Error otherwise
-    pub async fn create_trustline(&self) -> Result<Response, Error> {
-
-        let receiver_account = stellar_base::PublicKey::from_account_id(
-            self.receiver_keypair
-                .as_ref()
-                .unwrap()
-                .public_key()
-                .as_str(),
-        )?;
-    
-        let time_bounds = TimeBounds::always_valid();
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_trustline_transaction(&self, time_bounds: TimeBounds) -> Result<Transaction, Error> {
+
+        let receiver_account = self.receiver_public_key.clone();
 
         let trust_operation_builder = ChangeTrustOperationBuilder::new();
 
         let trust_operation = trust_operation_builder
             .with_source_account(receiver_account.clone())
             .with_asset(Asset::Credit(self.asset.as_ref().unwrap().clone()))
-            .with_limit(Some(Stroops::new(10000000)))?
+            .with_limit(self.trust_limit)?
             .build()?;
 
         // Fetch the current account details to get the sequence number
         let receiver_account_details = self.client
-            .load_account(&self.receiver_keypair.as_ref().unwrap().public_key())?;
+            .load_account(&receiver_account.account_id())?;
 
-        let mut trust_transaction = Transaction::builder(
+        let trust_transaction = Transaction::builder(
                 receiver_account, 
                 receiver_account_details.sequence_number().parse::<i64>()? + 1, // Convert to i64
                 Stroops::new(100)
@@ -129,147 +166,245 @@ impl AssetIssuer {
             .with_time_bounds(time_bounds)
             .into_transaction()?;
 
-        let receiver_key = KeyPair::from_str(&self.receiver_secret)?;
-        trust_transaction.sign(&receiver_key, &Network::new_test())?;
+        Ok(trust_transaction)
+    }
 
-        let base64_transaction = trust_transaction.into_envelope().xdr_base64()?;
+    /// Builds the unsigned transaction that sets up the defined asset: the issuer flags
+    /// and home domain, the receiver's trustline and, when holders need authorization,
+    /// the authorization of that trustline. It is signed by both the receiver and the
+    /// issuer.
+    ///
+    /// # Arguments
+    /// * `time_bounds` - The period the transaction is valid for
+    /// * `issuer_flags` - The flags to set on the issuer account
+    /// * `home_domain` - The home domain to set on the issuer account, serving the
+    ///   `stellar.toml` that describes the asset
+    ///
+    /// # Returns
+    /// * `Result<RawTransaction, Error>` - The unsigned transaction or an error
+    pub fn build_register_asset_transaction(&self, time_bounds: TimeBounds, issuer_flags: IssuerFlags, home_domain: Option<&str>) -> Result<RawTransaction, Error> {
+        issuer_flags.validate()?;
+
+        let receiver_account = self.receiver_public_key.clone();
 
-        // Submit the transaction to Horizon testnet using reqwest
-        let client = reqwest::Client::new();
-        let response = client
-            .post(self.server_url.clone() + "/transactions")
-            .header("Accept", "application/json")
-            .form(&[("tx", base64_transaction)])
-            .send()
-            .await?;
+        let receiver_account_details = self.client
+            .load_account(&receiver_account.account_id())?;
 
-        Ok(response)
+        let mut operations = Vec::new();
+
+        if issuer_flags.bits() != 0 {
+            operations.push(RawOperation::SetAccountFlags {
+                source_account: self.issuer_public_key.clone(),
+                set_flags: issuer_flags.bits(),
+                clear_flags: 0,
+            });
+        }
+
+        if let Some(home_domain) = home_domain {
+            operations.push(RawOperation::Standard(set_home_domain_operation(&self.issuer_public_key, home_domain)?));
+        }
+
+        operations.push(RawOperation::Standard(ChangeTrustOperationBuilder::new()
+            .with_source_account(receiver_account.clone())
+            .with_asset(self.asset())
+            .with_limit(self.trust_limit)?
+            .build()?));
+
+        if issuer_flags.auth_required {
+            operations.push(RawOperation::SetTrustLineFlags {
+                source_account: self.issuer_public_key.clone(),
+                trustor: receiver_account.clone(),
+                asset: self.asset.as_ref().unwrap().clone(),
+                authorization: TrustlineAuthorization::Authorized,
+            });
+        }
+
+        RawTransaction::new(
+            receiver_account,
+            receiver_account_details.sequence_number().parse::<i64>()? + 1,
+            time_bounds,
+            operations,
+        )
     }
 
-    /// Issues the defined asset from the issuer to the receiver
+    /// Creates a trustline between the issuer and receiver for the defined asset
     ///
-    /// This operation transfers the custom asset from the issuer's account
-    /// to the receiver's account.
+    /// This operation allows the receiver to hold the custom asset by establishing
+    /// trust with the issuer.
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding the receiver key
     ///
     /// # Returns
-    /// * `Result<(), Error>` - Ok if asset is issued successfully, Error otherwise
-    pub async fn issue_asset(&self) -> Result<Response, Error> {
-        let issuer_account = stellar_base::PublicKey::from_account_id(
-            self.issuer_keypair.as_ref().unwrap().public_key().as_str(),
-        )?;
+    /// * `Result<(), Error>` - Ok if trustline is created successfully, Error otherwise
+    pub async fn create_trustline<S: Signer>(&self, signer: &S) -> Result<Response, Error> {
+        let trust_transaction = self.build_trustline_transaction(TimeBounds::valid_for(self.transaction_timeout))?;
 
-        let receiver_account = stellar_base::PublicKey::from_account_id(
-            self.receiver_keypair.as_ref().unwrap().public_key().as_str(),
-        )?;
+        self.sign_and_submit(trust_transaction, signer, &self.receiver_account_id).await
+    }
 
-        let issuer_account_details = self.client
-            .load_account(&self.issuer_keypair.as_ref().unwrap().public_key())?;
+    /// Builds the unsigned transaction that issues the defined asset from the issuer to
+    /// the receiver, so the issuer key can sign it offline
+    ///
+    /// # Arguments
+    /// * `time_bounds` - The period the transaction is valid for
+    /// * `amount` - The amount to issue, in stroops
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_issue_asset_transaction(&self, time_bounds: TimeBounds, amount: Stroops) -> Result<Transaction, Error> {
+        let issuer_account = self.issuer_public_key.clone();
 
-        let payment_transaction = TransactionBuilder::new(issuer_account,issuer_account_details.sequence_number().parse::<i64>()? + 1, Stroops::new(100));
+        let receiver_account = self.receiver_public_key.clone();
 
-        let source_account = stellar_base::PublicKey::from_account_id(
-            self.issuer_keypair.as_ref().unwrap().public_key().as_str(),
-        )?;
+        let issuer_account_details = self.client
+            .load_account(&issuer_account.account_id())?;
+
+        let payment_transaction = TransactionBuilder::new(issuer_account.clone(),issuer_account_details.sequence_number().parse::<i64>()? + 1, Stroops::new(100));
 
         let payment_operation_builder = PaymentOperationBuilder::new();
 
         let payment_operation = payment_operation_builder
-            .with_source_account(source_account)
+            .with_source_account(issuer_account)
             .with_asset(Asset::Credit(self.asset.as_ref().unwrap().clone()))
-            .with_amount(Stroops::new(10))?
+            .with_amount(amount)?
             .with_destination(receiver_account)
             .build()?;
 
-        let mut transaction = payment_transaction
+        let transaction = payment_transaction
             .add_operation(payment_operation)
-            .into_transaction()
-            .unwrap();
+            .with_time_bounds(time_bounds)
+            .into_transaction()?;
 
-        let issuer_key = KeyPair::from_str(&self.issuer_secret)?;
-        transaction.sign(&issuer_key, &Network::new_test())?;
+        Ok(transaction)
+    }
 
-        let base64_transaction = transaction.into_envelope().xdr_base64()?;
+    /// Issues the defined asset from the issuer to the receiver
+    ///
+    /// This operation transfers the custom asset from the issuer's account
+    /// to the receiver's account.
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding the issuer key
+    /// * `amount` - The amount to issue, in stroops
+    ///
+    /// # Returns
+    /// * `Result<(), Error>` - Ok if asset is issued successfully, Error otherwise
+    pub async fn issue_asset<S: Signer>(&self, signer: &S, amount: Stroops) -> Result<Response, Error> {
+        let transaction = self.build_issue_asset_transaction(TimeBounds::valid_for(self.transaction_timeout), amount)?;
+
+        self.sign_and_submit(transaction, signer, &self.issuer_account_id).await
+    }
 
-         // Submit the transaction to Horizon testnet using reqwest
-         let client = reqwest::Client::new();
-         let response = client
-             .post(self.server_url.clone() + "/transactions")
-             .header("Accept", "application/json")
-             .form(&[("tx", base64_transaction)])
-             .send()
-             .await?;
+    /// Builds the unsigned transaction that burns the defined asset by paying it from
+    /// the receiver back to the issuer, where it stops existing
+    ///
+    /// # Arguments
+    /// * `time_bounds` - The period the transaction is valid for
+    /// * `amount` - The amount to burn, in stroops
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_burn_asset_transaction(&self, time_bounds: TimeBounds, amount: Stroops) -> Result<Transaction, Error> {
+        let receiver_account = self.receiver_public_key.clone();
 
-        Ok(response)
+        let receiver_account_details = self.client
+            .load_account(&receiver_account.account_id())?;
+
+        let payment_operation = PaymentOperationBuilder::new()
+            .with_source_account(receiver_account.clone())
+            .with_asset(self.asset())
+            .with_amount(amount)?
+            .with_destination(self.issuer_public_key.clone())
+            .build()?;
+
+        let transaction = Transaction::builder(
+                receiver_account,
+                receiver_account_details.sequence_number().parse::<i64>()? + 1,
+                Stroops::new(100)
+            )
+            .add_operation(payment_operation)
+            .with_time_bounds(time_bounds)
+            .into_transaction()?;
+
+        Ok(transaction)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signer::KeyPairSigner;
+    use stellar_sdk::Keypair;
     use tokio;
 
-    // Helper function to generate test keypairs
-    fn generate_test_keypairs() -> (String, String) {
+    // Helper function to generate a signer holding test issuer and receiver keys
+    fn generate_test_signer() -> KeyPairSigner {
         let issuer = Keypair::random();
         let receiver = Keypair::random();
-        (
-            issuer.unwrap().secret_key().unwrap().to_string(),
-            receiver.unwrap().secret_key().unwrap().to_string(),
+        KeyPairSigner::new()
+            .with_account("issuer", &issuer.unwrap().secret_key().unwrap().to_string())
+            .unwrap()
+            .with_account("receiver", &receiver.unwrap().secret_key().unwrap().to_string())
+            .unwrap()
+    }
+
+    async fn new_test_asset_issuer(server_url: String, signer: &KeyPairSigner) -> AssetIssuer {
+        AssetIssuer::new(
+            server_url,
+            Network::new_test(),
+            signer,
+            "issuer".to_string(),
+            "receiver".to_string(),
+            "TEST".to_string(),
         )
+        .await
+        .unwrap()
     }
 
     #[tokio::test]
     async fn test_new_asset_issuer() {
-        let (issuer_secret, receiver_secret) = generate_test_keypairs();
+        let signer = generate_test_signer();
         let server_url = "https://horizon-testnet.stellar.org".to_string();
         
-        let asset_issuer = AssetIssuer::new(
-            server_url.clone(),
-            issuer_secret.clone(),
-            receiver_secret.clone(),
-            "TEST".to_string(),
-        );
+        let asset_issuer = new_test_asset_issuer(server_url.clone(), &signer).await;
 
-        assert!(asset_issuer.issuer_keypair.is_some());
-        assert!(asset_issuer.receiver_keypair.is_some());
+        assert_eq!(
+            asset_issuer.issuer_public_key,
+            signer.public_key("issuer").await.unwrap()
+        );
+        assert_eq!(
+            asset_issuer.receiver_public_key,
+            signer.public_key("receiver").await.unwrap()
+        );
         assert!(asset_issuer.asset.is_some());
         assert_eq!(asset_issuer.server_url, server_url);
     }
 
     #[tokio::test]
     async fn test_create_trustline() {
-        let (issuer_secret, receiver_secret) = generate_test_keypairs();
+        let signer = generate_test_signer();
         let server_url = "https://horizon-testnet.stellar.org".to_string();
         
-        let asset_issuer = AssetIssuer::new(
-            server_url,
-            issuer_secret,
-            receiver_secret,
-            "TEST".to_string(),
-        );
+        let asset_issuer = new_test_asset_issuer(server_url, &signer).await;
 
-        let result = asset_issuer.create_trustline().await;
+        let result = asset_issuer.create_trustline(&signer).await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
     async fn test_issue_asset() {
-        let (issuer_secret, receiver_secret) = generate_test_keypairs();
+        let signer = generate_test_signer();
         let server_url = "https://horizon-testnet.stellar.org".to_string();
         
-        let asset_issuer = AssetIssuer::new(
-            server_url,
-            issuer_secret,
-            receiver_secret,
-            "TEST".to_string(),
-        );
+        let asset_issuer = new_test_asset_issuer(server_url, &signer).await;
 
         // First create trustline
-        let trustline_result = asset_issuer.create_trustline().await;
+        let trustline_result = asset_issuer.create_trustline(&signer).await;
         assert!(trustline_result.is_ok());
 
         // Then issue asset
-        let issue_result = asset_issuer.issue_asset().await;
+        let issue_result = asset_issuer.issue_asset(&signer, Stroops::new(10)).await;
         assert!(issue_result.is_ok());
     }
 }
@@ -1,56 +1,415 @@
-use openssl::symm::{decrypt, encrypt, Cipher};
+use std::collections::BTreeMap;
+
+use anyhow::Error;
+use openssl::symm::{decrypt, decrypt_aead, encrypt_aead, Cipher};
 use rand::rngs::OsRng;
 use rand::RngCore;
+use zeroize::Zeroizing;
+
+use crate::secret::{SecretBytes, SecretString};
 
-/// Generates a random encryption key and initialization vector (IV) for AES-256-CBC encryption.
+/// Key version stored on rows written by the original AES-256-CBC scheme.
+///
+/// These rows were encrypted with `ENCRYPTION_KEY` and the global `ENCRYPTION_IV`
+/// and carry no authentication tag.
+pub const LEGACY_KEY_VERSION: i32 = 0;
+
+/// Key version whose key also decrypts [`LEGACY_KEY_VERSION`] rows.
+pub const LEGACY_DECRYPTION_KEY_VERSION: i32 = 1;
+
+/// Length in bytes of the random AES-256-GCM nonce prepended to each ciphertext.
+const NONCE_LEN: usize = 12;
+
+/// Length in bytes of the AES-256-GCM authentication tag appended to each ciphertext.
+const TAG_LEN: usize = 16;
+
+/// Generates a random encryption key and initialization vector (IV).
 ///
 /// The function generates:
 /// - A 32-byte (256-bit) random key suitable for AES-256
 /// - A 16-byte (128-bit) random initialization vector
 ///
-/// Prints both values to stdout for debugging/demonstration purposes.
-pub fn generate_encryption_key_and_iv() {
-    let mut key = [0u8; 32]; // 32 bytes for AES-256
-    let mut iv = [0u8; 16]; // 16 bytes for AES block size
-    OsRng.fill_bytes(&mut key);
-    OsRng.fill_bytes(&mut iv);
+/// Both values are returned hex encoded and are never written to stdout or logs;
+/// store them directly in the secret manager they are meant for.
+pub fn generate_encryption_key_and_iv() -> (SecretString, SecretString) {
+    let mut key = Zeroizing::new([0u8; 32]); // 32 bytes for AES-256
+    let mut iv = Zeroizing::new([0u8; 16]); // 16 bytes for AES block size
+    OsRng.fill_bytes(key.as_mut());
+    OsRng.fill_bytes(iv.as_mut());
 
-    println!("Encryption Key: {:?}", hex::encode(key));
-    println!("Initialization Vector (IV): {:?}", hex::encode(iv))
+    (
+        SecretString::new(hex::encode(key.as_ref())),
+        SecretString::new(hex::encode(iv.as_ref())),
+    )
 }
 
-/// Encrypts a private key using AES-256-CBC encryption.
+/// The set of key-encryption keys the service can use, indexed by version.
+///
+/// New secrets are always encrypted under the highest version; older versions are kept
+/// only so existing rows can still be decrypted until they are re-encrypted.
+///
+/// Keys are wiped from memory when the key ring is dropped.
+pub struct KeyRing {
+    keys: BTreeMap<i32, Zeroizing<Vec<u8>>>,
+    legacy_iv: Option<Vec<u8>>,
+}
+
+impl KeyRing {
+    /// Creates a key ring from explicit keys.
+    ///
+    /// # Arguments
+    /// * `keys` - Key versions mapped to their 32-byte keys
+    /// * `legacy_iv` - The global IV used by [`LEGACY_KEY_VERSION`] rows, if any remain
+    pub fn new(keys: BTreeMap<i32, Vec<u8>>, legacy_iv: Option<Vec<u8>>) -> Result<Self, Error> {
+        if keys.is_empty() {
+            return Err(anyhow::anyhow!("At least one encryption key is required"));
+        }
+
+        for (version, key) in &keys {
+            if *version <= LEGACY_KEY_VERSION {
+                return Err(anyhow::anyhow!("Encryption key versions must be positive"));
+            }
+            if key.len() != 32 {
+                return Err(anyhow::anyhow!(
+                    "Encryption key {} must be 32 bytes",
+                    version
+                ));
+            }
+        }
+
+        let keys = keys
+            .into_iter()
+            .map(|(version, key)| (version, Zeroizing::new(key)))
+            .collect();
+
+        Ok(Self { keys, legacy_iv })
+    }
+
+    /// Loads the key ring from the environment.
+    ///
+    /// `ENCRYPTION_KEYS` holds a comma separated list of `version:hex_key` pairs, e.g.
+    /// `1:ab12...,2:cd34...`. When it is unset, `ENCRYPTION_KEY` is used as version 1.
+    /// `ENCRYPTION_KEY` and `ENCRYPTION_IV` also decrypt [`LEGACY_KEY_VERSION`] rows.
+    pub fn from_env() -> Result<Self, Error> {
+        let mut keys = BTreeMap::new();
+
+        match std::env::var("ENCRYPTION_KEYS") {
+            Ok(key_list) => {
+                let key_list = Zeroizing::new(key_list);
+                for entry in key_list.split(',').filter(|entry| !entry.trim().is_empty()) {
+                    let (version, key) = entry
+                        .trim()
+                        .split_once(':')
+                        .ok_or_else(|| anyhow::anyhow!("Malformed ENCRYPTION_KEYS entry"))?;
+                    keys.insert(version.parse::<i32>()?, decode_key(key)?);
+                }
+            }
+            Err(_) => {
+                let encryption_key = Zeroizing::new(std::env::var("ENCRYPTION_KEY")?);
+                keys.insert(1, decode_key(&encryption_key)?);
+            }
+        }
+
+        let legacy_iv = match std::env::var("ENCRYPTION_IV") {
+            Ok(iv) => Some(hex::decode(iv)?),
+            Err(_) => None,
+        };
+
+        Self::new(keys, legacy_iv)
+    }
+
+    /// Returns the key version new secrets are encrypted under.
+    pub fn current_version(&self) -> i32 {
+        *self.keys.keys().next_back().unwrap()
+    }
+
+    /// Returns every configured key version in ascending order.
+    pub fn versions(&self) -> Vec<i32> {
+        self.keys.keys().copied().collect()
+    }
+
+    fn key(&self, version: i32) -> Result<&[u8], Error> {
+        self.keys
+            .get(&version)
+            .map(|key| key.as_slice())
+            .ok_or_else(|| anyhow::anyhow!("Encryption key version {} is not configured", version))
+    }
+
+    /// Encrypts data under the current key using AES-256-GCM with a random nonce.
+    ///
+    /// # Returns
+    /// * `Ok((Vec<u8>, i32))` - The record laid out as `nonce || ciphertext || tag`, and
+    ///   the key version it was encrypted under
+    pub fn encrypt(&self, data: &[u8], associated_data: &[u8]) -> Result<(Vec<u8>, i32), Error> {
+        let key_version = self.current_version();
+        let encryption_key = self.key(key_version)?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut tag = [0u8; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            encryption_key,
+            Some(&nonce),
+            associated_data,
+            data,
+            &mut tag,
+        )?;
+
+        let mut encrypted_data = Vec::with_capacity(NONCE_LEN + ciphertext.len() + TAG_LEN);
+        encrypted_data.extend_from_slice(&nonce);
+        encrypted_data.extend_from_slice(&ciphertext);
+        encrypted_data.extend_from_slice(&tag);
+
+        Ok((encrypted_data, key_version))
+    }
+
+    /// Decrypts a record written under `key_version`.
+    pub fn decrypt(
+        &self,
+        encrypted_data: &[u8],
+        associated_data: &[u8],
+        key_version: i32,
+    ) -> Result<SecretBytes, Error> {
+        if key_version == LEGACY_KEY_VERSION {
+            return self.decrypt_legacy(encrypted_data);
+        }
+
+        if encrypted_data.len() < NONCE_LEN + TAG_LEN {
+            return Err(anyhow::anyhow!("Encrypted key is too short"));
+        }
+
+        let encryption_key = self.key(key_version)?;
+
+        let (nonce, rest) = encrypted_data.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+        let decrypted = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            encryption_key,
+            Some(nonce),
+            associated_data,
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| anyhow::anyhow!("Encrypted key failed authentication"))?;
+
+        Ok(SecretBytes::new(decrypted))
+    }
+
+    /// Decrypts a record and encrypts it again under the current key.
+    pub fn reencrypt(
+        &self,
+        encrypted_data: &[u8],
+        associated_data: &[u8],
+        key_version: i32,
+    ) -> Result<(Vec<u8>, i32), Error> {
+        let decrypted = self.decrypt(encrypted_data, associated_data, key_version)?;
+        self.encrypt(decrypted.expose_secret(), associated_data)
+    }
+
+    /// Decrypts a record stored under the legacy AES-256-CBC scheme with the global IV.
+    ///
+    /// Legacy rows were written with the key now configured as
+    /// [`LEGACY_DECRYPTION_KEY_VERSION`].
+    fn decrypt_legacy(&self, encrypted_data: &[u8]) -> Result<SecretBytes, Error> {
+        let iv = self
+            .legacy_iv
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("ENCRYPTION_IV is required for legacy keys"))?;
+        let encryption_key = self.key(LEGACY_DECRYPTION_KEY_VERSION)?;
+        let decrypted = decrypt(
+            Cipher::aes_256_cbc(),
+            encryption_key,
+            Some(iv),
+            encrypted_data,
+        )
+        .map_err(|_| anyhow::anyhow!("Legacy encrypted key could not be decrypted"))?;
+        Ok(SecretBytes::new(decrypted))
+    }
+}
+
+/// Decodes a hex encoded key without echoing it in the error.
+fn decode_key(key: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(key.trim()).map_err(|_| anyhow::anyhow!("Encryption key is not valid hex"))
+}
+
+/// Returns the key version new secrets are encrypted under.
+pub fn current_key_version() -> Result<i32, Error> {
+    Ok(KeyRing::from_env()?.current_version())
+}
+
+/// Returns the stored key versions that can no longer be decrypted once a key version
+/// is removed from the key ring.
+///
+/// That is the version itself, and for [`LEGACY_DECRYPTION_KEY_VERSION`] also the
+/// [`LEGACY_KEY_VERSION`] rows it decrypts.
+pub fn versions_decrypted_with(key_version: i32) -> Vec<i32> {
+    if key_version == LEGACY_DECRYPTION_KEY_VERSION {
+        vec![key_version, LEGACY_KEY_VERSION]
+    } else {
+        vec![key_version]
+    }
+}
+
+/// Encrypts a private key using AES-256-GCM with a random per-record nonce.
 ///
 /// # Arguments
 /// - `key`: The private key data to encrypt as a byte slice.
+/// - `associated_data`: Data bound to the ciphertext (the owning account id), which must
+///   be supplied again on decryption.
 ///
 /// # Returns
-/// - `Ok(Vec<u8>)`: The encrypted private key data.
-/// - `Err(openssl::error::ErrorStack)`: If encryption fails.
-///
-/// # Note
-/// Currently uses hardcoded encryption key and IV - should be updated for production use.
-pub fn encrypt_private_key(key: &[u8]) -> Result<Vec<u8>, openssl::error::ErrorStack> {
-    let cipher = Cipher::aes_256_cbc();
-    let encryption_key = hex::decode(std::env::var("ENCRYPTION_KEY").unwrap()).unwrap();
-    let iv = hex::decode(std::env::var("ENCRYPTION_IV").unwrap()).unwrap();
-
-    encrypt(cipher, &encryption_key, Some(&iv), key)
+/// - `Ok((Vec<u8>, i32))`: The encrypted record laid out as `nonce || ciphertext || tag`,
+///   and the key version it was encrypted under.
+/// - `Err(Error)`: If the key is misconfigured or encryption fails.
+pub fn encrypt_private_key(key: &[u8], associated_data: &[u8]) -> Result<(Vec<u8>, i32), Error> {
+    KeyRing::from_env()?.encrypt(key, associated_data)
 }
 
-/// Decrypts an encrypted private key using AES-256-CBC.
+/// Decrypts an encrypted private key.
+///
+/// Rows at [`LEGACY_KEY_VERSION`] are decrypted with the original AES-256-CBC scheme,
+/// everything else is treated as an AES-256-GCM record produced by [`encrypt_private_key`].
 ///
 /// # Arguments
 /// - `encrypted_data`: The encrypted private key as a byte slice.
-/// - `key`: The 256-bit encryption key (32 bytes).
-/// - `iv`: The initialization vector (16 bytes).
+/// - `associated_data`: The data the record was bound to on encryption (the owning account id).
+/// - `key_version`: The key version stored alongside the record.
 ///
 /// # Returns
-/// - `Ok(Vec<u8>)`: The decrypted private key.
-/// - `Err(openssl::error::ErrorStack)`: If decryption fails.
-pub fn decrypt_private_key(encrypted_data: &[u8]) -> Result<Vec<u8>, openssl::error::ErrorStack> {
-    let cipher = Cipher::aes_256_cbc();
-    let encryption_key = hex::decode(std::env::var("ENCRYPTION_KEY").unwrap()).unwrap();
-    let iv = hex::decode(std::env::var("ENCRYPTION_IV").unwrap()).unwrap();
-    decrypt(cipher, &encryption_key, Some(&iv), encrypted_data)
+/// - `Ok(SecretBytes)`: The decrypted private key, wiped from memory when dropped.
+/// - `Err(Error)`: If the record is malformed, has been tampered with, or decryption fails.
+pub fn decrypt_private_key(
+    encrypted_data: &[u8],
+    associated_data: &[u8],
+    key_version: i32,
+) -> Result<SecretBytes, Error> {
+    KeyRing::from_env()?.decrypt(encrypted_data, associated_data, key_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::symm::encrypt;
+
+    const TEST_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const TEST_KEY_2: &str = "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100";
+    const TEST_IV: &str = "0f0e0d0c0b0a09080706050403020100";
+
+    fn test_key_ring(versions: &[(i32, &str)]) -> KeyRing {
+        let keys = versions
+            .iter()
+            .map(|(version, key)| (*version, hex::decode(key).unwrap()))
+            .collect();
+        KeyRing::new(keys, Some(hex::decode(TEST_IV).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let key_ring = test_key_ring(&[(1, TEST_KEY)]);
+
+        let secret = b"SBXYZTESTSECRET";
+        let (encrypted, version) = key_ring.encrypt(secret, b"account-1").unwrap();
+
+        assert_eq!(version, 1);
+        assert_eq!(
+            key_ring
+                .decrypt(&encrypted, b"account-1", version)
+                .unwrap()
+                .expose_secret(),
+            secret
+        );
+    }
+
+    #[test]
+    fn test_encrypt_uses_fresh_nonce() {
+        let key_ring = test_key_ring(&[(1, TEST_KEY)]);
+
+        let (first, _) = key_ring.encrypt(b"same secret", b"account-1").unwrap();
+        let (second, _) = key_ring.encrypt(b"same secret", b"account-1").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_account() {
+        let key_ring = test_key_ring(&[(1, TEST_KEY)]);
+
+        let (encrypted, version) = key_ring.encrypt(b"secret", b"account-1").unwrap();
+
+        assert!(key_ring.decrypt(&encrypted, b"account-2", version).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_record() {
+        let key_ring = test_key_ring(&[(1, TEST_KEY)]);
+
+        let (mut encrypted, version) = key_ring.encrypt(b"secret", b"account-1").unwrap();
+        encrypted[NONCE_LEN] ^= 0x01;
+
+        assert!(key_ring.decrypt(&encrypted, b"account-1", version).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_legacy_record() {
+        let key_ring = test_key_ring(&[(1, TEST_KEY)]);
+
+        let legacy = encrypt(
+            Cipher::aes_256_cbc(),
+            &hex::decode(TEST_KEY).unwrap(),
+            Some(&hex::decode(TEST_IV).unwrap()),
+            b"legacy secret",
+        )
+        .unwrap();
+
+        assert_eq!(
+            key_ring
+                .decrypt(&legacy, b"ignored", LEGACY_KEY_VERSION)
+                .unwrap()
+                .expose_secret(),
+            b"legacy secret"
+        );
+    }
+
+    #[test]
+    fn test_versions_decrypted_with() {
+        assert_eq!(
+            versions_decrypted_with(LEGACY_DECRYPTION_KEY_VERSION),
+            vec![LEGACY_DECRYPTION_KEY_VERSION, LEGACY_KEY_VERSION]
+        );
+        assert_eq!(versions_decrypted_with(2), vec![2]);
+    }
+
+    #[test]
+    fn test_reencrypt_moves_record_to_newest_key() {
+        let old_ring = test_key_ring(&[(1, TEST_KEY)]);
+        let rotated_ring = test_key_ring(&[(1, TEST_KEY), (2, TEST_KEY_2)]);
+
+        let (encrypted, version) = old_ring.encrypt(b"secret", b"account-1").unwrap();
+        let (reencrypted, new_version) = rotated_ring
+            .reencrypt(&encrypted, b"account-1", version)
+            .unwrap();
+
+        assert_eq!(new_version, 2);
+        assert_eq!(
+            test_key_ring(&[(2, TEST_KEY_2)])
+                .decrypt(&reencrypted, b"account-1", new_version)
+                .unwrap()
+                .expose_secret(),
+            b"secret"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_fails_for_retired_key() {
+        let old_ring = test_key_ring(&[(1, TEST_KEY)]);
+        let (encrypted, version) = old_ring.encrypt(b"secret", b"account-1").unwrap();
+
+        let retired_ring = test_key_ring(&[(2, TEST_KEY_2)]);
+        assert!(retired_ring
+            .decrypt(&encrypted, b"account-1", version)
+            .is_err());
+    }
 }
@@ -0,0 +1,234 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Timelike, Utc};
+
+/// How far ahead to look for the next occurrence before deciding there is none,
+/// e.g. for `0 0 31 2 *`
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A five field cron expression, `minute hour day-of-month month day-of-week`,
+/// evaluated in UTC.
+///
+/// Each field takes `*`, a value, a range `a-b`, a step `*/n` or `a-b/n`, or a comma
+/// separated list of those. Days of the week run from 0 (Sunday) to 6; 7 is also
+/// Sunday. As in cron, if both day fields are restricted a day matching either is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpression {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronExpression {
+    /// Returns the first occurrence strictly after a point in time
+    ///
+    /// # Arguments
+    /// * `after` - The point in time to search from
+    ///
+    /// # Returns
+    /// * `Option<DateTime<Utc>>` - The next occurrence, or `None` if the expression
+    ///   never matches
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // Occurrences fall on whole minutes
+        let start = after
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(Duration::minutes(1))?;
+
+        let mut date = start.date_naive();
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_day(date) {
+                let earliest = if date == start.date_naive() {
+                    start.time()
+                } else {
+                    NaiveTime::MIN
+                };
+
+                if let Some(time) = self.first_time_from(earliest) {
+                    return Some(date.and_time(time).and_utc());
+                }
+            }
+            date = date.succ_opt()?;
+        }
+
+        None
+    }
+
+    /// Returns true if the expression matches a calendar day
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+
+    /// Returns the first matching time of day at or after `earliest`
+    fn first_time_from(&self, earliest: NaiveTime) -> Option<NaiveTime> {
+        for hour in earliest.hour()..24 {
+            if self.hours & (1 << hour) == 0 {
+                continue;
+            }
+
+            let first_minute = if hour == earliest.hour() {
+                earliest.minute()
+            } else {
+                0
+            };
+
+            for minute in first_minute..60 {
+                if self.minutes & (1 << minute) != 0 {
+                    return NaiveTime::from_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl FromStr for CronExpression {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(anyhow::anyhow!(
+                "A cron expression has five fields: minute hour day-of-month month day-of-week"
+            ));
+        };
+
+        // Sunday may be written as 7, fold it onto 0
+        let days_of_week_bits = parse_field(days_of_week, 0, 7)?;
+        let days_of_week_bits = (days_of_week_bits | (days_of_week_bits >> 7)) & 0x7f;
+
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)? as u32,
+            days_of_month: parse_field(days_of_month, 1, 31)? as u32,
+            months: parse_field(months, 1, 12)? as u16,
+            days_of_week: days_of_week_bits as u8,
+            day_of_month_restricted: days_of_month != "*",
+            day_of_week_restricted: days_of_week != "*",
+        })
+    }
+}
+
+/// Parses one field into a bit set of the values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, Error> {
+    let invalid = || anyhow::anyhow!("Invalid cron field '{}'", field);
+    let parse_value = |value: &str| -> Result<u32, Error> {
+        let value = value.parse::<u32>().map_err(|_| invalid())?;
+        if value < min || value > max {
+            return Err(anyhow::anyhow!(
+                "Cron value {} is outside {}-{}",
+                value,
+                min,
+                max
+            ));
+        }
+        Ok(value)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                None => {
+                    let value = parse_value(range)?;
+                    // `5/15` runs from 5 to the end of the range
+                    (value, if step > 1 { max } else { value })
+                }
+            },
+        };
+
+        if start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_weekly_occurrence() {
+        // Mondays at 09:30, 2025-03-24 is a Monday
+        let expression = "30 9 * * 1".parse::<CronExpression>().unwrap();
+
+        assert_eq!(
+            expression.next_after(at(2025, 3, 20, 12, 0)),
+            Some(at(2025, 3, 24, 9, 30))
+        );
+        // Strictly after, an occurrence is never repeated
+        assert_eq!(
+            expression.next_after(at(2025, 3, 24, 9, 30)),
+            Some(at(2025, 3, 31, 9, 30))
+        );
+    }
+
+    #[test]
+    fn test_steps_lists_and_day_fields() {
+        let expression = "*/15 8-10 1,15 * *".parse::<CronExpression>().unwrap();
+        assert_eq!(
+            expression.next_after(at(2025, 3, 1, 10, 50)),
+            Some(at(2025, 3, 15, 8, 0))
+        );
+
+        // Either the 1st of the month or a Sunday (7), as in cron
+        let expression = "0 0 1 * 7".parse::<CronExpression>().unwrap();
+        assert_eq!(
+            expression.next_after(at(2025, 3, 20, 0, 0)),
+            Some(at(2025, 3, 23, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!("* * * *".parse::<CronExpression>().is_err());
+        assert!("60 * * * *".parse::<CronExpression>().is_err());
+        assert!("*/0 * * * *".parse::<CronExpression>().is_err());
+        assert!("0 0 0 * *".parse::<CronExpression>().is_err());
+
+        // Valid, but February never has 31 days
+        let expression = "0 0 31 2 *".parse::<CronExpression>().unwrap();
+        assert_eq!(expression.next_after(at(2025, 1, 1, 0, 0)), None);
+    }
+}
@@ -0,0 +1,344 @@
+//! Federation (SEP-0002): addresses like `alice*example.com` that name a Stellar
+//! account, and the memo payments to it must carry.
+//!
+//! Our own names are served from the handle table. Names on other domains are
+//! resolved through the `FEDERATION_SERVER` listed in that domain's `stellar.toml`.
+
+use std::time::Duration;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use stellar_base::{Memo, PublicKey};
+
+use crate::submitter::http_client;
+
+/// Separates the name from the domain in a federation address
+pub const FEDERATION_SEPARATOR: char = '*';
+
+/// Longest handle we accept for one of our own names
+pub const MAX_HANDLE_LENGTH: usize = 64;
+
+/// Memo types a federation record can ask payments to carry
+pub const MEMO_TYPES: [&str; 3] = ["text", "id", "hash"];
+
+/// Longest text memo a transaction can carry, in bytes
+const MAX_TEXT_MEMO_LENGTH: usize = 28;
+
+/// Largest `stellar.toml` we read, as SEP-0001 allows
+const MAX_STELLAR_TOML_SIZE: usize = 100 * 1024;
+
+/// How long a request to another domain may take
+const FEDERATION_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A federation record, in the shape SEP-0002 defines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederationRecord {
+    /// The federation address, e.g. `alice*example.com`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stellar_address: Option<String>,
+    /// The Stellar public key the address names
+    pub account_id: String,
+    /// `text`, `id` or `hash` when payments must carry a memo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo_type: Option<String>,
+    /// The memo, as a string even for `id`, and base64 encoded for `hash`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl FederationRecord {
+    /// Returns the memo payments to this record must carry, if any
+    ///
+    /// # Errors
+    /// Fails if only one of the memo type and memo is set, or the memo does not fit
+    /// its type
+    pub fn payment_memo(&self) -> Result<Option<Memo>, Error> {
+        match (self.memo_type.as_deref(), self.memo.as_deref()) {
+            (None, None) => Ok(None),
+            (Some(memo_type), Some(memo)) => Ok(Some(federation_memo(memo_type, memo)?)),
+            _ => Err(anyhow::anyhow!(
+                "A federation record needs both a memo type and a memo, or neither"
+            )),
+        }
+    }
+}
+
+/// Returns whether an address is a federation address rather than a public key
+pub fn is_federation_address(address: &str) -> bool {
+    address.contains(FEDERATION_SEPARATOR)
+}
+
+/// Splits a federation address into its name and domain
+///
+/// # Arguments
+/// * `address` - The federation address, e.g. `alice*example.com`
+///
+/// # Returns
+/// * `Result<(&str, String), Error>` - The name and the lowercased domain
+///
+/// # Errors
+/// Fails if the name or domain is empty or the domain is not a plain host name
+pub fn parse_federation_address(address: &str) -> Result<(&str, String), Error> {
+    let (name, domain) = address
+        .rsplit_once(FEDERATION_SEPARATOR)
+        .ok_or_else(|| anyhow::anyhow!("Federation address must look like name*domain"))?;
+
+    let valid_domain = !domain.is_empty()
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.');
+
+    if name.is_empty() || !valid_domain {
+        return Err(anyhow::anyhow!(
+            "Federation address must look like name*domain"
+        ));
+    }
+
+    Ok((name, domain.to_ascii_lowercase()))
+}
+
+/// Normalizes a handle for one of our own names
+///
+/// Handles are case insensitive and stored lowercased. They may hold letters, digits
+/// and `.`, `_`, `-`, `+` or `@`, so email addresses work as names.
+///
+/// # Errors
+/// Fails if the handle is empty, too long or holds any other character
+pub fn normalize_handle(handle: &str) -> Result<String, Error> {
+    let handle = handle.trim().to_lowercase();
+
+    let valid = !handle.is_empty()
+        && handle.len() <= MAX_HANDLE_LENGTH
+        && handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+' | '@'));
+
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Handle must be 1 to {} letters, digits or . _ - + @",
+            MAX_HANDLE_LENGTH
+        ));
+    }
+
+    Ok(handle)
+}
+
+/// Builds the memo a federation record asks for
+///
+/// # Arguments
+/// * `memo_type` - `text`, `id` or `hash`
+/// * `memo` - The memo as SEP-0002 writes it: text, a decimal id, or a base64 hash
+///
+/// # Errors
+/// Fails on an unknown memo type or a memo that does not fit its type
+pub fn federation_memo(memo_type: &str, memo: &str) -> Result<Memo, Error> {
+    match memo_type {
+        "text" if memo.len() <= MAX_TEXT_MEMO_LENGTH => Ok(Memo::new_text(memo)?),
+        "text" => Err(anyhow::anyhow!(
+            "Text memo must be at most {} bytes",
+            MAX_TEXT_MEMO_LENGTH
+        )),
+        "id" => Ok(Memo::new_id(memo.parse::<u64>().map_err(|_| {
+            anyhow::anyhow!("Id memo must be an unsigned 64-bit integer")
+        })?)),
+        "hash" => {
+            let hash = openssl::base64::decode_block(memo)
+                .ok()
+                .filter(|hash| hash.len() == 32)
+                .ok_or_else(|| anyhow::anyhow!("Hash memo must be 32 bytes in base64"))?;
+            Ok(Memo::new_hash(&hash)?)
+        }
+        _ => Err(anyhow::anyhow!(
+            "Memo type must be one of {}",
+            MEMO_TYPES.join(", ")
+        )),
+    }
+}
+
+/// Writes a memo the way SEP-0002 does, the inverse of [`federation_memo`]
+///
+/// # Returns
+/// * `Option<String>` - The text, the decimal id or the base64 hash, or `None` for no
+///   memo and return memos
+pub fn memo_value(memo: &Memo) -> Option<String> {
+    match memo {
+        Memo::Text(text) => Some(text.clone()),
+        Memo::Id(id) => Some(id.to_string()),
+        Memo::Hash(hash) => Some(openssl::base64::encode_block(hash)),
+        Memo::None | Memo::Return(_) => None,
+    }
+}
+
+/// Resolves a federation address on another domain
+///
+/// The domain's `stellar.toml` is fetched over HTTPS to find its federation server,
+/// which is then asked for the name.
+///
+/// # Arguments
+/// * `address` - The federation address, e.g. `alice*example.com`
+///
+/// # Returns
+/// * `Result<FederationRecord, Error>` - The record, with a valid account id and memo
+pub async fn resolve_federation_address(address: &str) -> Result<FederationRecord, Error> {
+    let (_, domain) = parse_federation_address(address)?;
+    let federation_server = federation_server(&domain).await?;
+
+    let response = http_client()
+        .get(&federation_server)
+        .query(&[("q", address), ("type", "name")])
+        .header("Accept", "application/json")
+        .timeout(FEDERATION_REQUEST_TIMEOUT)
+        .send()
+        .await?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(anyhow::anyhow!("Federation address {} not found", address));
+    }
+
+    let record = response
+        .error_for_status()?
+        .json::<FederationRecord>()
+        .await?;
+
+    PublicKey::from_account_id(&record.account_id).map_err(|_| {
+        anyhow::anyhow!(
+            "Federation server returned an invalid account id for {}",
+            address
+        )
+    })?;
+    record.payment_memo()?;
+
+    Ok(record)
+}
+
+/// Returns the federation server a domain lists in its `stellar.toml`
+async fn federation_server(domain: &str) -> Result<String, Error> {
+    let response = http_client()
+        .get(format!("https://{}/.well-known/stellar.toml", domain))
+        .timeout(FEDERATION_REQUEST_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    if response
+        .content_length()
+        .is_some_and(|length| length as usize > MAX_STELLAR_TOML_SIZE)
+    {
+        return Err(anyhow::anyhow!("stellar.toml of {} is too large", domain));
+    }
+
+    let body = response.text().await?;
+    if body.len() > MAX_STELLAR_TOML_SIZE {
+        return Err(anyhow::anyhow!("stellar.toml of {} is too large", domain));
+    }
+
+    federation_server_from_toml(&body)
+        .ok_or_else(|| anyhow::anyhow!("{} does not run a federation server", domain))
+}
+
+/// Reads the HTTPS federation server from a `stellar.toml`
+fn federation_server_from_toml(stellar_toml: &str) -> Option<String> {
+    stellar_toml
+        .parse::<toml::Table>()
+        .ok()?
+        .get("FEDERATION_SERVER")?
+        .as_str()
+        .filter(|server| server.starts_with("https://"))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_federation_address() {
+        assert_eq!(
+            parse_federation_address("alice*Greep.io").unwrap(),
+            ("alice", "greep.io".to_string())
+        );
+        // Names may be email addresses, the domain follows the last separator
+        assert_eq!(
+            parse_federation_address("alice@mail.com*greep.io").unwrap(),
+            ("alice@mail.com", "greep.io".to_string())
+        );
+
+        assert!(parse_federation_address("alice").is_err());
+        assert!(parse_federation_address("*greep.io").is_err());
+        assert!(parse_federation_address("alice*").is_err());
+        assert!(parse_federation_address("alice*greep.io/evil").is_err());
+        assert!(parse_federation_address("alice*greep.io:8080").is_err());
+    }
+
+    #[test]
+    fn test_normalize_handle() {
+        assert_eq!(normalize_handle(" Alice ").unwrap(), "alice");
+        assert_eq!(
+            normalize_handle("alice+pay@mail.com").unwrap(),
+            "alice+pay@mail.com"
+        );
+
+        assert!(normalize_handle("").is_err());
+        assert!(normalize_handle("alice*greep.io").is_err());
+        assert!(normalize_handle(&"a".repeat(MAX_HANDLE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_federation_memo() {
+        assert_eq!(federation_memo("id", "42").unwrap(), Memo::new_id(42));
+        assert_eq!(
+            federation_memo("text", "invoice 7").unwrap(),
+            Memo::new_text("invoice 7").unwrap()
+        );
+
+        let hash = [7u8; 32];
+        assert_eq!(
+            federation_memo("hash", &openssl::base64::encode_block(&hash)).unwrap(),
+            Memo::new_hash(&hash).unwrap()
+        );
+
+        for (memo_type, memo) in [("id", "42"), ("text", "invoice 7")] {
+            assert_eq!(
+                memo_value(&federation_memo(memo_type, memo).unwrap()).as_deref(),
+                Some(memo)
+            );
+        }
+
+        assert!(federation_memo("id", "-1").is_err());
+        assert!(federation_memo("text", &"x".repeat(29)).is_err());
+        assert!(federation_memo("hash", "c2hvcnQ=").is_err());
+        assert!(federation_memo("return", "42").is_err());
+    }
+
+    #[test]
+    fn test_payment_memo() {
+        let mut record = FederationRecord {
+            stellar_address: Some("alice*greep.io".to_string()),
+            account_id: "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ".to_string(),
+            memo_type: None,
+            memo: None,
+        };
+        assert_eq!(record.payment_memo().unwrap(), None);
+
+        record.memo_type = Some("id".to_string());
+        assert!(record.payment_memo().is_err());
+
+        record.memo = Some("42".to_string());
+        assert_eq!(record.payment_memo().unwrap(), Some(Memo::new_id(42)));
+    }
+
+    #[test]
+    fn test_federation_server_from_toml() {
+        assert_eq!(
+            federation_server_from_toml("FEDERATION_SERVER = \"https://greep.io/federation\""),
+            Some("https://greep.io/federation".to_string())
+        );
+        assert_eq!(
+            federation_server_from_toml("FEDERATION_SERVER = \"http://greep.io/federation\""),
+            None
+        );
+        assert_eq!(federation_server_from_toml("VERSION = \"2.7.0\""), None);
+    }
+}
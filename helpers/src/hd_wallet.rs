@@ -0,0 +1,207 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+use bip39::Mnemonic;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer as HmacSigner;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use stellar_base::KeyPair;
+use zeroize::Zeroizing;
+
+use crate::secret::{SecretBytes, SecretString};
+
+/// BIP-44 purpose used by SEP-0005 paths.
+const PURPOSE: u32 = 44;
+/// SLIP-44 coin type registered for Stellar.
+pub const STELLAR_COIN_TYPE: u32 = 148;
+/// Highest account index that can be derived as a hardened child.
+pub const MAX_DERIVATION_INDEX: u32 = 0x7FFF_FFFF;
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+const ED25519_CURVE_SEED: &[u8] = b"ed25519 seed";
+/// Entropy for a 24 word mnemonic, as recommended by SEP-0005.
+const MNEMONIC_ENTROPY_LEN: usize = 32;
+
+/// Generates a new random 24 word BIP-39 mnemonic.
+pub fn generate_mnemonic() -> Result<SecretString, Error> {
+    let mut entropy = Zeroizing::new([0u8; MNEMONIC_ENTROPY_LEN]);
+    OsRng.fill_bytes(entropy.as_mut());
+
+    let mnemonic = Mnemonic::from_entropy(entropy.as_ref())?;
+    Ok(SecretString::new(mnemonic.to_string()))
+}
+
+/// Turns a BIP-39 mnemonic and optional passphrase into the 64 byte master seed.
+///
+/// # Errors
+/// Returns an error if the mnemonic has unknown words or a bad checksum. The words
+/// themselves are never included in the error.
+pub fn seed_from_mnemonic(
+    mnemonic: &SecretString,
+    passphrase: &SecretString,
+) -> Result<SecretBytes, Error> {
+    let mnemonic = Mnemonic::from_str(mnemonic.expose_secret())
+        .map_err(|_| anyhow::anyhow!("Invalid BIP-39 mnemonic"))?;
+
+    let seed = Zeroizing::new(mnemonic.to_seed(passphrase.expose_secret()));
+    Ok(SecretBytes::new(seed.to_vec()))
+}
+
+/// Returns the SEP-0005 derivation path for an account index, `m/44'/148'/index'`.
+pub fn derivation_path(index: u32) -> String {
+    format!("m/{}'/{}'/{}'", PURPOSE, STELLAR_COIN_TYPE, index)
+}
+
+/// Derives the Stellar key pair at `m/44'/148'/index'` from a master seed (SLIP-0010, ed25519).
+///
+/// # Arguments
+/// * `seed` - The 64 byte BIP-39 master seed
+/// * `index` - The account index, at most [`MAX_DERIVATION_INDEX`]
+pub fn derive_keypair(seed: &SecretBytes, index: u32) -> Result<KeyPair, Error> {
+    if index > MAX_DERIVATION_INDEX {
+        return Err(anyhow::anyhow!(
+            "Derivation index {} is out of range",
+            index
+        ));
+    }
+
+    let master = hmac_sha512(ED25519_CURVE_SEED, seed.expose_secret())?;
+    let (mut key, mut chain_code) = split_node(&master);
+
+    for child in [PURPOSE, STELLAR_COIN_TYPE, index] {
+        let mut data = Zeroizing::new(Vec::with_capacity(37));
+        data.push(0u8);
+        data.extend_from_slice(key.as_ref());
+        data.extend_from_slice(&(child | HARDENED_OFFSET).to_be_bytes());
+
+        let node = hmac_sha512(chain_code.as_ref(), &data)?;
+        (key, chain_code) = split_node(&node);
+    }
+
+    KeyPair::from_seed_bytes(key.as_ref())
+        .map_err(|_| anyhow::anyhow!("Derived key at {} is invalid", derivation_path(index)))
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let pkey = PKey::hmac(key)?;
+    let mut signer = HmacSigner::new(MessageDigest::sha512(), &pkey)?;
+    signer.update(data)?;
+    Ok(Zeroizing::new(signer.sign_to_vec()?))
+}
+
+/// Splits a SLIP-0010 node into its private key and chain code halves.
+fn split_node(node: &[u8]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut chain_code = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&node[..32]);
+    chain_code.copy_from_slice(&node[32..64]);
+    (key, chain_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(value: &str) -> SecretString {
+        SecretString::new(value.to_string())
+    }
+
+    fn assert_derives(seed: &SecretBytes, index: u32, public_key: &str, secret_seed: &str) {
+        let keypair = derive_keypair(seed, index).unwrap();
+        assert_eq!(keypair.public_key().account_id(), public_key);
+        assert_eq!(keypair.secret_key().secret_seed(), secret_seed);
+    }
+
+    #[test]
+    fn test_sep5_vector_12_words() {
+        let seed = seed_from_mnemonic(
+            &secret("illness spike retreat truth genius clock brain pass fit cave bargain toe"),
+            &secret(""),
+        )
+        .unwrap();
+
+        assert_derives(
+            &seed,
+            0,
+            "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6",
+            "SBGWSG6BTNCKCOB3DIFBGCVMUPQFYPA2G4O34RMTB343OYPXU5DJDVMN",
+        );
+        assert_derives(
+            &seed,
+            1,
+            "GBAW5XGWORWVFE2XTJYDTLDHXTY2Q2MO73HYCGB3XMFMQ562Q2W2GJQX",
+            "SCEPFFWGAG5P2VX5DHIYK3XEMZYLTYWIPWYEKXFHSK25RVMIUNJ7CTIS",
+        );
+        assert_derives(
+            &seed,
+            2,
+            "GAY5PRAHJ2HIYBYCLZXTHID6SPVELOOYH2LBPH3LD4RUMXUW3DOYTLXW",
+            "SDAILLEZCSA67DUEP3XUPZJ7NYG7KGVRM46XA7K5QWWUIGADUZCZWTJP",
+        );
+    }
+
+    #[test]
+    fn test_sep5_vector_24_words() {
+        let seed = seed_from_mnemonic(
+            &secret(
+                "bench hurt jump file august wise shallow faculty impulse spring exact slush \
+                 thunder author capable act festival slice deposit sauce coconut afford frown better",
+            ),
+            &secret(""),
+        )
+        .unwrap();
+
+        assert_derives(
+            &seed,
+            0,
+            "GC3MMSXBWHL6CPOAVERSJITX7BH76YU252WGLUOM5CJX3E7UCYZBTPJQ",
+            "SAEWIVK3VLNEJ3WEJRZXQGDAS5NVG2BYSYDFRSH4GKVTS5RXNVED5AX7",
+        );
+    }
+
+    #[test]
+    fn test_sep5_vector_with_passphrase() {
+        let seed = seed_from_mnemonic(
+            &secret(
+                "cable spray genius state float twenty onion head street palace net private \
+                 method loan turn phrase state blanket interest dry amazing dress blast tube",
+            ),
+            &secret("p4ssphr4se"),
+        )
+        .unwrap();
+
+        assert_derives(
+            &seed,
+            0,
+            "GDAHPZ2NSYIIHZXM56Y36SBVTV5QKFIZGYMMBHOU53ETUSWTP62B63EQ",
+            "SAFWTGXVS7ELMNCXELFWCFZOPMHUZ5LXNBGUVRCY3FHLFPXK4QPXYP2X",
+        );
+    }
+
+    #[test]
+    fn test_generated_mnemonic_is_valid() {
+        let mnemonic = generate_mnemonic().unwrap();
+        assert_eq!(mnemonic.expose_secret().split_whitespace().count(), 24);
+
+        let seed = seed_from_mnemonic(&mnemonic, &secret("")).unwrap();
+        assert_eq!(seed.expose_secret().len(), 64);
+    }
+
+    #[test]
+    fn test_invalid_mnemonic_is_rejected_without_echo() {
+        let error = seed_from_mnemonic(&secret("illness spike retreat truth"), &secret(""))
+            .err()
+            .unwrap();
+        assert!(!error.to_string().contains("illness"));
+    }
+
+    #[test]
+    fn test_derivation_path_and_range() {
+        assert_eq!(derivation_path(7), "m/44'/148'/7'");
+
+        let seed = SecretBytes::new(vec![1u8; 64]);
+        assert!(derive_keypair(&seed, MAX_DERIVATION_INDEX + 1).is_err());
+    }
+}
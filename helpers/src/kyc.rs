@@ -0,0 +1,554 @@
+//! Customer identity verification (SEP-0012): the SEP-0009 fields we collect, which of
+//! them each customer type must provide, and the review status they move through.
+//!
+//! A customer needs information until every required field is provided, is processing
+//! while an operator reviews it, and is then accepted or rejected. Only accepted
+//! customers may activate an account, hold an authorized trustline or send payments
+//! above the configured limits.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Error;
+use chrono::NaiveDate;
+
+/// The customer type used when a request names none: someone holding an account with us
+pub const DEFAULT_TYPE: &str = "individual";
+
+/// The customer sending a SEP-0031 payment
+pub const SENDER_TYPE: &str = "sep31-sender";
+
+/// The customer receiving a SEP-0031 payment
+pub const RECEIVER_TYPE: &str = "sep31-receiver";
+
+/// Every customer type we know
+pub const CUSTOMER_TYPES: [&str; 3] = [DEFAULT_TYPE, SENDER_TYPE, RECEIVER_TYPE];
+
+/// Largest document accepted, matching Rocket's default limit for uploaded files
+pub const MAX_DOCUMENT_BYTES: usize = 1024 * 1024;
+
+/// Content types accepted for documents
+pub const DOCUMENT_CONTENT_TYPES: [&str; 3] = ["image/jpeg", "image/png", "application/pdf"];
+
+/// A SEP-0009 field, as SEP-0012 describes it to clients.
+pub struct KycField {
+    pub name: &'static str,
+    /// `string`, `date` or `binary`
+    pub field_type: &'static str,
+    pub description: &'static str,
+}
+
+impl KycField {
+    /// Returns whether the field is a document upload
+    pub fn is_binary(&self) -> bool {
+        self.field_type == "binary"
+    }
+}
+
+/// Every field we collect
+pub const FIELDS: [KycField; 16] = [
+    KycField {
+        name: "first_name",
+        field_type: "string",
+        description: "Given or first name",
+    },
+    KycField {
+        name: "last_name",
+        field_type: "string",
+        description: "Family or last name",
+    },
+    KycField {
+        name: "email_address",
+        field_type: "string",
+        description: "Email address",
+    },
+    KycField {
+        name: "mobile_number",
+        field_type: "string",
+        description: "Mobile phone number in E.164 format",
+    },
+    KycField {
+        name: "birth_date",
+        field_type: "date",
+        description: "Date of birth, as YYYY-MM-DD",
+    },
+    KycField {
+        name: "address",
+        field_type: "string",
+        description: "Street address",
+    },
+    KycField {
+        name: "city",
+        field_type: "string",
+        description: "City of residence",
+    },
+    KycField {
+        name: "postal_code",
+        field_type: "string",
+        description: "Postal or ZIP code",
+    },
+    KycField {
+        name: "country_code",
+        field_type: "string",
+        description: "Country of residence, as an ISO 3166-1 alpha-3 code",
+    },
+    KycField {
+        name: "id_type",
+        field_type: "string",
+        description: "Type of identity document, e.g. passport or drivers_license",
+    },
+    KycField {
+        name: "id_number",
+        field_type: "string",
+        description: "Number of the identity document",
+    },
+    KycField {
+        name: "bank_account_number",
+        field_type: "string",
+        description: "Number of the bank account the payout goes to",
+    },
+    KycField {
+        name: "bank_number",
+        field_type: "string",
+        description: "Routing or sort code of the bank",
+    },
+    KycField {
+        name: "photo_id_front",
+        field_type: "binary",
+        description: "Image of the front of the identity document",
+    },
+    KycField {
+        name: "photo_id_back",
+        field_type: "binary",
+        description: "Image of the back of the identity document",
+    },
+    KycField {
+        name: "proof_of_address",
+        field_type: "binary",
+        description: "Document proving the address, e.g. a utility bill",
+    },
+];
+
+/// Returns a field by its SEP-0009 name
+pub fn field(name: &str) -> Option<&'static KycField> {
+    FIELDS.iter().find(|field| field.name == name)
+}
+
+/// Returns the fields a customer type must provide before it can be reviewed
+///
+/// # Errors
+/// Fails for a customer type we do not know
+pub fn required_fields(customer_type: &str) -> Result<&'static [&'static str], Error> {
+    match customer_type {
+        DEFAULT_TYPE => Ok(&[
+            "first_name",
+            "last_name",
+            "email_address",
+            "birth_date",
+            "address",
+            "country_code",
+            "id_type",
+            "id_number",
+            "photo_id_front",
+        ]),
+        SENDER_TYPE => Ok(&["first_name", "last_name", "country_code"]),
+        RECEIVER_TYPE => Ok(&[
+            "first_name",
+            "last_name",
+            "country_code",
+            "bank_account_number",
+            "bank_number",
+        ]),
+        _ => Err(anyhow::anyhow!("Unknown customer type {}", customer_type)),
+    }
+}
+
+/// Returns the required fields of a customer type that were not provided yet
+///
+/// # Arguments
+/// * `customer_type` - The customer type
+/// * `provided` - The names of the fields and documents provided so far
+pub fn missing_fields(
+    customer_type: &str,
+    provided: &[&str],
+) -> Result<Vec<&'static KycField>, Error> {
+    Ok(required_fields(customer_type)?
+        .iter()
+        .filter(|name| !provided.contains(name))
+        .filter_map(|name| field(name))
+        .collect())
+}
+
+/// Checks the value of a text field
+///
+/// # Errors
+/// Fails for an unknown or binary field, or a value in the wrong format
+pub fn validate_field(name: &str, value: &str) -> Result<(), Error> {
+    let field = field(name).ok_or_else(|| anyhow::anyhow!("Unknown field {}", name))?;
+    if field.is_binary() {
+        return Err(anyhow::anyhow!("Field {} must be uploaded as a file", name));
+    }
+
+    let value = value.trim();
+    if value.is_empty() || value.len() > 256 {
+        return Err(anyhow::anyhow!(
+            "Field {} must be between 1 and 256 characters",
+            name
+        ));
+    }
+
+    let valid = match name {
+        "birth_date" => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        "country_code" => value.len() == 3 && value.chars().all(|c| c.is_ascii_uppercase()),
+        "email_address" => value
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.')),
+        "mobile_number" => value
+            .strip_prefix('+')
+            .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())),
+        _ => true,
+    };
+
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Field {} is not valid: {}",
+            name,
+            field.description
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks an uploaded document
+///
+/// # Errors
+/// Fails for an unknown or text field, an unsupported content type or a file too large
+pub fn validate_document(name: &str, content_type: &str, size: usize) -> Result<(), Error> {
+    let field = field(name).ok_or_else(|| anyhow::anyhow!("Unknown field {}", name))?;
+    if !field.is_binary() {
+        return Err(anyhow::anyhow!("Field {} is not a document", name));
+    }
+
+    if !DOCUMENT_CONTENT_TYPES.contains(&content_type) {
+        return Err(anyhow::anyhow!(
+            "Document {} must be one of {}",
+            name,
+            DOCUMENT_CONTENT_TYPES.join(", ")
+        ));
+    }
+
+    if size == 0 || size > MAX_DOCUMENT_BYTES {
+        return Err(anyhow::anyhow!(
+            "Document {} must be between 1 and {} bytes",
+            name,
+            MAX_DOCUMENT_BYTES
+        ));
+    }
+
+    Ok(())
+}
+
+/// The review status of a customer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomerStatus {
+    /// Required fields are missing
+    NeedsInfo,
+    /// Every required field was provided and an operator reviews them
+    Processing,
+    Accepted,
+    Rejected,
+}
+
+impl CustomerStatus {
+    /// Every status
+    pub const ALL: [CustomerStatus; 4] = [
+        CustomerStatus::NeedsInfo,
+        CustomerStatus::Processing,
+        CustomerStatus::Accepted,
+        CustomerStatus::Rejected,
+    ];
+
+    /// Returns the name stored with the customer
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomerStatus::NeedsInfo => "needs_info",
+            CustomerStatus::Processing => "processing",
+            CustomerStatus::Accepted => "accepted",
+            CustomerStatus::Rejected => "rejected",
+        }
+    }
+
+    /// Returns the name used in SEP-0012 responses
+    pub fn sep12_name(&self) -> &'static str {
+        match self {
+            CustomerStatus::NeedsInfo => "NEEDS_INFO",
+            CustomerStatus::Processing => "PROCESSING",
+            CustomerStatus::Accepted => "ACCEPTED",
+            CustomerStatus::Rejected => "REJECTED",
+        }
+    }
+
+    /// Returns the status after the customer provided information
+    ///
+    /// Missing fields always need information. Otherwise any change is reviewed again,
+    /// even for a customer accepted before.
+    ///
+    /// # Arguments
+    /// * `missing` - Whether required fields are still missing
+    /// * `changed` - Whether any field or document was provided
+    pub fn after_update(&self, missing: bool, changed: bool) -> CustomerStatus {
+        if missing {
+            CustomerStatus::NeedsInfo
+        } else if changed || *self == CustomerStatus::NeedsInfo {
+            CustomerStatus::Processing
+        } else {
+            *self
+        }
+    }
+
+    /// Returns the status an operator's decision moves the customer to
+    ///
+    /// # Errors
+    /// Fails unless the decision is to accept or reject, or while information is missing
+    pub fn review(&self, decision: CustomerStatus) -> Result<CustomerStatus, Error> {
+        if !matches!(
+            decision,
+            CustomerStatus::Accepted | CustomerStatus::Rejected
+        ) {
+            return Err(anyhow::anyhow!("A review accepts or rejects a customer"));
+        }
+
+        if *self == CustomerStatus::NeedsInfo {
+            return Err(anyhow::anyhow!(
+                "Customer cannot be reviewed before every required field is provided"
+            ));
+        }
+
+        Ok(decision)
+    }
+}
+
+impl fmt::Display for CustomerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CustomerStatus {
+    type Err = Error;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        CustomerStatus::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == status || candidate.sep12_name() == status)
+            .ok_or_else(|| anyhow::anyhow!("Unknown customer status {}", status))
+    }
+}
+
+/// What identity verification knows of the holder of a user account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HolderReview {
+    /// The review status of the holder's customer record
+    Customer(CustomerStatus),
+    /// The account was opened before identity verification was required, and its
+    /// holder has no customer record yet
+    Legacy,
+    /// The holder has no customer record
+    Unverified,
+}
+
+impl HolderReview {
+    /// Returns true if the holder may activate its account and hold our assets
+    pub fn is_verified(&self) -> bool {
+        matches!(
+            self,
+            HolderReview::Customer(CustomerStatus::Accepted) | HolderReview::Legacy
+        )
+    }
+}
+
+/// The largest payment an account holder may send, by review status.
+pub struct PaymentLimits {
+    /// Limit for accepted customers, none for no limit
+    pub accepted: Option<u64>,
+    /// Limit for customers whose changed information is being reviewed again
+    pub processing: u64,
+    /// Limit for holders of accounts opened before identity verification was required
+    /// who have no customer record yet, none for no limit
+    pub legacy: Option<u64>,
+}
+
+impl PaymentLimits {
+    /// Reads the limits from `KYC_ACCEPTED_PAYMENT_LIMIT`,
+    /// `KYC_PROCESSING_PAYMENT_LIMIT` and `KYC_LEGACY_PAYMENT_LIMIT`, in the units
+    /// payments are sent in
+    ///
+    /// Accepted customers and legacy account holders have no limit and customers being
+    /// reviewed cannot pay unless configured otherwise.
+    pub fn from_env() -> Result<Self, Error> {
+        let limit = |name: &str| -> Result<Option<u64>, Error> {
+            match std::env::var(name) {
+                Ok(value) if !value.is_empty() => {
+                    Ok(Some(value.parse::<u64>().map_err(|_| {
+                        anyhow::anyhow!("{} must be a whole number", name)
+                    })?))
+                }
+                _ => Ok(None),
+            }
+        };
+
+        Ok(Self {
+            accepted: limit("KYC_ACCEPTED_PAYMENT_LIMIT")?,
+            processing: limit("KYC_PROCESSING_PAYMENT_LIMIT")?.unwrap_or(0),
+            legacy: limit("KYC_LEGACY_PAYMENT_LIMIT")?,
+        })
+    }
+
+    /// Returns the largest payment a customer may send, none for no limit
+    ///
+    /// Customers that need information or were rejected, and holders of newer
+    /// accounts without a customer record, cannot pay.
+    pub fn limit(&self, review: HolderReview) -> Option<u64> {
+        match review {
+            HolderReview::Customer(CustomerStatus::Accepted) => self.accepted,
+            HolderReview::Customer(CustomerStatus::Processing) => Some(self.processing),
+            HolderReview::Legacy => self.legacy,
+            _ => Some(0),
+        }
+    }
+
+    /// Checks a payment against the limit of the sending customer
+    ///
+    /// # Errors
+    /// Fails if the amount is above the limit
+    pub fn check(&self, review: HolderReview, amount: u64) -> Result<(), Error> {
+        match self.limit(review) {
+            Some(0) => Err(anyhow::anyhow!(
+                "Payments need an accepted identity verification"
+            )),
+            Some(limit) if amount > limit => Err(anyhow::anyhow!(
+                "Payment of {} is above the limit of {}",
+                amount,
+                limit
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_names_round_trip() {
+        for status in CustomerStatus::ALL {
+            assert_eq!(status.as_str().parse::<CustomerStatus>().unwrap(), status);
+            assert_eq!(
+                status.sep12_name().parse::<CustomerStatus>().unwrap(),
+                status
+            );
+        }
+        assert!("pending".parse::<CustomerStatus>().is_err());
+    }
+
+    #[test]
+    fn test_required_fields_are_known() {
+        for customer_type in CUSTOMER_TYPES {
+            for name in required_fields(customer_type).unwrap() {
+                assert!(field(name).is_some(), "{} is not a known field", name);
+            }
+        }
+        assert!(required_fields("sep31-agent").is_err());
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let missing = missing_fields(SENDER_TYPE, &["first_name"]).unwrap();
+        let names: Vec<_> = missing.iter().map(|field| field.name).collect();
+        assert_eq!(names, ["last_name", "country_code"]);
+
+        let provided = ["first_name", "last_name", "country_code", "email_address"];
+        assert!(missing_fields(SENDER_TYPE, &provided).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_field() {
+        assert!(validate_field("first_name", "Ada").is_ok());
+        assert!(validate_field("first_name", "  ").is_err());
+        assert!(validate_field("birth_date", "1990-02-28").is_ok());
+        assert!(validate_field("birth_date", "28/02/1990").is_err());
+        assert!(validate_field("country_code", "NGA").is_ok());
+        assert!(validate_field("country_code", "NG").is_err());
+        assert!(validate_field("email_address", "ada@example.com").is_ok());
+        assert!(validate_field("email_address", "ada@").is_err());
+        assert!(validate_field("mobile_number", "+2348012345678").is_ok());
+        assert!(validate_field("mobile_number", "08012345678").is_err());
+        assert!(validate_field("photo_id_front", "abc").is_err());
+        assert!(validate_field("favourite_color", "blue").is_err());
+    }
+
+    #[test]
+    fn test_validate_document() {
+        assert!(validate_document("photo_id_front", "image/png", 1024).is_ok());
+        assert!(validate_document("photo_id_front", "text/plain", 1024).is_err());
+        assert!(validate_document("photo_id_front", "image/png", MAX_DOCUMENT_BYTES + 1).is_err());
+        assert!(validate_document("first_name", "image/png", 1024).is_err());
+    }
+
+    #[test]
+    fn test_status_after_update_and_review() {
+        use CustomerStatus::*;
+
+        assert_eq!(NeedsInfo.after_update(true, true), NeedsInfo);
+        assert_eq!(NeedsInfo.after_update(false, false), Processing);
+        assert_eq!(Accepted.after_update(false, false), Accepted);
+        assert_eq!(Accepted.after_update(false, true), Processing);
+        assert_eq!(Rejected.after_update(true, false), NeedsInfo);
+
+        assert_eq!(Processing.review(Accepted).unwrap(), Accepted);
+        assert_eq!(Accepted.review(Rejected).unwrap(), Rejected);
+        assert!(NeedsInfo.review(Accepted).is_err());
+        assert!(Processing.review(Processing).is_err());
+    }
+
+    #[test]
+    fn test_payment_limits() {
+        use CustomerStatus::*;
+        use HolderReview::*;
+
+        let limits = PaymentLimits {
+            accepted: Some(1000),
+            processing: 100,
+            legacy: Some(50),
+        };
+        assert!(limits.check(Customer(Accepted), 1000).is_ok());
+        assert!(limits.check(Customer(Accepted), 1001).is_err());
+        assert!(limits.check(Customer(Processing), 100).is_ok());
+        assert!(limits.check(Customer(Processing), 101).is_err());
+        assert!(limits.check(Customer(NeedsInfo), 1).is_err());
+        assert!(limits.check(Customer(Rejected), 1).is_err());
+        assert!(limits.check(Legacy, 50).is_ok());
+        assert!(limits.check(Legacy, 51).is_err());
+        assert!(limits.check(Unverified, 1).is_err());
+
+        let unlimited = PaymentLimits {
+            accepted: None,
+            processing: 0,
+            legacy: None,
+        };
+        assert!(unlimited.check(Customer(Accepted), u64::MAX).is_ok());
+        assert!(unlimited.check(Legacy, u64::MAX).is_ok());
+        assert!(unlimited.check(Customer(Processing), 1).is_err());
+        assert!(unlimited.check(Unverified, 1).is_err());
+    }
+
+    #[test]
+    fn test_holder_review_is_verified() {
+        assert!(HolderReview::Customer(CustomerStatus::Accepted).is_verified());
+        assert!(HolderReview::Legacy.is_verified());
+        assert!(!HolderReview::Customer(CustomerStatus::Processing).is_verified());
+        assert!(!HolderReview::Unverified.is_verified());
+    }
+}
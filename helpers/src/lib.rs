@@ -3,6 +3,21 @@
 //! This crate provides various helpers for interacting with the Stellar blockchain,
 //! including asset issuance, account management, and other common operations.
 
+pub mod anchor;
 pub mod asset_issuer;
 pub mod stellar_chain;
-pub mod common;
\ No newline at end of file
+pub mod common;
+pub mod cron;
+pub mod federation;
+pub mod hd_wallet;
+pub mod kyc;
+pub mod multisig;
+pub mod qr_code;
+pub mod raw_transaction;
+pub mod recovery;
+pub mod secret;
+pub mod signer;
+pub mod stellar_toml;
+pub mod stellar_uri;
+pub mod submitter;
+pub mod web_auth;
\ No newline at end of file
@@ -0,0 +1,508 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use stellar_base::signature::{DecoratedSignature, Signature, SignatureHint};
+use stellar_base::transaction::TransactionEnvelope;
+use stellar_base::xdr::{XDRDeserialize, XDRSerialize};
+use stellar_base::{Network, Operation, PublicKey, Transaction};
+
+use crate::stellar_chain::AccountAuthorization;
+
+/// The account threshold a transaction has to meet, as defined by its operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThresholdLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl ThresholdLevel {
+    /// Returns the name stored alongside pending transactions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThresholdLevel::Low => "low",
+            ThresholdLevel::Medium => "medium",
+            ThresholdLevel::High => "high",
+        }
+    }
+
+    /// Returns the weight an account requires at this level
+    pub fn required_weight(&self, authorization: &AccountAuthorization) -> u32 {
+        match self {
+            ThresholdLevel::Low => authorization.low_threshold,
+            ThresholdLevel::Medium => authorization.medium_threshold,
+            ThresholdLevel::High => authorization.high_threshold,
+        }
+    }
+}
+
+impl std::str::FromStr for ThresholdLevel {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "low" => Ok(ThresholdLevel::Low),
+            "medium" => Ok(ThresholdLevel::Medium),
+            "high" => Ok(ThresholdLevel::High),
+            _ => Err(anyhow::anyhow!("Invalid threshold level {}", level)),
+        }
+    }
+}
+
+/// Returns the threshold level an operation needs
+fn operation_threshold_level(operation: &Operation) -> ThresholdLevel {
+    match operation {
+        Operation::AllowTrust(_)
+        | Operation::BumpSequence(_)
+        | Operation::ClaimClaimableBalance(_) => ThresholdLevel::Low,
+        Operation::AccountMerge(_) => ThresholdLevel::High,
+        Operation::SetOptions(set_options) => {
+            let changes_authorization = set_options.signer().is_some()
+                || set_options.master_weight().is_some()
+                || set_options.low_threshold().is_some()
+                || set_options.medium_threshold().is_some()
+                || set_options.high_threshold().is_some();
+
+            if changes_authorization {
+                ThresholdLevel::High
+            } else {
+                ThresholdLevel::Medium
+            }
+        }
+        _ => ThresholdLevel::Medium,
+    }
+}
+
+/// Returns the highest threshold level needed by any operation of a transaction
+pub fn required_threshold_level(transaction: &Transaction) -> ThresholdLevel {
+    transaction
+        .operations()
+        .iter()
+        .map(operation_threshold_level)
+        .max()
+        .unwrap_or(ThresholdLevel::Low)
+}
+
+/// Returns true if a signature on the transaction hash was made by `key`
+fn is_signed_by(signature: &DecoratedSignature, key: &PublicKey, hash: &[u8]) -> bool {
+    *signature.hint() == SignatureHint::from_public_key(key)
+        && signature.signature().verify(key, hash)
+}
+
+/// Sums the weights of the account signers that have validly signed the transaction.
+/// Each signer counts once, however many times it signed.
+///
+/// # Arguments
+/// * `transaction` - The transaction with the signatures collected so far
+/// * `network` - The network the transaction is signed for
+/// * `authorization` - The signers and thresholds of the source account
+pub fn collected_weight(
+    transaction: &Transaction,
+    network: &Network,
+    authorization: &AccountAuthorization,
+) -> Result<u32, Error> {
+    let hash = transaction.hash(network)?;
+
+    Ok(authorization
+        .signers
+        .iter()
+        .filter(|(key, _)| {
+            transaction
+                .signatures()
+                .iter()
+                .any(|signature| is_signed_by(signature, key, &hash))
+        })
+        .map(|(_, weight)| *weight)
+        .sum())
+}
+
+/// Adds a signature to a transaction after checking it was made by a signer of the
+/// account for this transaction
+///
+/// # Returns
+/// * `Result<PublicKey, Error>` - The signer the signature belongs to
+///
+/// # Errors
+/// Returns an error if no signer of the account made the signature or that signer
+/// already signed
+pub fn add_decorated_signature(
+    transaction: &mut Transaction,
+    network: &Network,
+    authorization: &AccountAuthorization,
+    signature: DecoratedSignature,
+) -> Result<PublicKey, Error> {
+    let hash = transaction.hash(network)?;
+
+    let (signer_key, _) = authorization
+        .signers
+        .iter()
+        .find(|(key, _)| is_signed_by(&signature, key, &hash))
+        .ok_or_else(|| anyhow::anyhow!("Signature is not from a signer of the account"))?;
+
+    if transaction
+        .signatures()
+        .iter()
+        .any(|existing| is_signed_by(existing, signer_key, &hash))
+    {
+        return Err(anyhow::anyhow!(
+            "{} has already signed the transaction",
+            signer_key.account_id()
+        ));
+    }
+
+    transaction.signatures_mut().push(signature);
+    Ok(signer_key.clone())
+}
+
+/// Adds a co-signer's signature to a transaction after checking it
+///
+/// # Arguments
+/// * `transaction` - The transaction to add the signature to
+/// * `network` - The network the transaction is signed for
+/// * `authorization` - The signers and thresholds of the source account
+/// * `signer_key` - The public key that made the signature
+/// * `signature` - The raw 64 byte ed25519 signature of the transaction hash
+///
+/// # Errors
+/// Returns an error if the key is not a signer of the account, the signature does not
+/// match the transaction, or the key already signed
+pub fn add_signature(
+    transaction: &mut Transaction,
+    network: &Network,
+    authorization: &AccountAuthorization,
+    signer_key: &PublicKey,
+    signature: &[u8],
+) -> Result<(), Error> {
+    if authorization.weight_of(signer_key) == 0 {
+        return Err(anyhow::anyhow!(
+            "{} is not a signer of the account",
+            signer_key.account_id()
+        ));
+    }
+
+    let decorated_signature = DecoratedSignature::new(
+        SignatureHint::from_public_key(signer_key),
+        Signature::from_slice(signature)
+            .map_err(|_| anyhow::anyhow!("Signature is not a valid ed25519 signature"))?,
+    );
+
+    let hash = transaction.hash(network)?;
+    if !is_signed_by(&decorated_signature, signer_key, &hash) {
+        return Err(anyhow::anyhow!("Signature does not match the transaction"));
+    }
+
+    add_decorated_signature(transaction, network, authorization, decorated_signature)?;
+    Ok(())
+}
+
+/// Copies the valid, new signatures of account signers from another envelope of the
+/// same transaction, e.g. one a co-signer signed in their own wallet
+///
+/// # Returns
+/// * `Result<usize, Error>` - The number of signatures added
+pub fn merge_signatures(
+    transaction: &mut Transaction,
+    network: &Network,
+    authorization: &AccountAuthorization,
+    signed: &Transaction,
+) -> Result<usize, Error> {
+    let hash = transaction.hash(network)?;
+    if signed.hash(network)? != hash {
+        return Err(anyhow::anyhow!("Envelope is for a different transaction"));
+    }
+
+    let mut added = 0;
+    for signature in signed.signatures() {
+        let signer = authorization
+            .signers
+            .iter()
+            .find(|(key, _)| is_signed_by(signature, key, &hash));
+
+        let Some((key, _)) = signer else {
+            continue;
+        };
+
+        let already_signed = transaction
+            .signatures()
+            .iter()
+            .any(|existing| is_signed_by(existing, key, &hash));
+
+        if !already_signed {
+            transaction.signatures_mut().push(signature.clone());
+            added += 1;
+        }
+    }
+
+    Ok(added)
+}
+
+/// Checks that every signature on an externally signed transaction was made by a signer
+/// of the account for this network, and that together they meet the required weight
+///
+/// Signatures made for another network passphrase do not verify against the hash and
+/// are rejected like any other foreign signature, as Horizon would reject the extra
+/// signature too.
+///
+/// # Arguments
+/// * `transaction` - The signed transaction
+/// * `network` - The network the transaction must be signed for
+/// * `authorization` - The signers and thresholds of the source account
+/// * `required_weight` - The weight the signatures must add up to
+///
+/// # Returns
+/// * `Result<u32, Error>` - The weight the signatures add up to
+pub fn verify_signatures(
+    transaction: &Transaction,
+    network: &Network,
+    authorization: &AccountAuthorization,
+    required_weight: u32,
+) -> Result<u32, Error> {
+    let hash = transaction.hash(network)?;
+
+    for signature in transaction.signatures() {
+        if !authorization
+            .signers
+            .iter()
+            .any(|(key, _)| is_signed_by(signature, key, &hash))
+        {
+            return Err(anyhow::anyhow!(
+                "Transaction carries a signature that is not from a signer of the account on this network"
+            ));
+        }
+    }
+
+    let weight = collected_weight(transaction, network, authorization)?;
+    if weight < required_weight.max(1) {
+        return Err(anyhow::anyhow!(
+            "Signatures add up to weight {}, {} is required",
+            weight,
+            required_weight.max(1)
+        ));
+    }
+
+    Ok(weight)
+}
+
+/// Returns when a transaction stops being valid, taken from the upper time bound.
+/// `None` means the transaction never expires.
+pub fn transaction_expiry(transaction: &Transaction) -> Option<DateTime<Utc>> {
+    transaction
+        .time_bounds()
+        .as_ref()
+        .and_then(|time_bounds| *time_bounds.upper())
+}
+
+/// Encodes a transaction and its signatures as a base64 envelope
+pub fn encode_transaction(transaction: &Transaction) -> Result<String, Error> {
+    Ok(transaction.to_envelope().xdr_base64()?)
+}
+
+/// Decodes a base64 envelope into a transaction
+///
+/// # Errors
+/// Returns an error if the envelope is malformed or a fee bump envelope
+pub fn decode_transaction(envelope_xdr: &str) -> Result<Transaction, Error> {
+    let envelope = TransactionEnvelope::from_xdr_base64(envelope_xdr)
+        .map_err(|_| anyhow::anyhow!("Transaction envelope is not valid XDR"))?;
+
+    envelope
+        .as_transaction()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Fee bump envelopes are not supported"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_base::amount::Stroops;
+    use stellar_base::operations::{PaymentOperationBuilder, SetOptionsOperationBuilder};
+    use stellar_base::time_bounds::TimeBounds;
+    use stellar_base::{Asset, KeyPair};
+
+    fn payment_transaction(source: &KeyPair) -> Transaction {
+        let payment = PaymentOperationBuilder::new()
+            .with_destination(KeyPair::random().unwrap().public_key().clone())
+            .with_asset(Asset::new_native())
+            .with_amount(Stroops::new(100))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        Transaction::builder(source.public_key().clone(), 1, Stroops::new(100))
+            .add_operation(payment)
+            .with_time_bounds(TimeBounds::always_valid())
+            .into_transaction()
+            .unwrap()
+    }
+
+    fn authorization(signers: &[(&KeyPair, u32)], threshold: u32) -> AccountAuthorization {
+        AccountAuthorization {
+            signers: signers
+                .iter()
+                .map(|(keypair, weight)| (keypair.public_key().clone(), *weight))
+                .collect(),
+            low_threshold: threshold,
+            medium_threshold: threshold,
+            high_threshold: threshold,
+        }
+    }
+
+    #[test]
+    fn test_required_threshold_level() {
+        let source = KeyPair::random().unwrap();
+        assert_eq!(
+            required_threshold_level(&payment_transaction(&source)),
+            ThresholdLevel::Medium
+        );
+
+        let set_options = SetOptionsOperationBuilder::new()
+            .with_master_weight(Some(0))
+            .build()
+            .unwrap();
+        let transaction = Transaction::builder(source.public_key().clone(), 1, Stroops::new(100))
+            .add_operation(set_options)
+            .into_transaction()
+            .unwrap();
+        assert_eq!(required_threshold_level(&transaction), ThresholdLevel::High);
+    }
+
+    #[test]
+    fn test_collects_weight_until_threshold() {
+        let network = Network::new_test();
+        let master = KeyPair::random().unwrap();
+        let cosigner = KeyPair::random().unwrap();
+        let authorization = authorization(&[(&master, 1), (&cosigner, 1)], 2);
+
+        let mut transaction = payment_transaction(&master);
+        let hash = transaction.hash(&network).unwrap();
+
+        transaction
+            .signatures_mut()
+            .push(master.sign_decorated(&hash));
+        assert_eq!(
+            collected_weight(&transaction, &network, &authorization).unwrap(),
+            1
+        );
+
+        add_signature(
+            &mut transaction,
+            &network,
+            &authorization,
+            cosigner.public_key(),
+            &cosigner.sign(&hash).to_vec(),
+        )
+        .unwrap();
+        assert_eq!(
+            collected_weight(&transaction, &network, &authorization).unwrap(),
+            2
+        );
+
+        // Signing twice does not count twice
+        assert!(add_signature(
+            &mut transaction,
+            &network,
+            &authorization,
+            cosigner.public_key(),
+            &cosigner.sign(&hash).to_vec(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rejects_foreign_or_invalid_signatures() {
+        let network = Network::new_test();
+        let master = KeyPair::random().unwrap();
+        let stranger = KeyPair::random().unwrap();
+        let authorization = authorization(&[(&master, 1)], 1);
+
+        let mut transaction = payment_transaction(&master);
+        let hash = transaction.hash(&network).unwrap();
+
+        assert!(add_signature(
+            &mut transaction,
+            &network,
+            &authorization,
+            stranger.public_key(),
+            &stranger.sign(&hash).to_vec(),
+        )
+        .is_err());
+
+        assert!(add_signature(
+            &mut transaction,
+            &network,
+            &authorization,
+            master.public_key(),
+            &master.sign(b"something else").to_vec(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_merge_signatures_from_envelope() {
+        let network = Network::new_test();
+        let master = KeyPair::random().unwrap();
+        let cosigner = KeyPair::random().unwrap();
+        let stranger = KeyPair::random().unwrap();
+        let authorization = authorization(&[(&master, 1), (&cosigner, 2)], 3);
+
+        let mut transaction = payment_transaction(&master);
+
+        let mut signed = decode_transaction(&encode_transaction(&transaction).unwrap()).unwrap();
+        signed.sign(&cosigner, &network).unwrap();
+        signed.sign(&stranger, &network).unwrap();
+
+        let added = merge_signatures(&mut transaction, &network, &authorization, &signed).unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(
+            collected_weight(&transaction, &network, &authorization).unwrap(),
+            2
+        );
+
+        let other = payment_transaction(&master);
+        assert!(merge_signatures(&mut transaction, &network, &authorization, &other).is_err());
+    }
+
+    #[test]
+    fn test_verify_signatures_checks_network_and_weight() {
+        let network = Network::new_test();
+        let master = KeyPair::random().unwrap();
+        let cosigner = KeyPair::random().unwrap();
+        let authorization = authorization(&[(&master, 1), (&cosigner, 1)], 2);
+
+        let mut transaction = payment_transaction(&master);
+        transaction.sign(&master, &network).unwrap();
+        assert!(verify_signatures(&transaction, &network, &authorization, 2).is_err());
+
+        transaction.sign(&cosigner, &network).unwrap();
+        assert_eq!(
+            verify_signatures(&transaction, &network, &authorization, 2).unwrap(),
+            2
+        );
+
+        // Signed for the public network instead of the configured one
+        let mut other_network = payment_transaction(&master);
+        other_network.sign(&master, &Network::new_public()).unwrap();
+        other_network
+            .sign(&cosigner, &Network::new_public())
+            .unwrap();
+        assert!(verify_signatures(&other_network, &network, &authorization, 2).is_err());
+    }
+
+    #[test]
+    fn test_transaction_expiry_from_time_bounds() {
+        let master = KeyPair::random().unwrap();
+        assert_eq!(transaction_expiry(&payment_transaction(&master)), None);
+
+        let transaction = Transaction::builder(master.public_key().clone(), 1, Stroops::new(100))
+            .add_operation(
+                SetOptionsOperationBuilder::new()
+                    .with_master_weight(Some(1))
+                    .build()
+                    .unwrap(),
+            )
+            .with_time_bounds(TimeBounds::valid_for(chrono::Duration::minutes(5)))
+            .into_transaction()
+            .unwrap();
+        let expiry = transaction_expiry(&transaction).unwrap();
+        assert!(expiry > Utc::now() && expiry <= Utc::now() + chrono::Duration::minutes(5));
+    }
+}
@@ -0,0 +1,215 @@
+//! QR codes (ISO/IEC 18004) for payment request URIs.
+//!
+//! Data is encoded by the `qrcode` crate at error correction level M, in the smallest
+//! version that holds it. Codes are rendered here as SVG or as a black and white PNG,
+//! both with the four module quiet zone scanners expect.
+
+use std::io::Write;
+
+use anyhow::Error;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use qrcode::types::{Color, QrError};
+use qrcode::{EcLevel, Version};
+
+/// Modules of light border around the code
+pub const QUIET_ZONE: usize = 4;
+
+/// A QR code, as a square grid of dark and light modules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    /// Dark modules, row by row
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes bytes in the smallest version that holds them
+    ///
+    /// # Arguments
+    /// * `data` - The bytes to encode, e.g. a URI
+    ///
+    /// # Returns
+    /// * `Result<QrCode, Error>` - The code, or an error if the data does not fit in
+    ///   version 40
+    pub fn encode(data: &[u8]) -> Result<Self, Error> {
+        let code =
+            qrcode::QrCode::with_error_correction_level(data, EcLevel::M).map_err(|error| {
+                match error {
+                    QrError::DataTooLong => {
+                        anyhow::anyhow!("{} bytes is too long for a QR code", data.len())
+                    }
+                    error => anyhow::anyhow!("Failed to encode a QR code: {}", error),
+                }
+            })?;
+
+        let Version::Normal(version) = code.version() else {
+            return Err(anyhow::anyhow!("Expected a QR code, got a Micro QR code"));
+        };
+
+        Ok(QrCode {
+            version: version as usize,
+            size: code.width(),
+            modules: code
+                .to_colors()
+                .into_iter()
+                .map(|color| color == Color::Dark)
+                .collect(),
+        })
+    }
+
+    /// Returns the version, 1 to 40
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// Returns the width of the code in modules, without the quiet zone
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns whether the module in column `x` and row `y` is dark. Modules outside
+    /// the code are light.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// Renders the code as an SVG image, one unit per module
+    pub fn to_svg(&self) -> String {
+        let dimension = self.size + QUIET_ZONE * 2;
+
+        let mut path = String::new();
+        for y in 0..self.size {
+            let mut x = 0;
+            while x < self.size {
+                if !self.is_dark(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while self.is_dark(x, y) {
+                    x += 1;
+                }
+                path.push_str(&format!(
+                    "M{},{}h{}v1h-{}z",
+                    start + QUIET_ZONE,
+                    y + QUIET_ZONE,
+                    x - start,
+                    x - start
+                ));
+            }
+        }
+
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" ",
+                "viewBox=\"0 0 {0} {0}\" shape-rendering=\"crispEdges\">\n",
+                "<rect width=\"100%\" height=\"100%\" fill=\"#FFFFFF\"/>\n",
+                "<path d=\"{1}\" fill=\"#000000\"/>\n",
+                "</svg>\n"
+            ),
+            dimension, path
+        )
+    }
+
+    /// Renders the code as a 1-bit grayscale PNG image
+    ///
+    /// # Arguments
+    /// * `scale` - Pixels per module, at least 1
+    pub fn to_png(&self, scale: usize) -> Result<Vec<u8>, Error> {
+        if scale == 0 {
+            return Err(anyhow::anyhow!("Scale must be at least 1"));
+        }
+
+        let width = (self.size + QUIET_ZONE * 2) * scale;
+        let row_bytes = width.div_ceil(8);
+
+        // Every scanline starts with filter type 0; set bits are white
+        let mut scanlines = Vec::with_capacity((row_bytes + 1) * width);
+        for pixel_y in 0..width {
+            let y = (pixel_y / scale).wrapping_sub(QUIET_ZONE);
+            let mut row = vec![0xFFu8; row_bytes];
+            for pixel_x in 0..width {
+                let x = (pixel_x / scale).wrapping_sub(QUIET_ZONE);
+                if self.is_dark(x, y) {
+                    row[pixel_x / 8] &= !(0x80 >> (pixel_x % 8));
+                }
+            }
+            scanlines.push(0);
+            scanlines.extend_from_slice(&row);
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&scanlines)?;
+        let image_data = encoder.finish()?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        // Bit depth 1, grayscale, deflate, adaptive filtering, no interlace
+        header.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &image_data);
+        write_png_chunk(&mut png, b"IEND", &[]);
+
+        Ok(png)
+    }
+}
+
+/// Appends a chunk with its length and CRC to a PNG stream
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+
+    let mut crc = Crc::new();
+    crc.update(chunk_type);
+    crc.update(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_picks_smallest_version() {
+        assert_eq!(QrCode::encode(b"web+stellar:").unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[0; 14]).unwrap().version(), 1);
+        assert_eq!(QrCode::encode(&[0; 15]).unwrap().version(), 2);
+        assert_eq!(QrCode::encode(&[0; 2331]).unwrap().version(), 40);
+        assert!(QrCode::encode(&[0; 2332]).is_err());
+        assert_eq!(QrCode::encode(&[0; 40]).unwrap().size(), 3 * 4 + 17);
+    }
+
+    #[test]
+    fn test_function_patterns() {
+        let code = QrCode::encode(b"web+stellar:pay?destination=x").unwrap();
+        let size = code.size();
+
+        for (x, y) in [(0, 0), (size - 7, 0), (0, size - 7)] {
+            assert!(code.is_dark(x, y) && code.is_dark(x + 6, y + 6));
+            assert!(!code.is_dark(x + 1, y + 1) && code.is_dark(x + 3, y + 3));
+        }
+        assert!((8..size - 8).all(|i| code.is_dark(i, 6) == (i % 2 == 0)));
+        assert!(code.is_dark(8, size - 8));
+    }
+
+    #[test]
+    fn test_renders_svg_and_png() {
+        let code = QrCode::encode(b"web+stellar:").unwrap();
+
+        let svg = code.to_svg();
+        assert!(svg.contains("viewBox=\"0 0 29 29\""));
+        assert!(svg.contains("M4,4h7v1h-7z"));
+
+        let png = code.to_png(4).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x74\0\0\0\x74\x01\0"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+        assert!(code.to_png(0).is_err());
+    }
+}
@@ -0,0 +1,533 @@
+//! Transactions with operations newer than stellar-base can encode.
+//!
+//! stellar-base 0.5 speaks protocol 15, so it can neither build `SetTrustLineFlags`,
+//! `Clawback` or `ClawbackClaimableBalance` nor set `AUTH_CLAWBACK_ENABLED` on an account. A [`RawTransaction`] mixes operations
+//! encoded here with those stellar-base builds, and is signed, hashed and submitted
+//! like any other transaction through [`SignedTransaction`].
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use openssl::sha::sha256;
+use stellar_base::amount::Stroops;
+use stellar_base::asset::CreditAsset;
+use stellar_base::claim::ClaimableBalanceId;
+use stellar_base::signature::DecoratedSignature;
+use stellar_base::time_bounds::TimeBounds;
+use stellar_base::xdr::XDRSerialize;
+use stellar_base::{Asset, Network, Operation, PublicKey, Transaction};
+
+use crate::multisig::{encode_transaction, transaction_expiry};
+use crate::signer::Signer;
+use crate::stellar_chain::MAX_OPERATIONS_PER_TRANSACTION;
+
+/// Fee paid per operation, in stroops
+const BASE_FEE: u32 = 100;
+
+const ENVELOPE_TYPE_TX: i32 = 2;
+const KEY_TYPE_ED25519: i32 = 0;
+
+const SET_OPTIONS: i32 = 5;
+const CLAWBACK: i32 = 19;
+const CLAWBACK_CLAIMABLE_BALANCE: i32 = 20;
+const SET_TRUST_LINE_FLAGS: i32 = 21;
+
+const CLAIMABLE_BALANCE_ID_TYPE_V0: i32 = 0;
+
+/// Account flags of an issuer
+pub const AUTH_REQUIRED_FLAG: u32 = 0x1;
+pub const AUTH_REVOCABLE_FLAG: u32 = 0x2;
+pub const AUTH_IMMUTABLE_FLAG: u32 = 0x4;
+pub const AUTH_CLAWBACK_ENABLED_FLAG: u32 = 0x8;
+
+/// Trustline flags an issuer sets on its holders
+pub const AUTHORIZED_FLAG: u32 = 0x1;
+pub const AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG: u32 = 0x2;
+
+/// The flags an issuer account controls its assets with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IssuerFlags {
+    /// Holders need the issuer to authorize their trustline before receiving the asset
+    pub auth_required: bool,
+    /// The issuer can revoke or freeze a holder's authorization
+    pub auth_revocable: bool,
+    /// The issuer can claw the asset back from holders
+    pub auth_clawback_enabled: bool,
+    /// None of the flags can change again
+    pub auth_immutable: bool,
+}
+
+impl IssuerFlags {
+    /// Returns the flags as account flag bits
+    pub fn bits(&self) -> u32 {
+        [
+            (self.auth_required, AUTH_REQUIRED_FLAG),
+            (self.auth_revocable, AUTH_REVOCABLE_FLAG),
+            (self.auth_clawback_enabled, AUTH_CLAWBACK_ENABLED_FLAG),
+            (self.auth_immutable, AUTH_IMMUTABLE_FLAG),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |bits, (_, flag)| bits | flag)
+    }
+
+    /// Returns the flags set in account flag bits
+    pub fn from_bits(bits: u32) -> Self {
+        Self {
+            auth_required: bits & AUTH_REQUIRED_FLAG != 0,
+            auth_revocable: bits & AUTH_REVOCABLE_FLAG != 0,
+            auth_clawback_enabled: bits & AUTH_CLAWBACK_ENABLED_FLAG != 0,
+            auth_immutable: bits & AUTH_IMMUTABLE_FLAG != 0,
+        }
+    }
+
+    /// Returns an error for a combination the network rejects
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.auth_clawback_enabled && !self.auth_revocable {
+            return Err(anyhow::anyhow!(
+                "AUTH_CLAWBACK_ENABLED requires AUTH_REVOCABLE"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The authorization an issuer gives a holder's trustline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustlineAuthorization {
+    /// The holder can send and receive the asset
+    Authorized,
+    /// The holder is frozen: it keeps its balance and open offers but cannot transact
+    MaintainLiabilities,
+    /// The holder's authorization is revoked and its offers are removed
+    Deauthorized,
+}
+
+impl TrustlineAuthorization {
+    /// Returns the trustline flags to set
+    pub fn set_flags(&self) -> u32 {
+        match self {
+            TrustlineAuthorization::Authorized => AUTHORIZED_FLAG,
+            TrustlineAuthorization::MaintainLiabilities => AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG,
+            TrustlineAuthorization::Deauthorized => 0,
+        }
+    }
+
+    /// Returns the trustline flags to clear
+    pub fn clear_flags(&self) -> u32 {
+        (AUTHORIZED_FLAG | AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG) & !self.set_flags()
+    }
+}
+
+/// An operation of a [`RawTransaction`].
+pub enum RawOperation {
+    /// An operation stellar-base builds
+    Standard(Operation),
+    /// A `SetOptions` operation changing only account flags, which may include flags
+    /// stellar-base does not know
+    SetAccountFlags {
+        source_account: PublicKey,
+        set_flags: u32,
+        clear_flags: u32,
+    },
+    /// A `SetTrustLineFlags` operation, from the issuer of the asset
+    SetTrustLineFlags {
+        source_account: PublicKey,
+        trustor: PublicKey,
+        asset: CreditAsset,
+        authorization: TrustlineAuthorization,
+    },
+    /// A `Clawback` operation, burning an amount of the asset held by an account
+    Clawback {
+        source_account: PublicKey,
+        asset: CreditAsset,
+        from: PublicKey,
+        amount: Stroops,
+    },
+    /// A `ClawbackClaimableBalance` operation, burning a claimable balance of the asset
+    ClawbackClaimableBalance {
+        source_account: PublicKey,
+        balance_id: ClaimableBalanceId,
+    },
+}
+
+impl RawOperation {
+    /// Appends the XDR encoding of the operation
+    fn write_xdr(&self, out: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            RawOperation::Standard(operation) => {
+                operation.write_xdr(out)?;
+            }
+            RawOperation::SetAccountFlags {
+                source_account,
+                set_flags,
+                clear_flags,
+            } => {
+                write_source_account(out, source_account);
+                out.extend(SET_OPTIONS.to_be_bytes());
+                // inflationDest
+                write_absent(out);
+                write_optional_u32(out, *clear_flags);
+                write_optional_u32(out, *set_flags);
+                // masterWeight, lowThreshold, medThreshold, highThreshold, homeDomain, signer
+                for _ in 0..6 {
+                    write_absent(out);
+                }
+            }
+            RawOperation::SetTrustLineFlags {
+                source_account,
+                trustor,
+                asset,
+                authorization,
+            } => {
+                write_source_account(out, source_account);
+                out.extend(SET_TRUST_LINE_FLAGS.to_be_bytes());
+                write_account_id(out, trustor);
+                Asset::Credit(asset.clone()).write_xdr(out)?;
+                out.extend(authorization.clear_flags().to_be_bytes());
+                out.extend(authorization.set_flags().to_be_bytes());
+            }
+            RawOperation::Clawback {
+                source_account,
+                asset,
+                from,
+                amount,
+            } => {
+                write_source_account(out, source_account);
+                out.extend(CLAWBACK.to_be_bytes());
+                Asset::Credit(asset.clone()).write_xdr(out)?;
+                write_account_id(out, from);
+                out.extend(amount.to_i64().to_be_bytes());
+            }
+            RawOperation::ClawbackClaimableBalance {
+                source_account,
+                balance_id,
+            } => {
+                write_source_account(out, source_account);
+                out.extend(CLAWBACK_CLAIMABLE_BALANCE.to_be_bytes());
+                out.extend(CLAIMABLE_BALANCE_ID_TYPE_V0.to_be_bytes());
+                out.extend(balance_id.as_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A transaction assembled from [`RawOperation`]s.
+pub struct RawTransaction {
+    source_account: PublicKey,
+    sequence: i64,
+    time_bounds: TimeBounds,
+    operations: Vec<RawOperation>,
+    signatures: Vec<DecoratedSignature>,
+}
+
+impl RawTransaction {
+    /// Creates an unsigned transaction
+    ///
+    /// # Arguments
+    /// * `source_account` - The account paying the fee and consuming the sequence number
+    /// * `sequence` - The sequence number of the transaction
+    /// * `time_bounds` - The period the transaction is valid for
+    /// * `operations` - The operations, in order
+    pub fn new(
+        source_account: PublicKey,
+        sequence: i64,
+        time_bounds: TimeBounds,
+        operations: Vec<RawOperation>,
+    ) -> Result<Self, Error> {
+        if operations.is_empty() || operations.len() > MAX_OPERATIONS_PER_TRANSACTION {
+            return Err(anyhow::anyhow!(
+                "A transaction needs 1 to {} operations",
+                MAX_OPERATIONS_PER_TRANSACTION
+            ));
+        }
+
+        Ok(Self {
+            source_account,
+            sequence,
+            time_bounds,
+            operations,
+            signatures: Vec::new(),
+        })
+    }
+
+    /// Signs the transaction with the key of the given account
+    ///
+    /// # Arguments
+    /// * `network` - The network the transaction is for
+    /// * `signer` - The signer holding the account key
+    /// * `account_id` - The id of the account to sign for
+    pub async fn sign<S: Signer>(
+        &mut self,
+        network: &Network,
+        signer: &S,
+        account_id: &str,
+    ) -> Result<(), Error> {
+        let hash = self.hash(network)?;
+        let signature = signer.sign_hash(account_id, &hash).await?;
+        self.signatures.push(signature);
+        Ok(())
+    }
+
+    /// Returns the hash signers sign, over the network id and the transaction body
+    pub fn hash(&self, network: &Network) -> Result<[u8; 32], Error> {
+        let mut payload = network.network_id();
+        payload.extend(ENVELOPE_TYPE_TX.to_be_bytes());
+        payload.extend(self.to_xdr_bytes()?);
+        Ok(sha256(&payload))
+    }
+
+    /// Returns the XDR encoding of the transaction body
+    fn to_xdr_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+        write_account_id(&mut out, &self.source_account);
+        out.extend((BASE_FEE * self.operations.len() as u32).to_be_bytes());
+        out.extend(self.sequence.to_be_bytes());
+        out.extend(1u32.to_be_bytes());
+        self.time_bounds.write_xdr(&mut out)?;
+        stellar_base::Memo::None.write_xdr(&mut out)?;
+        out.extend((self.operations.len() as u32).to_be_bytes());
+        for operation in &self.operations {
+            operation.write_xdr(&mut out)?;
+        }
+        // ext
+        out.extend(0i32.to_be_bytes());
+        Ok(out)
+    }
+}
+
+/// A signed transaction the service can record and submit.
+pub trait SignedTransaction {
+    /// Returns the hash identifying the transaction on the network
+    fn transaction_hash(&self, network: &Network) -> Result<Vec<u8>, Error>;
+
+    /// Returns the base64 envelope XDR to submit
+    fn envelope_xdr(&self) -> Result<String, Error>;
+
+    /// Returns when the transaction stops being valid, if ever
+    fn expiry(&self) -> Option<DateTime<Utc>>;
+}
+
+impl SignedTransaction for Transaction {
+    fn transaction_hash(&self, network: &Network) -> Result<Vec<u8>, Error> {
+        Ok(self.hash(network)?)
+    }
+
+    fn envelope_xdr(&self) -> Result<String, Error> {
+        encode_transaction(self)
+    }
+
+    fn expiry(&self) -> Option<DateTime<Utc>> {
+        transaction_expiry(self)
+    }
+}
+
+impl SignedTransaction for RawTransaction {
+    fn transaction_hash(&self, network: &Network) -> Result<Vec<u8>, Error> {
+        Ok(self.hash(network)?.to_vec())
+    }
+
+    fn envelope_xdr(&self) -> Result<String, Error> {
+        let mut out = Vec::new();
+        out.extend(ENVELOPE_TYPE_TX.to_be_bytes());
+        out.extend(self.to_xdr_bytes()?);
+        out.extend((self.signatures.len() as u32).to_be_bytes());
+        for signature in &self.signatures {
+            out.extend(signature.hint().to_vec());
+            write_opaque(&mut out, &signature.signature().to_vec());
+        }
+        Ok(openssl::base64::encode_block(&out))
+    }
+
+    fn expiry(&self) -> Option<DateTime<Utc>> {
+        *self.time_bounds.upper()
+    }
+}
+
+fn write_account_id(out: &mut Vec<u8>, public_key: &PublicKey) {
+    out.extend(KEY_TYPE_ED25519.to_be_bytes());
+    out.extend(public_key.as_bytes());
+}
+
+fn write_source_account(out: &mut Vec<u8>, public_key: &PublicKey) {
+    out.extend(1u32.to_be_bytes());
+    write_account_id(out, public_key);
+}
+
+fn write_absent(out: &mut Vec<u8>) {
+    out.extend(0u32.to_be_bytes());
+}
+
+/// Writes an optional uint32 that is present only when non zero
+fn write_optional_u32(out: &mut Vec<u8>, value: u32) {
+    if value == 0 {
+        write_absent(out);
+    } else {
+        out.extend(1u32.to_be_bytes());
+        out.extend(value.to_be_bytes());
+    }
+}
+
+fn write_opaque(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend((bytes.len() as u32).to_be_bytes());
+    out.extend(bytes);
+    out.extend(vec![0; (4 - bytes.len() % 4) % 4]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::KeyPairSigner;
+    use stellar_base::account::AccountFlags;
+    use stellar_base::operations::{ChangeTrustOperationBuilder, SetOptionsOperationBuilder};
+    use stellar_sdk::Keypair;
+
+    fn new_signer() -> (KeyPairSigner, PublicKey) {
+        let mut keypair = Keypair::random().unwrap();
+        let public_key = PublicKey::from_account_id(&keypair.public_key()).unwrap();
+        let signer = KeyPairSigner::new()
+            .with_account("issuer", &keypair.secret_key().unwrap())
+            .unwrap();
+        (signer, public_key)
+    }
+
+    #[tokio::test]
+    async fn test_matches_stellar_base_encoding() {
+        let (signer, issuer) = new_signer();
+        let network = Network::new_test();
+        let time_bounds = TimeBounds::valid_for(chrono::Duration::seconds(300));
+        let asset = CreditAsset::new("TEST".to_string(), issuer.clone()).unwrap();
+
+        let change_trust = || {
+            ChangeTrustOperationBuilder::new()
+                .with_asset(Asset::Credit(asset.clone()))
+                .with_limit(Some(Stroops::new(1000)))
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+        let set_options = || {
+            SetOptionsOperationBuilder::new()
+                .with_source_account(issuer.clone())
+                .with_set_flags(Some(
+                    AccountFlags::AUTH_REQUIRED | AccountFlags::AUTH_REVOCABLE,
+                ))
+                .build()
+                .unwrap()
+        };
+
+        let mut expected = Transaction::builder(issuer.clone(), 42, Stroops::new(100))
+            .add_operation(change_trust())
+            .add_operation(set_options())
+            .with_time_bounds(time_bounds.clone())
+            .into_transaction()
+            .unwrap();
+        let hash = expected.hash(&network).unwrap();
+        let signature = signer.sign_hash("issuer", &hash).await.unwrap();
+        expected.signatures_mut().push(signature);
+
+        let mut transaction = RawTransaction::new(
+            issuer.clone(),
+            42,
+            time_bounds,
+            vec![
+                RawOperation::Standard(change_trust()),
+                RawOperation::SetAccountFlags {
+                    source_account: issuer.clone(),
+                    set_flags: AUTH_REQUIRED_FLAG | AUTH_REVOCABLE_FLAG,
+                    clear_flags: 0,
+                },
+            ],
+        )
+        .unwrap();
+        transaction.sign(&network, &signer, "issuer").await.unwrap();
+
+        assert_eq!(transaction.hash(&network).unwrap().to_vec(), hash);
+        assert_eq!(
+            transaction.envelope_xdr().unwrap(),
+            expected.envelope_xdr().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_trust_line_flags_encoding() {
+        let (_, issuer) = new_signer();
+        let (_, trustor) = new_signer();
+        let asset = CreditAsset::new("TEST".to_string(), issuer.clone()).unwrap();
+
+        let mut out = Vec::new();
+        RawOperation::SetTrustLineFlags {
+            source_account: issuer.clone(),
+            trustor: trustor.clone(),
+            asset,
+            authorization: TrustlineAuthorization::MaintainLiabilities,
+        }
+        .write_xdr(&mut out)
+        .unwrap();
+
+        // source, type, trustor, alphanum4 asset, clear flags, set flags
+        assert_eq!(out.len(), 40 + 4 + 36 + 44 + 4 + 4);
+        assert_eq!(&out[40..44], &SET_TRUST_LINE_FLAGS.to_be_bytes());
+        assert_eq!(&out[48..80], trustor.as_bytes());
+        assert_eq!(&out[124..128], &AUTHORIZED_FLAG.to_be_bytes());
+        assert_eq!(
+            &out[128..132],
+            &AUTHORIZED_TO_MAINTAIN_LIABILITIES_FLAG.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn test_clawback_encoding() {
+        let (_, issuer) = new_signer();
+        let (_, holder) = new_signer();
+        let asset = CreditAsset::new("TEST".to_string(), issuer.clone()).unwrap();
+
+        let mut out = Vec::new();
+        RawOperation::Clawback {
+            source_account: issuer.clone(),
+            asset,
+            from: holder.clone(),
+            amount: Stroops::new(25),
+        }
+        .write_xdr(&mut out)
+        .unwrap();
+
+        // source, type, alphanum4 asset, from, amount
+        assert_eq!(out.len(), 40 + 4 + 44 + 36 + 8);
+        assert_eq!(&out[40..44], &CLAWBACK.to_be_bytes());
+        assert_eq!(&out[92..124], holder.as_bytes());
+        assert_eq!(&out[124..132], &25i64.to_be_bytes());
+
+        let balance_id = ClaimableBalanceId::new(vec![7; 32]).unwrap();
+        let mut out = Vec::new();
+        RawOperation::ClawbackClaimableBalance {
+            source_account: issuer,
+            balance_id,
+        }
+        .write_xdr(&mut out)
+        .unwrap();
+
+        assert_eq!(out.len(), 40 + 4 + 4 + 32);
+        assert_eq!(&out[40..44], &CLAWBACK_CLAIMABLE_BALANCE.to_be_bytes());
+        assert_eq!(&out[48..80], &[7; 32]);
+    }
+
+    #[test]
+    fn test_issuer_flags() {
+        let flags = IssuerFlags {
+            auth_required: true,
+            auth_revocable: true,
+            auth_clawback_enabled: true,
+            auth_immutable: false,
+        };
+        assert_eq!(flags.bits(), 0xb);
+        assert_eq!(IssuerFlags::from_bits(0xb), flags);
+        assert!(flags.validate().is_ok());
+
+        let flags = IssuerFlags {
+            auth_clawback_enabled: true,
+            ..IssuerFlags::default()
+        };
+        assert!(flags.validate().is_err());
+    }
+}
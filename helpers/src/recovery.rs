@@ -0,0 +1,690 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::rsa::{Padding, Rsa};
+use openssl::sha::sha256;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use stellar_base::KeyPair;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::secret::{SecretBytes, SecretString};
+
+/// Format version written into every recovery bundle.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// PBKDF2-HMAC-SHA256 iterations used to derive a key from a bundle passphrase.
+pub const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+const BUNDLE_KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const SALT_LEN: usize = 16;
+
+/// Nonce, ciphertext and tag produced by one AES-256-GCM encryption.
+type AeadParts = ([u8; NONCE_LEN], Vec<u8>, [u8; TAG_LEN]);
+
+/// A single custodial account and its secret seed, as stored in a recovery bundle.
+#[derive(Debug)]
+pub struct RecoveryEntry {
+    pub account_id: String,
+    pub stellar_address: String,
+    pub account_type: String,
+    pub status: String,
+    pub secret_key: SecretString,
+}
+
+/// Serialisable form of [`RecoveryEntry`], only ever held inside a zeroized buffer.
+#[derive(Serialize, Deserialize)]
+struct PlainRecoveryEntry {
+    account_id: String,
+    stellar_address: String,
+    account_type: String,
+    status: String,
+    secret_key: String,
+}
+
+/// How the bundle key of a recovery bundle is protected.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeyProtection {
+    /// The bundle key is wrapped with a key derived from a passphrase.
+    Passphrase {
+        salt: String,
+        iterations: u32,
+        nonce: String,
+        wrapped_key: String,
+        tag: String,
+    },
+    /// The bundle key is wrapped for an RSA recipient public key using OAEP.
+    Recipient { wrapped_key: String },
+    /// The bundle key is split into Shamir shares held by custodians.
+    Shamir {
+        threshold: u8,
+        shares: u8,
+        key_check: String,
+    },
+}
+
+/// An encrypted, integrity-checked backup of custodial account seeds.
+///
+/// The entries are encrypted with a random bundle key using AES-256-GCM; the header
+/// fields are bound as associated data so any tampering is detected on open.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecoveryBundle {
+    pub format_version: u32,
+    pub created_at: String,
+    pub account_count: usize,
+    pub key_protection: KeyProtection,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+/// How a new bundle key should be protected.
+pub enum BundleProtection {
+    Passphrase(SecretString),
+    /// PEM encoded RSA public key of the recipient
+    Recipient(Vec<u8>),
+    Shamir {
+        shares: u8,
+        threshold: u8,
+    },
+}
+
+/// The material needed to recover the bundle key of an existing bundle.
+pub enum BundleUnlock {
+    Passphrase(SecretString),
+    /// PEM encoded RSA private key of the recipient
+    RecipientKey(SecretBytes),
+    /// Hex encoded Shamir shares, at least `threshold` of them
+    Shares(Vec<SecretString>),
+}
+
+/// A sealed bundle together with the custodian shares when Shamir protection is used.
+pub struct SealedBundle {
+    pub bundle: RecoveryBundle,
+    pub shares: Vec<SecretString>,
+}
+
+/// Encrypts account seeds into a recovery bundle.
+///
+/// # Arguments
+/// * `entries` - The accounts and seeds to back up
+/// * `protection` - How the bundle key should be protected
+///
+/// # Returns
+/// * `Result<SealedBundle, Error>` - The bundle, plus one share per custodian for Shamir protection
+pub fn seal_bundle(
+    entries: &[RecoveryEntry],
+    protection: BundleProtection,
+) -> Result<SealedBundle, Error> {
+    let mut bundle_key = Zeroizing::new([0u8; BUNDLE_KEY_LEN]);
+    OsRng.fill_bytes(bundle_key.as_mut());
+
+    let mut shares = Vec::new();
+
+    let key_protection = match protection {
+        BundleProtection::Passphrase(passphrase) => {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+
+            let wrapping_key =
+                derive_passphrase_key(passphrase.expose_secret(), &salt, PASSPHRASE_ITERATIONS)?;
+            let (nonce, wrapped_key, tag) =
+                aead_encrypt(wrapping_key.as_ref(), b"", bundle_key.as_ref())?;
+
+            KeyProtection::Passphrase {
+                salt: hex::encode(salt),
+                iterations: PASSPHRASE_ITERATIONS,
+                nonce: hex::encode(nonce),
+                wrapped_key: hex::encode(wrapped_key),
+                tag: hex::encode(tag),
+            }
+        }
+        BundleProtection::Recipient(public_key_pem) => {
+            let rsa = Rsa::public_key_from_pem(&public_key_pem)?;
+            let mut wrapped_key = vec![0u8; rsa.size() as usize];
+            let length =
+                rsa.public_encrypt(bundle_key.as_ref(), &mut wrapped_key, Padding::PKCS1_OAEP)?;
+            wrapped_key.truncate(length);
+
+            KeyProtection::Recipient {
+                wrapped_key: hex::encode(wrapped_key),
+            }
+        }
+        BundleProtection::Shamir {
+            shares: share_count,
+            threshold,
+        } => {
+            shares = shamir::split(bundle_key.as_ref(), share_count, threshold)?
+                .into_iter()
+                .map(|share| SecretString::new(hex::encode(share.as_slice())))
+                .collect();
+
+            KeyProtection::Shamir {
+                threshold,
+                shares: share_count,
+                key_check: hex::encode(sha256(bundle_key.as_ref())),
+            }
+        }
+    };
+
+    let plain_entries: Vec<PlainRecoveryEntry> = entries
+        .iter()
+        .map(|entry| PlainRecoveryEntry {
+            account_id: entry.account_id.clone(),
+            stellar_address: entry.stellar_address.clone(),
+            account_type: entry.account_type.clone(),
+            status: entry.status.clone(),
+            secret_key: entry.secret_key.expose_secret().to_string(),
+        })
+        .collect();
+    let plaintext = Zeroizing::new(serde_json::to_vec(&plain_entries)?);
+
+    // Wipe the plain copies of the seeds now they are serialised
+    for mut entry in plain_entries {
+        entry.secret_key.zeroize();
+    }
+
+    let mut bundle = RecoveryBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        account_count: entries.len(),
+        key_protection,
+        nonce: String::new(),
+        ciphertext: String::new(),
+        tag: String::new(),
+    };
+
+    let (nonce, ciphertext, tag) = aead_encrypt(
+        bundle_key.as_ref(),
+        &bundle_associated_data(&bundle)?,
+        &plaintext,
+    )?;
+    bundle.nonce = hex::encode(nonce);
+    bundle.ciphertext = hex::encode(ciphertext);
+    bundle.tag = hex::encode(tag);
+
+    Ok(SealedBundle { bundle, shares })
+}
+
+/// Decrypts a recovery bundle and checks every seed against its recorded address.
+///
+/// # Arguments
+/// * `bundle` - The bundle to open
+/// * `unlock` - The passphrase, recipient private key or custodian shares
+///
+/// # Returns
+/// * `Result<Vec<RecoveryEntry>, Error>` - The recovered accounts and seeds
+///
+/// # Errors
+/// Returns an error if the unlock material is wrong, the bundle was modified, or a seed
+/// does not match the address it was exported with
+pub fn open_bundle(
+    bundle: &RecoveryBundle,
+    unlock: BundleUnlock,
+) -> Result<Vec<RecoveryEntry>, Error> {
+    if bundle.format_version != BUNDLE_FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported recovery bundle version {}",
+            bundle.format_version
+        ));
+    }
+
+    let bundle_key = recover_bundle_key(&bundle.key_protection, unlock)?;
+
+    let plaintext = Zeroizing::new(
+        aead_decrypt(
+            bundle_key.as_ref(),
+            &bundle_associated_data(bundle)?,
+            &hex::decode(&bundle.nonce)?,
+            &hex::decode(&bundle.ciphertext)?,
+            &hex::decode(&bundle.tag)?,
+        )
+        .map_err(|_| anyhow::anyhow!("Recovery bundle failed its integrity check"))?,
+    );
+
+    let plain_entries: Vec<PlainRecoveryEntry> = serde_json::from_slice(&plaintext)
+        .map_err(|_| anyhow::anyhow!("Recovery bundle contents are malformed"))?;
+
+    if plain_entries.len() != bundle.account_count {
+        return Err(anyhow::anyhow!(
+            "Recovery bundle account count does not match"
+        ));
+    }
+
+    let entries: Vec<RecoveryEntry> = plain_entries
+        .into_iter()
+        .map(|entry| RecoveryEntry {
+            account_id: entry.account_id,
+            stellar_address: entry.stellar_address,
+            account_type: entry.account_type,
+            status: entry.status,
+            secret_key: SecretString::new(entry.secret_key),
+        })
+        .collect();
+
+    for entry in &entries {
+        let keypair = KeyPair::from_str(entry.secret_key.expose_secret()).map_err(|_| {
+            anyhow::anyhow!(
+                "Recovery bundle seed for account {} is invalid",
+                entry.account_id
+            )
+        })?;
+
+        if keypair.public_key().account_id() != entry.stellar_address {
+            return Err(anyhow::anyhow!(
+                "Recovery bundle seed for account {} does not match {}",
+                entry.account_id,
+                entry.stellar_address
+            ));
+        }
+    }
+
+    Ok(entries)
+}
+
+fn recover_bundle_key(
+    key_protection: &KeyProtection,
+    unlock: BundleUnlock,
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    match (key_protection, unlock) {
+        (
+            KeyProtection::Passphrase {
+                salt,
+                iterations,
+                nonce,
+                wrapped_key,
+                tag,
+            },
+            BundleUnlock::Passphrase(passphrase),
+        ) => {
+            let wrapping_key = derive_passphrase_key(
+                passphrase.expose_secret(),
+                &hex::decode(salt)?,
+                *iterations,
+            )?;
+            let bundle_key = aead_decrypt(
+                wrapping_key.as_ref(),
+                b"",
+                &hex::decode(nonce)?,
+                &hex::decode(wrapped_key)?,
+                &hex::decode(tag)?,
+            )
+            .map_err(|_| anyhow::anyhow!("Incorrect recovery bundle passphrase"))?;
+            Ok(Zeroizing::new(bundle_key))
+        }
+        (KeyProtection::Recipient { wrapped_key }, BundleUnlock::RecipientKey(private_key_pem)) => {
+            let rsa = Rsa::private_key_from_pem(private_key_pem.expose_secret())?;
+            let mut bundle_key = Zeroizing::new(vec![0u8; rsa.size() as usize]);
+            let length = rsa
+                .private_decrypt(
+                    &hex::decode(wrapped_key)?,
+                    &mut bundle_key,
+                    Padding::PKCS1_OAEP,
+                )
+                .map_err(|_| anyhow::anyhow!("Recovery bundle was not sealed for this key"))?;
+            bundle_key.truncate(length);
+            Ok(bundle_key)
+        }
+        (
+            KeyProtection::Shamir {
+                threshold,
+                key_check,
+                ..
+            },
+            BundleUnlock::Shares(shares),
+        ) => {
+            if shares.len() < *threshold as usize {
+                return Err(anyhow::anyhow!(
+                    "At least {} custodian shares are required",
+                    threshold
+                ));
+            }
+
+            let decoded_shares = shares
+                .iter()
+                .map(|share| {
+                    hex::decode(share.expose_secret())
+                        .map(Zeroizing::new)
+                        .map_err(|_| anyhow::anyhow!("Custodian share is not valid hex"))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            let bundle_key = shamir::combine(&decoded_shares)?;
+            if hex::encode(sha256(&bundle_key)) != *key_check {
+                return Err(anyhow::anyhow!(
+                    "Custodian shares do not reconstruct the bundle key"
+                ));
+            }
+            Ok(bundle_key)
+        }
+        _ => Err(anyhow::anyhow!(
+            "Unlock material does not match the bundle key protection"
+        )),
+    }
+}
+
+/// Header fields bound to the bundle ciphertext as associated data.
+fn bundle_associated_data(bundle: &RecoveryBundle) -> Result<Vec<u8>, Error> {
+    Ok(format!(
+        "{}|{}|{}|{}",
+        bundle.format_version,
+        bundle.created_at,
+        bundle.account_count,
+        serde_json::to_string(&bundle.key_protection)?
+    )
+    .into_bytes())
+}
+
+fn derive_passphrase_key(
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<Zeroizing<[u8; BUNDLE_KEY_LEN]>, Error> {
+    let mut key = Zeroizing::new([0u8; BUNDLE_KEY_LEN]);
+    pbkdf2_hmac(
+        passphrase.as_bytes(),
+        salt,
+        iterations as usize,
+        MessageDigest::sha256(),
+        key.as_mut(),
+    )?;
+    Ok(key)
+}
+
+fn aead_encrypt(
+    key: &[u8],
+    associated_data: &[u8],
+    data: &[u8],
+) -> Result<AeadParts, Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(&nonce),
+        associated_data,
+        data,
+        &mut tag,
+    )?;
+
+    Ok((nonce, ciphertext, tag))
+}
+
+fn aead_decrypt(
+    key: &[u8],
+    associated_data: &[u8],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, Error> {
+    Ok(decrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(nonce),
+        associated_data,
+        ciphertext,
+        tag,
+    )?)
+}
+
+/// Shamir secret sharing over GF(256), splitting a secret byte by byte.
+///
+/// Each share is encoded as its x coordinate followed by one y value per secret byte.
+pub mod shamir {
+    use anyhow::Error;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+    use zeroize::Zeroizing;
+
+    /// Multiplies two elements of GF(256) using the AES polynomial.
+    fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+        let mut product = 0u8;
+        while b != 0 {
+            if b & 1 != 0 {
+                product ^= a;
+            }
+            let carry = a & 0x80;
+            a <<= 1;
+            if carry != 0 {
+                a ^= 0x1b;
+            }
+            b >>= 1;
+        }
+        product
+    }
+
+    /// Inverts a non-zero element of GF(256) as `a^254`.
+    fn gf_inv(a: u8) -> u8 {
+        let mut result = 1u8;
+        let mut base = a;
+        let mut exponent = 254u8;
+        while exponent != 0 {
+            if exponent & 1 != 0 {
+                result = gf_mul(result, base);
+            }
+            base = gf_mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Splits a secret into `shares` shares, any `threshold` of which recover it.
+    pub fn split(
+        secret: &[u8],
+        shares: u8,
+        threshold: u8,
+    ) -> Result<Vec<Zeroizing<Vec<u8>>>, Error> {
+        if threshold < 2 || shares < threshold {
+            return Err(anyhow::anyhow!(
+                "Shamir sharing needs 2 <= threshold <= shares"
+            ));
+        }
+
+        let mut output: Vec<Zeroizing<Vec<u8>>> = (1..=shares)
+            .map(|x| {
+                let mut share = Vec::with_capacity(secret.len() + 1);
+                share.push(x);
+                Zeroizing::new(share)
+            })
+            .collect();
+
+        let mut coefficients = Zeroizing::new(vec![0u8; threshold as usize]);
+        for secret_byte in secret {
+            coefficients[0] = *secret_byte;
+            OsRng.fill_bytes(&mut coefficients[1..]);
+
+            for share in output.iter_mut() {
+                let x = share[0];
+                // Horner evaluation of the polynomial at x
+                let y = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0u8, |accumulator, coefficient| {
+                        gf_mul(accumulator, x) ^ coefficient
+                    });
+                share.push(y);
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Recovers a secret from shares produced by [`split`].
+    ///
+    /// With fewer than the original threshold of shares the result is unrelated to the
+    /// secret, so callers should verify it against a known check value.
+    pub fn combine(shares: &[Zeroizing<Vec<u8>>]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let first = shares
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No custodian shares supplied"))?;
+        let secret_len = first.len().saturating_sub(1);
+
+        for (index, share) in shares.iter().enumerate() {
+            if share.len() != secret_len + 1 || share[0] == 0 {
+                return Err(anyhow::anyhow!(
+                    "Custodian share {} is malformed",
+                    index + 1
+                ));
+            }
+            if shares[..index].iter().any(|other| other[0] == share[0]) {
+                return Err(anyhow::anyhow!(
+                    "Custodian share {} is a duplicate",
+                    index + 1
+                ));
+            }
+        }
+
+        let mut secret = Zeroizing::new(vec![0u8; secret_len]);
+        for (index, share) in shares.iter().enumerate() {
+            // Lagrange basis polynomial for this share evaluated at x = 0
+            let mut basis = 1u8;
+            for (other_index, other) in shares.iter().enumerate() {
+                if index != other_index {
+                    basis = gf_mul(basis, gf_mul(other[0], gf_inv(other[0] ^ share[0])));
+                }
+            }
+
+            for (position, secret_byte) in secret.iter_mut().enumerate() {
+                *secret_byte ^= gf_mul(share[position + 1], basis);
+            }
+        }
+
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_sdk::Keypair;
+
+    fn test_entries(count: usize) -> Vec<RecoveryEntry> {
+        (0..count)
+            .map(|index| {
+                let mut keypair = Keypair::random().unwrap();
+                RecoveryEntry {
+                    account_id: format!("account-{}", index),
+                    stellar_address: keypair.public_key(),
+                    account_type: "user".to_string(),
+                    status: "active".to_string(),
+                    secret_key: SecretString::new(keypair.secret_key().unwrap()),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shamir_threshold_recovers_secret() {
+        let secret = b"0123456789abcdef0123456789abcdef";
+        let shares = shamir::split(secret, 5, 3).unwrap();
+
+        let subset = vec![shares[4].clone(), shares[0].clone(), shares[2].clone()];
+        assert_eq!(shamir::combine(&subset).unwrap().as_slice(), secret);
+
+        let too_few = vec![shares[1].clone(), shares[3].clone()];
+        assert_ne!(shamir::combine(&too_few).unwrap().as_slice(), secret);
+    }
+
+    #[test]
+    fn test_passphrase_bundle_round_trip() {
+        let entries = test_entries(3);
+        let sealed = seal_bundle(
+            &entries,
+            BundleProtection::Passphrase(SecretString::new("correct horse".to_string())),
+        )
+        .unwrap();
+
+        let opened = open_bundle(
+            &sealed.bundle,
+            BundleUnlock::Passphrase(SecretString::new("correct horse".to_string())),
+        )
+        .unwrap();
+
+        assert_eq!(opened.len(), 3);
+        assert_eq!(opened[1].stellar_address, entries[1].stellar_address);
+        assert_eq!(
+            opened[1].secret_key.expose_secret(),
+            entries[1].secret_key.expose_secret()
+        );
+
+        assert!(open_bundle(
+            &sealed.bundle,
+            BundleUnlock::Passphrase(SecretString::new("wrong".to_string())),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_recipient_bundle_round_trip() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let entries = test_entries(2);
+
+        let sealed = seal_bundle(
+            &entries,
+            BundleProtection::Recipient(rsa.public_key_to_pem().unwrap()),
+        )
+        .unwrap();
+
+        let opened = open_bundle(
+            &sealed.bundle,
+            BundleUnlock::RecipientKey(SecretBytes::new(rsa.private_key_to_pem().unwrap())),
+        )
+        .unwrap();
+
+        assert_eq!(opened.len(), 2);
+    }
+
+    #[test]
+    fn test_shamir_bundle_round_trip() {
+        let entries = test_entries(2);
+        let sealed = seal_bundle(
+            &entries,
+            BundleProtection::Shamir {
+                shares: 3,
+                threshold: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(sealed.shares.len(), 3);
+
+        let shares = vec![
+            SecretString::new(sealed.shares[2].expose_secret().to_string()),
+            SecretString::new(sealed.shares[0].expose_secret().to_string()),
+        ];
+        let opened = open_bundle(&sealed.bundle, BundleUnlock::Shares(shares)).unwrap();
+        assert_eq!(opened.len(), 2);
+
+        let one_share = vec![SecretString::new(
+            sealed.shares[1].expose_secret().to_string(),
+        )];
+        assert!(open_bundle(&sealed.bundle, BundleUnlock::Shares(one_share)).is_err());
+    }
+
+    #[test]
+    fn test_tampered_bundle_is_rejected() {
+        let entries = test_entries(2);
+        let sealed = seal_bundle(
+            &entries,
+            BundleProtection::Passphrase(SecretString::new("passphrase".to_string())),
+        )
+        .unwrap();
+
+        let mut tampered = sealed.bundle.clone();
+        tampered.account_count = 1;
+
+        assert!(open_bundle(
+            &tampered,
+            BundleUnlock::Passphrase(SecretString::new("passphrase".to_string())),
+        )
+        .is_err());
+    }
+}
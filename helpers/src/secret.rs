@@ -0,0 +1,225 @@
+use std::fmt;
+
+use zeroize::Zeroizing;
+
+/// Placeholder written wherever secret material would otherwise appear.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Length of an encoded Stellar secret seed (`S` followed by 55 base32 characters).
+const SECRET_SEED_LEN: usize = 56;
+
+/// A secret string, such as a Stellar secret seed, that is wiped from memory on drop.
+///
+/// It deliberately implements neither `Display` nor `Serialize`, and its `Debug`
+/// output is redacted, so it cannot end up in logs or API responses by accident.
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    /// Wraps a secret string
+    pub fn new(secret: String) -> Self {
+        Self(Zeroizing::new(secret))
+    }
+
+    /// Returns the secret value. Keep the borrow as short as possible.
+    pub fn expose_secret(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretString({})", REDACTED)
+    }
+}
+
+/// Secret bytes, such as a decrypted private key, that are wiped from memory on drop.
+pub struct SecretBytes(Zeroizing<Vec<u8>>);
+
+impl SecretBytes {
+    /// Wraps secret bytes
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self(Zeroizing::new(secret))
+    }
+
+    /// Returns the secret value. Keep the borrow as short as possible.
+    pub fn expose_secret(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    /// Interprets the secret as UTF-8 text, e.g. an encoded secret seed.
+    pub fn to_secret_string(&self) -> Result<SecretString, std::str::Utf8Error> {
+        Ok(SecretString::new(std::str::from_utf8(&self.0)?.to_string()))
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(secret: Vec<u8>) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes({})", REDACTED)
+    }
+}
+
+fn is_base32_char(byte: u8) -> bool {
+    byte.is_ascii_uppercase() || (b'2'..=b'7').contains(&byte)
+}
+
+/// Replaces anything shaped like a Stellar secret seed with [`REDACTED`].
+///
+/// Use this on any message that may contain user or library supplied text before it is
+/// logged or returned in an error.
+pub fn redact_secrets(message: &str) -> String {
+    let bytes = message.as_bytes();
+    let mut redacted = String::with_capacity(message.len());
+    let mut index = 0;
+    let mut copied_until = 0;
+
+    while index < bytes.len() {
+        let at_boundary = index == 0 || !is_base32_char(bytes[index - 1]);
+        let candidate_end = index + SECRET_SEED_LEN;
+
+        if at_boundary
+            && bytes[index] == b'S'
+            && candidate_end <= bytes.len()
+            && bytes[index..candidate_end]
+                .iter()
+                .all(|byte| is_base32_char(*byte))
+            && (candidate_end == bytes.len() || !is_base32_char(bytes[candidate_end]))
+        {
+            redacted.push_str(&message[copied_until..index]);
+            redacted.push_str(REDACTED);
+            index = candidate_end;
+            copied_until = candidate_end;
+        } else {
+            index += 1;
+        }
+    }
+
+    redacted.push_str(&message[copied_until..]);
+    redacted
+}
+
+/// Returns true if the text contains anything shaped like a Stellar secret seed.
+pub fn contains_secret_seed(message: &str) -> bool {
+    redact_secrets(message) != message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::KeyRing;
+    use crate::signer::{KeyPairSigner, Signer};
+    use crate::stellar_chain::StellarChain;
+    use std::collections::BTreeMap;
+    use std::process::Command;
+    use stellar_base::Network;
+
+    /// Set for the child process that runs the key path with its stderr captured.
+    const KEY_PATH_CHILD_VAR: &str = "HELPERS_KEY_PATH_CHILD";
+
+    #[test]
+    fn test_redact_secrets() {
+        let seed = "SBK2VIYYSVG76E7VC3QHYARNFLY2EAQXDHRC7BMXBBGIFG74ARPRMNQM";
+        let message = format!("failed to parse {} from input", seed);
+
+        let redacted = redact_secrets(&message);
+        assert!(!redacted.contains(seed));
+        assert_eq!(redacted, "failed to parse [REDACTED] from input");
+        assert!(contains_secret_seed(&message));
+
+        // Public keys and shorter strings are left alone
+        let public_key = "GAW2GOKRA6N63LPZ5YCR6NGR4KKX3EG72HW2MN5SKJE43HKMQQ4R66V4";
+        assert_eq!(redact_secrets(public_key), public_key);
+        assert_eq!(redact_secrets("SHORT"), "SHORT");
+    }
+
+    #[test]
+    fn test_secret_debug_is_redacted() {
+        let seed = "SBK2VIYYSVG76E7VC3QHYARNFLY2EAQXDHRC7BMXBBGIFG74ARPRMNQM";
+
+        let secret_string = SecretString::new(seed.to_string());
+        let secret_bytes = SecretBytes::new(seed.as_bytes().to_vec());
+
+        assert!(!format!("{:?}", secret_string).contains(seed));
+        assert!(!format!("{:?}", secret_bytes).contains(seed));
+        assert_eq!(secret_string.expose_secret(), seed);
+    }
+
+    /// Runs the key path and writes what it produces to stderr the way the services
+    /// report failures
+    async fn write_key_path_to_stderr() {
+        let chain = StellarChain::new(
+            "https://horizon-testnet.stellar.org".to_string(),
+            Network::new_test(),
+        );
+
+        // Generate and encrypt a new account the way account creation does
+        let account = chain.create_new_account().unwrap();
+        eprintln!("Created account {:?}", account);
+
+        let mut keys = BTreeMap::new();
+        keys.insert(1, vec![7u8; 32]);
+        let key_ring = KeyRing::new(keys, None).unwrap();
+
+        let (encrypted, version) = key_ring
+            .encrypt(account.secret_key.expose_secret().as_bytes(), b"account-1")
+            .unwrap();
+        let decrypted = key_ring.decrypt(&encrypted, b"account-1", version).unwrap();
+        eprintln!("Decrypted key {:?}", decrypted);
+
+        if let Err(error) = key_ring.decrypt(&encrypted, b"account-2", version) {
+            eprintln!(
+                "Failed to decrypt key: {}",
+                redact_secrets(&error.to_string())
+            );
+        }
+
+        let signer = KeyPairSigner::new()
+            .with_account("account", account.secret_key.expose_secret())
+            .unwrap();
+        signer.sign_hash("account", &[0u8; 32]).await.unwrap();
+        if let Err(error) = signer.sign_hash("missing", &[0u8; 32]).await {
+            eprintln!("Failed to sign: {}", redact_secrets(&error.to_string()));
+        }
+
+        // An error that echoes its input, as library errors may
+        let error = anyhow::anyhow!("Unexpected key {}", account.secret_key.expose_secret());
+        eprintln!("Failed to load key: {}", redact_secrets(&error.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_key_path_never_writes_secret_seed_to_stderr() {
+        if std::env::var_os(KEY_PATH_CHILD_VAR).is_some() {
+            write_key_path_to_stderr().await;
+            return;
+        }
+
+        // The test harness captures output per test, so the key path runs in a child
+        // process of this test binary and its real stderr is checked
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "secret::tests::test_key_path_never_writes_secret_seed_to_stderr",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(KEY_PATH_CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Created account"));
+        assert!(stderr.contains("Failed to load key: Unexpected key [REDACTED]"));
+        assert!(!contains_secret_seed(&stderr));
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use stellar_base::signature::{DecoratedSignature, Signature, SignatureHint};
+use stellar_base::{KeyPair, PublicKey};
+use zeroize::Zeroizing;
+
+/// Account id used for the funding/issuer account configured through `ISSUER_SECRET_KEY`.
+pub const ISSUER_ACCOUNT_ID: &str = "issuer";
+
+/// Account id used for the distributor account configured through `RECEIVER_SECRET_KEY`.
+pub const DISTRIBUTOR_ACCOUNT_ID: &str = "distributor";
+
+/// How long connecting to a remote signer may take, unless configured otherwise.
+pub const DEFAULT_REMOTE_SIGNER_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a whole request to a remote signer may take, unless configured otherwise.
+pub const DEFAULT_REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Signs transaction hashes on behalf of accounts, without exposing their private keys.
+///
+/// Chain helpers build transactions from the public key returned by [`Signer::public_key`]
+/// and hand only the transaction hash across the signing boundary.
+pub trait Signer: Send + Sync {
+    /// Returns the Stellar public key of the given account.
+    fn public_key(&self, account_id: &str)
+        -> impl Future<Output = Result<PublicKey, Error>> + Send;
+
+    /// Signs a transaction hash with the key of the given account.
+    fn sign_hash(
+        &self,
+        account_id: &str,
+        hash: &[u8],
+    ) -> impl Future<Output = Result<DecoratedSignature, Error>> + Send;
+}
+
+/// A signer holding keypairs in memory, for accounts whose keys are configured directly
+/// (such as the issuer) and for tests.
+#[derive(Default)]
+pub struct KeyPairSigner {
+    keypairs: HashMap<String, KeyPair>,
+}
+
+impl KeyPairSigner {
+    /// Creates an empty signer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the keypair for an account id
+    ///
+    /// # Arguments
+    /// * `account_id` - The id the account is referred to by
+    /// * `secret_key` - The secret seed of the account
+    pub fn with_account(mut self, account_id: &str, secret_key: &str) -> Result<Self, Error> {
+        let keypair = KeyPair::from_str(secret_key)
+            .map_err(|_| anyhow::anyhow!("Invalid secret key for account {}", account_id))?;
+        self.keypairs.insert(account_id.to_string(), keypair);
+        Ok(self)
+    }
+
+    /// Creates a signer for the service accounts configured in the environment
+    ///
+    /// Registers `ISSUER_SECRET_KEY` as [`ISSUER_ACCOUNT_ID`] and `RECEIVER_SECRET_KEY`
+    /// as [`DISTRIBUTOR_ACCOUNT_ID`], each when set. The issuer key is left out when it
+    /// signs offline.
+    pub fn from_env() -> Result<Self, Error> {
+        let mut signer = Self::new();
+
+        if let Ok(issuer_secret_key) = std::env::var("ISSUER_SECRET_KEY") {
+            let issuer_secret_key = Zeroizing::new(issuer_secret_key);
+            signer = signer.with_account(ISSUER_ACCOUNT_ID, &issuer_secret_key)?;
+        }
+
+        if let Ok(receiver_secret_key) = std::env::var("RECEIVER_SECRET_KEY") {
+            let receiver_secret_key = Zeroizing::new(receiver_secret_key);
+            signer = signer.with_account(DISTRIBUTOR_ACCOUNT_ID, &receiver_secret_key)?;
+        }
+
+        Ok(signer)
+    }
+
+    /// Returns true if the signer holds a key for the account id
+    pub fn has_account(&self, account_id: &str) -> bool {
+        self.keypairs.contains_key(account_id)
+    }
+
+    fn keypair(&self, account_id: &str) -> Result<&KeyPair, Error> {
+        self.keypairs
+            .get(account_id)
+            .ok_or_else(|| anyhow::anyhow!("No key available for account {}", account_id))
+    }
+}
+
+impl Signer for KeyPairSigner {
+    async fn public_key(&self, account_id: &str) -> Result<PublicKey, Error> {
+        Ok(self.keypair(account_id)?.public_key().clone())
+    }
+
+    async fn sign_hash(&self, account_id: &str, hash: &[u8]) -> Result<DecoratedSignature, Error> {
+        Ok(self.keypair(account_id)?.sign_decorated(hash))
+    }
+}
+
+#[derive(Deserialize)]
+struct RemotePublicKeyResponse {
+    public_key: String,
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    signature: String,
+}
+
+/// A signer delegating to a remote signing service over HTTP.
+///
+/// The remote service exposes:
+/// * `GET {url}/accounts/{account_id}/public_key` returning `{"public_key": "G..."}`
+/// * `POST {url}/accounts/{account_id}/sign` with `{"hash": "<hex>"}` returning
+///   `{"signature": "<hex>"}`, the raw ed25519 signature of the hash
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    auth_token: Option<String>,
+}
+
+impl RemoteSigner {
+    /// Creates a new RemoteSigner with the default timeouts
+    ///
+    /// # Arguments
+    /// * `url` - The base URL of the remote signing service
+    /// * `auth_token` - Optional bearer token sent with every request
+    pub fn new(url: String, auth_token: Option<String>) -> Result<Self, Error> {
+        Ok(Self {
+            client: Self::client(
+                DEFAULT_REMOTE_SIGNER_CONNECT_TIMEOUT,
+                DEFAULT_REMOTE_SIGNER_TIMEOUT,
+            )?,
+            url: url.trim_end_matches('/').to_string(),
+            auth_token,
+        })
+    }
+
+    /// Sets how long connecting and whole requests may take, so a hung signing service
+    /// fails the signing instead of stalling it
+    pub fn with_timeouts(
+        self,
+        connect_timeout: Duration,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            client: Self::client(connect_timeout, timeout)?,
+            ..self
+        })
+    }
+
+    fn client(connect_timeout: Duration, timeout: Duration) -> Result<reqwest::Client, Error> {
+        Ok(reqwest::Client::builder()
+            .connect_timeout(connect_timeout)
+            .timeout(timeout)
+            .build()?)
+    }
+
+    fn request(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.auth_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl Signer for RemoteSigner {
+    async fn public_key(&self, account_id: &str) -> Result<PublicKey, Error> {
+        let response = self
+            .request(
+                self.client
+                    .get(format!("{}/accounts/{}/public_key", self.url, account_id)),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RemotePublicKeyResponse>()
+            .await?;
+
+        Ok(PublicKey::from_account_id(&response.public_key)?)
+    }
+
+    async fn sign_hash(&self, account_id: &str, hash: &[u8]) -> Result<DecoratedSignature, Error> {
+        let public_key = self.public_key(account_id).await?;
+
+        let response = self
+            .request(
+                self.client
+                    .post(format!("{}/accounts/{}/sign", self.url, account_id)),
+            )
+            .json(&RemoteSignRequest {
+                hash: hex::encode(hash),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RemoteSignResponse>()
+            .await?;
+
+        let signature_bytes = hex::decode(response.signature)?;
+        let signature = Signature::from_slice(&signature_bytes)
+            .map_err(|_| anyhow::anyhow!("Remote signer returned an invalid signature"))?;
+
+        // A signature by any other key would only be rejected once submitted
+        if !signature.verify(&public_key, hash) {
+            return Err(anyhow::anyhow!(
+                "Remote signer returned a signature that does not match account {}",
+                account_id
+            ));
+        }
+
+        Ok(DecoratedSignature::new(
+            SignatureHint::from_public_key(&public_key),
+            signature,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_sdk::Keypair;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves the remote signer protocol on a local port for the account of `secret_key`,
+    /// signing with `signing_secret_key`.
+    async fn spawn_remote_signer_stand_in(
+        secret_key: String,
+        signing_secret_key: String,
+    ) -> String {
+        let keypair = KeyPair::from_str(&secret_key).unwrap();
+        let signing_keypair = KeyPair::from_str(&signing_secret_key).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 8192];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+                let body = if request.starts_with("GET") {
+                    format!(
+                        r#"{{"public_key":"{}"}}"#,
+                        keypair.public_key().account_id()
+                    )
+                } else {
+                    let payload = request.split("\r\n\r\n").nth(1).unwrap_or_default();
+                    let hash_hex = payload
+                        .split("\"hash\":\"")
+                        .nth(1)
+                        .and_then(|rest| rest.split('"').next())
+                        .unwrap_or_default();
+                    let signature = signing_keypair.sign(&hex::decode(hash_hex).unwrap());
+                    format!(r#"{{"signature":"{}"}}"#, hex::encode(signature.to_vec()))
+                };
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_keypair_signer_signs_for_known_account() {
+        let secret_key = Keypair::random().unwrap().secret_key().unwrap();
+        let keypair = KeyPair::from_str(&secret_key).unwrap();
+        let signer = KeyPairSigner::new()
+            .with_account("user-1", &secret_key)
+            .unwrap();
+
+        let public_key = signer.public_key("user-1").await.unwrap();
+        assert_eq!(public_key.account_id(), keypair.public_key().account_id());
+
+        let signature = signer.sign_hash("user-1", b"hash").await.unwrap();
+        assert_eq!(signature, keypair.sign_decorated(b"hash"));
+
+        assert!(signer.sign_hash("user-2", b"hash").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_matches_local_signature() {
+        let secret_key = Keypair::random().unwrap().secret_key().unwrap();
+        let keypair = KeyPair::from_str(&secret_key).unwrap();
+        let url = spawn_remote_signer_stand_in(secret_key.clone(), secret_key).await;
+
+        let signer = RemoteSigner::new(url, None).unwrap();
+
+        let public_key = signer.public_key("user-1").await.unwrap();
+        assert_eq!(public_key.account_id(), keypair.public_key().account_id());
+
+        let hash = [7u8; 32];
+        let signature = signer.sign_hash("user-1", &hash).await.unwrap();
+        assert_eq!(signature, keypair.sign_decorated(&hash));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_rejects_signature_by_other_key() {
+        let secret_key = Keypair::random().unwrap().secret_key().unwrap();
+        let other_secret_key = Keypair::random().unwrap().secret_key().unwrap();
+        let url = spawn_remote_signer_stand_in(secret_key, other_secret_key).await;
+
+        let signer = RemoteSigner::new(url, None).unwrap();
+        assert!(signer.sign_hash("user-1", &[7u8; 32]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_times_out() {
+        // Accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                sockets.push(socket);
+            }
+        });
+
+        let signer = RemoteSigner::new(url, None)
+            .unwrap()
+            .with_timeouts(Duration::from_secs(1), Duration::from_millis(200))
+            .unwrap();
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            signer.sign_hash("user-1", &[7u8; 32]),
+        )
+        .await
+        .expect("remote signer must time out on its own");
+        assert!(result.is_err());
+    }
+}
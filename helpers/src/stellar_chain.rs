@@ -1,24 +1,273 @@
-use std::str::FromStr;
-
 use anyhow::Error;
+use chrono::{DateTime, Duration, Utc};
+use openssl::sha::sha256;
 use reqwest::Response;
+use serde::Deserialize;
 use stellar_base::xdr::XDRSerialize;
 use stellar_base::{
+    account::AccountFlags,
     amount::Stroops,
+    asset::CreditAsset,
+    claim::{ClaimableBalanceId, Claimant},
     operations::{
-        ChangeTrustOperationBuilder, CreateAccountOperationBuilder, PaymentOperationBuilder,
+        ChangeTrustOperationBuilder, ClaimClaimableBalanceOperationBuilder,
+        CreateAccountOperationBuilder, CreateClaimableBalanceOperationBuilder,
+        PaymentOperationBuilder, SetOptionsOperationBuilder,
     },
+    signature::{Signer as AccountSigner, SignerKey},
     time_bounds::TimeBounds,
-    Asset, KeyPair, Network, PublicKey, Transaction,
+    Asset, Network, Operation, PublicKey, Transaction,
 };
 use stellar_sdk::{Keypair, Server};
 
+use crate::raw_transaction::{RawOperation, RawTransaction, TrustlineAuthorization};
+use crate::secret::SecretString;
+use crate::signer::Signer;
+use crate::submitter::{http_client, SubmissionOutcome, TransactionSubmitter};
+
 /// Represents a newly created Stellar account with its public and secret keys
+#[derive(Debug)]
 pub struct NewStellarAccount {
     /// The public key of the Stellar account
     pub public_key: String,
-    /// The secret key (private key) of the Stellar account
-    pub secret_key: String,
+    /// The secret key (private key) of the Stellar account, wiped from memory on drop
+    pub secret_key: SecretString,
+}
+
+/// Account settings changed through a SetOptions transaction. Fields left as `None`
+/// are not changed.
+#[derive(Debug, Clone, Default)]
+pub struct AccountOptions {
+    pub master_weight: Option<u32>,
+    pub low_threshold: Option<u32>,
+    pub medium_threshold: Option<u32>,
+    pub high_threshold: Option<u32>,
+    pub home_domain: Option<String>,
+    pub set_flags: Option<AccountFlags>,
+    pub clear_flags: Option<AccountFlags>,
+    /// Signers to add or update with their weight; a weight of 0 removes the signer
+    pub signers: Vec<(PublicKey, u32)>,
+}
+
+/// The signers and thresholds that authorise transactions for an account
+#[derive(Debug, Clone)]
+pub struct AccountAuthorization {
+    /// Ed25519 signers of the account, including the master key, with their weights
+    pub signers: Vec<(PublicKey, u32)>,
+    pub low_threshold: u32,
+    pub medium_threshold: u32,
+    pub high_threshold: u32,
+}
+
+impl AccountAuthorization {
+    /// Returns the weight of a key on the account, or 0 if it is not a signer
+    pub fn weight_of(&self, key: &PublicKey) -> u32 {
+        self.signers
+            .iter()
+            .find(|(signer, _)| signer == key)
+            .map(|(_, weight)| *weight)
+            .unwrap_or(0)
+    }
+}
+
+/// How long built transactions stay valid when no timeout is configured
+pub const DEFAULT_TRANSACTION_TIMEOUT_SECONDS: i64 = 300;
+
+/// Most operations the network accepts in a single transaction
+pub const MAX_OPERATIONS_PER_TRANSACTION: usize = 100;
+
+/// A single payment packed into a multi-operation transaction
+pub struct PaymentInstruction {
+    /// The public key of the receiving account
+    pub receiver_pub_key: String,
+    /// The asset to send
+    pub asset: Asset,
+    /// The amount to send (will be converted to stroops)
+    pub amount: u64,
+}
+
+/// `ENVELOPE_TYPE_OP_ID`, the hash preimage type claimable balance ids are derived from
+const ENVELOPE_TYPE_OP_ID: i32 = 6;
+
+/// Returns the id of the claimable balance a create operation will produce
+///
+/// The network derives the id from the transaction source account, its sequence
+/// number and the index of the operation, so it is known before submission.
+///
+/// # Arguments
+/// * `source_account` - The source account of the transaction
+/// * `sequence_number` - The sequence number of the transaction
+/// * `operation_index` - The index of the create operation in the transaction
+pub fn claimable_balance_id(
+    source_account: &PublicKey,
+    sequence_number: i64,
+    operation_index: u32,
+) -> Result<ClaimableBalanceId, Error> {
+    // XDR of HashIDPreimage::OperationID, an ed25519 account id is key type 0
+    let mut preimage = Vec::with_capacity(52);
+    preimage.extend_from_slice(&ENVELOPE_TYPE_OP_ID.to_be_bytes());
+    preimage.extend_from_slice(&0i32.to_be_bytes());
+    preimage.extend_from_slice(source_account.as_bytes());
+    preimage.extend_from_slice(&sequence_number.to_be_bytes());
+    preimage.extend_from_slice(&operation_index.to_be_bytes());
+
+    Ok(ClaimableBalanceId::new(sha256(&preimage).to_vec())?)
+}
+
+/// Builds the operation setting the home domain of an account, where wallets look up
+/// the `stellar.toml` describing the assets it issues
+pub fn set_home_domain_operation(
+    account: &PublicKey,
+    home_domain: &str,
+) -> Result<Operation, Error> {
+    let mut operation = SetOptionsOperationBuilder::new()
+        .with_source_account(account.clone())
+        .build()?;
+
+    if let Operation::SetOptions(set_options) = &mut operation {
+        *set_options.home_domain_mut() = Some(home_domain.to_string());
+    }
+
+    Ok(operation)
+}
+
+/// Encodes a claimable balance id the way Horizon shows it, the hex of its XDR
+pub fn encode_claimable_balance_id(balance_id: &ClaimableBalanceId) -> String {
+    format!("00000000{}", hex::encode(balance_id.as_bytes()))
+}
+
+/// Decodes a claimable balance id in the format Horizon shows it
+pub fn decode_claimable_balance_id(balance_id: &str) -> Result<ClaimableBalanceId, Error> {
+    let hash = balance_id
+        .strip_prefix("00000000")
+        .ok_or_else(|| anyhow::anyhow!("Unsupported claimable balance id type"))?;
+
+    Ok(ClaimableBalanceId::new(hex::decode(hash)?)?)
+}
+
+/// Whether an account can receive an asset, and if not why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestinationStatus {
+    /// A payment would be accepted
+    Ready,
+    /// The account does not exist, a payment fails with `op_no_destination`
+    NotFound,
+    /// The account has no trustline for the asset, a payment fails with `op_no_trust`
+    NoTrustline,
+    /// The issuer has not authorized the trustline, a payment fails with
+    /// `op_not_authorized`
+    NotAuthorized,
+}
+
+/// Outcome of a transaction Horizon knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    /// Included in a ledger and applied
+    Succeeded,
+    /// Included in a ledger but failed, only the fee was charged
+    Failed,
+}
+
+#[derive(Deserialize)]
+struct HorizonAccount {
+    balances: Vec<HorizonBalance>,
+}
+
+#[derive(Deserialize)]
+struct HorizonBalance {
+    asset_code: Option<String>,
+    asset_issuer: Option<String>,
+    is_authorized: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct HorizonClaimableBalance {
+    amount: String,
+}
+
+#[derive(Deserialize)]
+struct HorizonLedgerPage {
+    _embedded: HorizonLedgerRecords,
+}
+
+#[derive(Deserialize)]
+struct HorizonLedgerRecords {
+    records: Vec<HorizonLedger>,
+}
+
+#[derive(Deserialize)]
+struct HorizonLedger {
+    closed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct HorizonPaymentPage {
+    _embedded: HorizonPaymentRecords,
+}
+
+#[derive(Deserialize)]
+struct HorizonPaymentRecords {
+    records: Vec<HorizonPayment>,
+}
+
+#[derive(Deserialize)]
+struct HorizonPayment {
+    paging_token: String,
+    #[serde(rename = "type")]
+    operation_type: String,
+    transaction_hash: String,
+    transaction_successful: bool,
+    from: Option<String>,
+    to: Option<String>,
+    asset_code: Option<String>,
+    asset_issuer: Option<String>,
+    amount: Option<String>,
+    transaction: Option<HorizonPaymentTransaction>,
+}
+
+#[derive(Deserialize)]
+struct HorizonPaymentTransaction {
+    memo_type: String,
+    memo: Option<String>,
+}
+
+/// A payment an account received, with the memo of its transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedPayment {
+    pub paging_token: String,
+    pub transaction_hash: String,
+    pub from: String,
+    /// `None` for XLM
+    pub asset_code: Option<String>,
+    pub asset_issuer: Option<String>,
+    /// The amount in units of the asset, e.g. `10.0000000`
+    pub amount: String,
+    /// `none`, `text`, `id`, `hash` or `return`
+    pub memo_type: String,
+    /// The memo as Horizon writes it: text, a decimal id, or a base64 hash
+    pub memo: Option<String>,
+}
+
+impl ReceivedPayment {
+    /// Returns the position of the payment within its transaction
+    ///
+    /// The paging token of an operation is its id, whose low 12 bits are the
+    /// operation's 1-based position in the transaction.
+    pub fn operation_index(&self) -> Result<i32, Error> {
+        let operation_id = self
+            .paging_token
+            .parse::<i64>()
+            .map_err(|_| anyhow::anyhow!("Invalid paging token {}", self.paging_token))?;
+
+        Ok((operation_id & 0xFFF) as i32 - 1)
+    }
+}
+
+/// A page of payments an account received
+pub struct ReceivedPayments {
+    pub payments: Vec<ReceivedPayment>,
+    /// The paging token to continue from, or `None` if the page was empty
+    pub cursor: Option<String>,
 }
 
 /// Handles interactions with the Stellar blockchain network
@@ -26,6 +275,8 @@ pub struct StellarChain {
     pub client: Server,
     network: Network,
     server_url: String,
+    transaction_timeout: Duration,
+    submitter: TransactionSubmitter,
 }
 
 impl StellarChain {
@@ -38,10 +289,43 @@ impl StellarChain {
         Self {
             client: Server::new(server_url.clone(), None).unwrap(),
             network,
+            submitter: TransactionSubmitter::new(server_url.clone()),
             server_url,
+            transaction_timeout: Duration::seconds(DEFAULT_TRANSACTION_TIMEOUT_SECONDS),
         }
     }
 
+    /// Sets how long built transactions stay valid
+    ///
+    /// A transaction that has not reached a ledger by then can never be included, so
+    /// after the timeout it is safe to decide its outcome and retry.
+    ///
+    /// # Arguments
+    /// * `transaction_timeout` - The time between building a transaction and its max time
+    pub fn with_transaction_timeout(mut self, transaction_timeout: Duration) -> Self {
+        self.transaction_timeout = transaction_timeout;
+        self
+    }
+
+    /// Sets the submitter used to send envelopes to Horizon
+    ///
+    /// # Arguments
+    /// * `submitter` - The submitter with the retry policy to use
+    pub fn with_submitter(mut self, submitter: TransactionSubmitter) -> Self {
+        self.submitter = submitter;
+        self
+    }
+
+    /// Returns the submitter that retries envelopes until their outcome is known
+    pub fn submitter(&self) -> &TransactionSubmitter {
+        &self.submitter
+    }
+
+    /// Returns the time bounds for a transaction built now
+    pub fn time_bounds(&self) -> TimeBounds {
+        TimeBounds::valid_for(self.transaction_timeout)
+    }
+
     /// Creates a new Stellar account with randomly generated keys
     ///
     /// # Returns
@@ -54,34 +338,204 @@ impl StellarChain {
 
         Ok(NewStellarAccount {
             public_key: public_key.to_string(),
-            secret_key: secret_key.to_string(),
+            secret_key: SecretString::new(secret_key),
         })
     }
 
-    /// Activates a Stellar account by funding it with the minimum balance
+    /// Returns the next sequence number for an account
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the account
+    pub fn next_sequence_number(&self, account: &PublicKey) -> Result<i64, Error> {
+        let account_details = self.client.load_account(&account.account_id())?;
+        Ok(account_details.sequence_number().parse::<i64>()? + 1)
+    }
+
+    /// Signs a transaction through the signer and submits it to Horizon
     ///
     /// # Arguments
-    /// * `keypair` - The keypair of the account to activate
+    /// * `transaction` - The transaction to sign and submit
+    /// * `signer` - The signer holding the key of the signing account
+    /// * `account_id` - The id of the account signing the transaction
     ///
     /// # Returns
-    /// * `Result<Response, Error>` - The transaction response or an error
-    pub async fn activate_account(
+    /// * `Result<Response, Error>` - The Horizon response or an error
+    async fn sign_and_submit<S: Signer>(
         &self,
-        keypair: Keypair,
-    ) -> Result<(Response, PublicKey, PublicKey, Stroops), Error> {
-        let new_account =
-            stellar_base::PublicKey::from_account_id(keypair.public_key().as_str()).unwrap();
+        transaction: Transaction,
+        signer: &S,
+        account_id: &str,
+    ) -> Result<Response, Error> {
+        let transaction = self
+            .sign_transaction(transaction, signer, account_id)
+            .await?;
+
+        let base64_transaction = transaction.into_envelope().xdr_base64()?;
+
+        self.submit_transaction_xdr(&base64_transaction).await
+    }
+
+    /// Signs a transaction through the signer without submitting it
+    ///
+    /// # Arguments
+    /// * `transaction` - The transaction to sign
+    /// * `signer` - The signer holding the key of the signing account
+    /// * `account_id` - The id of the account signing the transaction
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The signed transaction or an error
+    pub async fn sign_transaction<S: Signer>(
+        &self,
+        mut transaction: Transaction,
+        signer: &S,
+        account_id: &str,
+    ) -> Result<Transaction, Error> {
+        let hash = transaction.hash(&self.network)?;
+        let signature = signer.sign_hash(account_id, &hash).await?;
+        transaction.signatures_mut().push(signature);
+
+        Ok(transaction)
+    }
+
+    /// Returns the network transactions are signed for
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Submits a signed transaction envelope to Horizon
+    ///
+    /// # Arguments
+    /// * `envelope_xdr` - The base64 encoded transaction envelope
+    ///
+    /// # Returns
+    /// * `Result<Response, Error>` - The Horizon response or an error
+    pub async fn submit_transaction_xdr(&self, envelope_xdr: &str) -> Result<Response, Error> {
+        self.submitter.post(envelope_xdr).await
+    }
+
+    /// Looks up a transaction on Horizon by its hash
+    ///
+    /// # Arguments
+    /// * `transaction_hash` - The hex encoded transaction hash
+    ///
+    /// # Returns
+    /// * `Result<Option<TransactionOutcome>, Error>` - The outcome, or `None` if the
+    ///   transaction is not in any ledger (yet)
+    pub async fn find_transaction(
+        &self,
+        transaction_hash: &str,
+    ) -> Result<Option<TransactionOutcome>, Error> {
+        Ok(match self.submitter.find(transaction_hash).await? {
+            Some(SubmissionOutcome::Completed) => Some(TransactionOutcome::Succeeded),
+            Some(_) => Some(TransactionOutcome::Failed),
+            None => None,
+        })
+    }
+
+    /// Returns the close time of the latest ledger, the clock time bounds are checked
+    /// against
+    pub async fn latest_ledger_close_time(&self) -> Result<DateTime<Utc>, Error> {
+        let page = http_client()
+            .get(format!("{}/ledgers?order=desc&limit=1", self.server_url))
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HorizonLedgerPage>()
+            .await?;
 
-        let issuer_secret_key = std::env::var("ISSUER_SECRET_KEY").unwrap();
+        page._embedded
+            .records
+            .first()
+            .map(|ledger| ledger.closed_at)
+            .ok_or_else(|| anyhow::anyhow!("Horizon returned no ledgers"))
+    }
 
-        let funding_keypair = Keypair::from_secret_key(&issuer_secret_key).unwrap();
+    /// Returns the successful payments an account received, oldest first
+    ///
+    /// Payments the account sent, other operations and failed transactions are skipped,
+    /// but still move the cursor.
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the receiving account
+    /// * `cursor` - The paging token to continue after, or `None` to start from the
+    ///   oldest payment
+    /// * `limit` - Most operations to read, at most 200
+    pub async fn received_payments(
+        &self,
+        account: &str,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<ReceivedPayments, Error> {
+        let mut query = vec![
+            ("order", "asc".to_string()),
+            ("limit", limit.min(200).to_string()),
+            ("join", "transactions".to_string()),
+        ];
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
 
-        let funding_account =
-            stellar_base::PublicKey::from_account_id(funding_keypair.public_key().as_str())
-                .unwrap();
+        let page = http_client()
+            .get(format!("{}/accounts/{}/payments", self.server_url, account))
+            .query(&query)
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HorizonPaymentPage>()
+            .await?;
 
-        let time_bounds = TimeBounds::always_valid();
+        let cursor = page
+            ._embedded
+            .records
+            .last()
+            .map(|record| record.paging_token.clone());
+
+        let payments = page
+            ._embedded
+            .records
+            .into_iter()
+            .filter(|record| {
+                record.transaction_successful
+                    && record.operation_type == "payment"
+                    && record.to.as_deref() == Some(account)
+            })
+            .filter_map(|record| {
+                let transaction = record.transaction?;
+                Some(ReceivedPayment {
+                    paging_token: record.paging_token,
+                    transaction_hash: record.transaction_hash,
+                    from: record.from?,
+                    asset_code: record.asset_code,
+                    asset_issuer: record.asset_issuer,
+                    amount: record.amount?,
+                    memo_type: transaction.memo_type,
+                    memo: transaction.memo,
+                })
+            })
+            .collect();
+
+        Ok(ReceivedPayments { payments, cursor })
+    }
 
+    /// Builds the unsigned transaction that activates an account by funding it with the
+    /// minimum balance
+    ///
+    /// # Arguments
+    /// * `funding_account` - The public key of the account paying for the activation
+    /// * `new_account` - The public key of the account to activate
+    /// * `time_bounds` - The period the transaction is valid for
+    ///
+    /// # Returns
+    /// * `Result<(Transaction, Stroops), Error>` - The unsigned transaction and the
+    ///   starting balance it sends
+    pub fn build_activate_account_transaction(
+        &self,
+        funding_account: &PublicKey,
+        new_account: &PublicKey,
+        time_bounds: TimeBounds,
+    ) -> Result<(Transaction, Stroops), Error> {
         let create_account_operation_builder = CreateAccountOperationBuilder::new();
 
         let amount = Stroops::new(10000000);
@@ -90,35 +544,46 @@ impl StellarChain {
         let create_account_operation = create_account_operation_builder
             .with_source_account(funding_account.clone())
             .with_destination(new_account.clone())
-            .with_starting_balance(amount.clone())?
+            .with_starting_balance(amount)?
             .build()?;
 
-        let funding_account_details = self
-            .client
-            .load_account(&funding_keypair.public_key())
-            .unwrap();
-
-        let mut transaction = Transaction::builder(
+        let transaction = Transaction::builder(
             funding_account.clone(),
-            funding_account_details.sequence_number().parse::<i64>()? + 1,
+            self.next_sequence_number(funding_account)?,
             Stroops::new(100),
         )
         .add_operation(create_account_operation)
         .with_time_bounds(time_bounds)
         .into_transaction()?;
 
-        // Sign the transaction
-        let funding_keypair = KeyPair::from_str(&issuer_secret_key).unwrap();
-        transaction.sign(&funding_keypair, &self.network)?;
+        Ok((transaction, amount))
+    }
 
-        let base64_transaction = transaction.into_envelope().xdr_base64()?;
+    /// Activates a Stellar account by funding it with the minimum balance
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding the key of the funding account
+    /// * `funding_account_id` - The id of the account paying for the activation
+    /// * `new_account` - The public key of the account to activate
+    ///
+    /// # Returns
+    /// * `Result<Response, Error>` - The transaction response or an error
+    pub async fn activate_account<S: Signer>(
+        &self,
+        signer: &S,
+        funding_account_id: &str,
+        new_account: PublicKey,
+    ) -> Result<(Response, PublicKey, PublicKey, Stroops), Error> {
+        let funding_account = signer.public_key(funding_account_id).await?;
 
-        // Submit the transaction to Horizon testnet using reqwest
-        let response = reqwest::Client::new()
-            .post(self.server_url.clone() + "/transactions")
-            .header("Accept", "application/json")
-            .form(&[("tx", base64_transaction)])
-            .send()
+        let (transaction, amount) = self.build_activate_account_transaction(
+            &funding_account,
+            &new_account,
+            self.time_bounds(),
+        )?;
+
+        let response = self
+            .sign_and_submit(transaction, signer, funding_account_id)
             .await?;
 
         Ok((response, funding_account, new_account, amount))
@@ -127,20 +592,21 @@ impl StellarChain {
     /// Establishes a trustline for a specific asset on behalf of an account
     ///
     /// # Arguments
-    /// * `keypair` - The keypair of the account establishing the trustline
+    /// * `signer` - The signer holding the key of the account establishing the trustline
+    /// * `account_id` - The id of the account establishing the trustline
     /// * `asset` - The asset to establish the trustline for
     ///
     /// # Returns
     /// * `Result<Response, Error>` - The transaction response or an error
-    pub async fn establish_trustline_for_asset(
+    pub async fn establish_trustline_for_asset<S: Signer>(
         &self,
-        keypair: Keypair,
+        signer: &S,
+        account_id: &str,
         asset: Asset,
     ) -> Result<Response, Error> {
-        let receiver_account =
-            stellar_base::PublicKey::from_account_id(keypair.public_key().as_str()).unwrap();
+        let receiver_account = signer.public_key(account_id).await?;
 
-        let time_bounds = TimeBounds::always_valid();
+        let time_bounds = self.time_bounds();
 
         let trust_operation_builder = ChangeTrustOperationBuilder::new();
 
@@ -154,111 +620,611 @@ impl StellarChain {
             .with_asset(asset)
             .build()?;
 
-        let receiver_account_details = self.client.load_account(&keypair.public_key())?;
-
-        let mut trust_transaction = Transaction::builder(
-            receiver_account,
-            receiver_account_details.sequence_number().parse::<i64>()? + 1, // Convert to i64
+        let trust_transaction = Transaction::builder(
+            receiver_account.clone(),
+            self.next_sequence_number(&receiver_account)?,
             Stroops::new(100),
         )
         .add_operation(trust_operation)
         .with_time_bounds(time_bounds)
         .into_transaction()?;
 
-        let mut keypair_clone = keypair.clone();
+        self.sign_and_submit(trust_transaction, signer, account_id)
+            .await
+    }
+
+    /// Builds the unsigned transaction that sends an asset from one account to another
+    ///
+    /// # Arguments
+    /// * `sender_account` - The public key of the sending account
+    /// * `receiver_pub_key` - The public key of the receiving account
+    /// * `asset` - The asset to send
+    /// * `amount` - The amount to send (will be converted to stroops)
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_payment_transaction(
+        &self,
+        sender_account: &PublicKey,
+        receiver_pub_key: &str,
+        asset: Asset,
+        amount: u64,
+    ) -> Result<Transaction, Error> {
+        let payment_operation =
+            self.payment_operation(sender_account, receiver_pub_key, asset, amount)?;
+
+        let transaction = Transaction::builder(
+            sender_account.clone(),
+            self.next_sequence_number(sender_account)?,
+            Stroops::new(100),
+        )
+        .add_operation(payment_operation)
+        .with_time_bounds(self.time_bounds())
+        .into_transaction()?;
+
+        Ok(transaction)
+    }
+
+    /// Builds the unsigned transaction that sends several payments at once, one
+    /// operation per payment in the given order
+    ///
+    /// # Arguments
+    /// * `sender_account` - The public key of the sending account
+    /// * `sequence_number` - The sequence number of the transaction, so several
+    ///   transactions can be built before any is submitted
+    /// * `payments` - The payments to pack, at most [`MAX_OPERATIONS_PER_TRANSACTION`]
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_payments_transaction(
+        &self,
+        sender_account: &PublicKey,
+        sequence_number: i64,
+        payments: &[PaymentInstruction],
+    ) -> Result<Transaction, Error> {
+        if payments.is_empty() || payments.len() > MAX_OPERATIONS_PER_TRANSACTION {
+            return Err(anyhow::anyhow!(
+                "A transaction takes between 1 and {} payments",
+                MAX_OPERATIONS_PER_TRANSACTION
+            ));
+        }
+
+        // The builder charges the base fee once per operation
+        let mut transaction_builder =
+            Transaction::builder(sender_account.clone(), sequence_number, Stroops::new(100));
+
+        for payment in payments {
+            transaction_builder = transaction_builder.add_operation(self.payment_operation(
+                sender_account,
+                &payment.receiver_pub_key,
+                payment.asset.clone(),
+                payment.amount,
+            )?);
+        }
+
+        Ok(transaction_builder
+            .with_time_bounds(self.time_bounds())
+            .into_transaction()?)
+    }
+
+    /// Builds the unsigned transaction that moves an asset into a claimable balance
+    ///
+    /// The id of the new balance is [`claimable_balance_id`] of the sender, the
+    /// transaction sequence number and operation index 0.
+    ///
+    /// # Arguments
+    /// * `sender_account` - The public key of the account funding the balance
+    /// * `asset` - The asset to hold
+    /// * `amount` - The amount to hold (will be converted to stroops)
+    /// * `claimants` - Who may claim the balance, and when
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_create_claimable_balance_transaction(
+        &self,
+        sender_account: &PublicKey,
+        asset: Asset,
+        amount: u64,
+        claimants: Vec<Claimant>,
+    ) -> Result<Transaction, Error> {
+        // Convert amount to stroops, as for payments
+        let amount_in_stroops = Stroops::new((amount * 1000000000).try_into()?);
+
+        let create_operation = CreateClaimableBalanceOperationBuilder::new()
+            .with_source_account(sender_account.clone())
+            .with_asset(asset)
+            .with_amount(amount_in_stroops)?
+            .with_claimants(claimants)
+            .build()?;
 
-        let receiver_key = KeyPair::from_str(&keypair_clone.secret_key().unwrap())?;
-        trust_transaction.sign(&receiver_key, &Network::new_test())?;
+        let transaction = Transaction::builder(
+            sender_account.clone(),
+            self.next_sequence_number(sender_account)?,
+            Stroops::new(100),
+        )
+        .add_operation(create_operation)
+        .with_time_bounds(self.time_bounds())
+        .into_transaction()?;
 
-        let base64_transaction = trust_transaction.into_envelope().xdr_base64()?;
+        Ok(transaction)
+    }
 
-        // Submit the transaction to Horizon testnet using reqwest
-        let response = reqwest::Client::new()
-            .post(self.server_url.clone() + "/transactions")
+    /// Builds the unsigned transaction that claims a claimable balance
+    ///
+    /// # Arguments
+    /// * `claimant_account` - The public key of the claiming account
+    /// * `balance_id` - The id of the claimable balance
+    /// * `trust_asset` - The asset of the balance, if the claimant has no trustline
+    ///   for it yet; the trustline is established in the same transaction
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_claim_claimable_balance_transaction(
+        &self,
+        claimant_account: &PublicKey,
+        balance_id: ClaimableBalanceId,
+        trust_asset: Option<Asset>,
+    ) -> Result<Transaction, Error> {
+        let mut transaction_builder = Transaction::builder(
+            claimant_account.clone(),
+            self.next_sequence_number(claimant_account)?,
+            Stroops::new(100),
+        );
+
+        if let Some(asset) = trust_asset {
+            transaction_builder = transaction_builder.add_operation(
+                ChangeTrustOperationBuilder::new()
+                    .with_source_account(claimant_account.clone())
+                    .with_asset(asset)
+                    .build()?,
+            );
+        }
+
+        let claim_operation = ClaimClaimableBalanceOperationBuilder::new()
+            .with_source_account(claimant_account.clone())
+            .with_claimable_balance_id(balance_id)
+            .build()?;
+
+        Ok(transaction_builder
+            .add_operation(claim_operation)
+            .with_time_bounds(self.time_bounds())
+            .into_transaction()?)
+    }
+
+    /// Builds the unsigned transaction in which an issuer sets the authorization of a
+    /// holder's trustline for one of its assets
+    ///
+    /// # Arguments
+    /// * `issuer_account` - The public key of the issuer
+    /// * `trustor` - The public key of the holder
+    /// * `asset` - The asset of the trustline, issued by `issuer_account`
+    /// * `authorization` - The authorization to give the trustline
+    ///
+    /// # Returns
+    /// * `Result<RawTransaction, Error>` - The unsigned transaction or an error
+    pub fn build_set_trustline_flags_transaction(
+        &self,
+        issuer_account: &PublicKey,
+        trustor: &PublicKey,
+        asset: CreditAsset,
+        authorization: TrustlineAuthorization,
+    ) -> Result<RawTransaction, Error> {
+        RawTransaction::new(
+            issuer_account.clone(),
+            self.next_sequence_number(issuer_account)?,
+            self.time_bounds(),
+            vec![RawOperation::SetTrustLineFlags {
+                source_account: issuer_account.clone(),
+                trustor: trustor.clone(),
+                asset,
+                authorization,
+            }],
+        )
+    }
+
+    /// Builds the unsigned transaction that sets the home domain of an issuer, so
+    /// wallets find the `stellar.toml` describing its assets
+    ///
+    /// # Arguments
+    /// * `issuer_account` - The public key of the issuer
+    /// * `home_domain` - The domain serving `/.well-known/stellar.toml`
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_set_home_domain_transaction(
+        &self,
+        issuer_account: &PublicKey,
+        home_domain: &str,
+    ) -> Result<Transaction, Error> {
+        Ok(Transaction::builder(
+            issuer_account.clone(),
+            self.next_sequence_number(issuer_account)?,
+            Stroops::new(100),
+        )
+        .add_operation(set_home_domain_operation(issuer_account, home_domain)?)
+        .with_time_bounds(self.time_bounds())
+        .into_transaction()?)
+    }
+
+    /// Builds the unsigned transaction in which an issuer claws back an amount of one
+    /// of its assets from a holder, burning it
+    ///
+    /// # Arguments
+    /// * `issuer_account` - The public key of the issuer
+    /// * `from` - The public key of the holder
+    /// * `asset` - The asset to claw back, issued by `issuer_account`
+    /// * `amount` - The amount to claw back, in stroops
+    ///
+    /// # Returns
+    /// * `Result<RawTransaction, Error>` - The unsigned transaction or an error
+    pub fn build_clawback_transaction(
+        &self,
+        issuer_account: &PublicKey,
+        from: &PublicKey,
+        asset: CreditAsset,
+        amount: Stroops,
+    ) -> Result<RawTransaction, Error> {
+        RawTransaction::new(
+            issuer_account.clone(),
+            self.next_sequence_number(issuer_account)?,
+            self.time_bounds(),
+            vec![RawOperation::Clawback {
+                source_account: issuer_account.clone(),
+                asset,
+                from: from.clone(),
+                amount,
+            }],
+        )
+    }
+
+    /// Builds the unsigned transaction in which an issuer claws back a claimable balance
+    /// of one of its assets, burning it
+    ///
+    /// # Arguments
+    /// * `issuer_account` - The public key of the issuer
+    /// * `balance_id` - The id of the claimable balance
+    ///
+    /// # Returns
+    /// * `Result<RawTransaction, Error>` - The unsigned transaction or an error
+    pub fn build_clawback_claimable_balance_transaction(
+        &self,
+        issuer_account: &PublicKey,
+        balance_id: ClaimableBalanceId,
+    ) -> Result<RawTransaction, Error> {
+        RawTransaction::new(
+            issuer_account.clone(),
+            self.next_sequence_number(issuer_account)?,
+            self.time_bounds(),
+            vec![RawOperation::ClawbackClaimableBalance {
+                source_account: issuer_account.clone(),
+                balance_id,
+            }],
+        )
+    }
+
+    /// Returns the amount held in a claimable balance, in units of its asset
+    ///
+    /// # Arguments
+    /// * `balance_id` - The hex encoded id of the claimable balance
+    pub async fn claimable_balance_amount(&self, balance_id: &str) -> Result<String, Error> {
+        let balance = http_client()
+            .get(format!(
+                "{}/claimable_balances/{}",
+                self.server_url, balance_id
+            ))
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HorizonClaimableBalance>()
+            .await?;
+
+        Ok(balance.amount)
+    }
+
+    /// Builds a payment operation
+    fn payment_operation(
+        &self,
+        sender_account: &PublicKey,
+        receiver_pub_key: &str,
+        asset: Asset,
+        amount: u64,
+    ) -> Result<Operation, Error> {
+        let receiver_account = PublicKey::from_account_id(receiver_pub_key)?;
+
+        // Convert amount to stroops
+        let amount_in_stroops = Stroops::new((amount * 1000000000).try_into().unwrap());
+
+        Ok(PaymentOperationBuilder::new()
+            .with_source_account(sender_account.clone())
+            .with_destination(receiver_account)
+            .with_asset(asset)
+            .with_amount(amount_in_stroops)?
+            .build()?)
+    }
+
+    /// Checks that an account exists and can receive an asset
+    ///
+    /// # Arguments
+    /// * `receiver_pub_key` - The public key of the receiving account
+    /// * `asset` - The asset to receive
+    ///
+    /// # Returns
+    /// * `Result<(), Error>` - An error describing why the account cannot receive it
+    pub fn check_destination(&self, receiver_pub_key: &str, asset: &Asset) -> Result<(), Error> {
+        PublicKey::from_account_id(receiver_pub_key)
+            .map_err(|_| anyhow::anyhow!("Invalid destination address"))?;
+
+        let account = self
+            .client
+            .load_account(receiver_pub_key)
+            .map_err(|_| anyhow::anyhow!("Destination account does not exist"))?;
+
+        let Asset::Credit(credit_asset) = asset else {
+            return Ok(());
+        };
+
+        let trustline = account.balances.iter().find(|balance| {
+            balance.asset_code.as_deref() == Some(credit_asset.code())
+                && balance.asset_issuer.as_deref()
+                    == Some(credit_asset.issuer().account_id().as_str())
+        });
+
+        match trustline {
+            None => Err(anyhow::anyhow!(
+                "Destination has no trustline for {}",
+                credit_asset.code()
+            )),
+            Some(balance) if balance.is_authorized == Some(false) => Err(anyhow::anyhow!(
+                "Destination is not authorized to hold {}",
+                credit_asset.code()
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Returns whether an account can receive an asset
+    ///
+    /// Unlike [`Self::check_destination`], only a missing account counts as not
+    /// found; any other lookup failure is returned as an error.
+    ///
+    /// # Arguments
+    /// * `receiver_pub_key` - The public key of the receiving account
+    /// * `asset` - The asset to receive
+    pub async fn destination_status(
+        &self,
+        receiver_pub_key: &str,
+        asset: &Asset,
+    ) -> Result<DestinationStatus, Error> {
+        PublicKey::from_account_id(receiver_pub_key)
+            .map_err(|_| anyhow::anyhow!("Invalid destination address"))?;
+
+        let response = http_client()
+            .get(format!("{}/accounts/{}", self.server_url, receiver_pub_key))
             .header("Accept", "application/json")
-            .form(&[("tx", base64_transaction)])
             .send()
             .await?;
 
-        Ok(response)
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(DestinationStatus::NotFound);
+        }
+
+        let account = response
+            .error_for_status()?
+            .json::<HorizonAccount>()
+            .await?;
+
+        let Asset::Credit(credit_asset) = asset else {
+            return Ok(DestinationStatus::Ready);
+        };
+
+        let trustline = account.balances.iter().find(|balance| {
+            balance.asset_code.as_deref() == Some(credit_asset.code())
+                && balance.asset_issuer.as_deref()
+                    == Some(credit_asset.issuer().account_id().as_str())
+        });
+
+        Ok(match trustline {
+            None => DestinationStatus::NoTrustline,
+            Some(balance) if balance.is_authorized == Some(false) => {
+                DestinationStatus::NotAuthorized
+            }
+            Some(_) => DestinationStatus::Ready,
+        })
     }
 
     /// Sends an asset from one account to another
     ///
     /// # Arguments
-    /// * `sender_keypair` - The keypair of the sending account
+    /// * `signer` - The signer holding the key of the sending account
+    /// * `sender_account_id` - The id of the sending account
     /// * `receiver_pub_key` - The public key of the receiving account
     /// * `asset` - The asset to send
     /// * `amount` - The amount to send (will be converted to stroops)
     ///
     /// # Returns
     /// * `Result<Response, Error>` - The transaction response or an error
-    pub async fn send_asset(
+    pub async fn send_asset<S: Signer>(
         &self,
-        sender_keypair: Keypair,
+        signer: &S,
+        sender_account_id: &str,
         receiver_pub_key: String,
         asset: Asset,
         amount: u64,
     ) -> Result<Response, Error> {
-        let sender_account =
-            stellar_base::PublicKey::from_account_id(sender_keypair.public_key().as_str()).unwrap();
+        let sender_account = signer.public_key(sender_account_id).await?;
 
-        let receiver_account =
-            stellar_base::PublicKey::from_account_id(receiver_pub_key.as_str()).unwrap();
+        let transaction =
+            self.build_payment_transaction(&sender_account, &receiver_pub_key, asset, amount)?;
 
-        let time_bounds = TimeBounds::always_valid();
+        self.sign_and_submit(transaction, signer, sender_account_id)
+            .await
+    }
 
-        let payment_operation_builder = PaymentOperationBuilder::new();
+    /// Loads the signers and thresholds of an account from Horizon
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the account
+    pub fn account_authorization(
+        &self,
+        account: &PublicKey,
+    ) -> Result<AccountAuthorization, Error> {
+        let account_details = self.client.load_account(&account.account_id())?;
+
+        // Pre-authorised transaction and hash(x) signers cannot sign envelopes we build
+        let signers = account_details
+            .signers
+            .iter()
+            .filter(|signer| signer.r#type == "ed25519_public_key")
+            .map(|signer| Ok((PublicKey::from_account_id(&signer.key)?, signer.weight)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(AccountAuthorization {
+            signers,
+            low_threshold: account_details.thresholds.low_threshold.into(),
+            medium_threshold: account_details.thresholds.med_threshold.into(),
+            high_threshold: account_details.thresholds.high_threshold.into(),
+        })
+    }
 
-        // Convert amount to stroops
-        let amount_in_stroops = Stroops::new((amount * 1000000000).try_into().unwrap());
+    /// Returns the weight a key has as a signer on an account, or 0 if it is not a signer
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the account
+    /// * `signer_key` - The public key of the signer to look up
+    pub fn signer_weight(&self, account: &PublicKey, signer_key: &PublicKey) -> Result<u32, Error> {
+        Ok(self.account_authorization(account)?.weight_of(signer_key))
+    }
 
-        let payment_operation = payment_operation_builder
-            .with_source_account(sender_account.clone())
-            .with_destination(receiver_account)
-            .with_asset(asset)
-            .with_amount(amount_in_stroops)?
+    /// Builds an unsigned SetOptions transaction for an account
+    ///
+    /// Stellar allows one signer change per operation, so every signer after the first
+    /// gets an operation of its own.
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the account to configure
+    /// * `options` - The settings to change
+    ///
+    /// # Returns
+    /// * `Result<Transaction, Error>` - The unsigned transaction or an error
+    pub fn build_set_options_transaction(
+        &self,
+        account: &PublicKey,
+        options: &AccountOptions,
+    ) -> Result<Transaction, Error> {
+        let mut signers = options.signers.iter();
+
+        let mut set_options_operation = SetOptionsOperationBuilder::new()
+            .with_source_account(account.clone())
+            .with_master_weight(options.master_weight)
+            .with_low_threshold(options.low_threshold)
+            .with_medium_threshold(options.medium_threshold)
+            .with_high_threshold(options.high_threshold)
+            .with_set_flags(options.set_flags)
+            .with_clear_flags(options.clear_flags)
+            .with_signer(signers.next().map(|(key, weight)| {
+                AccountSigner::new(SignerKey::new_from_public_key(key.clone()), *weight)
+            }))
             .build()?;
 
-        let sender_account_details = self
-            .client
-            .load_account(&sender_keypair.public_key())
-            .unwrap();
+        if let Operation::SetOptions(operation) = &mut set_options_operation {
+            *operation.home_domain_mut() = options.home_domain.clone();
+        }
 
-        let mut transaction = Transaction::builder(
-            sender_account,
-            sender_account_details.sequence_number().parse::<i64>()? + 1,
+        let mut transaction_builder = Transaction::builder(
+            account.clone(),
+            self.next_sequence_number(account)?,
             Stroops::new(100),
         )
-        .add_operation(payment_operation)
-        .with_time_bounds(time_bounds)
-        .into_transaction()?;
+        .add_operation(set_options_operation);
+
+        for (key, weight) in signers {
+            transaction_builder = transaction_builder.add_operation(
+                SetOptionsOperationBuilder::new()
+                    .with_source_account(account.clone())
+                    .with_signer(Some(AccountSigner::new(
+                        SignerKey::new_from_public_key(key.clone()),
+                        *weight,
+                    )))
+                    .build()?,
+            );
+        }
 
-        // Sign the transaction
-        let mut sender_keypair_clone = sender_keypair.clone();
-        let sender_key = KeyPair::from_str(&sender_keypair_clone.secret_key().unwrap())?;
-        transaction.sign(&sender_key, &Network::new_test())?;
+        Ok(transaction_builder
+            .with_time_bounds(self.time_bounds())
+            .into_transaction()?)
+    }
 
-        let base64_transaction = transaction.into_envelope().xdr_base64()?;
+    /// Changes the signers, thresholds, home domain or flags of an account
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding a key of the account with enough weight
+    /// * `account_id` - The id of the account to configure
+    /// * `options` - The settings to change
+    ///
+    /// # Returns
+    /// * `Result<Response, Error>` - The transaction response or an error
+    pub async fn set_options<S: Signer>(
+        &self,
+        signer: &S,
+        account_id: &str,
+        options: &AccountOptions,
+    ) -> Result<Response, Error> {
+        let account = signer.public_key(account_id).await?;
 
-        // Submit the transaction to Horizon testnet using reqwest
-        let client = reqwest::Client::new();
-        let response = client
-            .post(self.server_url.clone() + "/transactions")
-            .header("Accept", "application/json")
-            .form(&[("tx", base64_transaction)])
-            .send()
-            .await?;
+        let transaction = self.build_set_options_transaction(&account, options)?;
+
+        self.sign_and_submit(transaction, signer, account_id).await
+    }
+
+    /// Hands control of an account over to a new key
+    ///
+    /// The new key is added as a signer with the weight the master key had, and the
+    /// master key weight is set to 0 in the same transaction, so the account is never
+    /// left without a usable signer.
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding the current master key of the account
+    /// * `account_id` - The id of the account
+    /// * `new_key` - The public key that takes over the account
+    ///
+    /// # Returns
+    /// * `Result<Response, Error>` - The transaction response or an error
+    pub async fn replace_master_key<S: Signer>(
+        &self,
+        signer: &S,
+        account_id: &str,
+        new_key: PublicKey,
+    ) -> Result<Response, Error> {
+        let account = signer.public_key(account_id).await?;
+
+        let master_weight = self.signer_weight(&account, &account)?;
+        if master_weight == 0 {
+            return Err(anyhow::anyhow!(
+                "Master key of account {} is disabled",
+                account_id
+            ));
+        }
 
-        Ok(response)
+        let options = AccountOptions {
+            master_weight: Some(0),
+            signers: vec![(new_key, master_weight)],
+            ..Default::default()
+        };
+
+        let transaction = self.build_set_options_transaction(&account, &options)?;
+
+        self.sign_and_submit(transaction, signer, account_id).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signer::{KeyPairSigner, ISSUER_ACCOUNT_ID};
     // use mockall::predicate::*;
     use stellar_base::asset::CreditAsset;
+    use stellar_base::KeyPair;
 
     #[test]
     fn test_create_new_account() {
@@ -272,8 +1238,8 @@ mod tests {
 
         let account = result.unwrap();
         assert!(!account.public_key.is_empty());
-        assert!(!account.secret_key.is_empty());
-        assert_ne!(account.public_key, account.secret_key);
+        assert!(!account.secret_key.expose_secret().is_empty());
+        assert_ne!(account.public_key, account.secret_key.expose_secret());
     }
 
     #[tokio::test]
@@ -284,14 +1250,39 @@ mod tests {
         );
 
         let new_account = chain.create_new_account().unwrap();
-        let keypair = Keypair::from_secret_key(&new_account.secret_key).unwrap();
+        let new_public_key = PublicKey::from_account_id(&new_account.public_key).unwrap();
 
         // Note: This test requires ISSUER_SECRET_KEY to be set in environment
         // and requires actual network connection
-        let result = chain.activate_account(keypair).await;
+        let signer = KeyPairSigner::from_env().unwrap();
+        let result = chain
+            .activate_account(&signer, ISSUER_ACCOUNT_ID, new_public_key)
+            .await;
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_received_payment_operation_index() {
+        let mut payment = ReceivedPayment {
+            paging_token: "12884905985".to_string(),
+            transaction_hash: "abc".to_string(),
+            from: "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ".to_string(),
+            asset_code: None,
+            asset_issuer: None,
+            amount: "1.0000000".to_string(),
+            memo_type: "none".to_string(),
+            memo: None,
+        };
+        // 12884905985 is 3 << 32 | 1 << 12 | 1, the first operation of its transaction
+        assert_eq!(payment.operation_index().unwrap(), 0);
+
+        payment.paging_token = "12884905987".to_string();
+        assert_eq!(payment.operation_index().unwrap(), 2);
+
+        payment.paging_token = "now".to_string();
+        assert!(payment.operation_index().is_err());
+    }
+
     #[test]
     fn test_new_stellar_chain() {
         let server_url = "https://horizon-testnet.stellar.org".to_string();
@@ -303,6 +1294,91 @@ mod tests {
         assert_eq!(chain.network, Network::new_test());
     }
 
+    #[test]
+    fn test_time_bounds_expire_after_timeout() {
+        let chain = StellarChain::new(
+            "https://horizon-testnet.stellar.org".to_string(),
+            Network::new_test(),
+        )
+        .with_transaction_timeout(Duration::seconds(30));
+
+        let time_bounds = chain.time_bounds();
+        let max_time = time_bounds.upper().unwrap();
+
+        assert!(max_time > Utc::now());
+        assert!(max_time <= Utc::now() + Duration::seconds(30));
+    }
+
+    #[test]
+    fn test_build_payments_transaction_packs_operations() {
+        let chain = StellarChain::new(
+            "https://horizon-testnet.stellar.org".to_string(),
+            Network::new_test(),
+        );
+
+        let sender =
+            PublicKey::from_account_id(&chain.create_new_account().unwrap().public_key).unwrap();
+        let payment = || PaymentInstruction {
+            receiver_pub_key: chain.create_new_account().unwrap().public_key,
+            asset: Asset::new_native(),
+            amount: 1,
+        };
+
+        let payments = (0..3).map(|_| payment()).collect::<Vec<_>>();
+        let transaction = chain
+            .build_payments_transaction(&sender, 42, &payments)
+            .unwrap();
+
+        assert_eq!(transaction.operations().len(), 3);
+        assert_eq!(*transaction.sequence(), 42);
+        assert_eq!(transaction.fee().to_i64(), 300);
+
+        let too_many = (0..=MAX_OPERATIONS_PER_TRANSACTION)
+            .map(|_| payment())
+            .collect::<Vec<_>>();
+        assert!(chain
+            .build_payments_transaction(&sender, 42, &too_many)
+            .is_err());
+    }
+
+    #[test]
+    fn test_claimable_balance_id() {
+        let chain = StellarChain::new(
+            "https://horizon-testnet.stellar.org".to_string(),
+            Network::new_test(),
+        );
+
+        let source =
+            PublicKey::from_account_id(&chain.create_new_account().unwrap().public_key).unwrap();
+
+        let balance_id = claimable_balance_id(&source, 42, 0).unwrap();
+        assert_eq!(balance_id, claimable_balance_id(&source, 42, 0).unwrap());
+        assert_ne!(balance_id, claimable_balance_id(&source, 42, 1).unwrap());
+        assert_ne!(balance_id, claimable_balance_id(&source, 43, 0).unwrap());
+
+        let encoded = encode_claimable_balance_id(&balance_id);
+        assert_eq!(encoded.len(), 72);
+        assert!(encoded.starts_with("00000000"));
+        assert_eq!(decode_claimable_balance_id(&encoded).unwrap(), balance_id);
+        assert!(decode_claimable_balance_id(&encoded[8..]).is_err());
+    }
+
+    #[test]
+    fn test_set_home_domain_operation() {
+        let account = KeyPair::random().unwrap().public_key().clone();
+
+        let operation = set_home_domain_operation(&account, "example.com").unwrap();
+        let Operation::SetOptions(set_options) = &operation else {
+            panic!("expected a set options operation");
+        };
+        assert_eq!(set_options.home_domain().as_deref(), Some("example.com"));
+        assert!(operation.to_xdr().is_ok());
+
+        let too_long = "a".repeat(33);
+        let operation = set_home_domain_operation(&account, &too_long).unwrap();
+        assert!(operation.to_xdr().is_err());
+    }
+
     #[tokio::test]
     async fn test_establish_trustline_for_asset() {
         let chain = StellarChain::new(
@@ -312,7 +1388,9 @@ mod tests {
 
         // Create a test account
         let new_account = chain.create_new_account().unwrap();
-        let keypair = Keypair::from_secret_key(&new_account.secret_key).unwrap();
+        let signer = KeyPairSigner::new()
+            .with_account("account", new_account.secret_key.expose_secret())
+            .unwrap();
 
         // Create a test asset
         let asset_code = "TEST";
@@ -326,7 +1404,9 @@ mod tests {
         );
 
         // Note: This test requires the account to be funded first
-        let result = chain.establish_trustline_for_asset(keypair, asset).await;
+        let result = chain
+            .establish_trustline_for_asset(&signer, "account", asset)
+            .await;
         assert!(result.is_ok());
     }
 
@@ -341,7 +1421,9 @@ mod tests {
         let sender_account = chain.create_new_account().unwrap();
         let receiver_account = chain.create_new_account().unwrap();
 
-        let sender_keypair = Keypair::from_secret_key(&sender_account.secret_key).unwrap();
+        let signer = KeyPairSigner::new()
+            .with_account("sender", sender_account.secret_key.expose_secret())
+            .unwrap();
 
         // Create a test asset
         let asset_code = "TEST";
@@ -357,7 +1439,8 @@ mod tests {
         // Note: This test requires funded accounts and established trustlines
         let result = chain
             .send_asset(
-                sender_keypair,
+                &signer,
+                "sender",
                 receiver_account.public_key,
                 asset,
                 100, // amount to send
@@ -367,14 +1450,27 @@ mod tests {
     }
 
     // Helper function to create and fund a test account
-    async fn setup_test_account(chain: &StellarChain) -> (Keypair, String) {
+    async fn setup_test_account(
+        chain: &StellarChain,
+        signer: KeyPairSigner,
+        account_id: &str,
+    ) -> (KeyPairSigner, String) {
         let account = chain.create_new_account().unwrap();
-        let keypair = Keypair::from_secret_key(&account.secret_key).unwrap();
+        let signer = signer
+            .with_account(account_id, account.secret_key.expose_secret())
+            .unwrap();
 
         // Activate the account
-        chain.activate_account(keypair.clone()).await.unwrap();
+        chain
+            .activate_account(
+                &signer,
+                ISSUER_ACCOUNT_ID,
+                PublicKey::from_account_id(&account.public_key).unwrap(),
+            )
+            .await
+            .unwrap();
 
-        (keypair, account.public_key)
+        (signer, account.public_key)
     }
 
     #[tokio::test]
@@ -385,8 +1481,9 @@ mod tests {
         );
 
         // Setup sender and receiver accounts
-        let (sender_keypair, _) = setup_test_account(&chain).await;
-        let (receiver_keypair, receiver_public_key) = setup_test_account(&chain).await;
+        let signer = KeyPairSigner::from_env().unwrap();
+        let (signer, _) = setup_test_account(&chain, signer, "sender").await;
+        let (signer, receiver_public_key) = setup_test_account(&chain, signer, "receiver").await;
 
         // Create a test asset
         let asset_code = "TEST";
@@ -401,17 +1498,17 @@ mod tests {
 
         // Establish trustlines for both accounts
         chain
-            .establish_trustline_for_asset(sender_keypair.clone(), asset.clone())
+            .establish_trustline_for_asset(&signer, "sender", asset.clone())
             .await
             .unwrap();
         chain
-            .establish_trustline_for_asset(receiver_keypair, asset.clone())
+            .establish_trustline_for_asset(&signer, "receiver", asset.clone())
             .await
             .unwrap();
 
         // Send asset from sender to receiver
         let result = chain
-            .send_asset(sender_keypair, receiver_public_key, asset, 100)
+            .send_asset(&signer, "sender", receiver_public_key, asset, 100)
             .await;
 
         assert!(result.is_ok());
@@ -422,9 +1519,12 @@ mod tests {
         let chain = StellarChain::new("invalid-url".to_string(), Network::new_test());
 
         let new_account = chain.create_new_account().unwrap();
-        let keypair = Keypair::from_secret_key(&new_account.secret_key).unwrap();
+        let signer = KeyPairSigner::new()
+            .with_account(ISSUER_ACCOUNT_ID, new_account.secret_key.expose_secret())
+            .unwrap();
+        let new_public_key = PublicKey::from_account_id(&new_account.public_key).unwrap();
 
-        let future = chain.activate_account(keypair);
+        let future = chain.activate_account(&signer, ISSUER_ACCOUNT_ID, new_public_key);
         let result = tokio_test::block_on(future);
         assert!(result.is_err());
     }
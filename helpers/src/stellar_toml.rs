@@ -0,0 +1,233 @@
+//! Generation of the `stellar.toml` file (SEP-0001) that describes our organization,
+//! the accounts we control, the assets we issue and the SEP services we run.
+//!
+//! Wallets fetch it from `https://<home domain>/.well-known/stellar.toml` after reading
+//! the home domain set on an issuer account, so the two must match.
+
+use anyhow::Error;
+use serde::Serialize;
+use stellar_base::PublicKey;
+
+/// The version of SEP-0001 the generated file follows
+pub const SEP1_VERSION: &str = "2.7.0";
+
+/// Longest home domain an account can hold on chain
+pub const MAX_HOME_DOMAIN_LENGTH: usize = 32;
+
+/// The contents of a `stellar.toml` file.
+///
+/// Fields are written under their SEP-0001 names and left out when not set.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct StellarToml {
+    pub version: String,
+    pub network_passphrase: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub federation_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_server_sep0024: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kyc_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_auth_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direct_payment_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri_request_signing_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub horizon_url: Option<String>,
+    /// Accounts controlled by the home domain
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<OrgDocumentation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub currencies: Vec<Currency>,
+}
+
+/// The `[DOCUMENTATION]` table describing the organization.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct OrgDocumentation {
+    pub org_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_logo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_physical_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_official_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub org_support_email: Option<String>,
+}
+
+/// A `[[CURRENCIES]]` entry describing one of our assets.
+#[derive(Debug, Default, Serialize)]
+pub struct Currency {
+    pub code: String,
+    pub issuer: String,
+    /// `live`, `dead`, `test` or `private`
+    pub status: String,
+    pub display_decimals: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    pub is_asset_anchored: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor_asset_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor_asset: Option<String>,
+}
+
+impl StellarToml {
+    /// Creates a `stellar.toml` for a network, taking the organization and the SEP
+    /// endpoints from the environment
+    ///
+    /// The organization is described by `ORG_NAME`, `ORG_URL`, `ORG_LOGO`,
+    /// `ORG_DESCRIPTION`, `ORG_PHYSICAL_ADDRESS`, `ORG_OFFICIAL_EMAIL` and
+    /// `ORG_SUPPORT_EMAIL`, and is left out without `ORG_NAME`. The endpoints and keys
+    /// are read from environment variables named like their SEP-0001 fields, e.g.
+    /// `TRANSFER_SERVER` or `SIGNING_KEY`.
+    ///
+    /// # Arguments
+    /// * `network_passphrase` - The passphrase of the network our accounts live on
+    ///
+    /// # Returns
+    /// * `Result<StellarToml, Error>` - The file without accounts and currencies, or an
+    ///   error if a configured key is not a Stellar public key
+    pub fn from_env(network_passphrase: &str) -> Result<Self, Error> {
+        let signing_key = public_key_var("SIGNING_KEY")?;
+        let uri_request_signing_key = public_key_var("URI_REQUEST_SIGNING_KEY")?;
+
+        let documentation = optional_var("ORG_NAME").map(|org_name| OrgDocumentation {
+            org_name,
+            org_url: optional_var("ORG_URL"),
+            org_logo: optional_var("ORG_LOGO"),
+            org_description: optional_var("ORG_DESCRIPTION"),
+            org_physical_address: optional_var("ORG_PHYSICAL_ADDRESS"),
+            org_official_email: optional_var("ORG_OFFICIAL_EMAIL"),
+            org_support_email: optional_var("ORG_SUPPORT_EMAIL"),
+        });
+
+        Ok(StellarToml {
+            version: SEP1_VERSION.to_string(),
+            network_passphrase: network_passphrase.to_string(),
+            federation_server: optional_var("FEDERATION_SERVER"),
+            transfer_server: optional_var("TRANSFER_SERVER"),
+            transfer_server_sep0024: optional_var("TRANSFER_SERVER_SEP0024"),
+            kyc_server: optional_var("KYC_SERVER"),
+            web_auth_endpoint: optional_var("WEB_AUTH_ENDPOINT"),
+            signing_key,
+            direct_payment_server: optional_var("DIRECT_PAYMENT_SERVER"),
+            uri_request_signing_key,
+            horizon_url: optional_var("STELLAR_HORIZON_URL"),
+            accounts: Vec::new(),
+            documentation,
+            currencies: Vec::new(),
+        })
+    }
+
+    /// Renders the file as TOML
+    pub fn to_toml_string(&self) -> Result<String, Error> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+/// Returns the home domain configured in `HOME_DOMAIN`, if any
+///
+/// # Returns
+/// * `Result<Option<String>, Error>` - The domain, or an error if it is too long to be
+///   set on an account
+pub fn home_domain_from_env() -> Result<Option<String>, Error> {
+    let Some(home_domain) = optional_var("HOME_DOMAIN") else {
+        return Ok(None);
+    };
+
+    if home_domain.len() > MAX_HOME_DOMAIN_LENGTH || home_domain.contains('/') {
+        return Err(anyhow::anyhow!(
+            "HOME_DOMAIN must be a domain of at most {} characters",
+            MAX_HOME_DOMAIN_LENGTH
+        ));
+    }
+
+    Ok(Some(home_domain))
+}
+
+/// Returns an environment variable, treating an empty one as unset
+fn optional_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Returns an environment variable holding a Stellar public key
+fn public_key_var(name: &str) -> Result<Option<String>, Error> {
+    let Some(value) = optional_var(name) else {
+        return Ok(None);
+    };
+
+    PublicKey::from_account_id(&value)
+        .map_err(|_| anyhow::anyhow!("{} must be a Stellar public key", name))?;
+
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_toml_string() {
+        let stellar_toml = StellarToml {
+            version: SEP1_VERSION.to_string(),
+            network_passphrase: "Test SDF Network ; September 2015".to_string(),
+            web_auth_endpoint: Some("https://example.com/auth".to_string()),
+            accounts: vec!["GISSUER".to_string()],
+            documentation: Some(OrgDocumentation {
+                org_name: "Example".to_string(),
+                ..Default::default()
+            }),
+            currencies: vec![Currency {
+                code: "GRP".to_string(),
+                issuer: "GISSUER".to_string(),
+                status: "live".to_string(),
+                display_decimals: 2,
+                is_asset_anchored: true,
+                anchor_asset_type: Some("fiat".to_string()),
+                anchor_asset: Some("USD".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let rendered = stellar_toml.to_toml_string().unwrap();
+        let parsed = rendered.parse::<toml::Table>().unwrap();
+
+        assert_eq!(parsed["VERSION"].as_str(), Some(SEP1_VERSION));
+        assert_eq!(
+            parsed["WEB_AUTH_ENDPOINT"].as_str(),
+            Some("https://example.com/auth")
+        );
+        assert!(!parsed.contains_key("TRANSFER_SERVER"));
+        assert_eq!(parsed["ACCOUNTS"][0].as_str(), Some("GISSUER"));
+        assert_eq!(
+            parsed["DOCUMENTATION"]["ORG_NAME"].as_str(),
+            Some("Example")
+        );
+
+        let currency = &parsed["CURRENCIES"][0];
+        assert_eq!(currency["code"].as_str(), Some("GRP"));
+        assert_eq!(currency["display_decimals"].as_integer(), Some(2));
+        assert_eq!(currency["is_asset_anchored"].as_bool(), Some(true));
+        assert_eq!(currency["anchor_asset"].as_str(), Some("USD"));
+        assert!(currency.get("desc").is_none());
+    }
+}
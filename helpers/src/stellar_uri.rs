@@ -0,0 +1,394 @@
+//! Payment request URIs (SEP-0007).
+//!
+//! A `web+stellar:pay` URI asks a wallet to pay an account and leaves building the
+//! transaction to the wallet; a `web+stellar:tx` URI hands it a transaction to sign.
+//! Our URIs name our home domain as `origin_domain` and are signed with the key
+//! published as `URI_REQUEST_SIGNING_KEY`, so wallets can show who is asking. With a
+//! callback, the wallet posts the signed transaction there instead of submitting it.
+
+use anyhow::Error;
+use openssl::base64::{decode_block, encode_block};
+use stellar_base::signature::Signature;
+use stellar_base::{KeyPair, Memo, PublicKey};
+
+use crate::federation::memo_value;
+
+/// Scheme and operation of a URI asking for a payment
+pub const PAY_OPERATION: &str = "web+stellar:pay";
+
+/// Scheme and operation of a URI asking for a transaction to be signed
+pub const TX_OPERATION: &str = "web+stellar:tx";
+
+/// Longest message shown to the user, in characters
+pub const MAX_MESSAGE_LENGTH: usize = 300;
+
+/// Prefix of the callback parameter, the only kind of callback SEP-0007 defines
+const CALLBACK_PREFIX: &str = "url:";
+
+/// Signed after 35 zero bytes and the byte 4, ahead of the URI
+const SIGNATURE_PREFIX: &str = "stellar.sep.7 - URI Scheme";
+
+/// Name of the parameter carrying the signature, which always comes last
+const SIGNATURE_PARAM: &str = "&signature=";
+
+/// A request for a payment, which the wallet builds and signs itself.
+#[derive(Debug, Clone, Default)]
+pub struct PayRequest<'a> {
+    /// The account to pay
+    pub destination: &'a str,
+    /// The amount in units, or `None` to let the user choose
+    pub amount: Option<&'a str>,
+    /// The asset to pay, or `None` for XLM
+    pub asset_code: Option<&'a str>,
+    pub asset_issuer: Option<&'a str>,
+    pub memo: Option<&'a Memo>,
+    /// An HTTPS URL the signed transaction is posted to instead of being submitted
+    pub callback: Option<&'a str>,
+    /// A message shown to the user
+    pub msg: Option<&'a str>,
+    /// Set for networks other than the public network
+    pub network_passphrase: Option<&'a str>,
+    /// The domain whose `URI_REQUEST_SIGNING_KEY` signs the URI
+    pub origin_domain: Option<&'a str>,
+}
+
+/// A request to sign a transaction we built.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionRequest<'a> {
+    /// The base64 encoded transaction envelope
+    pub xdr: &'a str,
+    /// An HTTPS URL the signed transaction is posted to instead of being submitted
+    pub callback: Option<&'a str>,
+    /// The account expected to sign
+    pub pubkey: Option<&'a str>,
+    /// A message shown to the user
+    pub msg: Option<&'a str>,
+    /// Set for networks other than the public network
+    pub network_passphrase: Option<&'a str>,
+    /// The domain whose `URI_REQUEST_SIGNING_KEY` signs the URI
+    pub origin_domain: Option<&'a str>,
+}
+
+impl PayRequest<'_> {
+    /// Builds the unsigned `web+stellar:pay` URI
+    ///
+    /// # Errors
+    /// Fails if the destination is not a public key, the asset code and issuer are not
+    /// given together, or the callback or message are invalid
+    pub fn to_uri(&self) -> Result<String, Error> {
+        PublicKey::from_account_id(self.destination)
+            .map_err(|_| anyhow::anyhow!("Destination must be a Stellar public key"))?;
+
+        match (self.asset_code, self.asset_issuer) {
+            (Some(_), Some(asset_issuer)) => {
+                PublicKey::from_account_id(asset_issuer)
+                    .map_err(|_| anyhow::anyhow!("Asset issuer must be a Stellar public key"))?;
+            }
+            (None, None) => {}
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Asset code and issuer must be given together"
+                ));
+            }
+        }
+
+        let mut params = vec![("destination", self.destination.to_string())];
+        if let Some(amount) = self.amount {
+            params.push(("amount", amount.to_string()));
+        }
+        if let (Some(asset_code), Some(asset_issuer)) = (self.asset_code, self.asset_issuer) {
+            params.push(("asset_code", asset_code.to_string()));
+            params.push(("asset_issuer", asset_issuer.to_string()));
+        }
+        if let Some((value, memo_type)) = self
+            .memo
+            .and_then(|memo| Some((memo_value(memo)?, memo_type(memo)?)))
+        {
+            params.push(("memo", value));
+            params.push(("memo_type", memo_type.to_string()));
+        }
+
+        build_uri(
+            PAY_OPERATION,
+            params,
+            self.callback,
+            self.msg,
+            self.network_passphrase,
+            self.origin_domain,
+        )
+    }
+}
+
+impl TransactionRequest<'_> {
+    /// Builds the unsigned `web+stellar:tx` URI
+    ///
+    /// # Errors
+    /// Fails if the expected signer is not a public key, or the callback or message
+    /// are invalid
+    pub fn to_uri(&self) -> Result<String, Error> {
+        let mut params = vec![("xdr", self.xdr.to_string())];
+        if let Some(pubkey) = self.pubkey {
+            PublicKey::from_account_id(pubkey)
+                .map_err(|_| anyhow::anyhow!("Signer must be a Stellar public key"))?;
+            params.push(("pubkey", pubkey.to_string()));
+        }
+
+        build_uri(
+            TX_OPERATION,
+            params,
+            self.callback,
+            self.msg,
+            self.network_passphrase,
+            self.origin_domain,
+        )
+    }
+}
+
+/// Signs a URI, appending the `signature` parameter
+///
+/// # Arguments
+/// * `uri` - The unsigned URI, which must name its `origin_domain`
+/// * `signing_key` - The key published as `URI_REQUEST_SIGNING_KEY`
+///
+/// # Returns
+/// * `Result<String, Error>` - The signed URI
+pub fn sign_uri(uri: &str, signing_key: &KeyPair) -> Result<String, Error> {
+    if uri.contains(SIGNATURE_PARAM) {
+        return Err(anyhow::anyhow!("URI is already signed"));
+    }
+    if !uri.contains("&origin_domain=") {
+        return Err(anyhow::anyhow!(
+            "Only URIs naming their origin domain are signed"
+        ));
+    }
+
+    let signature = signing_key.sign(&signature_payload(uri));
+
+    Ok(format!(
+        "{}{}{}",
+        uri,
+        SIGNATURE_PARAM,
+        percent_encode(&encode_block(signature.as_bytes()))
+    ))
+}
+
+/// Checks the signature of a signed URI
+///
+/// # Arguments
+/// * `uri` - The signed URI
+/// * `public_key` - The `URI_REQUEST_SIGNING_KEY` of its origin domain
+///
+/// # Errors
+/// Fails if the URI is not signed or the signature is not valid for the key
+pub fn verify_uri(uri: &str, public_key: &PublicKey) -> Result<(), Error> {
+    let (unsigned, signature) = uri
+        .rsplit_once(SIGNATURE_PARAM)
+        .ok_or_else(|| anyhow::anyhow!("URI is not signed"))?;
+
+    let signature = percent_decode(signature)
+        .and_then(|signature| decode_block(&signature).ok())
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .ok_or_else(|| anyhow::anyhow!("URI signature is malformed"))?;
+
+    if !signature.verify(public_key, &signature_payload(unsigned)) {
+        return Err(anyhow::anyhow!("URI signature is not valid"));
+    }
+
+    Ok(())
+}
+
+/// Encodes every byte outside the unreserved characters of RFC 3986
+pub fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Decodes a percent-encoded value, the inverse of [`percent_encode`]
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Joins the operation parameters and the shared ones into a URI
+fn build_uri(
+    operation: &str,
+    mut params: Vec<(&str, String)>,
+    callback: Option<&str>,
+    msg: Option<&str>,
+    network_passphrase: Option<&str>,
+    origin_domain: Option<&str>,
+) -> Result<String, Error> {
+    if let Some(callback) = callback {
+        let url = reqwest::Url::parse(callback)
+            .map_err(|_| anyhow::anyhow!("Callback URL is invalid"))?;
+        if url.scheme() != "https" {
+            return Err(anyhow::anyhow!("Callback URL must use https"));
+        }
+        params.push(("callback", format!("{}{}", CALLBACK_PREFIX, callback)));
+    }
+    if let Some(msg) = msg {
+        if msg.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(anyhow::anyhow!(
+                "Message must be at most {} characters",
+                MAX_MESSAGE_LENGTH
+            ));
+        }
+        params.push(("msg", msg.to_string()));
+    }
+    if let Some(network_passphrase) = network_passphrase {
+        params.push(("network_passphrase", network_passphrase.to_string()));
+    }
+    if let Some(origin_domain) = origin_domain {
+        params.push(("origin_domain", origin_domain.to_string()));
+    }
+
+    let query = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, percent_encode(value)))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    Ok(format!("{}?{}", operation, query))
+}
+
+/// The memo type as SEP-0007 names it
+fn memo_type(memo: &Memo) -> Option<&'static str> {
+    match memo {
+        Memo::Text(_) => Some("MEMO_TEXT"),
+        Memo::Id(_) => Some("MEMO_ID"),
+        Memo::Hash(_) => Some("MEMO_HASH"),
+        Memo::Return(_) => Some("MEMO_RETURN"),
+        Memo::None => None,
+    }
+}
+
+/// The bytes a URI signature covers
+fn signature_payload(uri: &str) -> Vec<u8> {
+    let mut payload = vec![0u8; 36];
+    payload[35] = 4;
+    payload.extend_from_slice(SIGNATURE_PREFIX.as_bytes());
+    payload.extend_from_slice(uri.as_bytes());
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESTINATION: &str = "GDEB2CDOO4PUAN7QOXX6VUQI4IM22DB7DLWURM4SQOPMXMNW7KMLUEGF";
+
+    #[test]
+    fn test_pay_uri() {
+        let memo = Memo::new_id(42);
+        let uri = PayRequest {
+            destination: DESTINATION,
+            amount: Some("120.5"),
+            asset_code: Some("USDC"),
+            asset_issuer: Some(DESTINATION),
+            memo: Some(&memo),
+            callback: Some("https://example.com/pay?id=1"),
+            msg: Some("Order #7"),
+            origin_domain: Some("example.com"),
+            ..Default::default()
+        }
+        .to_uri()
+        .unwrap();
+
+        assert_eq!(
+            uri,
+            format!(
+                "web+stellar:pay?destination={0}&amount=120.5&asset_code=USDC&asset_issuer={0}\
+                 &memo=42&memo_type=MEMO_ID&callback=url%3Ahttps%3A%2F%2Fexample.com%2Fpay%3Fid%3D1\
+                 &msg=Order%20%237&origin_domain=example.com",
+                DESTINATION
+            )
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_requests() {
+        let request = PayRequest {
+            destination: DESTINATION,
+            ..Default::default()
+        };
+        assert!(request.to_uri().is_ok());
+
+        assert!(PayRequest {
+            destination: "alice*example.com",
+            ..request.clone()
+        }
+        .to_uri()
+        .is_err());
+        assert!(PayRequest {
+            asset_code: Some("USDC"),
+            ..request.clone()
+        }
+        .to_uri()
+        .is_err());
+        assert!(PayRequest {
+            callback: Some("http://example.com/pay"),
+            ..request.clone()
+        }
+        .to_uri()
+        .is_err());
+        assert!(PayRequest {
+            msg: Some(&"a".repeat(MAX_MESSAGE_LENGTH + 1)),
+            ..request
+        }
+        .to_uri()
+        .is_err());
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signing_key = KeyPair::random().unwrap();
+        let uri = TransactionRequest {
+            xdr: "AAAAAgAAAAA=",
+            pubkey: Some(DESTINATION),
+            network_passphrase: Some("Test SDF Network ; September 2015"),
+            origin_domain: Some("example.com"),
+            ..Default::default()
+        }
+        .to_uri()
+        .unwrap();
+        assert!(uri.starts_with("web+stellar:tx?xdr=AAAAAgAAAAA%3D&pubkey="));
+
+        let signed = sign_uri(&uri, &signing_key).unwrap();
+        assert!(verify_uri(&signed, signing_key.public_key()).is_ok());
+        assert!(sign_uri(&signed, &signing_key).is_err());
+
+        let other_key = KeyPair::random().unwrap();
+        assert!(verify_uri(&signed, other_key.public_key()).is_err());
+        assert!(verify_uri(&uri, signing_key.public_key()).is_err());
+
+        let tampered = signed.replace("example.com", "example.org");
+        assert!(verify_uri(&tampered, signing_key.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_percent_encoding_round_trip() {
+        let value = "a+b/c=d e~ü";
+        assert_eq!(percent_encode(value), "a%2Bb%2Fc%3Dd%20e~%C3%BC");
+        assert_eq!(percent_decode(&percent_encode(value)).unwrap(), value);
+        assert!(percent_decode("%G1").is_none());
+    }
+}
@@ -0,0 +1,436 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Error;
+use rand::Rng;
+use serde::Deserialize;
+
+/// Attempts made to submit one envelope before its outcome is left to the resolver.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry, doubled on every further attempt.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Upper limit for the delay between attempts.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(8);
+/// How long a single request to Horizon may take.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the HTTP client shared by everything talking to Horizon, so connections are
+/// pooled and reused instead of opened per request.
+pub fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// Final outcome of submitting a transaction envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubmissionOutcome {
+    /// The transaction was applied in a ledger
+    Completed,
+    /// The transaction was rejected or failed on chain and will never apply
+    Failed {
+        /// The Horizon transaction result code, e.g. `tx_bad_seq`
+        error_code: String,
+        /// The result code of every operation, in order, if Horizon reported them
+        operation_codes: Vec<String>,
+        /// The response that reported the failure
+        error_message: String,
+    },
+    /// Horizon never answered conclusively and the transaction was not found by hash.
+    /// It may still be included until its max time passes.
+    Unknown {
+        /// The last error seen
+        error_message: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct HorizonTransaction {
+    successful: bool,
+}
+
+/// What a single submission attempt told us.
+enum Attempt {
+    Settled(SubmissionOutcome),
+    /// Horizon did not process the envelope; safe to send it again
+    Retryable(String),
+    /// The envelope may have reached the network; look it up before sending it again
+    Ambiguous(String),
+}
+
+/// Submits signed envelopes to Horizon, retrying until the outcome is known.
+///
+/// Retries always resend the identical envelope, so a transaction that already landed
+/// can never be applied twice: its sequence number is used up. Before giving up, and
+/// after every ambiguous attempt (`504`, timeouts, lost connections), the transaction is
+/// looked up by hash.
+#[derive(Clone)]
+pub struct TransactionSubmitter {
+    server_url: String,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    request_timeout: Duration,
+}
+
+impl TransactionSubmitter {
+    /// Creates a submitter for a Horizon server with the default retry policy
+    ///
+    /// # Arguments
+    /// * `server_url` - The URL of the Stellar Horizon server
+    pub fn new(server_url: String) -> Self {
+        Self {
+            server_url,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Sets how many times an envelope is submitted at most
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the first and the largest delay between attempts
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff.max(initial_backoff);
+        self
+    }
+
+    /// Sets how long a single request to Horizon may take
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Posts an envelope to Horizon once
+    ///
+    /// # Arguments
+    /// * `envelope_xdr` - The base64 encoded transaction envelope
+    ///
+    /// # Returns
+    /// * `Result<reqwest::Response, Error>` - The Horizon response or an error
+    pub async fn post(&self, envelope_xdr: &str) -> Result<reqwest::Response, Error> {
+        Ok(http_client()
+            .post(format!("{}/transactions", self.server_url))
+            .header("Accept", "application/json")
+            .timeout(self.request_timeout)
+            .form(&[("tx", envelope_xdr)])
+            .send()
+            .await?)
+    }
+
+    /// Looks up a transaction on Horizon by its hash
+    ///
+    /// # Returns
+    /// * `Result<Option<SubmissionOutcome>, Error>` - `Completed` or `Failed` if the
+    ///   transaction is in a ledger, `None` if Horizon does not know it
+    pub async fn find(&self, transaction_hash: &str) -> Result<Option<SubmissionOutcome>, Error> {
+        let response = http_client()
+            .get(format!(
+                "{}/transactions/{}",
+                self.server_url, transaction_hash
+            ))
+            .header("Accept", "application/json")
+            .timeout(self.request_timeout)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let transaction = response
+            .error_for_status()?
+            .json::<HorizonTransaction>()
+            .await?;
+
+        Ok(Some(if transaction.successful {
+            SubmissionOutcome::Completed
+        } else {
+            SubmissionOutcome::Failed {
+                error_code: "tx_failed".to_string(),
+                operation_codes: Vec::new(),
+                error_message: "Transaction failed on chain".to_string(),
+            }
+        }))
+    }
+
+    /// Submits an envelope until Horizon settles it or the attempts run out
+    ///
+    /// # Arguments
+    /// * `envelope_xdr` - The base64 encoded, signed transaction envelope
+    /// * `transaction_hash` - The hex encoded hash of the transaction
+    ///
+    /// # Returns
+    /// * `SubmissionOutcome` - `Unknown` only if no attempt and no lookup was conclusive
+    pub async fn submit(&self, envelope_xdr: &str, transaction_hash: &str) -> SubmissionOutcome {
+        let mut may_have_landed = false;
+        let mut last_error = String::new();
+
+        for attempt in 0..self.max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(self.backoff(attempt)).await;
+
+                // Resending is harmless, but a lookup tells us sooner if it already landed
+                let landed = if may_have_landed {
+                    self.find(transaction_hash).await.ok().flatten()
+                } else {
+                    None
+                };
+                if let Some(outcome) = landed {
+                    return outcome;
+                }
+            }
+
+            match self.attempt(envelope_xdr).await {
+                Attempt::Settled(SubmissionOutcome::Failed {
+                    error_code,
+                    error_message,
+                    ..
+                }) if may_have_landed && error_code == "tx_bad_seq" => {
+                    // An earlier attempt may have used up the sequence number. Horizon
+                    // may not have ingested it yet, so not finding it proves nothing.
+                    match self.find(transaction_hash).await {
+                        Ok(Some(outcome)) => return outcome,
+                        Ok(None) => {
+                            return SubmissionOutcome::Unknown {
+                                error_message: format!(
+                                    "Sequence number used after an attempt that may have landed: {}",
+                                    error_message
+                                ),
+                            };
+                        }
+                        Err(error) => last_error = error.to_string(),
+                    }
+                }
+                Attempt::Settled(outcome) => return outcome,
+                Attempt::Retryable(error) => last_error = error,
+                Attempt::Ambiguous(error) => {
+                    may_have_landed = true;
+                    last_error = error;
+                }
+            }
+        }
+
+        match self.find(transaction_hash).await {
+            Ok(Some(outcome)) => outcome,
+            Ok(None) => SubmissionOutcome::Unknown {
+                error_message: last_error,
+            },
+            Err(error) => SubmissionOutcome::Unknown {
+                error_message: error.to_string(),
+            },
+        }
+    }
+
+    /// Submits the envelope once and classifies the response
+    async fn attempt(&self, envelope_xdr: &str) -> Attempt {
+        let response = match self.post(envelope_xdr).await {
+            Ok(response) => response,
+            // Nothing was sent if the connection could not be opened
+            Err(error) if is_connect_error(&error) => return Attempt::Retryable(error.to_string()),
+            Err(error) => return Attempt::Ambiguous(error.to_string()),
+        };
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+
+        match status.as_u16() {
+            // Horizon only answers 200 once the transaction is applied in a ledger
+            200..=299 => Attempt::Settled(SubmissionOutcome::Completed),
+            400 => Attempt::Settled(SubmissionOutcome::Failed {
+                error_code: result_code(&body),
+                operation_codes: operation_codes(&body),
+                error_message: body,
+            }),
+            // Horizon forwarded the envelope but stopped waiting for the ledger
+            504 => Attempt::Ambiguous(format!("Horizon timed out: {}", body)),
+            429 | 502 | 503 => Attempt::Retryable(format!("Horizon returned {}: {}", status, body)),
+            _ => Attempt::Ambiguous(format!("Horizon returned {}: {}", status, body)),
+        }
+    }
+
+    /// Returns the exponential delay before an attempt, with jitter so concurrent
+    /// submitters do not retry in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_backoff);
+
+        let jitter = rand::thread_rng().gen_range(0.0..0.25);
+        exponential.mul_f64(1.0 + jitter).min(self.max_backoff)
+    }
+}
+
+/// Returns true if a request failed before anything was sent
+fn is_connect_error(error: &Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|error| error.is_connect())
+}
+
+/// Extracts the transaction result code from a Horizon problem response
+fn result_code(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|problem| {
+            problem["extras"]["result_codes"]["transaction"]
+                .as_str()
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "bad_request".to_string())
+}
+
+/// Extracts the result code of every operation from a Horizon problem response
+fn operation_codes(body: &str) -> Vec<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|problem| {
+            problem["extras"]["result_codes"]["operations"]
+                .as_array()
+                .map(|codes| {
+                    codes
+                        .iter()
+                        .filter_map(|code| code.as_str().map(str::to_string))
+                        .collect()
+                })
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves scripted Horizon responses: `submissions` answer POSTs in order and
+    /// `lookups` answer transaction lookups in order. Returns the URL and a counter of
+    /// submissions received.
+    async fn spawn_horizon_stand_in(
+        submissions: Vec<(u16, &'static str)>,
+        lookups: Vec<(u16, &'static str)>,
+    ) -> (String, Arc<Mutex<usize>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let submitted = Arc::new(Mutex::new(0));
+        let counter = submitted.clone();
+
+        tokio::spawn(async move {
+            let mut submissions = submissions.into_iter();
+            let mut lookups = lookups.into_iter();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = vec![0u8; 8192];
+                let read = socket.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+                let (status, body) = if request.starts_with("POST") {
+                    *counter.lock().unwrap() += 1;
+                    submissions.next().unwrap_or((503, "{}"))
+                } else {
+                    lookups.next().unwrap_or((404, "{}"))
+                };
+
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (format!("http://{}", address), submitted)
+    }
+
+    fn submitter(url: String) -> TransactionSubmitter {
+        TransactionSubmitter::new(url)
+            .with_max_attempts(3)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_unavailable() {
+        let (url, submitted) =
+            spawn_horizon_stand_in(vec![(503, "{}"), (200, r#"{"successful":true}"#)], vec![])
+                .await;
+
+        let outcome = submitter(url).submit("AAAA", "hash").await;
+
+        assert_eq!(outcome, SubmissionOutcome::Completed);
+        assert_eq!(*submitted.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_finds_transaction_after_gateway_timeout() {
+        let (url, submitted) =
+            spawn_horizon_stand_in(vec![(504, "{}")], vec![(200, r#"{"successful":true}"#)]).await;
+
+        let outcome = submitter(url).submit("AAAA", "hash").await;
+
+        // The lookup before the second attempt found it, so it was not resent
+        assert_eq!(outcome, SubmissionOutcome::Completed);
+        assert_eq!(*submitted.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejection_is_final() {
+        let (url, submitted) = spawn_horizon_stand_in(
+            vec![(
+                400,
+                r#"{"extras":{"result_codes":{"transaction":"tx_failed","operations":["op_success","op_no_trust"]}}}"#,
+            )],
+            vec![],
+        )
+        .await;
+
+        let outcome = submitter(url).submit("AAAA", "hash").await;
+
+        assert!(matches!(
+            outcome,
+            SubmissionOutcome::Failed { ref error_code, ref operation_codes, .. }
+                if error_code == "tx_failed" && operation_codes == &["op_success", "op_no_trust"]
+        ));
+        assert_eq!(*submitted.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bad_sequence_after_gateway_timeout_is_unknown() {
+        let (url, submitted) = spawn_horizon_stand_in(
+            vec![
+                (504, "{}"),
+                (
+                    400,
+                    r#"{"extras":{"result_codes":{"transaction":"tx_bad_seq"}}}"#,
+                ),
+            ],
+            vec![],
+        )
+        .await;
+
+        let outcome = submitter(url).submit("AAAA", "hash").await;
+
+        // The first attempt may have used the sequence number without being ingested yet
+        assert!(matches!(outcome, SubmissionOutcome::Unknown { .. }));
+        assert_eq!(*submitted.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_when_never_conclusive() {
+        let (url, submitted) =
+            spawn_horizon_stand_in(vec![(504, "{}"), (504, "{}"), (504, "{}")], vec![]).await;
+
+        let outcome = submitter(url).submit("AAAA", "hash").await;
+
+        assert!(matches!(outcome, SubmissionOutcome::Unknown { .. }));
+        assert_eq!(*submitted.lock().unwrap(), 3);
+    }
+}
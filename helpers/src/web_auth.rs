@@ -0,0 +1,554 @@
+//! Stellar web authentication (SEP-0010).
+//!
+//! A wallet proves it controls a Stellar account by signing a challenge transaction
+//! built and signed by our server key. The challenge has sequence number 0, so it can
+//! never be submitted. Once the signatures meet the account's medium threshold, or
+//! come from the account's own key if it does not exist yet, we issue a JWT naming the
+//! account.
+
+use anyhow::Error;
+use chrono::{DateTime, Duration, Utc};
+use openssl::base64::{decode_block, encode_block};
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::sign::Signer as HmacSigner;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use stellar_base::account::DataValue;
+use stellar_base::amount::Stroops;
+use stellar_base::operations::ManageDataOperationBuilder;
+use stellar_base::signature::{DecoratedSignature, SignatureHint};
+use stellar_base::time_bounds::TimeBounds;
+use stellar_base::{KeyPair, Network, Operation, PublicKey, Transaction};
+
+use crate::multisig::decode_transaction;
+use crate::stellar_chain::AccountAuthorization;
+
+/// How long a challenge can be answered
+pub const CHALLENGE_TIMEOUT_SECONDS: i64 = 900;
+
+/// Name of the operation carrying the domain of the authentication endpoint
+pub const WEB_AUTH_DOMAIN_KEY: &str = "web_auth_domain";
+
+/// Bytes of randomness in a challenge nonce, 64 once base64 encoded
+const NONCE_LEN: usize = 48;
+
+/// Leeway for clocks that run slightly ahead of ours
+const CLOCK_SKEW_SECONDS: i64 = 300;
+
+/// A challenge whose structure and server signature were checked.
+#[derive(Debug)]
+pub struct Challenge {
+    pub transaction: Transaction,
+    /// The account the client claims to control
+    pub client_account: PublicKey,
+    /// The home domain the challenge was built for
+    pub home_domain: String,
+}
+
+/// The claims of a web authentication token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebAuthClaims {
+    /// The authentication endpoint that issued the token
+    pub iss: String,
+    /// The authenticated account
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+    /// The hex hash of the challenge the token was issued for
+    pub jti: String,
+}
+
+/// Builds a challenge for an account, signed by the server key
+///
+/// # Arguments
+/// * `server_key` - The key published as `SIGNING_KEY` in our `stellar.toml`
+/// * `client_account` - The account the client wants to authenticate as
+/// * `home_domain` - The home domain the client asked for
+/// * `web_auth_domain` - The domain serving the authentication endpoint
+/// * `network` - The network the client signs for
+///
+/// # Returns
+/// * `Result<Transaction, Error>` - The signed challenge
+pub fn build_challenge(
+    server_key: &KeyPair,
+    client_account: &PublicKey,
+    home_domain: &str,
+    web_auth_domain: &str,
+    network: &Network,
+) -> Result<Transaction, Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut time_bounds = TimeBounds::valid_for(Duration::seconds(CHALLENGE_TIMEOUT_SECONDS));
+    *time_bounds.lower_mut() = Some(Utc::now());
+
+    let mut transaction =
+        Transaction::builder(server_key.public_key().clone(), 0, Stroops::new(100))
+            .add_operation(
+                ManageDataOperationBuilder::new()
+                    .with_source_account(client_account.clone())
+                    .with_data_name(format!("{} auth", home_domain))
+                    .with_data_value(Some(DataValue::from_slice(
+                        encode_block(&nonce).as_bytes(),
+                    )?))
+                    .build()?,
+            )
+            .add_operation(
+                ManageDataOperationBuilder::new()
+                    .with_source_account(server_key.public_key().clone())
+                    .with_data_name(WEB_AUTH_DOMAIN_KEY.to_string())
+                    .with_data_value(Some(DataValue::from_slice(web_auth_domain.as_bytes())?))
+                    .build()?,
+            )
+            .with_time_bounds(time_bounds)
+            .into_transaction()?;
+
+    transaction.sign(server_key, network)?;
+    Ok(transaction)
+}
+
+/// Reads a challenge returned by a client, checking it is one we built and still valid
+///
+/// # Arguments
+/// * `envelope_xdr` - The base64 envelope of the signed challenge
+/// * `server_account` - The public key of the server signing key
+/// * `home_domains` - The home domains we serve
+/// * `web_auth_domain` - The domain serving the authentication endpoint
+/// * `network` - The network the challenge is signed for
+///
+/// # Errors
+/// Returns an error if the challenge is malformed, expired or not signed by us
+pub fn read_challenge(
+    envelope_xdr: &str,
+    server_account: &PublicKey,
+    home_domains: &[&str],
+    web_auth_domain: &str,
+    network: &Network,
+) -> Result<Challenge, Error> {
+    let transaction = decode_transaction(envelope_xdr)?;
+
+    if transaction.source_account().account_id() != server_account.account_id() {
+        return Err(anyhow::anyhow!("Challenge was not built by this server"));
+    }
+
+    if *transaction.sequence() != 0 {
+        return Err(anyhow::anyhow!("Challenge must have sequence number 0"));
+    }
+
+    let now = Utc::now();
+    let (lower, upper) = match transaction.time_bounds() {
+        Some(time_bounds) => (*time_bounds.lower(), *time_bounds.upper()),
+        None => (None, None),
+    };
+    let (Some(lower), Some(upper)) = (lower, upper) else {
+        return Err(anyhow::anyhow!("Challenge must have time bounds"));
+    };
+    if now + Duration::seconds(CLOCK_SKEW_SECONDS) < lower || now > upper {
+        return Err(anyhow::anyhow!("Challenge has expired"));
+    }
+
+    let mut operations = transaction.operations().iter();
+
+    let Some(Operation::ManageData(first)) = operations.next() else {
+        return Err(anyhow::anyhow!(
+            "Challenge must start with a manage data operation"
+        ));
+    };
+    let client_account = first
+        .source_account()
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Challenge operation has no client account"))?;
+    let client_account = PublicKey::from_account_id(&client_account.account_id())
+        .map_err(|_| anyhow::anyhow!("Challenge client account must be a G address"))?;
+
+    let home_domain = home_domains
+        .iter()
+        .find(|home_domain| first.data_name() == format!("{} auth", home_domain))
+        .ok_or_else(|| anyhow::anyhow!("Challenge is not for a home domain we serve"))?;
+
+    let nonce_len = first
+        .data_value()
+        .as_ref()
+        .map(|value| value.as_bytes().len());
+    if nonce_len != Some(64) {
+        return Err(anyhow::anyhow!("Challenge nonce must be 64 bytes"));
+    }
+
+    // Further operations must come from the server, which the client cannot forge
+    for operation in operations {
+        let Operation::ManageData(manage_data) = operation else {
+            return Err(anyhow::anyhow!(
+                "Challenge may only hold manage data operations"
+            ));
+        };
+
+        let from_server = manage_data
+            .source_account()
+            .as_ref()
+            .is_some_and(|source| source.account_id() == server_account.account_id());
+        if !from_server {
+            return Err(anyhow::anyhow!(
+                "Challenge operation is not from this server"
+            ));
+        }
+
+        let value = manage_data
+            .data_value()
+            .as_ref()
+            .map(|value| value.as_bytes());
+        if manage_data.data_name() == WEB_AUTH_DOMAIN_KEY
+            && value != Some(web_auth_domain.as_bytes())
+        {
+            return Err(anyhow::anyhow!(
+                "Challenge is for another authentication domain"
+            ));
+        }
+    }
+
+    let hash = transaction.hash(network)?;
+    if !transaction
+        .signatures()
+        .iter()
+        .any(|signature| is_signed_by(signature, server_account, &hash))
+    {
+        return Err(anyhow::anyhow!("Challenge is not signed by this server"));
+    }
+
+    Ok(Challenge {
+        transaction,
+        client_account,
+        home_domain: home_domain.to_string(),
+    })
+}
+
+/// Verifies the client signatures on a challenge
+///
+/// # Arguments
+/// * `challenge` - The challenge read with [`read_challenge`]
+/// * `server_account` - The public key of the server signing key
+/// * `network` - The network the challenge is signed for
+/// * `authorization` - The signers and thresholds of the client account, or `None` if
+///   it does not exist yet, in which case its own key must sign
+///
+/// # Returns
+/// * `Result<u32, Error>` - The weight the client signatures add up to
+///
+/// # Errors
+/// Returns an error if a signature is from anyone but the server or a signer of the
+/// account, or the signatures do not meet the medium threshold
+pub fn verify_challenge_signatures(
+    challenge: &Challenge,
+    server_account: &PublicKey,
+    network: &Network,
+    authorization: Option<&AccountAuthorization>,
+) -> Result<u32, Error> {
+    let master_key = [(challenge.client_account.clone(), 1)];
+    let (signers, required_weight) = match authorization {
+        Some(authorization) => (
+            authorization.signers.as_slice(),
+            authorization.medium_threshold,
+        ),
+        None => (master_key.as_slice(), 1),
+    };
+
+    let hash = challenge.transaction.hash(network)?;
+    let client_signatures = challenge
+        .transaction
+        .signatures()
+        .iter()
+        .filter(|signature| !is_signed_by(signature, server_account, &hash));
+
+    let mut signed = Vec::new();
+    for signature in client_signatures {
+        let signer = signers
+            .iter()
+            .find(|(key, _)| is_signed_by(signature, key, &hash))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Challenge carries a signature that is not from a signer of the account"
+                )
+            })?;
+
+        if !signed.contains(&signer) {
+            signed.push(signer);
+        }
+    }
+
+    let weight = signed.iter().map(|(_, weight)| *weight).sum::<u32>();
+    if weight < required_weight.max(1) {
+        return Err(anyhow::anyhow!(
+            "Signatures add up to weight {}, {} is required",
+            weight,
+            required_weight.max(1)
+        ));
+    }
+
+    Ok(weight)
+}
+
+/// Issues a token for verified claims, signed with HMAC-SHA256
+///
+/// # Arguments
+/// * `claims` - The claims of the token
+/// * `secret` - The key the token is signed with
+pub fn issue_token(claims: &WebAuthClaims, secret: &[u8]) -> Result<String, Error> {
+    let header = base64_url_encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = base64_url_encode(&serde_json::to_vec(claims)?);
+    let signing_input = format!("{}.{}", header, payload);
+    let signature = base64_url_encode(&hmac_sha256(secret, signing_input.as_bytes())?);
+
+    Ok(format!("{}.{}", signing_input, signature))
+}
+
+/// Verifies a token we issued and returns its claims
+///
+/// # Arguments
+/// * `token` - The token, without the `Bearer` prefix
+/// * `secret` - The key the token was signed with
+/// * `issuer` - The authentication endpoint that must have issued it
+/// * `now` - The time to check the expiry against
+///
+/// # Errors
+/// Returns an error if the token is malformed, not signed with `secret`, from another
+/// issuer or expired
+pub fn verify_token(
+    token: &str,
+    secret: &[u8],
+    issuer: &str,
+    now: DateTime<Utc>,
+) -> Result<WebAuthClaims, Error> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow::anyhow!("Token is malformed"));
+    };
+
+    let expected = hmac_sha256(secret, format!("{}.{}", header, payload).as_bytes())?;
+    let signature = base64_url_decode(signature)?;
+    if signature.len() != expected.len() || !memcmp::eq(&signature, &expected) {
+        return Err(anyhow::anyhow!("Token signature is invalid"));
+    }
+
+    let header = serde_json::from_slice::<serde_json::Value>(&base64_url_decode(header)?)?;
+    if header["alg"] != "HS256" {
+        return Err(anyhow::anyhow!("Token algorithm is not supported"));
+    }
+
+    let claims = serde_json::from_slice::<WebAuthClaims>(&base64_url_decode(payload)?)?;
+    if claims.iss != issuer {
+        return Err(anyhow::anyhow!("Token was issued by {}", claims.iss));
+    }
+    if claims.exp <= now.timestamp() {
+        return Err(anyhow::anyhow!("Token has expired"));
+    }
+
+    Ok(claims)
+}
+
+/// Returns true if a signature on the transaction hash was made by `key`
+fn is_signed_by(signature: &DecoratedSignature, key: &PublicKey, hash: &[u8]) -> bool {
+    *signature.hint() == SignatureHint::from_public_key(key)
+        && signature.signature().verify(key, hash)
+}
+
+fn hmac_sha256(secret: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let key = PKey::hmac(secret)?;
+    let mut signer = HmacSigner::new(MessageDigest::sha256(), &key)?;
+    Ok(signer.sign_oneshot_to_vec(data)?)
+}
+
+/// Encodes bytes as unpadded base64url, the alphabet of JWTs
+fn base64_url_encode(data: &[u8]) -> String {
+    encode_block(data)
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_")
+}
+
+fn base64_url_decode(encoded: &str) -> Result<Vec<u8>, Error> {
+    if encoded.contains(['+', '/', '=']) {
+        return Err(anyhow::anyhow!("Token is not base64url encoded"));
+    }
+
+    let mut standard = encoded.replace('-', "+").replace('_', "/");
+    while !standard.len().is_multiple_of(4) {
+        standard.push('=');
+    }
+
+    decode_block(&standard).map_err(|_| anyhow::anyhow!("Token is not base64url encoded"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stellar_base::xdr::XDRSerialize;
+
+    const HOME_DOMAIN: &str = "example.com";
+    const WEB_AUTH_DOMAIN: &str = "auth.example.com";
+
+    fn read(transaction: &Transaction, server: &KeyPair) -> Result<Challenge, Error> {
+        read_challenge(
+            &transaction.to_envelope().xdr_base64().unwrap(),
+            server.public_key(),
+            &[HOME_DOMAIN],
+            WEB_AUTH_DOMAIN,
+            &Network::new_test(),
+        )
+    }
+
+    #[test]
+    fn test_challenge_signed_by_client_key() {
+        let network = Network::new_test();
+        let server = KeyPair::random().unwrap();
+        let client = KeyPair::random().unwrap();
+
+        let mut transaction = build_challenge(
+            &server,
+            client.public_key(),
+            HOME_DOMAIN,
+            WEB_AUTH_DOMAIN,
+            &network,
+        )
+        .unwrap();
+
+        // Only the server signed so far
+        let challenge = read(&transaction, &server).unwrap();
+        assert_eq!(challenge.client_account, *client.public_key());
+        assert_eq!(challenge.home_domain, HOME_DOMAIN);
+        assert!(
+            verify_challenge_signatures(&challenge, server.public_key(), &network, None).is_err()
+        );
+
+        transaction.sign(&client, &network).unwrap();
+        let challenge = read(&transaction, &server).unwrap();
+        assert_eq!(
+            verify_challenge_signatures(&challenge, server.public_key(), &network, None).unwrap(),
+            1
+        );
+
+        // Another server cannot have built it
+        assert!(read(&transaction, &KeyPair::random().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_challenge_signed_by_account_signers() {
+        let network = Network::new_test();
+        let server = KeyPair::random().unwrap();
+        let client = KeyPair::random().unwrap();
+        let cosigner = KeyPair::random().unwrap();
+        let stranger = KeyPair::random().unwrap();
+
+        let authorization = AccountAuthorization {
+            signers: vec![
+                (client.public_key().clone(), 1),
+                (cosigner.public_key().clone(), 1),
+            ],
+            low_threshold: 1,
+            medium_threshold: 2,
+            high_threshold: 2,
+        };
+
+        let mut transaction = build_challenge(
+            &server,
+            client.public_key(),
+            HOME_DOMAIN,
+            WEB_AUTH_DOMAIN,
+            &network,
+        )
+        .unwrap();
+        transaction.sign(&client, &network).unwrap();
+
+        let challenge = read(&transaction, &server).unwrap();
+        assert!(verify_challenge_signatures(
+            &challenge,
+            server.public_key(),
+            &network,
+            Some(&authorization)
+        )
+        .is_err());
+
+        transaction.sign(&cosigner, &network).unwrap();
+        let challenge = read(&transaction, &server).unwrap();
+        assert_eq!(
+            verify_challenge_signatures(
+                &challenge,
+                server.public_key(),
+                &network,
+                Some(&authorization)
+            )
+            .unwrap(),
+            2
+        );
+
+        transaction.sign(&stranger, &network).unwrap();
+        let challenge = read(&transaction, &server).unwrap();
+        assert!(verify_challenge_signatures(
+            &challenge,
+            server.public_key(),
+            &network,
+            Some(&authorization)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_challenge_rejects_other_domains_and_expiry() {
+        let network = Network::new_test();
+        let server = KeyPair::random().unwrap();
+        let client = KeyPair::random().unwrap();
+
+        let transaction = build_challenge(
+            &server,
+            client.public_key(),
+            "other.com",
+            WEB_AUTH_DOMAIN,
+            &network,
+        )
+        .unwrap();
+        assert!(read(&transaction, &server).is_err());
+
+        let mut transaction = build_challenge(
+            &server,
+            client.public_key(),
+            HOME_DOMAIN,
+            WEB_AUTH_DOMAIN,
+            &network,
+        )
+        .unwrap();
+        let expired = Utc::now() - Duration::seconds(1);
+        *transaction.time_bounds_mut().as_mut().unwrap().upper_mut() = Some(expired);
+        transaction.signatures_mut().clear();
+        transaction.sign(&server, &network).unwrap();
+        assert!(read(&transaction, &server).is_err());
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let now = Utc::now();
+        let claims = WebAuthClaims {
+            iss: "https://auth.example.com/auth".to_string(),
+            sub: KeyPair::random().unwrap().public_key().account_id(),
+            iat: now.timestamp(),
+            exp: now.timestamp() + 60,
+            jti: "00".repeat(32),
+        };
+
+        let token = issue_token(&claims, b"secret").unwrap();
+        assert_eq!(
+            verify_token(&token, b"secret", &claims.iss, now).unwrap(),
+            claims
+        );
+
+        assert!(verify_token(&token, b"other secret", &claims.iss, now).is_err());
+        assert!(verify_token(&token, b"secret", "https://other.com/auth", now).is_err());
+        assert!(verify_token(&token, b"secret", &claims.iss, now + Duration::seconds(60)).is_err());
+
+        let (signing_input, _) = token.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", signing_input, base64_url_encode(b"forged"));
+        assert!(verify_token(&forged, b"secret", &claims.iss, now).is_err());
+    }
+}
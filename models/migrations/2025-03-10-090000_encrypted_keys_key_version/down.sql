@@ -0,0 +1 @@
+ALTER TABLE encrypted_keys DROP COLUMN key_version;
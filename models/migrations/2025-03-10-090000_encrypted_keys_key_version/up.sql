@@ -0,0 +1,2 @@
+-- Existing rows were written with AES-256-CBC and the global IV (version 0).
+ALTER TABLE encrypted_keys ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+DROP INDEX encrypted_keys_key_version_idx;
+DROP TABLE key_rotation_jobs;
@@ -0,0 +1,13 @@
+CREATE TABLE key_rotation_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    target_key_version INTEGER NOT NULL,
+    last_processed_key_id UUID,
+    processed_count BIGINT NOT NULL DEFAULT 0,
+    failed_count BIGINT NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK (status IN ('running', 'completed', 'failed')),
+    started_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    completed_at TIMESTAMP
+);
+
+CREATE INDEX encrypted_keys_key_version_idx ON encrypted_keys (key_version);
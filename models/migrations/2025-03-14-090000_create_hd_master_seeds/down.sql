@@ -0,0 +1,3 @@
+DROP INDEX accounts_derivation_index_idx;
+ALTER TABLE accounts DROP COLUMN derivation_index;
+DROP TABLE hd_master_seeds;
@@ -0,0 +1,15 @@
+-- Single encrypted BIP-39 master seed that derived account keys come from (SEP-0005).
+CREATE TABLE hd_master_seeds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    encrypted_seed BYTEA NOT NULL,
+    key_version INTEGER NOT NULL,
+    next_index INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX hd_master_seeds_singleton_idx ON hd_master_seeds ((true));
+
+-- Accounts with a derivation index sign with the key at m/44'/148'/index'.
+ALTER TABLE accounts ADD COLUMN derivation_index INTEGER;
+
+CREATE UNIQUE INDEX accounts_derivation_index_idx ON accounts (derivation_index);
@@ -0,0 +1,2 @@
+DROP INDEX pending_transactions_status_idx;
+DROP TABLE pending_transactions;
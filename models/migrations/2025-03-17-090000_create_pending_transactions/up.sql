@@ -0,0 +1,18 @@
+-- Transactions that need more signatures than the service holds, kept until the
+-- source account's threshold is met.
+CREATE TABLE pending_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_address TEXT NOT NULL,
+    envelope_xdr TEXT NOT NULL,
+    transaction_hash TEXT NOT NULL,
+    threshold_level TEXT NOT NULL CHECK (threshold_level IN ('low', 'medium', 'high')),
+    required_weight INTEGER NOT NULL,
+    collected_weight INTEGER NOT NULL DEFAULT 0,
+    description TEXT,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'submitted', 'failed')),
+    result TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX pending_transactions_status_idx ON pending_transactions (status);
@@ -0,0 +1,10 @@
+DROP INDEX pending_transactions_transaction_hash_idx;
+
+ALTER TABLE pending_transactions DROP CONSTRAINT pending_transactions_status_check;
+ALTER TABLE pending_transactions ADD CONSTRAINT pending_transactions_status_check
+    CHECK (status IN ('pending', 'submitted', 'failed'));
+
+ALTER TABLE pending_transactions
+    DROP COLUMN expires_at,
+    DROP COLUMN reference_id,
+    DROP COLUMN purpose;
@@ -0,0 +1,15 @@
+-- Prepared envelopes: transactions built by the service and signed outside of it.
+-- `purpose` and `reference_id` say what to finish once the transaction lands, and
+-- `expires_at` mirrors the upper time bound of the envelope.
+ALTER TABLE pending_transactions
+    ADD COLUMN purpose TEXT NOT NULL DEFAULT 'custom'
+        CHECK (purpose IN ('custom', 'signer_policy', 'activate_account')),
+    ADD COLUMN reference_id UUID,
+    ADD COLUMN expires_at TIMESTAMP;
+
+ALTER TABLE pending_transactions DROP CONSTRAINT pending_transactions_status_check;
+ALTER TABLE pending_transactions ADD CONSTRAINT pending_transactions_status_check
+    CHECK (status IN ('pending', 'submitted', 'failed', 'expired'));
+
+CREATE UNIQUE INDEX pending_transactions_transaction_hash_idx
+    ON pending_transactions (transaction_hash);
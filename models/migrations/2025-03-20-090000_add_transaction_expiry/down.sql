@@ -0,0 +1,3 @@
+DROP INDEX transactions_pending_expiry_idx;
+
+ALTER TABLE transactions DROP COLUMN expires_at;
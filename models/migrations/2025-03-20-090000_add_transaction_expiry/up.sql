@@ -0,0 +1,6 @@
+-- Transactions are recorded as pending before submission. expires_at is the max time
+-- of the transaction, after which it can no longer be included in a ledger.
+ALTER TABLE transactions ADD COLUMN expires_at TIMESTAMP;
+
+CREATE INDEX transactions_pending_expiry_idx ON transactions (expires_at)
+    WHERE status = 'pending';
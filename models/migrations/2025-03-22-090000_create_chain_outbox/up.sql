@@ -0,0 +1,20 @@
+-- Chain submissions written in the same database transaction as the business state
+-- they belong to. The outbox worker submits each signed envelope, records the result
+-- and applies its effects; the transaction hash makes resubmitting an entry harmless.
+CREATE TABLE chain_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES transactions(id),
+    purpose TEXT NOT NULL CHECK (purpose IN ('activate_account', 'payment')),
+    reference_id UUID,
+    envelope_xdr TEXT NOT NULL,
+    transaction_hash TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'completed', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    locked_until TIMESTAMP,
+    created_at TIMESTAMP DEFAULT NOW(),
+    processed_at TIMESTAMP
+);
+
+CREATE INDEX chain_outbox_pending_idx ON chain_outbox (created_at)
+    WHERE status = 'pending';
@@ -0,0 +1,10 @@
+DROP INDEX transactions_batch_idx;
+
+ALTER TABLE transactions DROP CONSTRAINT transactions_transaction_hash_operation_index_key;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_hash_key UNIQUE (transaction_hash);
+
+ALTER TABLE transactions DROP COLUMN operation_index;
+ALTER TABLE transactions DROP COLUMN batch_index;
+ALTER TABLE transactions DROP COLUMN batch_id;
+
+DROP TABLE payment_batches;
@@ -0,0 +1,19 @@
+-- Batch payouts. Every item is its own row in transactions; items packed into the
+-- same chain transaction share its hash and are told apart by their operation index.
+CREATE TABLE payment_batches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    item_count INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+ALTER TABLE transactions ADD COLUMN batch_id UUID REFERENCES payment_batches(id);
+ALTER TABLE transactions ADD COLUMN batch_index INTEGER;
+ALTER TABLE transactions ADD COLUMN operation_index INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE transactions DROP CONSTRAINT transactions_transaction_hash_key;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_hash_operation_index_key
+    UNIQUE (transaction_hash, operation_index);
+
+CREATE INDEX transactions_batch_idx ON transactions (batch_id, batch_index)
+    WHERE batch_id IS NOT NULL;
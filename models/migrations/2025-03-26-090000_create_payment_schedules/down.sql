@@ -0,0 +1,3 @@
+DROP TABLE payment_schedule_runs;
+
+DROP TABLE payment_schedules;
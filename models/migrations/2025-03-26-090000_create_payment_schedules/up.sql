@@ -0,0 +1,38 @@
+-- Standing orders and future dated payouts. A schedule without a recurrence runs once
+-- at next_run_at; a recurring one runs on its cron expression until end_at or
+-- max_runs. next_run_at is NULL once nothing is left to run.
+CREATE TABLE payment_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    receiver_public_key TEXT NOT NULL,
+    asset_code TEXT NOT NULL,
+    asset_issuer TEXT,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    recurrence TEXT,
+    next_run_at TIMESTAMP,
+    end_at TIMESTAMP,
+    max_runs INTEGER CHECK (max_runs > 0),
+    run_count INTEGER NOT NULL DEFAULT 0,
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    status TEXT NOT NULL CHECK (status IN ('active', 'paused', 'cancelled', 'completed')),
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX payment_schedules_due_idx ON payment_schedules (next_run_at)
+    WHERE status = 'active';
+
+-- One row per occurrence. The unique key makes executing an occurrence idempotent: a
+-- second worker, or a retry after a crash, cannot pay the same occurrence twice.
+CREATE TABLE payment_schedule_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    schedule_id UUID NOT NULL REFERENCES payment_schedules(id),
+    occurrence_at TIMESTAMP NOT NULL,
+    transaction_id UUID REFERENCES transactions(id),
+    status TEXT NOT NULL CHECK (status IN ('running', 'submitted', 'completed', 'failed')),
+    error_message TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (schedule_id, occurrence_at)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment'));
+
+DROP TABLE escrows;
@@ -0,0 +1,26 @@
+-- Funds held in claimable balances until a recipient claims them or, once the
+-- deadline has passed, the sender takes them back. balance_id is known before the
+-- create transaction is submitted, it is derived from the transaction.
+CREATE TABLE escrows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sender_account_id UUID NOT NULL REFERENCES accounts(id),
+    recipient_public_key TEXT NOT NULL,
+    recipient_account_id UUID REFERENCES accounts(id),
+    asset_code TEXT NOT NULL,
+    asset_issuer TEXT,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    balance_id TEXT NOT NULL UNIQUE,
+    deadline TIMESTAMP,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'held', 'released', 'reclaimed', 'failed')),
+    create_transaction_id UUID NOT NULL REFERENCES transactions(id),
+    settle_transaction_id UUID REFERENCES transactions(id),
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX escrows_sender_account_id_idx ON escrows (sender_account_id);
+CREATE INDEX escrows_recipient_account_id_idx ON escrows (recipient_account_id);
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow', 'reclaim_escrow'));
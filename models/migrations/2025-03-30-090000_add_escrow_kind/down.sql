@@ -0,0 +1,3 @@
+DROP INDEX escrows_pending_payments_idx;
+
+ALTER TABLE escrows DROP COLUMN kind;
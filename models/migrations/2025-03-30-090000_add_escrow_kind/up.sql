@@ -0,0 +1,8 @@
+-- Payments to recipients that could not receive them yet, because the account does
+-- not exist or has no trustline, are held in claimable balances like escrows. They
+-- are claimed for the recipient once it is activated.
+ALTER TABLE escrows ADD COLUMN kind TEXT NOT NULL DEFAULT 'escrow'
+    CHECK (kind IN ('escrow', 'payment'));
+
+CREATE INDEX escrows_pending_payments_idx ON escrows (recipient_public_key)
+    WHERE kind = 'payment' AND status = 'held';
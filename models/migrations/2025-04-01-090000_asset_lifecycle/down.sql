@@ -0,0 +1,9 @@
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow'));
+
+ALTER TABLE tokens
+    DROP COLUMN updated_at,
+    DROP COLUMN status,
+    DROP COLUMN distributor_account_id;
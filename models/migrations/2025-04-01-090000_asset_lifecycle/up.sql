@@ -0,0 +1,12 @@
+-- Assets registered with an issuer and a distributor account. total_supply moves with
+-- mint and burn transactions as they land on chain, so it only counts issued units.
+ALTER TABLE tokens
+    ADD COLUMN distributor_account_id UUID REFERENCES accounts(id),
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'active'
+        CHECK (status IN ('pending', 'active', 'failed')),
+    ADD COLUMN updated_at TIMESTAMP DEFAULT NOW();
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset'));
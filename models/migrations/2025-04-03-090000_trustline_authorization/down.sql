@@ -0,0 +1,27 @@
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset'));
+
+ALTER TABLE tokens DROP COLUMN issuer_flags;
+
+DROP INDEX trustlines_holder_asset_idx;
+
+ALTER TABLE trustlines ALTER COLUMN stellar_address DROP NOT NULL;
+INSERT INTO trustlines (id, account_id, asset_code, asset_issuer, trust_limit, created_at,
+    status, stellar_address, updated_at)
+SELECT id, account_id, asset_code, asset_issuer, trust_limit, created_at, status,
+    stellar_address, updated_at
+FROM trustlines_archive;
+DROP TABLE trustlines_archive;
+
+DELETE FROM trustlines WHERE status = 'pending';
+UPDATE trustlines SET status = 'active' WHERE status = 'authorized';
+UPDATE trustlines SET status = 'revoked' WHERE status = 'frozen';
+
+ALTER TABLE trustlines DROP CONSTRAINT trustlines_status_check;
+ALTER TABLE trustlines ADD CONSTRAINT trustlines_status_check
+    CHECK (status IN ('active', 'revoked'));
+ALTER TABLE trustlines
+    DROP COLUMN updated_at,
+    DROP COLUMN stellar_address;
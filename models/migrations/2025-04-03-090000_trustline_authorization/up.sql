@@ -0,0 +1,60 @@
+-- Trustlines of the assets we issue, as authorized by their issuer. Holders may be
+-- accounts we do not manage, so they are identified by their Stellar address.
+-- 'active' trustlines need no authorization, 'pending' ones wait for their first
+-- authorization to land on chain and 'frozen' ones can only maintain liabilities.
+ALTER TABLE trustlines
+    ADD COLUMN stellar_address TEXT,
+    ADD COLUMN updated_at TIMESTAMP DEFAULT NOW();
+
+UPDATE trustlines
+SET stellar_address = accounts.stellar_address
+FROM accounts
+WHERE accounts.id = trustlines.account_id;
+
+-- Trustlines with no account have no holder to identify them by, and a holder has one
+-- trustline per asset. Those that do not fit are moved to trustlines_archive for an
+-- operator to review, keeping the latest trustline of each holder for an asset.
+CREATE TABLE trustlines_archive (LIKE trustlines INCLUDING DEFAULTS);
+ALTER TABLE trustlines_archive
+    ADD PRIMARY KEY (id),
+    ADD COLUMN archive_reason TEXT NOT NULL
+        CHECK (archive_reason IN ('no_holder', 'duplicate')),
+    ADD COLUMN archived_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+WITH archived AS (
+    DELETE FROM trustlines WHERE stellar_address IS NULL RETURNING *
+)
+INSERT INTO trustlines_archive SELECT archived.*, 'no_holder' FROM archived;
+
+WITH archived AS (
+    DELETE FROM trustlines
+    WHERE id IN (
+        SELECT id FROM (
+            SELECT id, ROW_NUMBER() OVER (
+                PARTITION BY stellar_address, asset_code, asset_issuer
+                ORDER BY created_at DESC NULLS LAST, id
+            ) AS position
+            FROM trustlines
+        ) AS ranked
+        WHERE position > 1
+    )
+    RETURNING *
+)
+INSERT INTO trustlines_archive SELECT archived.*, 'duplicate' FROM archived;
+
+ALTER TABLE trustlines ALTER COLUMN stellar_address SET NOT NULL;
+ALTER TABLE trustlines DROP CONSTRAINT trustlines_status_check;
+ALTER TABLE trustlines ADD CONSTRAINT trustlines_status_check
+    CHECK (status IN ('active', 'pending', 'authorized', 'frozen', 'revoked'));
+
+CREATE UNIQUE INDEX trustlines_holder_asset_idx
+    ON trustlines (stellar_address, asset_code, asset_issuer);
+
+-- Account flags set on the issuer when the asset was registered
+ALTER TABLE tokens ADD COLUMN issuer_flags INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline'));
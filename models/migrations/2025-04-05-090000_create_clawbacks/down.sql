@@ -0,0 +1,11 @@
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline'));
+
+ALTER TABLE escrows DROP CONSTRAINT escrows_status_check;
+ALTER TABLE escrows ADD CONSTRAINT escrows_status_check
+    CHECK (status IN ('pending', 'held', 'released', 'reclaimed', 'failed'));
+
+DROP TABLE clawbacks;
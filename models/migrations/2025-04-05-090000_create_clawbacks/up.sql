@@ -0,0 +1,34 @@
+-- Amounts of our assets clawed back from a holder, or from a claimable balance held
+-- in an escrow, after fraud or a legal order. Each clawback records why it happened
+-- and the transaction it reverses. The clawed back amount is burned, so it leaves
+-- tokens.total_supply once the clawback lands.
+CREATE TABLE clawbacks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_id UUID NOT NULL REFERENCES tokens(id),
+    holder_address TEXT NOT NULL,
+    account_id UUID REFERENCES accounts(id),
+    escrow_id UUID REFERENCES escrows(id),
+    amount NUMERIC NOT NULL CHECK (amount > 0),
+    reason_code TEXT NOT NULL CHECK (reason_code IN ('fraud', 'court_order', 'sanctions',
+        'compromised_account', 'erroneous_issuance')),
+    note TEXT,
+    original_transaction_id UUID NOT NULL REFERENCES transactions(id),
+    clawback_transaction_id UUID NOT NULL REFERENCES transactions(id),
+    status TEXT NOT NULL CHECK (status IN ('pending', 'completed', 'failed')),
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE INDEX clawbacks_token_id_idx ON clawbacks (token_id);
+CREATE INDEX clawbacks_original_transaction_id_idx ON clawbacks (original_transaction_id);
+
+ALTER TABLE escrows DROP CONSTRAINT escrows_status_check;
+ALTER TABLE escrows ADD CONSTRAINT escrows_status_check
+    CHECK (status IN ('pending', 'held', 'released', 'reclaimed', 'failed', 'clawed_back'));
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline', 'clawback',
+        'clawback_claimable_balance'));
@@ -0,0 +1,15 @@
+DELETE FROM chain_outbox WHERE purpose = 'set_home_domain';
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline', 'clawback',
+        'clawback_claimable_balance'));
+
+ALTER TABLE tokens DROP COLUMN anchor_asset;
+ALTER TABLE tokens DROP COLUMN anchor_asset_type;
+ALTER TABLE tokens DROP COLUMN display_decimals;
+ALTER TABLE tokens DROP COLUMN image;
+ALTER TABLE tokens DROP COLUMN description;
+ALTER TABLE tokens DROP COLUMN name;
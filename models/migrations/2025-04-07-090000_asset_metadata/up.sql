@@ -0,0 +1,17 @@
+-- Metadata published for each asset in stellar.toml
+ALTER TABLE tokens ADD COLUMN name TEXT;
+ALTER TABLE tokens ADD COLUMN description TEXT;
+ALTER TABLE tokens ADD COLUMN image TEXT;
+ALTER TABLE tokens ADD COLUMN display_decimals INT NOT NULL DEFAULT 7
+    CHECK (display_decimals BETWEEN 0 AND 7);
+ALTER TABLE tokens ADD COLUMN anchor_asset_type TEXT
+    CHECK (anchor_asset_type IN ('fiat', 'crypto', 'nft', 'stock', 'bond', 'commodity',
+        'realestate', 'other'));
+ALTER TABLE tokens ADD COLUMN anchor_asset TEXT;
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline', 'clawback',
+        'clawback_claimable_balance', 'set_home_domain'));
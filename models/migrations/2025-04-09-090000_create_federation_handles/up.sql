@@ -0,0 +1,15 @@
+-- Federation names (SEP-0002) for our accounts. A handle is the name part of
+-- `handle*<home domain>`, stored lowercased. Payments to a handle with a memo must
+-- carry that memo, so several handles can share one account.
+CREATE TABLE federation_handles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    handle TEXT NOT NULL UNIQUE CHECK (handle ~ '^[a-z0-9._+@-]{1,64}$'),
+    memo_type TEXT CHECK (memo_type IN ('text', 'id', 'hash')),
+    memo TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    CHECK ((memo_type IS NULL) = (memo IS NULL))
+);
+
+CREATE INDEX federation_handles_account_id_idx ON federation_handles (account_id);
@@ -0,0 +1,2 @@
+DROP TABLE anchor_payment_cursors;
+DROP TABLE anchor_transactions;
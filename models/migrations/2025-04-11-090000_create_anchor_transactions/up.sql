@@ -0,0 +1,44 @@
+-- Deposits and withdrawals we run as an anchor (SEP-0006 and SEP-0024). A deposit
+-- pays the asset out from the distributor once the user's fiat arrived. A withdrawal
+-- waits for the user's payment to the distributor, tagged with withdraw_memo, and
+-- pays fiat out through the rail.
+CREATE TABLE anchor_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind TEXT NOT NULL CHECK (kind IN ('deposit', 'withdrawal')),
+    protocol TEXT NOT NULL CHECK (protocol IN ('sep6', 'sep24')),
+    status TEXT NOT NULL CHECK (status IN ('incomplete', 'pending_user_transfer_start',
+        'pending_user_transfer_complete', 'pending_external', 'pending_anchor',
+        'pending_stellar', 'pending_trust', 'pending_user', 'completed', 'refunded',
+        'expired', 'error')),
+    token_id UUID NOT NULL REFERENCES tokens(id),
+    -- The account authenticated with SEP-0010
+    stellar_account TEXT NOT NULL,
+    amount_in NUMERIC CHECK (amount_in > 0),
+    amount_out NUMERIC CHECK (amount_out >= 0),
+    amount_fee NUMERIC CHECK (amount_fee >= 0),
+    -- Where a withdrawal is paid to and the id memo its payment must carry
+    withdraw_anchor_account TEXT,
+    withdraw_memo TEXT UNIQUE,
+    -- Where the user wants the fiat of a withdrawal
+    fiat_destination TEXT,
+    -- The fiat rail's reference for the deposit or payout
+    external_reference TEXT,
+    -- The payout of a deposit
+    stellar_transaction_id UUID REFERENCES transactions(id),
+    -- The chain transaction that paid the asset in or out
+    stellar_transaction_hash TEXT,
+    message TEXT,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    completed_at TIMESTAMP
+);
+
+CREATE INDEX anchor_transactions_stellar_account_idx ON anchor_transactions (stellar_account);
+CREATE INDEX anchor_transactions_status_idx ON anchor_transactions (status);
+
+-- How far the payments received by each distributor were scanned for withdrawals
+CREATE TABLE anchor_payment_cursors (
+    stellar_address TEXT PRIMARY KEY,
+    paging_token TEXT NOT NULL,
+    updated_at TIMESTAMP DEFAULT NOW()
+);
@@ -0,0 +1,21 @@
+DELETE FROM anchor_transactions WHERE kind = 'receive';
+
+ALTER TABLE anchor_transactions
+    DROP COLUMN callback_url,
+    DROP COLUMN receiver_id,
+    DROP COLUMN sender_id;
+
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_status_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_status_check
+    CHECK (status IN ('incomplete', 'pending_user_transfer_start',
+        'pending_user_transfer_complete', 'pending_external', 'pending_anchor',
+        'pending_stellar', 'pending_trust', 'pending_user', 'completed', 'refunded',
+        'expired', 'error'));
+
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_protocol_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_protocol_check
+    CHECK (protocol IN ('sep6', 'sep24'));
+
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_kind_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_kind_check
+    CHECK (kind IN ('deposit', 'withdrawal'));
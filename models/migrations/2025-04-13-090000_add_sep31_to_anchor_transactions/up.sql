@@ -0,0 +1,25 @@
+-- Payments we receive from sending anchors (SEP-0031). The sending anchor pays the
+-- distributor in withdraw_anchor_account with withdraw_memo, the received payment is
+-- recorded in transactions, and the fiat is paid out to the receiver. Status changes
+-- are posted to callback_url.
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_kind_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_kind_check
+    CHECK (kind IN ('deposit', 'withdrawal', 'receive'));
+
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_protocol_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_protocol_check
+    CHECK (protocol IN ('sep6', 'sep24', 'sep31'));
+
+ALTER TABLE anchor_transactions DROP CONSTRAINT anchor_transactions_status_check;
+ALTER TABLE anchor_transactions ADD CONSTRAINT anchor_transactions_status_check
+    CHECK (status IN ('incomplete', 'pending_user_transfer_start',
+        'pending_user_transfer_complete', 'pending_external', 'pending_anchor',
+        'pending_stellar', 'pending_trust', 'pending_user', 'pending_sender',
+        'pending_receiver', 'pending_customer_info_update', 'completed', 'refunded',
+        'expired', 'error'));
+
+-- The SEP-0012 customers sending and receiving the payment
+ALTER TABLE anchor_transactions
+    ADD COLUMN sender_id TEXT,
+    ADD COLUMN receiver_id TEXT,
+    ADD COLUMN callback_url TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE customer_documents;
+DROP TABLE customers;
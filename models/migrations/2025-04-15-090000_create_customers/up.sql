@@ -0,0 +1,37 @@
+-- Customers verified with SEP-0012. A customer is the Stellar account that
+-- authenticated with SEP-0010, with the memo a sending anchor tells its own customers
+-- apart by, under one customer type. The SEP-0009 fields are a JSON object encrypted
+-- with the key ring, bound to the customer id.
+CREATE TABLE customers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    stellar_account TEXT NOT NULL,
+    memo TEXT,
+    customer_type TEXT NOT NULL CHECK (customer_type IN ('individual', 'sep31-sender',
+        'sep31-receiver')),
+    status TEXT NOT NULL DEFAULT 'needs_info' CHECK (status IN ('needs_info',
+        'processing', 'accepted', 'rejected')),
+    -- Why the customer was rejected, shown to the client
+    status_message TEXT,
+    encrypted_fields BYTEA NOT NULL,
+    key_version INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX customers_identity_idx
+    ON customers (stellar_account, COALESCE(memo, ''), customer_type);
+CREATE INDEX customers_status_idx ON customers (status);
+
+-- Documents uploaded for a customer, one per SEP-0009 binary field, encrypted with
+-- the key ring and bound to the customer id and field
+CREATE TABLE customer_documents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    field TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    encrypted_content BYTEA NOT NULL,
+    key_version INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    UNIQUE (customer_id, field)
+);
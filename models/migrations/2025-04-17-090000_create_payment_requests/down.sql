@@ -0,0 +1,10 @@
+DELETE FROM chain_outbox WHERE purpose = 'payment_request';
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline', 'clawback',
+        'clawback_claimable_balance', 'set_home_domain'));
+
+DROP TABLE payment_requests;
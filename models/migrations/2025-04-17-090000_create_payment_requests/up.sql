@@ -0,0 +1,41 @@
+-- Payment requests (SEP-0007) a merchant hands to a customer's wallet as a signed
+-- web+stellar URI. A `pay` request leaves building the payment to the wallet, a `tx`
+-- request carries the payment we built for the payer's account. Either way the
+-- wallet posts the signed transaction to the request's callback, and we submit it
+-- through the outbox.
+CREATE TABLE payment_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind TEXT NOT NULL CHECK (kind IN ('pay', 'tx')),
+    -- The merchant's account, which is paid
+    account_id UUID NOT NULL REFERENCES accounts(id),
+    destination TEXT NOT NULL,
+    asset_code TEXT NOT NULL,
+    asset_issuer TEXT,
+    -- Left out of a `pay` request to let the customer choose
+    amount NUMERIC CHECK (amount > 0),
+    -- The id memo the payment carries, derived from the request id
+    memo TEXT NOT NULL UNIQUE,
+    -- Shown to the customer by the wallet
+    message TEXT,
+    -- The account a `tx` request is built for
+    payer TEXT,
+    uri TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'submitted',
+        'paid', 'failed')),
+    -- The transaction a `tx` request carries, then the one the wallet signed
+    transaction_hash TEXT,
+    transaction_id UUID REFERENCES transactions(id),
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    updated_at TIMESTAMP DEFAULT NOW(),
+    CHECK (kind = 'pay' OR (amount IS NOT NULL AND payer IS NOT NULL))
+);
+
+CREATE INDEX payment_requests_account_id_idx ON payment_requests (account_id);
+
+ALTER TABLE chain_outbox DROP CONSTRAINT chain_outbox_purpose_check;
+ALTER TABLE chain_outbox ADD CONSTRAINT chain_outbox_purpose_check
+    CHECK (purpose IN ('activate_account', 'payment', 'create_escrow', 'release_escrow',
+        'reclaim_escrow', 'register_asset', 'mint_asset', 'burn_asset',
+        'authorize_trustline', 'freeze_trustline', 'revoke_trustline', 'clawback',
+        'clawback_claimable_balance', 'set_home_domain', 'payment_request'));
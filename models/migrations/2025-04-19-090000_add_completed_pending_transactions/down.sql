@@ -0,0 +1,5 @@
+UPDATE pending_transactions SET status = 'submitted' WHERE status = 'completed';
+
+ALTER TABLE pending_transactions DROP CONSTRAINT pending_transactions_status_check;
+ALTER TABLE pending_transactions ADD CONSTRAINT pending_transactions_status_check
+    CHECK (status IN ('pending', 'submitted', 'failed', 'expired'));
@@ -0,0 +1,6 @@
+-- Pending transactions that landed on chain are marked `completed`, so they can be told
+-- apart from `submitted` ones whose outcome is not known yet. Rows submitted before
+-- this stay `submitted`.
+ALTER TABLE pending_transactions DROP CONSTRAINT pending_transactions_status_check;
+ALTER TABLE pending_transactions ADD CONSTRAINT pending_transactions_status_check
+    CHECK (status IN ('pending', 'submitted', 'completed', 'failed', 'expired'));
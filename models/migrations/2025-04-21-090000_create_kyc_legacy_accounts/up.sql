@@ -0,0 +1,10 @@
+-- User accounts opened before payments needed identity verification. Until their
+-- holders start a customer record they keep paying, within KYC_LEGACY_PAYMENT_LIMIT,
+-- instead of being blocked on deploy. Accounts opened from now on are not listed.
+CREATE TABLE kyc_legacy_accounts (
+    stellar_address TEXT PRIMARY KEY REFERENCES accounts(stellar_address) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+INSERT INTO kyc_legacy_accounts (stellar_address)
+SELECT stellar_address FROM accounts WHERE account_type = 'user';
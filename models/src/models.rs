@@ -9,7 +9,6 @@ use crate::schema::*;
 /// Represents an account in the system.
 #[derive(Queryable, Serialize, Deserialize, Selectable, Insertable)]
 #[diesel(table_name = accounts)]
-
 pub struct Account {
     pub id: Uuid,
     pub stellar_address: String,
@@ -17,6 +16,7 @@ pub struct Account {
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
     pub status: String,
+    pub derivation_index: Option<i32>,
 }
 
 /// Represents a trustline for a specific asset.
@@ -24,22 +24,63 @@ pub struct Account {
 #[diesel(table_name = trustlines)]
 pub struct Trustline {
     pub id: Uuid,
-    pub account_id: Uuid,
+    pub account_id: Option<Uuid>,
     pub asset_code: String,
     pub asset_issuer: String,
-    pub trust_limit: f64,
-    pub created_at: NaiveDateTime,
+    pub trust_limit: Option<BigDecimal>,
+    pub created_at: Option<NaiveDateTime>,
     pub status: String,
+    pub stellar_address: String,
+    pub updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = trustlines)]
 pub struct NewTrustline<'a> {
-    pub account_id: Uuid,
+    pub account_id: Option<Uuid>,
     pub asset_code: &'a str,
     pub asset_issuer: &'a str,
     pub trust_limit: Option<BigDecimal>,
     pub status: &'a str,
+    pub stellar_address: &'a str,
+}
+
+/// An asset issued by one of our accounts and held by its distributor account.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = tokens)]
+pub struct Token {
+    pub id: Uuid,
+    pub asset_code: String,
+    pub issuer_account_id: Option<Uuid>,
+    pub total_supply: Option<BigDecimal>,
+    pub created_at: Option<NaiveDateTime>,
+    pub distributor_account_id: Option<Uuid>,
+    pub status: String,
+    pub updated_at: Option<NaiveDateTime>,
+    pub issuer_flags: i32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub display_decimals: i32,
+    pub anchor_asset_type: Option<String>,
+    pub anchor_asset: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = tokens)]
+pub struct NewToken<'a> {
+    pub id: Uuid,
+    pub asset_code: &'a str,
+    pub issuer_account_id: Option<Uuid>,
+    pub distributor_account_id: Option<Uuid>,
+    pub status: &'a str,
+    pub issuer_flags: i32,
+    pub name: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub image: Option<&'a str>,
+    pub display_decimals: i32,
+    pub anchor_asset_type: Option<&'a str>,
+    pub anchor_asset: Option<&'a str>,
 }
 
 /// Represents a transaction in the blockchain system.
@@ -47,28 +88,36 @@ pub struct NewTrustline<'a> {
 #[diesel(table_name = transactions)]
 pub struct Transaction {
     pub id: Uuid,
-    pub source_account_id: Uuid,
-    pub destination_account_id: Uuid,
+    pub source_account_id: Option<Uuid>,
+    pub destination_account_id: Option<Uuid>,
     pub transaction_hash: String,
     pub amount: f64,
     pub asset_code: String,
     pub memo: Option<String>,
     pub created_at: Option<NaiveDateTime>,
     pub status: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub batch_id: Option<Uuid>,
+    pub batch_index: Option<i32>,
+    pub operation_index: i32,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = transactions)]
 pub struct NewTransaction<'a> {
     pub id: Uuid,
-    pub source_account_id: Uuid,
-    pub destination_account_id: Uuid,
+    pub source_account_id: Option<Uuid>,
+    pub destination_account_id: Option<Uuid>,
     pub transaction_hash: &'a str,
     pub amount: Option<BigDecimal>,
     pub asset_code: &'a str,
     pub memo: Option<&'a str>,
     pub created_at: Option<NaiveDateTime>,
     pub status: &'a str,
+    pub expires_at: Option<NaiveDateTime>,
+    pub batch_id: Option<Uuid>,
+    pub batch_index: Option<i32>,
+    pub operation_index: i32,
 }
 
 /// Represents an error that occurred during a transaction.
@@ -99,6 +148,7 @@ pub struct EncryptedKey {
     pub account_id: Uuid,
     pub encrypted_key: Vec<u8>,
     pub created_at: Option<NaiveDateTime>,
+    pub key_version: i32,
 }
 
 #[derive(Queryable)]
@@ -110,4 +160,433 @@ pub struct AccountWithKey {
     pub updated_at: Option<chrono::NaiveDateTime>,
     pub status: String,
     pub encrypted_key: Vec<u8>,
+    pub key_version: i32,
+}
+
+/// Tracks the progress of re-encrypting stored keys under a new key version.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = key_rotation_jobs)]
+pub struct KeyRotationJob {
+    pub id: Uuid,
+    pub target_key_version: i32,
+    pub last_processed_key_id: Option<Uuid>,
+    pub processed_count: i64,
+    pub failed_count: i64,
+    pub status: String,
+    pub started_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = key_rotation_jobs)]
+pub struct NewKeyRotationJob<'a> {
+    pub id: Uuid,
+    pub target_key_version: i32,
+    pub status: &'a str,
+}
+
+/// The encrypted BIP-39 master seed that derived account keys come from.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = hd_master_seeds)]
+pub struct HdMasterSeed {
+    pub id: Uuid,
+    pub encrypted_seed: Vec<u8>,
+    pub key_version: i32,
+    pub next_index: i32,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = hd_master_seeds)]
+pub struct NewHdMasterSeed {
+    pub id: Uuid,
+    pub encrypted_seed: Vec<u8>,
+    pub key_version: i32,
+}
+
+/// A transaction waiting for co-signers until its source account threshold is met.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = pending_transactions)]
+pub struct PendingTransaction {
+    pub id: Uuid,
+    pub source_address: String,
+    pub envelope_xdr: String,
+    pub transaction_hash: String,
+    pub threshold_level: String,
+    pub required_weight: i32,
+    pub collected_weight: i32,
+    pub description: Option<String>,
+    pub status: String,
+    pub result: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub purpose: String,
+    pub reference_id: Option<Uuid>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = pending_transactions)]
+pub struct NewPendingTransaction<'a> {
+    pub id: Uuid,
+    pub source_address: &'a str,
+    pub envelope_xdr: &'a str,
+    pub transaction_hash: &'a str,
+    pub threshold_level: &'a str,
+    pub required_weight: i32,
+    pub collected_weight: i32,
+    pub description: Option<&'a str>,
+    pub status: &'a str,
+    pub purpose: &'a str,
+    pub reference_id: Option<Uuid>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// A signed chain submission waiting in the outbox, written together with the
+/// business state it belongs to.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = chain_outbox)]
+pub struct ChainOutboxEntry {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub purpose: String,
+    pub reference_id: Option<Uuid>,
+    pub envelope_xdr: String,
+    pub transaction_hash: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub locked_until: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub processed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = chain_outbox)]
+pub struct NewChainOutboxEntry<'a> {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub purpose: &'a str,
+    pub reference_id: Option<Uuid>,
+    pub envelope_xdr: &'a str,
+    pub transaction_hash: &'a str,
+    pub status: &'a str,
+}
+
+/// A batch payout, its items are rows in `transactions`.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = payment_batches)]
+pub struct PaymentBatch {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub item_count: i32,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = payment_batches)]
+pub struct NewPaymentBatch {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub item_count: i32,
+}
+
+/// A scheduled or recurring payment.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = payment_schedules)]
+pub struct PaymentSchedule {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_public_key: String,
+    pub asset_code: String,
+    pub asset_issuer: Option<String>,
+    pub amount: i64,
+    pub recurrence: Option<String>,
+    pub next_run_at: Option<NaiveDateTime>,
+    pub end_at: Option<NaiveDateTime>,
+    pub max_runs: Option<i32>,
+    pub run_count: i32,
+    pub consecutive_failures: i32,
+    pub last_error: Option<String>,
+    pub status: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = payment_schedules)]
+pub struct NewPaymentSchedule<'a> {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub receiver_public_key: &'a str,
+    pub asset_code: &'a str,
+    pub asset_issuer: Option<&'a str>,
+    pub amount: i64,
+    pub recurrence: Option<&'a str>,
+    pub next_run_at: Option<NaiveDateTime>,
+    pub end_at: Option<NaiveDateTime>,
+    pub max_runs: Option<i32>,
+    pub status: &'a str,
+}
+
+/// A single occurrence of a payment schedule.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = payment_schedule_runs)]
+pub struct PaymentScheduleRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub occurrence_at: NaiveDateTime,
+    pub transaction_id: Option<Uuid>,
+    pub status: String,
+    pub error_message: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = payment_schedule_runs)]
+pub struct NewPaymentScheduleRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub occurrence_at: NaiveDateTime,
+    pub status: &'static str,
+}
+
+/// Funds held in a claimable balance for a recipient. `kind` is `escrow`, or
+/// `payment` for a payment the recipient could not receive yet.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = escrows)]
+pub struct Escrow {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub recipient_public_key: String,
+    pub recipient_account_id: Option<Uuid>,
+    pub asset_code: String,
+    pub asset_issuer: Option<String>,
+    pub amount: i64,
+    pub balance_id: String,
+    pub deadline: Option<NaiveDateTime>,
+    pub status: String,
+    pub create_transaction_id: Uuid,
+    pub settle_transaction_id: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub kind: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = escrows)]
+pub struct NewEscrow<'a> {
+    pub id: Uuid,
+    pub sender_account_id: Uuid,
+    pub recipient_public_key: &'a str,
+    pub recipient_account_id: Option<Uuid>,
+    pub asset_code: &'a str,
+    pub asset_issuer: Option<&'a str>,
+    pub amount: i64,
+    pub balance_id: &'a str,
+    pub deadline: Option<NaiveDateTime>,
+    pub status: &'a str,
+    pub create_transaction_id: Uuid,
+    pub kind: &'a str,
+}
+
+/// An amount of one of our assets clawed back from a holder or from a claimable
+/// balance, with the reason and the transaction it reverses.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = clawbacks)]
+pub struct Clawback {
+    pub id: Uuid,
+    pub token_id: Uuid,
+    pub holder_address: String,
+    pub account_id: Option<Uuid>,
+    pub escrow_id: Option<Uuid>,
+    pub amount: BigDecimal,
+    pub reason_code: String,
+    pub note: Option<String>,
+    pub original_transaction_id: Uuid,
+    pub clawback_transaction_id: Uuid,
+    pub status: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = clawbacks)]
+pub struct NewClawback<'a> {
+    pub id: Uuid,
+    pub token_id: Uuid,
+    pub holder_address: &'a str,
+    pub account_id: Option<Uuid>,
+    pub escrow_id: Option<Uuid>,
+    pub amount: BigDecimal,
+    pub reason_code: &'a str,
+    pub note: Option<&'a str>,
+    pub original_transaction_id: Uuid,
+    pub clawback_transaction_id: Uuid,
+    pub status: &'a str,
+}
+
+/// A federation name (SEP-0002) for one of our accounts, with the memo payments to it
+/// must carry.
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = federation_handles)]
+pub struct FederationHandle {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub handle: String,
+    pub memo_type: Option<String>,
+    pub memo: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = federation_handles)]
+pub struct NewFederationHandle<'a> {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub handle: &'a str,
+    pub memo_type: Option<&'a str>,
+    pub memo: Option<&'a str>,
+}
+
+/// A deposit or withdrawal we run as an anchor (SEP-0006 or SEP-0024).
+#[derive(Queryable, Serialize, Deserialize, Selectable)]
+#[diesel(table_name = anchor_transactions)]
+pub struct AnchorTransaction {
+    pub id: Uuid,
+    pub kind: String,
+    pub protocol: String,
+    pub status: String,
+    pub token_id: Uuid,
+    pub stellar_account: String,
+    pub amount_in: Option<BigDecimal>,
+    pub amount_out: Option<BigDecimal>,
+    pub amount_fee: Option<BigDecimal>,
+    pub withdraw_anchor_account: Option<String>,
+    pub withdraw_memo: Option<String>,
+    pub fiat_destination: Option<String>,
+    pub external_reference: Option<String>,
+    pub stellar_transaction_id: Option<Uuid>,
+    pub stellar_transaction_hash: Option<String>,
+    pub message: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub sender_id: Option<String>,
+    pub receiver_id: Option<String>,
+    pub callback_url: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = anchor_transactions)]
+pub struct NewAnchorTransaction<'a> {
+    pub id: Uuid,
+    pub kind: &'a str,
+    pub protocol: &'a str,
+    pub status: &'a str,
+    pub token_id: Uuid,
+    pub stellar_account: &'a str,
+    pub amount_in: Option<BigDecimal>,
+    pub withdraw_anchor_account: Option<&'a str>,
+    pub withdraw_memo: Option<&'a str>,
+    pub fiat_destination: Option<&'a str>,
+    pub sender_id: Option<&'a str>,
+    pub receiver_id: Option<&'a str>,
+}
+
+/// A customer verified with SEP-0012. The SEP-0009 fields are encrypted.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = customers)]
+pub struct Customer {
+    pub id: Uuid,
+    pub stellar_account: String,
+    pub memo: Option<String>,
+    pub customer_type: String,
+    pub status: String,
+    pub status_message: Option<String>,
+    pub encrypted_fields: Vec<u8>,
+    pub key_version: i32,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = customers)]
+pub struct NewCustomer<'a> {
+    pub id: Uuid,
+    pub stellar_account: &'a str,
+    pub memo: Option<&'a str>,
+    pub customer_type: &'a str,
+    pub status: &'a str,
+    pub encrypted_fields: Vec<u8>,
+    pub key_version: i32,
+}
+
+/// A document uploaded for a customer, encrypted.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = customer_documents)]
+pub struct CustomerDocument {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub field: String,
+    pub content_type: String,
+    pub encrypted_content: Vec<u8>,
+    pub key_version: i32,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = customer_documents)]
+pub struct NewCustomerDocument<'a> {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub field: &'a str,
+    pub content_type: &'a str,
+    pub encrypted_content: Vec<u8>,
+    pub key_version: i32,
+}
+
+/// A payment request (SEP-0007) handed to a customer's wallet as a signed URI.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = payment_requests)]
+pub struct PaymentRequest {
+    pub id: Uuid,
+    pub kind: String,
+    pub account_id: Uuid,
+    pub destination: String,
+    pub asset_code: String,
+    pub asset_issuer: Option<String>,
+    pub amount: Option<BigDecimal>,
+    pub memo: String,
+    pub message: Option<String>,
+    pub payer: Option<String>,
+    pub uri: String,
+    pub status: String,
+    pub transaction_hash: Option<String>,
+    pub transaction_id: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = payment_requests)]
+pub struct NewPaymentRequest<'a> {
+    pub id: Uuid,
+    pub kind: &'a str,
+    pub account_id: Uuid,
+    pub destination: &'a str,
+    pub asset_code: &'a str,
+    pub asset_issuer: Option<&'a str>,
+    pub amount: Option<BigDecimal>,
+    pub memo: &'a str,
+    pub message: Option<&'a str>,
+    pub payer: Option<&'a str>,
+    pub uri: &'a str,
+    pub transaction_hash: Option<&'a str>,
+    pub expires_at: NaiveDateTime,
 }
@@ -8,6 +8,105 @@ diesel::table! {
         created_at -> Nullable<Timestamp>,
         updated_at -> Nullable<Timestamp>,
         status -> Text,
+        derivation_index -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    anchor_payment_cursors (stellar_address) {
+        stellar_address -> Text,
+        paging_token -> Text,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    anchor_transactions (id) {
+        id -> Uuid,
+        kind -> Text,
+        protocol -> Text,
+        status -> Text,
+        token_id -> Uuid,
+        stellar_account -> Text,
+        amount_in -> Nullable<Numeric>,
+        amount_out -> Nullable<Numeric>,
+        amount_fee -> Nullable<Numeric>,
+        withdraw_anchor_account -> Nullable<Text>,
+        withdraw_memo -> Nullable<Text>,
+        fiat_destination -> Nullable<Text>,
+        external_reference -> Nullable<Text>,
+        stellar_transaction_id -> Nullable<Uuid>,
+        stellar_transaction_hash -> Nullable<Text>,
+        message -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        completed_at -> Nullable<Timestamp>,
+        sender_id -> Nullable<Text>,
+        receiver_id -> Nullable<Text>,
+        callback_url -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    chain_outbox (id) {
+        id -> Uuid,
+        transaction_id -> Uuid,
+        purpose -> Text,
+        reference_id -> Nullable<Uuid>,
+        envelope_xdr -> Text,
+        transaction_hash -> Text,
+        status -> Text,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        locked_until -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+        processed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    clawbacks (id) {
+        id -> Uuid,
+        token_id -> Uuid,
+        holder_address -> Text,
+        account_id -> Nullable<Uuid>,
+        escrow_id -> Nullable<Uuid>,
+        amount -> Numeric,
+        reason_code -> Text,
+        note -> Nullable<Text>,
+        original_transaction_id -> Uuid,
+        clawback_transaction_id -> Uuid,
+        status -> Text,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    customer_documents (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        field -> Text,
+        content_type -> Text,
+        encrypted_content -> Bytea,
+        key_version -> Int4,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    customers (id) {
+        id -> Uuid,
+        stellar_account -> Text,
+        memo -> Nullable<Text>,
+        customer_type -> Text,
+        status -> Text,
+        status_message -> Nullable<Text>,
+        encrypted_fields -> Bytea,
+        key_version -> Int4,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
     }
 }
 
@@ -17,6 +116,155 @@ diesel::table! {
         account_id -> Uuid,
         encrypted_key -> Bytea,
         created_at -> Nullable<Timestamp>,
+        key_version -> Int4,
+    }
+}
+
+diesel::table! {
+    escrows (id) {
+        id -> Uuid,
+        sender_account_id -> Uuid,
+        recipient_public_key -> Text,
+        recipient_account_id -> Nullable<Uuid>,
+        asset_code -> Text,
+        asset_issuer -> Nullable<Text>,
+        amount -> Int8,
+        balance_id -> Text,
+        deadline -> Nullable<Timestamp>,
+        status -> Text,
+        create_transaction_id -> Uuid,
+        settle_transaction_id -> Nullable<Uuid>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        kind -> Text,
+    }
+}
+
+diesel::table! {
+    federation_handles (id) {
+        id -> Uuid,
+        account_id -> Uuid,
+        handle -> Text,
+        memo_type -> Nullable<Text>,
+        memo -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    hd_master_seeds (id) {
+        id -> Uuid,
+        encrypted_seed -> Bytea,
+        key_version -> Int4,
+        next_index -> Int4,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    key_rotation_jobs (id) {
+        id -> Uuid,
+        target_key_version -> Int4,
+        last_processed_key_id -> Nullable<Uuid>,
+        processed_count -> Int8,
+        failed_count -> Int8,
+        status -> Text,
+        started_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    kyc_legacy_accounts (stellar_address) {
+        stellar_address -> Text,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payment_batches (id) {
+        id -> Uuid,
+        sender_account_id -> Uuid,
+        item_count -> Int4,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payment_requests (id) {
+        id -> Uuid,
+        kind -> Text,
+        account_id -> Uuid,
+        destination -> Text,
+        asset_code -> Text,
+        asset_issuer -> Nullable<Text>,
+        amount -> Nullable<Numeric>,
+        memo -> Text,
+        message -> Nullable<Text>,
+        payer -> Nullable<Text>,
+        uri -> Text,
+        status -> Text,
+        transaction_hash -> Nullable<Text>,
+        transaction_id -> Nullable<Uuid>,
+        expires_at -> Timestamp,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payment_schedule_runs (id) {
+        id -> Uuid,
+        schedule_id -> Uuid,
+        occurrence_at -> Timestamp,
+        transaction_id -> Nullable<Uuid>,
+        status -> Text,
+        error_message -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    payment_schedules (id) {
+        id -> Uuid,
+        sender_account_id -> Uuid,
+        receiver_public_key -> Text,
+        asset_code -> Text,
+        asset_issuer -> Nullable<Text>,
+        amount -> Int8,
+        recurrence -> Nullable<Text>,
+        next_run_at -> Nullable<Timestamp>,
+        end_at -> Nullable<Timestamp>,
+        max_runs -> Nullable<Int4>,
+        run_count -> Int4,
+        consecutive_failures -> Int4,
+        last_error -> Nullable<Text>,
+        status -> Text,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    pending_transactions (id) {
+        id -> Uuid,
+        source_address -> Text,
+        envelope_xdr -> Text,
+        transaction_hash -> Text,
+        threshold_level -> Text,
+        required_weight -> Int4,
+        collected_weight -> Int4,
+        description -> Nullable<Text>,
+        status -> Text,
+        result -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        purpose -> Text,
+        reference_id -> Nullable<Uuid>,
+        expires_at -> Nullable<Timestamp>,
     }
 }
 
@@ -43,6 +291,16 @@ diesel::table! {
         issuer_account_id -> Nullable<Uuid>,
         total_supply -> Nullable<Numeric>,
         created_at -> Nullable<Timestamp>,
+        distributor_account_id -> Nullable<Uuid>,
+        status -> Text,
+        updated_at -> Nullable<Timestamp>,
+        issuer_flags -> Int4,
+        name -> Nullable<Text>,
+        description -> Nullable<Text>,
+        image -> Nullable<Text>,
+        display_decimals -> Int4,
+        anchor_asset_type -> Nullable<Text>,
+        anchor_asset -> Nullable<Text>,
     }
 }
 
@@ -67,6 +325,10 @@ diesel::table! {
         memo -> Nullable<Text>,
         created_at -> Nullable<Timestamp>,
         status -> Text,
+        expires_at -> Nullable<Timestamp>,
+        batch_id -> Nullable<Uuid>,
+        batch_index -> Nullable<Int4>,
+        operation_index -> Int4,
     }
 }
 
@@ -79,6 +341,24 @@ diesel::table! {
         trust_limit -> Nullable<Numeric>,
         created_at -> Nullable<Timestamp>,
         status -> Text,
+        stellar_address -> Text,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    trustlines_archive (id) {
+        id -> Uuid,
+        account_id -> Nullable<Uuid>,
+        asset_code -> Text,
+        asset_issuer -> Text,
+        trust_limit -> Nullable<Numeric>,
+        created_at -> Nullable<Timestamp>,
+        status -> Text,
+        stellar_address -> Nullable<Text>,
+        updated_at -> Nullable<Timestamp>,
+        archive_reason -> Text,
+        archived_at -> Timestamp,
     }
 }
 
@@ -92,19 +372,48 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(anchor_transactions -> tokens (token_id));
+diesel::joinable!(anchor_transactions -> transactions (stellar_transaction_id));
+diesel::joinable!(chain_outbox -> transactions (transaction_id));
+diesel::joinable!(customer_documents -> customers (customer_id));
 diesel::joinable!(encrypted_keys -> accounts (account_id));
+diesel::joinable!(federation_handles -> accounts (account_id));
+diesel::joinable!(payment_batches -> accounts (sender_account_id));
+diesel::joinable!(payment_requests -> accounts (account_id));
+diesel::joinable!(payment_requests -> transactions (transaction_id));
+diesel::joinable!(payment_schedule_runs -> payment_schedules (schedule_id));
+diesel::joinable!(payment_schedule_runs -> transactions (transaction_id));
+diesel::joinable!(payment_schedules -> accounts (sender_account_id));
 diesel::joinable!(tokens -> accounts (issuer_account_id));
 diesel::joinable!(transaction_errors -> transactions (transaction_id));
+diesel::joinable!(transactions -> payment_batches (batch_id));
 diesel::joinable!(trustlines -> accounts (account_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     accounts,
+    anchor_payment_cursors,
+    anchor_transactions,
+    chain_outbox,
+    clawbacks,
+    customer_documents,
+    customers,
     encrypted_keys,
+    escrows,
+    federation_handles,
+    hd_master_seeds,
+    key_rotation_jobs,
+    kyc_legacy_accounts,
+    payment_batches,
+    payment_requests,
+    payment_schedule_runs,
+    payment_schedules,
+    pending_transactions,
     store,
     store_migrations,
     tokens,
     transaction_errors,
     transactions,
     trustlines,
+    trustlines_archive,
     user,
 );
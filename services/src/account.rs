@@ -1,3 +1,5 @@
+#![allow(clippy::module_inception)]
+
 /// Account management module that handles blockchain account operations including creation,
 /// activation, retrieval, and updates. This module primarily works with Stellar blockchain
 /// accounts and their corresponding database records.
@@ -5,6 +7,7 @@ pub mod account {
 
     // Create account
     // Activate account
+    // Prepare account activation
     // Get account
     // Get many accounts
     // Update account
@@ -13,24 +16,33 @@ pub mod account {
     use anyhow::{Error, Ok};
     use bigdecimal::BigDecimal;
     use diesel::ExpressionMethods;
-    use diesel::JoinOnDsl;
     use diesel::QueryDsl;
-    use diesel_async::RunQueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::secret::redact_secrets;
     use helpers::{
-        common::{decrypt_private_key, encrypt_private_key},
+        common::encrypt_private_key,
+        signer::{Signer, ISSUER_ACCOUNT_ID},
         stellar_chain::StellarChain,
     };
     use models::common::Paginate;
     use models::common::Pagination;
-    use models::schema::accounts;
+    use models::schema::{accounts, chain_outbox};
     use models::{
         common::establish_connection,
-        models::{Account, AccountWithKey, EncryptedKey},
+        models::{Account, EncryptedKey, PendingTransaction},
     };
-    use stellar_sdk::Keypair;
+    use stellar_base::PublicKey;
     use uuid::Uuid;
 
     use crate::common::common;
+    use crate::customer::customer::check_verified;
+    use crate::envelope::envelope::prepared_time_bounds;
+    use crate::escrow::escrow;
+    use crate::hd_wallet::hd_wallet;
+    use crate::multisig::multisig::create_pending_transaction;
+    use crate::outbox::outbox;
+    use crate::signer::signer::get_signer;
 
     /// Retrieves an account by its unique identifier
     ///
@@ -103,6 +115,10 @@ pub mod account {
     /// # Returns
     /// * `Result<Account, Error>` - The newly created account or an error
     pub async fn create_account(status: &str, account_type: &str) -> Result<Account, Error> {
+        if hd_wallet::derived_keys_enabled()? {
+            return create_derived_account(status, account_type).await;
+        }
+
         let network = common::get_chain_network().unwrap();
 
         let stellar_chain =
@@ -118,6 +134,7 @@ pub mod account {
             status: status.to_string(),
             account_type: account_type.to_string(),
             stellar_address: new_stellar_account.public_key.as_str().to_string(),
+            derivation_index: None,
         };
 
         let account: Account = diesel::insert_into(models::schema::accounts::table)
@@ -126,15 +143,18 @@ pub mod account {
             .get_result(&mut db_connection)
             .await?;
 
-        // Save encrypted key  //shadowing right?
-        let new_encrypted_key: Vec<u8> =
-            encrypt_private_key(new_stellar_account.secret_key.as_str().as_bytes()).unwrap();
+        // Save encrypted key, bound to the account it belongs to
+        let (new_encrypted_key, key_version) = encrypt_private_key(
+            new_stellar_account.secret_key.expose_secret().as_bytes(),
+            account.id.as_bytes(),
+        )?;
 
         let new_encrypted_key = EncryptedKey {
             id: Uuid::new_v4(),
             account_id: account.id,
             encrypted_key: new_encrypted_key,
             created_at: Some(chrono::Utc::now().naive_utc()),
+            key_version,
         };
 
         diesel::insert_into(models::schema::encrypted_keys::table)
@@ -145,6 +165,51 @@ pub mod account {
         Ok(account)
     }
 
+    /// Creates an account whose key is derived from the master seed (SEP-0005)
+    ///
+    /// Only the derivation index is stored; no per-account secret is kept.
+    ///
+    /// # Arguments
+    /// * `status` - Initial status of the account
+    /// * `account_type` - Type of account to create
+    ///
+    /// # Returns
+    /// * `Result<Account, Error>` - The newly created account or an error
+    async fn create_derived_account(status: &str, account_type: &str) -> Result<Account, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let status = status.to_string();
+        let account_type = account_type.to_string();
+
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let derivation_index = hd_wallet::allocate_derivation_index(conn).await?;
+                    let keypair = hd_wallet::derive_account_keypair(conn, derivation_index).await?;
+
+                    let new_account = Account {
+                        id: Uuid::new_v4(),
+                        created_at: Some(chrono::Utc::now().naive_utc()),
+                        updated_at: Some(chrono::Utc::now().naive_utc()),
+                        status,
+                        account_type,
+                        stellar_address: keypair.public_key().account_id(),
+                        derivation_index: Some(derivation_index),
+                    };
+
+                    let account: Account = diesel::insert_into(models::schema::accounts::table)
+                        .values(&new_account)
+                        .returning(models::schema::accounts::all_columns)
+                        .get_result(conn)
+                        .await?;
+
+                    Ok(account)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
     /// Activates an existing account on the blockchain
     ///
     /// # Arguments
@@ -154,69 +219,163 @@ pub mod account {
     /// * `Result<bool, Error>` - True if activation successful, error otherwise
     ///
     /// # Errors
-    /// Returns an error if the account is already active
+    /// Returns an error if the account is already active, or its holder has not passed
+    /// identity verification
     pub async fn activate_account(account_id: &str) -> Result<bool, Error> {
         let mut db_connection = establish_connection().await.unwrap();
 
         let account_uuid = Uuid::parse_str(account_id).unwrap();
 
         // Get account
-        let account =
-            models::schema::accounts::table
-                .inner_join(models::schema::encrypted_keys::table.on(
-                    models::schema::encrypted_keys::account_id.eq(models::schema::accounts::id),
-                ))
-                .filter(models::schema::accounts::id.eq(account_uuid))
-                .select((
-                    models::schema::accounts::id,
-                    models::schema::accounts::stellar_address,
-                    models::schema::accounts::account_type,
-                    models::schema::accounts::created_at,
-                    models::schema::accounts::updated_at,
-                    models::schema::accounts::status,
-                    models::schema::encrypted_keys::encrypted_key,
-                ))
-                .first::<AccountWithKey>(&mut db_connection)
-                .await?;
+        let account = models::schema::accounts::table
+            .find(account_uuid)
+            .first::<Account>(&mut db_connection)
+            .await?;
 
         if account.status == "active" {
             return Err(anyhow::anyhow!("Account already active"));
         }
 
-        // Activate account on chain
-        let network = common::get_chain_network().unwrap();
-        let stellar_chain =
-            StellarChain::new(std::env::var("STELLAR_HORIZON_URL").unwrap(), network);
+        check_verified(&account.stellar_address).await?;
 
-        let decrypted_key = decrypt_private_key(&account.encrypted_key).unwrap();
+        // Activate account on chain, funded by the issuer account
+        let stellar_chain = common::get_stellar_chain()?;
 
-        let account_keypair =
-            Keypair::from_secret_key(std::str::from_utf8(&decrypted_key).unwrap()).unwrap();
+        let signer = get_signer()?;
+        let funding_account = signer.public_key(ISSUER_ACCOUNT_ID).await?;
 
-        let (activation_response, funding_account, new_account, amount) =
-            stellar_chain.activate_account(account_keypair).await?;
+        let (transaction, amount) = stellar_chain.build_activate_account_transaction(
+            &funding_account,
+            &PublicKey::from_account_id(&account.stellar_address)?,
+            stellar_chain.time_bounds(),
+        )?;
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &signer, ISSUER_ACCOUNT_ID)
+            .await?;
 
         let amount_to_bigint = amount.to_i64().to_string().parse::<BigDecimal>().unwrap();
 
-        common::save_chain_transaction(
-            activation_response,
-            funding_account,
-            new_account,
-            "XLM".to_string(),
-            amount_to_bigint,
-        )
-        .await?;
-
-        // Update account status
-        diesel::update(models::schema::accounts::table)
-            .filter(models::schema::accounts::id.eq(account_uuid))
-            .set(models::schema::accounts::status.eq("active"))
-            .execute(&mut db_connection)
+        // Record the activation and queue it in the outbox in one database transaction
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let funding_address = funding_account.account_id();
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let account = accounts::table
+                        .find(account_uuid)
+                        .for_update()
+                        .first::<Account>(conn)
+                        .await?;
+
+                    if account.status == "active" {
+                        return Err(anyhow::anyhow!("Account already active"));
+                    }
+
+                    let queued_activations = chain_outbox::table
+                        .filter(chain_outbox::purpose.eq("activate_account"))
+                        .filter(chain_outbox::reference_id.eq(account_uuid))
+                        .filter(chain_outbox::status.eq("pending"))
+                        .count()
+                        .get_result::<i64>(conn)
+                        .await?;
+
+                    if queued_activations > 0 {
+                        return Err(anyhow::anyhow!("Account activation already in progress"));
+                    }
+
+                    let transaction_id = common::record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        &funding_address,
+                        &account.stellar_address,
+                        "XLM",
+                        amount_to_bigint,
+                    )
+                    .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        "activate_account",
+                        Some(account_uuid),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
             .await?;
 
+        // The account is marked active once the transaction lands
+        let entry = outbox::process_entry(&stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        // Payments held until the account existed can be claimed now; the activation
+        // stands even if they cannot
+        if let Err(error) = escrow::claim_held_payments(account_uuid).await {
+            eprintln!(
+                "Failed to claim held payments of {}: {}",
+                account_uuid,
+                redact_secrets(&error.to_string())
+            );
+        }
+
         Ok(true)
     }
 
+    /// Prepares the activation of an account as an unsigned envelope, for deployments
+    /// where the issuer key signs offline
+    ///
+    /// The account is marked active once the signed envelope is submitted.
+    ///
+    /// # Arguments
+    /// * `account_id` - A string slice containing the UUID of the account to activate
+    ///
+    /// # Returns
+    /// * `Result<PendingTransaction, Error>` - The prepared envelope, its hash and expiry
+    ///
+    /// # Errors
+    /// Returns an error if the account is already active, or its holder has not passed
+    /// identity verification
+    pub async fn prepare_account_activation(account_id: &str) -> Result<PendingTransaction, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let account_uuid = Uuid::parse_str(account_id)?;
+
+        let account = accounts::table
+            .find(account_uuid)
+            .first::<Account>(&mut db_connection)
+            .await?;
+
+        if account.status == "active" {
+            return Err(anyhow::anyhow!("Account already active"));
+        }
+
+        check_verified(&account.stellar_address).await?;
+
+        let stellar_chain = common::get_stellar_chain()?;
+
+        let funding_account = get_signer()?.public_key(ISSUER_ACCOUNT_ID).await?;
+
+        let (transaction, _) = stellar_chain.build_activate_account_transaction(
+            &funding_account,
+            &PublicKey::from_account_id(&account.stellar_address)?,
+            prepared_time_bounds()?,
+        )?;
+
+        create_pending_transaction(
+            &stellar_chain,
+            &transaction,
+            "activate_account",
+            Some(account_uuid),
+            Some("Activate account"),
+        )
+        .await
+    }
+
     /// Updates an account's status
     ///
     /// # Arguments
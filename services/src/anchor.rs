@@ -0,0 +1,1631 @@
+#![allow(clippy::module_inception)]
+
+/// Anchor module that moves fiat in and out of our assets (SEP-0006 and SEP-0024).
+///
+/// A deposit waits for the user's fiat transfer, confirmed by the fiat rail or an
+/// operator, then pays the asset from the distributor through
+/// `send_non_native_payment`. A withdrawal hands out the distributor account and an
+/// id memo; the worker finds the user's payment tagged with that memo, records it in
+/// `transactions` and pays fiat out through the rail. Payments received from sending
+/// anchors (SEP-0031) go the same way. SEP-0024 transactions start `incomplete` and
+/// the interactive flow at `ANCHOR_INTERACTIVE_URL` completes them. Transactions nobody
+/// sent funds for expire after `ANCHOR_TRANSACTION_TTL_SECONDS`, a day by default.
+pub mod anchor {
+    use anyhow::{Error, Ok};
+    use bigdecimal::{BigDecimal, ToPrimitive};
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::anchor::{AnchorStatus, FiatDeposit, FiatPayout, FiatRail, ManualFiatRail};
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::{DestinationStatus, ReceivedPayment, StellarChain};
+    use models::common::establish_connection;
+    use models::models::{Account, AnchorTransaction, NewAnchorTransaction, NewTransaction, Token};
+    use models::schema::{
+        accounts, anchor_payment_cursors, anchor_transactions, tokens, transactions,
+    };
+    use serde::Serialize;
+    use serde_json::{json, Map, Value};
+    use stellar_base::asset::{Asset, CreditAsset};
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::common::common::{get_account_from_id, get_stellar_chain};
+    use crate::payment::payment::send_non_native_payment;
+    use crate::remittance::remittance::send_status_callback;
+
+    /// The SEPs an anchor transaction can be started through
+    pub const PROTOCOLS: [&str; 2] = ["sep6", "sep24"];
+
+    /// The only withdrawal type we support
+    pub const WITHDRAW_TYPE: &str = "bank_account";
+
+    /// How long a transaction may wait for the user's funds when no TTL is configured
+    const DEFAULT_TRANSACTION_TTL_SECONDS: i64 = 86_400;
+
+    /// Most transactions listed at once
+    const MAX_TRANSACTIONS_LIMIT: i64 = 200;
+
+    /// A deposit or withdrawal, in the shape SEP-0006 and SEP-0024 define.
+    #[derive(Serialize)]
+    pub struct AnchorTransactionView {
+        pub id: Uuid,
+        /// `deposit` or `withdrawal`
+        pub kind: String,
+        pub status: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub amount_in: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub amount_out: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub amount_fee: Option<String>,
+        pub started_at: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub completed_at: Option<String>,
+        /// The hash of the Stellar payment
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub stellar_transaction_id: Option<String>,
+        /// The fiat rail's reference for the transfer
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub external_transaction_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub from: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub to: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub withdraw_anchor_account: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub withdraw_memo: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub withdraw_memo_type: Option<String>,
+        /// Where a sending anchor pays a received payment to
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub stellar_account_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub stellar_memo: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub stellar_memo_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub message: Option<String>,
+    }
+
+    /// The answer to a SEP-0006 deposit: how to send us the fiat.
+    #[derive(Serialize)]
+    pub struct DepositInstructions {
+        pub id: Uuid,
+        pub how: String,
+    }
+
+    /// The answer to a SEP-0006 withdrawal: where to send the asset.
+    #[derive(Serialize)]
+    pub struct WithdrawalInstructions {
+        pub id: Uuid,
+        pub account_id: String,
+        pub memo_type: String,
+        pub memo: String,
+    }
+
+    /// The answer to a SEP-0024 deposit or withdrawal: the flow the user completes.
+    #[derive(Serialize)]
+    pub struct InteractiveResponse {
+        #[serde(rename = "type")]
+        pub response_type: String,
+        pub url: String,
+        pub id: Uuid,
+    }
+
+    /// A deposit or withdrawal a user starts.
+    pub struct AnchorRequest {
+        /// The Stellar account the user authenticated as
+        pub account: String,
+        pub asset_code: String,
+        /// The amount in units, if the user gave one
+        pub amount: Option<BigDecimal>,
+        /// Where fiat is paid for a withdrawal, e.g. a bank account number
+        pub destination: Option<String>,
+    }
+
+    /// Outcome of an anchor worker run.
+    pub struct AnchorSummary {
+        /// Deposits, withdrawals and received payments whose funds arrived
+        pub received: usize,
+        pub completed: usize,
+        /// Deposits waiting for the user to trust the asset
+        pub waiting_for_trust: usize,
+        pub expired: usize,
+        /// Transactions moved to `error` for an operator to look at
+        pub failed: usize,
+    }
+
+    /// Returns the fiat rail anchor transactions go through
+    pub fn get_fiat_rail() -> ManualFiatRail {
+        ManualFiatRail::from_env()
+    }
+
+    /// Lists the assets we anchor, in the `/info` shape of a protocol
+    ///
+    /// # Arguments
+    /// * `protocol` - `sep6` or `sep24`
+    pub async fn get_info(protocol: &str) -> Result<Value, Error> {
+        check_protocol(protocol)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let asset_codes = tokens::table
+            .filter(tokens::status.eq("active"))
+            .filter(tokens::distributor_account_id.is_not_null())
+            .filter(tokens::issuer_account_id.is_not_null())
+            .order(tokens::asset_code.asc())
+            .select(tokens::asset_code)
+            .load::<String>(&mut db_connection)
+            .await?;
+
+        let mut deposit = Map::new();
+        let mut withdraw = Map::new();
+        for asset_code in asset_codes {
+            if protocol == "sep6" {
+                deposit.insert(
+                    asset_code.clone(),
+                    json!({ "enabled": true, "authentication_required": true, "fields": {} }),
+                );
+                withdraw.insert(
+                    asset_code,
+                    json!({
+                        "enabled": true,
+                        "authentication_required": true,
+                        "types": { WITHDRAW_TYPE: { "fields": { "dest": { "description": "Bank account number" } } } }
+                    }),
+                );
+            } else {
+                deposit.insert(asset_code.clone(), json!({ "enabled": true }));
+                withdraw.insert(asset_code, json!({ "enabled": true }));
+            }
+        }
+
+        let mut info = json!({
+            "deposit": deposit,
+            "withdraw": withdraw,
+            "fee": { "enabled": false },
+            "features": { "account_creation": false, "claimable_balances": false },
+        });
+        if protocol == "sep6" {
+            info["transaction"] = json!({ "enabled": true, "authentication_required": true });
+            info["transactions"] = json!({ "enabled": true, "authentication_required": true });
+        }
+
+        Ok(info)
+    }
+
+    /// Starts a SEP-0006 deposit
+    ///
+    /// # Arguments
+    /// * `request` - The account to pay the asset to, the asset and the amount
+    ///
+    /// # Returns
+    /// * `Result<DepositInstructions, Error>` - The transaction and how to send the fiat
+    pub async fn start_deposit(request: AnchorRequest) -> Result<DepositInstructions, Error> {
+        check_deposit_amount(request.amount.as_ref())?;
+        let (token, _, _) = anchored_asset(&request.asset_code).await?;
+        check_account(&request.account)?;
+
+        let id = Uuid::new_v4();
+        let how = get_fiat_rail()
+            .deposit_instructions(&FiatDeposit {
+                reference: &id.to_string(),
+                asset_code: &token.asset_code,
+                amount: request.amount.as_ref(),
+            })
+            .await?;
+
+        let mut db_connection = establish_connection().await?;
+        diesel::insert_into(anchor_transactions::table)
+            .values(&NewAnchorTransaction {
+                id,
+                kind: "deposit",
+                protocol: "sep6",
+                status: AnchorStatus::PendingUserTransferStart.as_str(),
+                token_id: token.id,
+                stellar_account: &request.account,
+                amount_in: request.amount,
+                withdraw_anchor_account: None,
+                withdraw_memo: None,
+                fiat_destination: None,
+                sender_id: None,
+                receiver_id: None,
+            })
+            .execute(&mut db_connection)
+            .await?;
+        set_message(&mut db_connection, id, &how).await?;
+
+        Ok(DepositInstructions { id, how })
+    }
+
+    /// Starts a SEP-0006 withdrawal
+    ///
+    /// # Arguments
+    /// * `request` - The account paying the asset, the asset, the amount and where the
+    ///   fiat goes
+    ///
+    /// # Returns
+    /// * `Result<WithdrawalInstructions, Error>` - The transaction, and the account and
+    ///   memo to send the asset to
+    pub async fn start_withdrawal(request: AnchorRequest) -> Result<WithdrawalInstructions, Error> {
+        check_amount(request.amount.as_ref())?;
+        let destination = request
+            .destination
+            .as_deref()
+            .filter(|destination| !destination.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("A destination for the fiat is required"))?;
+        let (token, _, distributor) = anchored_asset(&request.asset_code).await?;
+        check_account(&request.account)?;
+
+        let id = Uuid::new_v4();
+        let memo = withdraw_memo(id);
+
+        let mut db_connection = establish_connection().await?;
+        diesel::insert_into(anchor_transactions::table)
+            .values(&NewAnchorTransaction {
+                id,
+                kind: "withdrawal",
+                protocol: "sep6",
+                status: AnchorStatus::PendingUserTransferStart.as_str(),
+                token_id: token.id,
+                stellar_account: &request.account,
+                amount_in: request.amount,
+                withdraw_anchor_account: Some(&distributor.stellar_address),
+                withdraw_memo: Some(&memo),
+                fiat_destination: Some(destination),
+                sender_id: None,
+                receiver_id: None,
+            })
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(WithdrawalInstructions {
+            id,
+            account_id: distributor.stellar_address,
+            memo_type: "id".to_string(),
+            memo,
+        })
+    }
+
+    /// Starts a SEP-0024 deposit or withdrawal, which the user completes in the
+    /// interactive flow
+    ///
+    /// # Arguments
+    /// * `kind` - `deposit` or `withdrawal`
+    /// * `request` - The account, the asset and the amount if known
+    ///
+    /// # Returns
+    /// * `Result<InteractiveResponse, Error>` - The transaction and the flow's URL
+    pub async fn start_interactive(
+        kind: &str,
+        request: AnchorRequest,
+    ) -> Result<InteractiveResponse, Error> {
+        match kind {
+            "deposit" => check_deposit_amount(request.amount.as_ref())?,
+            "withdrawal" => check_amount(request.amount.as_ref())?,
+            _ => return Err(anyhow::anyhow!("Kind must be deposit or withdrawal")),
+        }
+        let interactive_url = std::env::var("ANCHOR_INTERACTIVE_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("ANCHOR_INTERACTIVE_URL is not set"))?;
+        let (token, _, _) = anchored_asset(&request.asset_code).await?;
+        check_account(&request.account)?;
+
+        let id = Uuid::new_v4();
+
+        let mut db_connection = establish_connection().await?;
+        diesel::insert_into(anchor_transactions::table)
+            .values(&NewAnchorTransaction {
+                id,
+                kind,
+                protocol: "sep24",
+                status: AnchorStatus::Incomplete.as_str(),
+                token_id: token.id,
+                stellar_account: &request.account,
+                amount_in: request.amount,
+                withdraw_anchor_account: None,
+                withdraw_memo: None,
+                fiat_destination: None,
+                sender_id: None,
+                receiver_id: None,
+            })
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(InteractiveResponse {
+            response_type: "interactive_customer_info_needed".to_string(),
+            url: format!(
+                "{}?transaction_id={}&asset_code={}",
+                interactive_url, id, token.asset_code
+            ),
+            id,
+        })
+    }
+
+    /// Records what the user entered in the interactive flow, after which the
+    /// transaction waits for their funds
+    ///
+    /// A deposit gets the instructions for sending the fiat as its message. A
+    /// withdrawal gets the account and memo to send the asset to.
+    ///
+    /// # Arguments
+    /// * `transaction_id` - The UUID of the SEP-0024 transaction
+    /// * `amount` - The amount in units
+    /// * `destination` - Where the fiat goes, required for a withdrawal
+    pub async fn complete_interactive(
+        transaction_id: &str,
+        amount: BigDecimal,
+        destination: Option<&str>,
+    ) -> Result<AnchorTransactionView, Error> {
+        let transaction = get_anchor_transaction(Uuid::parse_str(transaction_id)?).await?;
+        let next = next_status(&transaction, AnchorStatus::PendingUserTransferStart)?;
+        let token = get_token(transaction.token_id).await?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let (withdraw_anchor_account, withdraw_memo, fiat_destination, message) =
+            if transaction.kind == "withdrawal" {
+                check_amount(Some(&amount))?;
+                let destination = destination
+                    .filter(|destination| !destination.trim().is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("A destination for the fiat is required"))?;
+                let (_, _, distributor) = anchored_asset(&token.asset_code).await?;
+                (
+                    Some(distributor.stellar_address),
+                    Some(withdraw_memo(transaction.id)),
+                    Some(destination.to_string()),
+                    None,
+                )
+            } else {
+                check_deposit_amount(Some(&amount))?;
+                let how = get_fiat_rail()
+                    .deposit_instructions(&FiatDeposit {
+                        reference: &transaction.id.to_string(),
+                        asset_code: &token.asset_code,
+                        amount: Some(&amount),
+                    })
+                    .await?;
+                (None, None, None, Some(how))
+            };
+
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::amount_in.eq(&amount),
+            anchor_transactions::withdraw_anchor_account.eq(withdraw_anchor_account),
+            anchor_transactions::withdraw_memo.eq(withdraw_memo),
+            anchor_transactions::fiat_destination.eq(fiat_destination),
+            anchor_transactions::message.eq(message),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(&mut db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+
+        Ok(transaction_view(transaction))
+    }
+
+    /// Returns one of a user's deposits or withdrawals
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account the user authenticated as
+    /// * `transaction_id` - The UUID of the transaction
+    pub async fn get_transaction(
+        account: &str,
+        transaction_id: &str,
+    ) -> Result<AnchorTransactionView, Error> {
+        let transaction_id = Uuid::parse_str(transaction_id)
+            .map_err(|_| anyhow::anyhow!("Transaction {} not found", transaction_id))?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let transaction = anchor_transactions::table
+            .find(transaction_id)
+            .filter(anchor_transactions::kind.ne("receive"))
+            .filter(anchor_transactions::stellar_account.eq(account))
+            .first::<AnchorTransaction>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Transaction {} not found", transaction_id))?;
+
+        Ok(transaction_view(transaction))
+    }
+
+    /// Lists a user's deposits and withdrawals of an asset, newest first
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account the user authenticated as
+    /// * `asset_code` - The asset
+    /// * `limit` - Most transactions to return
+    pub async fn get_transactions(
+        account: &str,
+        asset_code: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<AnchorTransactionView>, Error> {
+        let limit = limit
+            .unwrap_or(MAX_TRANSACTIONS_LIMIT)
+            .clamp(1, MAX_TRANSACTIONS_LIMIT);
+
+        let mut db_connection = establish_connection().await?;
+
+        let transactions = anchor_transactions::table
+            .inner_join(tokens::table)
+            .filter(anchor_transactions::kind.ne("receive"))
+            .filter(anchor_transactions::stellar_account.eq(account))
+            .filter(tokens::asset_code.eq(asset_code))
+            .order(anchor_transactions::created_at.desc())
+            .limit(limit)
+            .select(anchor_transactions::all_columns)
+            .load::<AnchorTransaction>(&mut db_connection)
+            .await?;
+
+        Ok(transactions.into_iter().map(transaction_view).collect())
+    }
+
+    /// Records the fiat received for a deposit and pays the asset out
+    ///
+    /// Called by an operator when the rail cannot report deposits itself. The amount
+    /// received is what gets paid, no fee is charged.
+    ///
+    /// # Arguments
+    /// * `transaction_id` - The UUID of the deposit
+    /// * `amount` - The amount received, in whole units
+    /// * `external_reference` - The rail's reference for the transfer
+    pub async fn confirm_deposit_received(
+        transaction_id: &str,
+        amount: BigDecimal,
+        external_reference: Option<&str>,
+    ) -> Result<AnchorTransactionView, Error> {
+        let transaction = get_anchor_transaction(Uuid::parse_str(transaction_id)?).await?;
+        if transaction.kind != "deposit" {
+            return Err(anyhow::anyhow!(
+                "Transaction {} is not a deposit",
+                transaction.id
+            ));
+        }
+
+        let transaction = receive_deposit(transaction, amount, external_reference).await?;
+        let transaction = pay_deposit(&get_stellar_chain()?, transaction).await?;
+
+        Ok(transaction_view(transaction))
+    }
+
+    /// Records that the fiat payout of a withdrawal or received payment settled
+    ///
+    /// # Arguments
+    /// * `transaction_id` - The UUID of the withdrawal or received payment
+    pub async fn confirm_payout_completed(
+        transaction_id: &str,
+    ) -> Result<AnchorTransactionView, Error> {
+        let transaction = get_anchor_transaction(Uuid::parse_str(transaction_id)?).await?;
+        if transaction.kind == "deposit" {
+            return Err(anyhow::anyhow!(
+                "Transaction {} is a deposit, it has no payout",
+                transaction.id
+            ));
+        }
+
+        let mut db_connection = establish_connection().await?;
+        let transaction = complete(&mut db_connection, &transaction).await?;
+
+        Ok(transaction_view(transaction))
+    }
+
+    /// Moves anchor transactions along
+    ///
+    /// Deposits the rail reports as received are paid out, and deposits waiting for a
+    /// trustline are retried. Payments to the distributors are matched to withdrawals
+    /// and received payments by memo, whose fiat is then paid out, and settled payouts
+    /// complete. Transactions whose funds never came expire.
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of transactions handled per step in this run
+    pub async fn process_anchor_transactions(limit: i64) -> Result<AnchorSummary, Error> {
+        let stellar_chain = get_stellar_chain()?;
+        let rail = get_fiat_rail();
+
+        let mut summary = AnchorSummary {
+            received: 0,
+            completed: 0,
+            waiting_for_trust: 0,
+            expired: 0,
+            failed: 0,
+        };
+
+        let mut db_connection = establish_connection().await?;
+
+        // Deposits whose fiat the rail has seen
+        let deposits = load_pending(
+            &mut db_connection,
+            &["deposit"],
+            &[AnchorStatus::PendingUserTransferStart],
+            limit,
+        )
+        .await?;
+        for deposit in deposits {
+            let amount = match rail.received_deposit(&deposit.id.to_string()).await {
+                std::result::Result::Ok(Some(amount)) => amount,
+                std::result::Result::Ok(None) => continue,
+                Err(error) => {
+                    eprintln!(
+                        "Failed to look up deposit {}: {}",
+                        deposit.id,
+                        redact_secrets(&error.to_string())
+                    );
+                    continue;
+                }
+            };
+
+            match receive_deposit(deposit, amount, None).await {
+                std::result::Result::Ok(deposit) => {
+                    summary.received += 1;
+                    record_payout(&mut summary, pay_deposit(&stellar_chain, deposit).await);
+                }
+                Err(error) => eprintln!(
+                    "Failed to receive deposit: {}",
+                    redact_secrets(&error.to_string())
+                ),
+            }
+        }
+
+        // Deposits received earlier that could not be paid yet
+        let deposits = load_pending(
+            &mut db_connection,
+            &["deposit"],
+            &[AnchorStatus::PendingAnchor, AnchorStatus::PendingTrust],
+            limit,
+        )
+        .await?;
+        for deposit in deposits {
+            record_payout(&mut summary, pay_deposit(&stellar_chain, deposit).await);
+        }
+
+        // Payments to the distributors, for withdrawals and from sending anchors
+        let anchor_accounts = anchor_transactions::table
+            .filter(anchor_transactions::status.eq_any(awaiting_payment()))
+            .select(anchor_transactions::withdraw_anchor_account)
+            .distinct()
+            .load::<Option<String>>(&mut db_connection)
+            .await?;
+        for anchor_account in anchor_accounts.into_iter().flatten() {
+            match receive_payments(&mut db_connection, &stellar_chain, &anchor_account, limit).await
+            {
+                std::result::Result::Ok(received) => summary.received += received,
+                Err(error) => eprintln!(
+                    "Failed to read payments to {}: {}",
+                    anchor_account,
+                    redact_secrets(&error.to_string())
+                ),
+            }
+        }
+
+        // Withdrawals and received payments whose fiat has not been sent yet
+        let withdrawals = load_pending(
+            &mut db_connection,
+            &["withdrawal", "receive"],
+            &[AnchorStatus::PendingAnchor, AnchorStatus::PendingReceiver],
+            limit,
+        )
+        .await?;
+        for withdrawal in withdrawals {
+            if let Err(error) = send_payout(&mut db_connection, &rail, &withdrawal).await {
+                eprintln!(
+                    "Failed to pay out {} {}: {}",
+                    withdrawal.kind,
+                    withdrawal.id,
+                    redact_secrets(&error.to_string())
+                );
+                fail(&mut db_connection, &withdrawal, &error.to_string()).await?;
+                summary.failed += 1;
+            }
+        }
+
+        // Payouts the rail has settled
+        let withdrawals = load_pending(
+            &mut db_connection,
+            &["withdrawal", "receive"],
+            &[AnchorStatus::PendingExternal],
+            limit,
+        )
+        .await?;
+        for withdrawal in withdrawals {
+            let Some(payout_reference) = withdrawal.external_reference.as_deref() else {
+                continue;
+            };
+            match rail.payout_completed(payout_reference).await {
+                std::result::Result::Ok(true) => {
+                    complete(&mut db_connection, &withdrawal).await?;
+                    summary.completed += 1;
+                }
+                std::result::Result::Ok(false) => {}
+                Err(error) => {
+                    eprintln!(
+                        "Failed to look up payout {}: {}",
+                        payout_reference,
+                        redact_secrets(&error.to_string())
+                    )
+                }
+            }
+        }
+
+        summary.expired = expire_transactions(&mut db_connection).await?;
+
+        Ok(summary)
+    }
+
+    /// Records the fiat received for a deposit, leaving it `pending_anchor`
+    async fn receive_deposit(
+        transaction: AnchorTransaction,
+        amount: BigDecimal,
+        external_reference: Option<&str>,
+    ) -> Result<AnchorTransaction, Error> {
+        // Payments are made in whole units
+        check_deposit_amount(Some(&amount))?;
+        if amount <= BigDecimal::from(0) {
+            return Err(anyhow::anyhow!("Amount must be positive"));
+        }
+        let next = next_status(&transaction, AnchorStatus::PendingAnchor)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::amount_in.eq(&amount),
+            anchor_transactions::amount_out.eq(&amount),
+            anchor_transactions::amount_fee.eq(BigDecimal::from(0)),
+            anchor_transactions::external_reference.eq(external_reference),
+            anchor_transactions::message.eq(None::<String>),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(&mut db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+
+        Ok(transaction)
+    }
+
+    /// Pays a received deposit out on chain
+    ///
+    /// A deposit to an account that cannot hold the asset yet waits in
+    /// `pending_trust`. A failed payment moves the deposit to `error` rather than being
+    /// retried, as it may still have landed.
+    async fn pay_deposit(
+        stellar_chain: &StellarChain,
+        transaction: AnchorTransaction,
+    ) -> Result<AnchorTransaction, Error> {
+        let token = get_token(transaction.token_id).await?;
+        let (_, issuer, distributor) = anchored_asset(&token.asset_code).await?;
+
+        let amount = transaction
+            .amount_out
+            .as_ref()
+            .and_then(|amount| amount.to_u64())
+            .ok_or_else(|| anyhow::anyhow!("Deposit {} has no amount to pay", transaction.id))?;
+
+        let asset = Asset::Credit(CreditAsset::new(
+            token.asset_code.clone(),
+            PublicKey::from_account_id(&issuer.stellar_address)?,
+        )?);
+
+        let mut db_connection = establish_connection().await?;
+
+        let destination_status = stellar_chain
+            .destination_status(&transaction.stellar_account, &asset)
+            .await?;
+        if destination_status != DestinationStatus::Ready {
+            if transaction.status == AnchorStatus::PendingTrust.as_str() {
+                return Ok(transaction);
+            }
+            let next = next_status(&transaction, AnchorStatus::PendingTrust)?;
+            let transaction = diesel::update(
+                anchor_transactions::table
+                    .find(transaction.id)
+                    .filter(anchor_transactions::status.eq(&transaction.status)),
+            )
+            .set((
+                anchor_transactions::status.eq(next),
+                anchor_transactions::message.eq(format!(
+                    "Waiting for a trustline to {} authorized by the issuer",
+                    token.asset_code
+                )),
+                anchor_transactions::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(anchor_transactions::all_columns)
+            .get_result::<AnchorTransaction>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| status_changed(transaction.id))?;
+
+            return Ok(transaction);
+        }
+
+        // Claim the deposit before paying, so it is never paid twice
+        let next = next_status(&transaction, AnchorStatus::PendingStellar)?;
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::message.eq(None::<String>),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(&mut db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+
+        let payment = send_non_native_payment(
+            distributor.id.to_string(),
+            &transaction.stellar_account,
+            &token.asset_code,
+            &issuer.stellar_address,
+            amount,
+            false,
+        )
+        .await;
+
+        let payment = match payment {
+            std::result::Result::Ok(payment) => payment,
+            Err(error) => {
+                return fail(&mut db_connection, &transaction, &error.to_string()).await;
+            }
+        };
+
+        let transaction_hash = transactions::table
+            .find(payment.transaction_id)
+            .select(transactions::transaction_hash)
+            .first::<String>(&mut db_connection)
+            .await?;
+
+        let next = next_status(&transaction, AnchorStatus::Completed)?;
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::stellar_transaction_id.eq(payment.transaction_id),
+            anchor_transactions::stellar_transaction_hash.eq(transaction_hash),
+            anchor_transactions::completed_at.eq(diesel::dsl::now),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(&mut db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+
+        Ok(transaction)
+    }
+
+    /// Counts the outcome of paying a deposit
+    fn record_payout(summary: &mut AnchorSummary, payout: Result<AnchorTransaction, Error>) {
+        match payout {
+            std::result::Result::Ok(transaction) => match transaction.status.as_str() {
+                "completed" => summary.completed += 1,
+                "pending_trust" => summary.waiting_for_trust += 1,
+                "error" => summary.failed += 1,
+                _ => {}
+            },
+            Err(error) => eprintln!(
+                "Failed to pay out deposit: {}",
+                redact_secrets(&error.to_string())
+            ),
+        }
+    }
+
+    /// Matches new payments to an anchor account with the transactions waiting for them
+    ///
+    /// # Returns
+    /// * `Result<usize, Error>` - The number of transactions whose payment arrived
+    async fn receive_payments(
+        db_connection: &mut AsyncPgConnection,
+        stellar_chain: &StellarChain,
+        anchor_account: &str,
+        limit: i64,
+    ) -> Result<usize, Error> {
+        let cursor = anchor_payment_cursors::table
+            .find(anchor_account)
+            .select(anchor_payment_cursors::paging_token)
+            .first::<String>(db_connection)
+            .await
+            .optional()?;
+
+        let page = stellar_chain
+            .received_payments(
+                anchor_account,
+                cursor.as_deref(),
+                limit.clamp(1, 200) as u32,
+            )
+            .await?;
+
+        let mut received = 0;
+        for payment in &page.payments {
+            if receive_payment(db_connection, anchor_account, payment).await? {
+                received += 1;
+            }
+        }
+
+        if let Some(paging_token) = page.cursor {
+            diesel::insert_into(anchor_payment_cursors::table)
+                .values((
+                    anchor_payment_cursors::stellar_address.eq(anchor_account),
+                    anchor_payment_cursors::paging_token.eq(&paging_token),
+                ))
+                .on_conflict(anchor_payment_cursors::stellar_address)
+                .do_update()
+                .set((
+                    anchor_payment_cursors::paging_token.eq(&paging_token),
+                    anchor_payment_cursors::updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(db_connection)
+                .await?;
+        }
+
+        Ok(received)
+    }
+
+    /// Records a payment as the one a withdrawal or received payment waits for, if its
+    /// memo and asset match
+    ///
+    /// The payment is recorded in `transactions`. The amount received is what gets paid
+    /// out, no fee is charged.
+    async fn receive_payment(
+        db_connection: &mut AsyncPgConnection,
+        anchor_account: &str,
+        payment: &ReceivedPayment,
+    ) -> Result<bool, Error> {
+        let Some(memo) = id_memo(payment) else {
+            return Ok(false);
+        };
+
+        let transaction = anchor_transactions::table
+            .filter(anchor_transactions::kind.eq_any(["withdrawal", "receive"]))
+            .filter(anchor_transactions::withdraw_anchor_account.eq(anchor_account))
+            .filter(anchor_transactions::withdraw_memo.eq(memo))
+            .filter(anchor_transactions::status.eq_any(awaiting_payment()))
+            .first::<AnchorTransaction>(db_connection)
+            .await
+            .optional()?;
+        let Some(transaction) = transaction else {
+            return Ok(false);
+        };
+
+        let token = get_token(transaction.token_id).await?;
+        let issuer = match token.issuer_account_id {
+            Some(issuer_account_id) => get_account_from_id(issuer_account_id.to_string()).await?,
+            None => return Ok(false),
+        };
+        let Some(amount) = received_amount(payment, &token.asset_code, &issuer.stellar_address)?
+        else {
+            return Ok(false);
+        };
+        let next = next_status(
+            &transaction,
+            if transaction.kind == "receive" {
+                AnchorStatus::PendingReceiver
+            } else {
+                AnchorStatus::PendingAnchor
+            },
+        )?;
+        let operation_index = payment.operation_index()?;
+
+        let transaction_id = transaction.id;
+        let status = transaction.status;
+        let asset_code = token.asset_code;
+        let transaction = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let stellar_transaction_id = record_received_payment(
+                        conn,
+                        payment,
+                        anchor_account,
+                        &asset_code,
+                        &amount,
+                        operation_index,
+                    )
+                    .await?;
+
+                    let transaction = diesel::update(
+                        anchor_transactions::table
+                            .find(transaction_id)
+                            .filter(anchor_transactions::status.eq(&status)),
+                    )
+                    .set((
+                        anchor_transactions::status.eq(next),
+                        anchor_transactions::amount_in.eq(&amount),
+                        anchor_transactions::amount_out.eq(&amount),
+                        anchor_transactions::amount_fee.eq(BigDecimal::from(0)),
+                        anchor_transactions::stellar_transaction_id.eq(stellar_transaction_id),
+                        anchor_transactions::stellar_transaction_hash.eq(&payment.transaction_hash),
+                        anchor_transactions::updated_at.eq(diesel::dsl::now),
+                    ))
+                    .returning(anchor_transactions::all_columns)
+                    .get_result::<AnchorTransaction>(conn)
+                    .await
+                    .optional()?;
+
+                    Ok(transaction)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(false);
+        };
+        notify(&transaction).await;
+
+        Ok(true)
+    }
+
+    /// Records a payment we received in `transactions`, once
+    ///
+    /// # Returns
+    /// * `Result<Uuid, Error>` - The id of the recorded transaction
+    async fn record_received_payment(
+        db_connection: &mut AsyncPgConnection,
+        payment: &ReceivedPayment,
+        anchor_account: &str,
+        asset_code: &str,
+        amount: &BigDecimal,
+        operation_index: i32,
+    ) -> Result<Uuid, Error> {
+        let recorded = transactions::table
+            .filter(transactions::transaction_hash.eq(&payment.transaction_hash))
+            .filter(transactions::operation_index.eq(operation_index))
+            .select(transactions::id)
+            .first::<Uuid>(db_connection)
+            .await
+            .optional()?;
+        if let Some(transaction_id) = recorded {
+            return Ok(transaction_id);
+        }
+
+        // Only accounts we manage are linked, the sender usually is not ours
+        let account_ids = accounts::table
+            .filter(accounts::stellar_address.eq_any([payment.from.as_str(), anchor_account]))
+            .select((accounts::id, accounts::stellar_address))
+            .load::<(Uuid, String)>(db_connection)
+            .await?;
+        let account_id = |address: &str| {
+            account_ids
+                .iter()
+                .find(|(_, stellar_address)| stellar_address == address)
+                .map(|(id, _)| *id)
+        };
+
+        let transaction_id = Uuid::new_v4();
+        diesel::insert_into(transactions::table)
+            .values(&NewTransaction {
+                id: transaction_id,
+                source_account_id: account_id(&payment.from),
+                destination_account_id: account_id(anchor_account),
+                transaction_hash: &payment.transaction_hash,
+                amount: Some(amount.clone()),
+                asset_code,
+                memo: payment.memo.as_deref(),
+                created_at: None,
+                status: "completed",
+                expires_at: None,
+                batch_id: None,
+                batch_index: None,
+                operation_index,
+            })
+            .execute(db_connection)
+            .await?;
+
+        Ok(transaction_id)
+    }
+
+    /// Starts the fiat payout of a withdrawal or received payment whose payment arrived
+    ///
+    /// A received payment is paid out to its receiver, whose bank details the rail
+    /// looks up by customer id.
+    async fn send_payout(
+        db_connection: &mut AsyncPgConnection,
+        rail: &impl FiatRail,
+        withdrawal: &AnchorTransaction,
+    ) -> Result<AnchorTransaction, Error> {
+        let next = next_status(withdrawal, AnchorStatus::PendingExternal)?;
+        let token = get_token(withdrawal.token_id).await?;
+
+        let (amount, destination) = payout_details(withdrawal)?;
+
+        let payout_reference = rail
+            .send_payout(&FiatPayout {
+                reference: &withdrawal.id.to_string(),
+                asset_code: &token.asset_code,
+                amount,
+                destination,
+            })
+            .await?;
+
+        let withdrawal = diesel::update(
+            anchor_transactions::table
+                .find(withdrawal.id)
+                .filter(anchor_transactions::status.eq(&withdrawal.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::external_reference.eq(payout_reference),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(withdrawal.id))?;
+        notify(&withdrawal).await;
+
+        Ok(withdrawal)
+    }
+
+    /// Completes a transaction
+    async fn complete(
+        db_connection: &mut AsyncPgConnection,
+        transaction: &AnchorTransaction,
+    ) -> Result<AnchorTransaction, Error> {
+        let next = next_status(transaction, AnchorStatus::Completed)?;
+
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::completed_at.eq(diesel::dsl::now),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+        notify(&transaction).await;
+
+        Ok(transaction)
+    }
+
+    /// Moves a transaction to `error` for an operator to look at
+    async fn fail(
+        db_connection: &mut AsyncPgConnection,
+        transaction: &AnchorTransaction,
+        message: &str,
+    ) -> Result<AnchorTransaction, Error> {
+        let next = next_status(transaction, AnchorStatus::Error)?;
+
+        let transaction = diesel::update(
+            anchor_transactions::table
+                .find(transaction.id)
+                .filter(anchor_transactions::status.eq(&transaction.status)),
+        )
+        .set((
+            anchor_transactions::status.eq(next),
+            anchor_transactions::message.eq(message),
+            anchor_transactions::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(anchor_transactions::all_columns)
+        .get_result::<AnchorTransaction>(db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| status_changed(transaction.id))?;
+        notify(&transaction).await;
+
+        Ok(transaction)
+    }
+
+    /// Expires transactions whose funds did not arrive in time
+    async fn expire_transactions(db_connection: &mut AsyncPgConnection) -> Result<usize, Error> {
+        let ttl_seconds = match std::env::var("ANCHOR_TRANSACTION_TTL_SECONDS") {
+            std::result::Result::Ok(ttl) => ttl.parse::<i64>()?,
+            Err(_) => DEFAULT_TRANSACTION_TTL_SECONDS,
+        };
+        if ttl_seconds <= 0 {
+            return Err(anyhow::anyhow!(
+                "ANCHOR_TRANSACTION_TTL_SECONDS must be positive"
+            ));
+        }
+
+        // Only transactions still waiting for funds can expire
+        let expiring = [
+            AnchorStatus::Incomplete,
+            AnchorStatus::PendingUserTransferStart,
+            AnchorStatus::PendingSender,
+        ]
+        .map(|status| status.as_str())
+        .to_vec();
+
+        let deadline = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(ttl_seconds);
+        let expired = diesel::update(anchor_transactions::table)
+            .filter(anchor_transactions::status.eq_any(expiring))
+            .filter(anchor_transactions::created_at.lt(deadline))
+            .set((
+                anchor_transactions::status.eq(AnchorStatus::Expired.as_str()),
+                anchor_transactions::message.eq("No funds were received in time"),
+                anchor_transactions::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(anchor_transactions::all_columns)
+            .get_results::<AnchorTransaction>(db_connection)
+            .await?;
+
+        for transaction in &expired {
+            notify(transaction).await;
+        }
+
+        Ok(expired.len())
+    }
+
+    /// Loads the oldest transactions of some kinds in some statuses
+    async fn load_pending(
+        db_connection: &mut AsyncPgConnection,
+        kinds: &[&str],
+        statuses: &[AnchorStatus],
+        limit: i64,
+    ) -> Result<Vec<AnchorTransaction>, Error> {
+        let transactions = anchor_transactions::table
+            .filter(anchor_transactions::kind.eq_any(kinds.to_vec()))
+            .filter(
+                anchor_transactions::status.eq_any(
+                    statuses
+                        .iter()
+                        .map(|status| status.as_str())
+                        .collect::<Vec<_>>(),
+                ),
+            )
+            .order(anchor_transactions::created_at.asc())
+            .limit(limit)
+            .load::<AnchorTransaction>(db_connection)
+            .await?;
+
+        Ok(transactions)
+    }
+
+    /// Posts the status of a received payment to the sending anchor's callback, if it
+    /// registered one
+    ///
+    /// A failed callback is only logged, the sending anchor can always poll.
+    pub(crate) async fn notify(transaction: &AnchorTransaction) {
+        if transaction.callback_url.is_none() {
+            return;
+        }
+
+        if let Err(error) = send_status_callback(transaction).await {
+            eprintln!(
+                "Failed to send status callback for {}: {}",
+                transaction.id,
+                redact_secrets(&error.to_string())
+            );
+        }
+    }
+
+    /// The statuses of transactions waiting for a payment to the distributor
+    fn awaiting_payment() -> Vec<&'static str> {
+        vec![
+            AnchorStatus::PendingUserTransferStart.as_str(),
+            AnchorStatus::PendingSender.as_str(),
+        ]
+    }
+
+    /// Returns the active asset with a code, with its issuer and distributor accounts
+    pub(crate) async fn anchored_asset(
+        asset_code: &str,
+    ) -> Result<(Token, Account, Account), Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .filter(tokens::asset_code.eq(asset_code))
+            .filter(tokens::status.eq("active"))
+            .first::<Token>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Asset {} is not supported", asset_code))?;
+
+        let (Some(issuer_account_id), Some(distributor_account_id)) =
+            (token.issuer_account_id, token.distributor_account_id)
+        else {
+            return Err(anyhow::anyhow!("Asset {} is not supported", asset_code));
+        };
+
+        let issuer = get_account_from_id(issuer_account_id.to_string()).await?;
+        let distributor = get_account_from_id(distributor_account_id.to_string()).await?;
+
+        Ok((token, issuer, distributor))
+    }
+
+    /// Returns an asset by id
+    async fn get_token(token_id: Uuid) -> Result<Token, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .find(token_id)
+            .first::<Token>(&mut db_connection)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Returns an anchor transaction by id
+    pub(crate) async fn get_anchor_transaction(
+        transaction_id: Uuid,
+    ) -> Result<AnchorTransaction, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let transaction = anchor_transactions::table
+            .find(transaction_id)
+            .first::<AnchorTransaction>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Anchor transaction {} not found", transaction_id))?;
+
+        Ok(transaction)
+    }
+
+    /// Sets the message of a transaction
+    async fn set_message(
+        db_connection: &mut AsyncPgConnection,
+        transaction_id: Uuid,
+        message: &str,
+    ) -> Result<(), Error> {
+        diesel::update(anchor_transactions::table.find(transaction_id))
+            .set(anchor_transactions::message.eq(message))
+            .execute(db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the status a transaction moves to, if it may move there
+    pub(crate) fn next_status(
+        transaction: &AnchorTransaction,
+        next: AnchorStatus,
+    ) -> Result<&'static str, Error> {
+        let status = transaction.status.parse::<AnchorStatus>()?;
+        Ok(status.transition(next)?.as_str())
+    }
+
+    /// Returns the id memo of a payment, the only kind a transaction is matched by
+    fn id_memo(payment: &ReceivedPayment) -> Option<&str> {
+        payment
+            .memo
+            .as_deref()
+            .filter(|_| payment.memo_type == "id")
+    }
+
+    /// Returns the amount of a payment in an asset, or `None` if it pays another asset
+    fn received_amount(
+        payment: &ReceivedPayment,
+        asset_code: &str,
+        asset_issuer: &str,
+    ) -> Result<Option<BigDecimal>, Error> {
+        if payment.asset_code.as_deref() != Some(asset_code)
+            || payment.asset_issuer.as_deref() != Some(asset_issuer)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(payment.amount.parse::<BigDecimal>()?))
+    }
+
+    /// Returns the amount and destination of a fiat payout
+    fn payout_details(withdrawal: &AnchorTransaction) -> Result<(&BigDecimal, &str), Error> {
+        let (Some(amount), Some(destination)) = (
+            withdrawal.amount_out.as_ref(),
+            withdrawal
+                .fiat_destination
+                .as_deref()
+                .or(withdrawal.receiver_id.as_deref()),
+        ) else {
+            return Err(anyhow::anyhow!(
+                "Transaction {} has no amount or destination",
+                withdrawal.id
+            ));
+        };
+
+        Ok((amount, destination))
+    }
+
+    /// The error for a transaction that changed while we were moving it
+    fn status_changed(transaction_id: Uuid) -> Error {
+        anyhow::anyhow!(
+            "Anchor transaction {} changed while being processed",
+            transaction_id
+        )
+    }
+
+    /// The id memo a withdrawal's payment must carry, derived from its id
+    pub(crate) fn withdraw_memo(transaction_id: Uuid) -> String {
+        let (high, _) = transaction_id.as_u64_pair();
+        high.to_string()
+    }
+
+    /// Checks that a protocol is one we serve
+    fn check_protocol(protocol: &str) -> Result<(), Error> {
+        if !PROTOCOLS.contains(&protocol) {
+            return Err(anyhow::anyhow!(
+                "Protocol must be one of {}",
+                PROTOCOLS.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that an account is a Stellar public key
+    pub(crate) fn check_account(account: &str) -> Result<(), Error> {
+        PublicKey::from_account_id(account)
+            .map_err(|_| anyhow::anyhow!("Account must be a Stellar public key"))?;
+        Ok(())
+    }
+
+    /// Checks that an amount, if given, is positive
+    pub(crate) fn check_amount(amount: Option<&BigDecimal>) -> Result<(), Error> {
+        if amount.is_some_and(|amount| *amount <= BigDecimal::from(0)) {
+            return Err(anyhow::anyhow!("Amount must be positive"));
+        }
+        Ok(())
+    }
+
+    /// Checks that a deposit amount, if given, is a positive number of whole units,
+    /// as deposits are paid in whole units
+    fn check_deposit_amount(amount: Option<&BigDecimal>) -> Result<(), Error> {
+        check_amount(amount)?;
+        if amount.is_some_and(|amount| !amount.is_integer() || amount.to_u64().is_none()) {
+            return Err(anyhow::anyhow!(
+                "Deposit amount must be a whole number of units"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Turns a transaction into the shape the SEPs define
+    pub(crate) fn transaction_view(transaction: AnchorTransaction) -> AnchorTransactionView {
+        let is_deposit = transaction.kind == "deposit";
+        let is_receive = transaction.kind == "receive";
+        let timestamp = |time: Option<chrono::NaiveDateTime>| {
+            time.map(|time| {
+                time.and_utc()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            })
+        };
+
+        // SEP-0031 names the account and memo a sending anchor pays differently
+        let (withdraw_anchor_account, withdraw_memo, stellar_account_id, stellar_memo) =
+            if is_receive {
+                (
+                    None,
+                    None,
+                    transaction.withdraw_anchor_account,
+                    transaction.withdraw_memo,
+                )
+            } else {
+                (
+                    transaction.withdraw_anchor_account,
+                    transaction.withdraw_memo,
+                    None,
+                    None,
+                )
+            };
+
+        AnchorTransactionView {
+            id: transaction.id,
+            status: transaction.status,
+            amount_in: transaction
+                .amount_in
+                .map(|amount| amount.normalized().to_string()),
+            amount_out: transaction
+                .amount_out
+                .map(|amount| amount.normalized().to_string()),
+            amount_fee: transaction
+                .amount_fee
+                .map(|amount| amount.normalized().to_string()),
+            started_at: timestamp(transaction.created_at),
+            completed_at: timestamp(transaction.completed_at),
+            stellar_transaction_id: transaction.stellar_transaction_hash,
+            external_transaction_id: transaction.external_reference,
+            from: (!is_deposit && !is_receive).then(|| transaction.stellar_account.clone()),
+            to: if is_deposit {
+                Some(transaction.stellar_account)
+            } else {
+                transaction.fiat_destination
+            },
+            withdraw_memo_type: withdraw_memo.as_ref().map(|_| "id".to_string()),
+            withdraw_anchor_account,
+            withdraw_memo,
+            stellar_memo_type: stellar_memo.as_ref().map(|_| "id".to_string()),
+            stellar_account_id,
+            stellar_memo,
+            message: transaction.message,
+            kind: transaction.kind,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::str::FromStr;
+
+        const ISSUER: &str = "GAHK7EEG2WWHVKDNT4CEQFZGKF2LGDSW2IVM4S5DP42RBW3K6BTODB4A";
+
+        fn transaction(kind: &str, status: &str) -> AnchorTransaction {
+            AnchorTransaction {
+                id: Uuid::new_v4(),
+                kind: kind.to_string(),
+                protocol: "sep6".to_string(),
+                status: status.to_string(),
+                token_id: Uuid::new_v4(),
+                stellar_account: ISSUER.to_string(),
+                amount_in: None,
+                amount_out: None,
+                amount_fee: None,
+                withdraw_anchor_account: None,
+                withdraw_memo: None,
+                fiat_destination: None,
+                external_reference: None,
+                stellar_transaction_id: None,
+                stellar_transaction_hash: None,
+                message: None,
+                created_at: None,
+                updated_at: None,
+                completed_at: None,
+                sender_id: None,
+                receiver_id: None,
+                callback_url: None,
+            }
+        }
+
+        fn payment(
+            memo_type: &str,
+            memo: Option<&str>,
+            asset_code: Option<&str>,
+        ) -> ReceivedPayment {
+            ReceivedPayment {
+                paging_token: "4294971393".to_string(),
+                transaction_hash: "hash".to_string(),
+                from: ISSUER.to_string(),
+                asset_code: asset_code.map(str::to_string),
+                asset_issuer: asset_code.map(|_| ISSUER.to_string()),
+                amount: "12.5000000".to_string(),
+                memo_type: memo_type.to_string(),
+                memo: memo.map(str::to_string),
+            }
+        }
+
+        #[test]
+        fn test_next_status() {
+            let deposit = transaction("deposit", "pending_anchor");
+            assert_eq!(
+                next_status(&deposit, AnchorStatus::PendingStellar).unwrap(),
+                "pending_stellar"
+            );
+            assert_eq!(
+                next_status(&deposit, AnchorStatus::PendingTrust).unwrap(),
+                "pending_trust"
+            );
+            assert_eq!(
+                next_status(&deposit, AnchorStatus::Refunded).unwrap(),
+                "refunded"
+            );
+            assert_eq!(next_status(&deposit, AnchorStatus::Error).unwrap(), "error");
+
+            let received = transaction("receive", "pending_sender");
+            assert_eq!(
+                next_status(&received, AnchorStatus::PendingReceiver).unwrap(),
+                "pending_receiver"
+            );
+        }
+
+        #[test]
+        fn test_next_status_rejects_illegal_moves() {
+            // Nothing was received yet, so there is nothing to pay out or refund
+            let waiting = transaction("withdrawal", "pending_user_transfer_start");
+            assert!(next_status(&waiting, AnchorStatus::PendingExternal).is_err());
+            assert!(next_status(&waiting, AnchorStatus::Refunded).is_err());
+            assert!(next_status(&waiting, AnchorStatus::Completed).is_err());
+
+            // Funds arrived, so it can no longer expire
+            let received = transaction("withdrawal", "pending_anchor");
+            assert!(next_status(&received, AnchorStatus::Expired).is_err());
+
+            // A finished transaction never moves again
+            for status in ["completed", "refunded", "expired", "error"] {
+                let finished = transaction("withdrawal", status);
+                assert!(next_status(&finished, AnchorStatus::Error).is_err());
+                assert!(next_status(&finished, AnchorStatus::PendingAnchor).is_err());
+            }
+
+            // An unknown status is rejected rather than treated as any other
+            let unknown = transaction("deposit", "settled");
+            assert!(next_status(&unknown, AnchorStatus::Completed).is_err());
+        }
+
+        #[test]
+        fn test_id_memo() {
+            assert_eq!(
+                id_memo(&payment("id", Some("42"), Some("USDC"))),
+                Some("42")
+            );
+            // A text memo with the same digits does not match a transaction
+            assert_eq!(id_memo(&payment("text", Some("42"), Some("USDC"))), None);
+            assert_eq!(id_memo(&payment("none", None, Some("USDC"))), None);
+        }
+
+        #[test]
+        fn test_received_amount() {
+            let received = payment("id", Some("42"), Some("USDC"));
+            assert_eq!(
+                received_amount(&received, "USDC", ISSUER).unwrap(),
+                Some(BigDecimal::from_str("12.5").unwrap())
+            );
+
+            // Another asset, another issuer, or XLM is not the payment we wait for
+            assert_eq!(received_amount(&received, "EURC", ISSUER).unwrap(), None);
+            assert_eq!(
+                received_amount(
+                    &received,
+                    "USDC",
+                    "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H"
+                )
+                .unwrap(),
+                None
+            );
+            let native = payment("id", Some("42"), None);
+            assert_eq!(received_amount(&native, "USDC", ISSUER).unwrap(), None);
+
+            let mut malformed = received;
+            malformed.amount = "twelve".to_string();
+            assert!(received_amount(&malformed, "USDC", ISSUER).is_err());
+        }
+
+        #[test]
+        fn test_payout_details() {
+            let mut withdrawal = transaction("withdrawal", "pending_anchor");
+            assert!(payout_details(&withdrawal).is_err());
+
+            withdrawal.amount_out = Some(BigDecimal::from(10));
+            assert!(payout_details(&withdrawal).is_err());
+
+            withdrawal.fiat_destination = Some("NL91ABNA0417164300".to_string());
+            let (amount, destination) = payout_details(&withdrawal).unwrap();
+            assert_eq!(*amount, BigDecimal::from(10));
+            assert_eq!(destination, "NL91ABNA0417164300");
+
+            // A received payment is paid out to its receiver
+            let mut received = transaction("receive", "pending_receiver");
+            received.amount_out = Some(BigDecimal::from(10));
+            received.receiver_id = Some("receiver-1".to_string());
+            assert_eq!(payout_details(&received).unwrap().1, "receiver-1");
+        }
+
+        #[test]
+        fn test_check_deposit_amount() {
+            assert!(check_deposit_amount(None).is_ok());
+            assert!(check_deposit_amount(Some(&BigDecimal::from(10))).is_ok());
+            assert!(check_deposit_amount(Some(&BigDecimal::from(0))).is_err());
+            assert!(check_deposit_amount(Some(&BigDecimal::from(-5))).is_err());
+            assert!(check_deposit_amount(Some(&BigDecimal::from_str("1.5").unwrap())).is_err());
+        }
+    }
+}
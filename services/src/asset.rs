@@ -0,0 +1,700 @@
+#![allow(clippy::module_inception)]
+
+/// Asset module that manages the lifecycle of the assets we issue.
+///
+/// An asset is registered with an issuer account and a distributor account, which
+/// trusts the issuer for it. Minting pays new units from the issuer to the distributor
+/// and burning pays them back to the issuer, where they stop existing. Every step goes
+/// through the outbox, and `tokens.total_supply` only moves once a mint or burn lands
+/// on chain. The metadata of an asset is published in our `stellar.toml`, which wallets
+/// find through the home domain set on the issuer.
+pub mod asset {
+    use anyhow::{Error, Ok};
+    use bigdecimal::{BigDecimal, ToPrimitive};
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::asset_issuer::AssetIssuer;
+    use helpers::raw_transaction::IssuerFlags;
+    use helpers::stellar_chain::StellarChain;
+    use helpers::stellar_toml::home_domain_from_env;
+    use models::common::establish_connection;
+    use models::models::{Account, NewToken, NewTrustline, Token};
+    use models::schema::{tokens, trustlines};
+    use serde::Serialize;
+    use stellar_base::amount::Stroops;
+    use stellar_base::{PublicKey, Transaction};
+    use uuid::Uuid;
+
+    use crate::common::common::{
+        get_account_from_id, get_chain_network, get_stellar_chain, record_pending_transaction,
+    };
+    use crate::outbox::outbox;
+    use crate::signer::signer::get_signer;
+
+    /// Stroops in one unit of an asset
+    const STROOPS_PER_UNIT: i64 = 10_000_000;
+
+    /// What an anchored asset can be redeemable for, as named by SEP-0001
+    pub const ANCHOR_ASSET_TYPES: [&str; 8] = [
+        "fiat",
+        "crypto",
+        "nft",
+        "stock",
+        "bond",
+        "commodity",
+        "realestate",
+        "other",
+    ];
+
+    /// An asset to register.
+    pub struct AssetRegistration {
+        pub issuer_account_id: String,
+        pub distributor_account_id: String,
+        pub asset_code: String,
+        /// The most the distributor trusts the issuer for, in units. No limit if not
+        /// given.
+        pub trust_limit: Option<BigDecimal>,
+        /// The flags to set on the issuer, controlling who may hold the asset
+        pub issuer_flags: IssuerFlags,
+        pub metadata: AssetMetadata,
+    }
+
+    /// Metadata of an asset, published in `stellar.toml`.
+    pub struct AssetMetadata {
+        pub name: Option<String>,
+        pub description: Option<String>,
+        /// URL of a PNG image of the asset
+        pub image: Option<String>,
+        /// Decimals wallets show, 7 if not given
+        pub display_decimals: Option<i32>,
+        /// What the asset is redeemable for, one of [`ANCHOR_ASSET_TYPES`]
+        pub anchor_asset_type: Option<String>,
+        /// The asset it is redeemable for, e.g. `USD`
+        pub anchor_asset: Option<String>,
+    }
+
+    impl AssetMetadata {
+        /// Returns an error unless the metadata can be published
+        pub fn validate(&self) -> Result<(), Error> {
+            if !(0..=7).contains(&self.display_decimals()) {
+                return Err(anyhow::anyhow!("Display decimals must be between 0 and 7"));
+            }
+
+            let anchor_asset_type = self.anchor_asset_type.as_deref();
+            if anchor_asset_type.is_some_and(|kind| !ANCHOR_ASSET_TYPES.contains(&kind)) {
+                return Err(anyhow::anyhow!(
+                    "Unknown anchor asset type {}, expected one of {}",
+                    anchor_asset_type.unwrap_or_default(),
+                    ANCHOR_ASSET_TYPES.join(", ")
+                ));
+            }
+
+            if self.anchor_asset.is_some() && self.anchor_asset_type.is_none() {
+                return Err(anyhow::anyhow!(
+                    "An anchor asset needs an anchor asset type"
+                ));
+            }
+
+            Ok(())
+        }
+
+        /// Returns the decimals wallets show
+        pub fn display_decimals(&self) -> i32 {
+            self.display_decimals.unwrap_or(7)
+        }
+    }
+
+    /// Supply of a registered asset, in units.
+    #[derive(Serialize)]
+    pub struct AssetSupply {
+        pub token_id: Uuid,
+        pub asset_code: String,
+        pub asset_issuer: String,
+        /// Units minted and not burned, as recorded from landed transactions
+        pub total_supply: BigDecimal,
+        /// Units the distributor holds on chain, not yet handed out
+        pub distributor_balance: BigDecimal,
+        /// Units held by anyone but the distributor
+        pub circulating_supply: BigDecimal,
+    }
+
+    /// Registers an asset and establishes the distributor's trustline for it, setting
+    /// the issuer flags and home domain and authorizing the distributor in the same
+    /// transaction
+    ///
+    /// The home domain is taken from `HOME_DOMAIN` and left unset without it.
+    ///
+    /// # Arguments
+    /// * `registration` - The asset code and the accounts issuing and distributing it
+    ///
+    /// # Returns
+    /// * `Result<Token, Error>` - The asset, `active` once the trustline exists
+    pub async fn register_asset(registration: AssetRegistration) -> Result<Token, Error> {
+        let issuer_account = get_account_from_id(registration.issuer_account_id.clone()).await?;
+        let distributor_account =
+            get_account_from_id(registration.distributor_account_id.clone()).await?;
+
+        if issuer_account.id == distributor_account.id {
+            return Err(anyhow::anyhow!(
+                "The issuer and distributor must be different accounts"
+            ));
+        }
+
+        registration.metadata.validate()?;
+        let home_domain = home_domain_from_env()?;
+
+        let trust_limit = registration
+            .trust_limit
+            .as_ref()
+            .map(to_stroops)
+            .transpose()?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let asset_issuer = new_asset_issuer(
+            &issuer_account,
+            &distributor_account,
+            &registration.asset_code,
+        )
+        .await?
+        .with_trust_limit(trust_limit);
+
+        let signer = get_signer()?;
+        let issuer_flags = registration.issuer_flags;
+        let mut transaction = asset_issuer.build_register_asset_transaction(
+            stellar_chain.time_bounds(),
+            issuer_flags,
+            home_domain.as_deref(),
+        )?;
+        transaction
+            .sign(
+                stellar_chain.network(),
+                &signer,
+                &distributor_account.id.to_string(),
+            )
+            .await?;
+
+        // Setting the flags and home domain and authorizing the distributor are issuer
+        // operations
+        if issuer_flags.bits() != 0 || home_domain.is_some() {
+            transaction
+                .sign(
+                    stellar_chain.network(),
+                    &signer,
+                    &issuer_account.id.to_string(),
+                )
+                .await?;
+        }
+
+        // Record the asset and queue its trustline in one database transaction
+        let mut db_connection = establish_connection().await?;
+
+        let token_id = Uuid::new_v4();
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let issuer_account_ref = &issuer_account;
+        let distributor_account_ref = &distributor_account;
+        let asset_code = registration.asset_code.as_str();
+        let trust_limit_ref = &registration.trust_limit;
+        let metadata = &registration.metadata;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    // A registration that failed on chain can be tried again
+                    diesel::delete(tokens::table)
+                        .filter(tokens::asset_code.eq(asset_code))
+                        .filter(tokens::status.eq("failed"))
+                        .execute(conn)
+                        .await?;
+
+                    let registered = tokens::table
+                        .filter(tokens::asset_code.eq(asset_code))
+                        .select(tokens::id)
+                        .first::<Uuid>(conn)
+                        .await
+                        .optional()?;
+
+                    if registered.is_some() {
+                        return Err(anyhow::anyhow!(
+                            "Asset {} is already registered",
+                            asset_code
+                        ));
+                    }
+
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        &distributor_account_ref.stellar_address,
+                        &issuer_account_ref.stellar_address,
+                        asset_code,
+                        BigDecimal::from(0),
+                    )
+                    .await?;
+
+                    diesel::insert_into(tokens::table)
+                        .values(&NewToken {
+                            id: token_id,
+                            asset_code,
+                            issuer_account_id: Some(issuer_account_ref.id),
+                            distributor_account_id: Some(distributor_account_ref.id),
+                            status: "pending",
+                            issuer_flags: issuer_flags.bits() as i32,
+                            name: metadata.name.as_deref(),
+                            description: metadata.description.as_deref(),
+                            image: metadata.image.as_deref(),
+                            display_decimals: metadata.display_decimals(),
+                            anchor_asset_type: metadata.anchor_asset_type.as_deref(),
+                            anchor_asset: metadata.anchor_asset.as_deref(),
+                        })
+                        .execute(conn)
+                        .await?;
+
+                    diesel::insert_into(trustlines::table)
+                        .values(&NewTrustline {
+                            account_id: Some(distributor_account_ref.id),
+                            asset_code,
+                            asset_issuer: &issuer_account_ref.stellar_address,
+                            trust_limit: trust_limit_ref.clone(),
+                            status: "pending",
+                            stellar_address: &distributor_account_ref.stellar_address,
+                        })
+                        .on_conflict_do_nothing()
+                        .execute(conn)
+                        .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        "register_asset",
+                        Some(token_id),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The asset is active once the trustline lands
+        let entry = outbox::process_entry(&stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        get_asset(token_id).await
+    }
+
+    /// Lists the registered assets, oldest first
+    pub async fn get_assets() -> Result<Vec<Token>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let assets = tokens::table
+            .order(tokens::created_at.asc())
+            .load::<Token>(&mut db_connection)
+            .await?;
+
+        Ok(assets)
+    }
+
+    /// Replaces the metadata of an asset
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    /// * `metadata` - The metadata to publish
+    ///
+    /// # Returns
+    /// * `Result<Token, Error>` - The asset with its new metadata
+    pub async fn update_asset_metadata(
+        token_id: &str,
+        metadata: AssetMetadata,
+    ) -> Result<Token, Error> {
+        metadata.validate()?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let token = diesel::update(tokens::table.find(Uuid::parse_str(token_id)?))
+            .set((
+                tokens::name.eq(metadata.name.as_deref()),
+                tokens::description.eq(metadata.description.as_deref()),
+                tokens::image.eq(metadata.image.as_deref()),
+                tokens::display_decimals.eq(metadata.display_decimals()),
+                tokens::anchor_asset_type.eq(metadata.anchor_asset_type.as_deref()),
+                tokens::anchor_asset.eq(metadata.anchor_asset.as_deref()),
+                tokens::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(tokens::all_columns)
+            .get_result::<Token>(&mut db_connection)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Sets the home domain configured in `HOME_DOMAIN` on the issuer of an asset, for
+    /// assets registered before it was configured or when it changes
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    ///
+    /// # Returns
+    /// * `Result<Token, Error>` - The asset, once its issuer points at the domain
+    pub async fn set_issuer_home_domain(token_id: &str) -> Result<Token, Error> {
+        let home_domain =
+            home_domain_from_env()?.ok_or_else(|| anyhow::anyhow!("HOME_DOMAIN is not set"))?;
+
+        let token = get_asset(Uuid::parse_str(token_id)?).await?;
+        let (issuer_account, _) = token_accounts(&token).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let transaction = stellar_chain.build_set_home_domain_transaction(
+            &PublicKey::from_account_id(&issuer_account.stellar_address)?,
+            &home_domain,
+        )?;
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &get_signer()?, &issuer_account.id.to_string())
+            .await?;
+
+        submit_asset_transaction(
+            &stellar_chain,
+            &token,
+            &transaction,
+            "set_home_domain",
+            (&issuer_account, &issuer_account),
+            BigDecimal::from(0),
+        )
+        .await
+    }
+
+    /// Mints new units of an asset to its distributor
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    /// * `amount` - The units to mint, with at most 7 decimals
+    ///
+    /// # Returns
+    /// * `Result<Token, Error>` - The asset with its new total supply
+    pub async fn mint_asset(token_id: &str, amount: BigDecimal) -> Result<Token, Error> {
+        let token = get_asset(Uuid::parse_str(token_id)?).await?;
+        let (issuer_account, distributor_account) = token_accounts(&token).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let asset_issuer =
+            new_asset_issuer(&issuer_account, &distributor_account, &token.asset_code).await?;
+
+        let transaction = asset_issuer
+            .build_issue_asset_transaction(stellar_chain.time_bounds(), to_stroops(&amount)?)?;
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &get_signer()?, &issuer_account.id.to_string())
+            .await?;
+
+        submit_asset_transaction(
+            &stellar_chain,
+            &token,
+            &transaction,
+            "mint_asset",
+            (&issuer_account, &distributor_account),
+            amount,
+        )
+        .await
+    }
+
+    /// Burns units of an asset by paying them from its distributor back to its issuer
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    /// * `amount` - The units to burn, with at most 7 decimals
+    ///
+    /// # Returns
+    /// * `Result<Token, Error>` - The asset with its new total supply
+    pub async fn burn_asset(token_id: &str, amount: BigDecimal) -> Result<Token, Error> {
+        let token = get_asset(Uuid::parse_str(token_id)?).await?;
+        let (issuer_account, distributor_account) = token_accounts(&token).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let asset_issuer =
+            new_asset_issuer(&issuer_account, &distributor_account, &token.asset_code).await?;
+
+        let transaction = asset_issuer
+            .build_burn_asset_transaction(stellar_chain.time_bounds(), to_stroops(&amount)?)?;
+        let transaction = stellar_chain
+            .sign_transaction(
+                transaction,
+                &get_signer()?,
+                &distributor_account.id.to_string(),
+            )
+            .await?;
+
+        submit_asset_transaction(
+            &stellar_chain,
+            &token,
+            &transaction,
+            "burn_asset",
+            (&distributor_account, &issuer_account),
+            amount,
+        )
+        .await
+    }
+
+    /// Returns the total and circulating supply of an asset
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    pub async fn get_asset_supply(token_id: &str) -> Result<AssetSupply, Error> {
+        let token = get_asset(Uuid::parse_str(token_id)?).await?;
+        let (issuer_account, distributor_account) = token_accounts(&token).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let distributor = stellar_chain
+            .client
+            .load_account(&distributor_account.stellar_address)?;
+
+        let distributor_balance = distributor
+            .balances
+            .iter()
+            .find(|balance| {
+                balance.asset_code.as_deref() == Some(token.asset_code.as_str())
+                    && balance.asset_issuer.as_deref()
+                        == Some(issuer_account.stellar_address.as_str())
+            })
+            .map(|balance| balance.balance.parse::<BigDecimal>())
+            .transpose()?
+            .unwrap_or_default();
+
+        let total_supply = token.total_supply.unwrap_or_default();
+
+        Ok(AssetSupply {
+            token_id: token.id,
+            asset_code: token.asset_code,
+            asset_issuer: issuer_account.stellar_address,
+            circulating_supply: &total_supply - &distributor_balance,
+            total_supply,
+            distributor_balance,
+        })
+    }
+
+    /// Converts units of an asset to stroops, rejecting amounts the network cannot hold
+    pub(crate) fn to_stroops(amount: &BigDecimal) -> Result<Stroops, Error> {
+        let stroops = amount * BigDecimal::from(STROOPS_PER_UNIT);
+        if stroops <= BigDecimal::from(0) || !stroops.is_integer() {
+            return Err(anyhow::anyhow!(
+                "Amount must be positive with at most 7 decimals"
+            ));
+        }
+
+        let stroops = stroops
+            .to_i64()
+            .ok_or_else(|| anyhow::anyhow!("Amount is too large"))?;
+        Ok(Stroops::new(stroops))
+    }
+
+    /// Returns an asset by id
+    async fn get_asset(token_id: Uuid) -> Result<Token, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .find(token_id)
+            .first::<Token>(&mut db_connection)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Returns the issuer and distributor accounts of an active asset
+    async fn token_accounts(token: &Token) -> Result<(Account, Account), Error> {
+        if token.status != "active" {
+            return Err(anyhow::anyhow!(
+                "Asset {} is {}",
+                token.asset_code,
+                token.status
+            ));
+        }
+
+        let (Some(issuer_account_id), Some(distributor_account_id)) =
+            (token.issuer_account_id, token.distributor_account_id)
+        else {
+            return Err(anyhow::anyhow!(
+                "Asset {} has no issuer or distributor account",
+                token.asset_code
+            ));
+        };
+
+        Ok((
+            get_account_from_id(issuer_account_id.to_string()).await?,
+            get_account_from_id(distributor_account_id.to_string()).await?,
+        ))
+    }
+
+    /// Returns an asset issuer for an asset issued by one account to another
+    async fn new_asset_issuer(
+        issuer_account: &Account,
+        distributor_account: &Account,
+        asset_code: &str,
+    ) -> Result<AssetIssuer, Error> {
+        let asset_issuer = AssetIssuer::new(
+            std::env::var("STELLAR_HORIZON_URL")?,
+            get_chain_network()?,
+            &get_signer()?,
+            issuer_account.id.to_string(),
+            distributor_account.id.to_string(),
+            asset_code.to_string(),
+        )
+        .await?;
+
+        Ok(asset_issuer)
+    }
+
+    /// Records a transaction on an asset, such as a mint or burn, and queues it in the
+    /// outbox, then submits it
+    async fn submit_asset_transaction(
+        stellar_chain: &StellarChain,
+        token: &Token,
+        transaction: &Transaction,
+        purpose: &str,
+        (source_account, destination_account): (&Account, &Account),
+        amount: BigDecimal,
+    ) -> Result<Token, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token_id = token.id;
+        let asset_code = token.asset_code.as_str();
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain,
+                        transaction,
+                        &source_account.stellar_address,
+                        &destination_account.stellar_address,
+                        asset_code,
+                        amount,
+                    )
+                    .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain,
+                        transaction,
+                        purpose,
+                        Some(token_id),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // A mint or burn changes the supply once it lands
+        let entry = outbox::process_entry(stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        get_asset(token_id).await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::str::FromStr;
+
+        fn metadata() -> AssetMetadata {
+            AssetMetadata {
+                name: Some("Dollar".to_string()),
+                description: None,
+                image: None,
+                display_decimals: None,
+                anchor_asset_type: Some("fiat".to_string()),
+                anchor_asset: Some("USD".to_string()),
+            }
+        }
+
+        fn token(status: &str) -> Token {
+            Token {
+                id: Uuid::new_v4(),
+                asset_code: "USDC".to_string(),
+                issuer_account_id: Some(Uuid::new_v4()),
+                total_supply: None,
+                created_at: None,
+                distributor_account_id: Some(Uuid::new_v4()),
+                status: status.to_string(),
+                updated_at: None,
+                issuer_flags: 0,
+                name: None,
+                description: None,
+                image: None,
+                display_decimals: 7,
+                anchor_asset_type: None,
+                anchor_asset: None,
+            }
+        }
+
+        #[test]
+        fn test_validate_metadata() {
+            assert!(metadata().validate().is_ok());
+            assert_eq!(metadata().display_decimals(), 7);
+
+            let mut decimals = metadata();
+            decimals.display_decimals = Some(2);
+            assert!(decimals.validate().is_ok());
+            assert_eq!(decimals.display_decimals(), 2);
+            for display_decimals in [-1, 8] {
+                decimals.display_decimals = Some(display_decimals);
+                assert!(decimals.validate().is_err());
+            }
+
+            let mut unknown_type = metadata();
+            unknown_type.anchor_asset_type = Some("cash".to_string());
+            assert!(unknown_type.validate().is_err());
+
+            // An anchor asset means nothing without its type
+            let mut untyped = metadata();
+            untyped.anchor_asset_type = None;
+            assert!(untyped.validate().is_err());
+            untyped.anchor_asset = None;
+            assert!(untyped.validate().is_ok());
+        }
+
+        #[test]
+        fn test_to_stroops() {
+            assert_eq!(
+                to_stroops(&BigDecimal::from(1)).unwrap(),
+                Stroops::new(STROOPS_PER_UNIT)
+            );
+            assert_eq!(
+                to_stroops(&BigDecimal::from_str("0.0000001").unwrap()).unwrap(),
+                Stroops::new(1)
+            );
+            assert_eq!(
+                to_stroops(&BigDecimal::from_str("1250.5").unwrap()).unwrap(),
+                Stroops::new(12_505_000_000)
+            );
+
+            // Nothing to mint, or less than a stroop
+            assert!(to_stroops(&BigDecimal::from(0)).is_err());
+            assert!(to_stroops(&BigDecimal::from(-1)).is_err());
+            assert!(to_stroops(&BigDecimal::from_str("0.00000001").unwrap()).is_err());
+            // More than the network can hold
+            assert!(to_stroops(&BigDecimal::from(i64::MAX)).is_err());
+        }
+
+        #[tokio::test]
+        async fn test_token_accounts_needs_an_active_asset_with_accounts() {
+            let pending = token("pending");
+            let Err(error) = token_accounts(&pending).await else {
+                panic!("A pending asset has no accounts to use");
+            };
+            assert_eq!(error.to_string(), "Asset USDC is pending");
+
+            let mut unissued = token("active");
+            unissued.issuer_account_id = None;
+            let Err(error) = token_accounts(&unissued).await else {
+                panic!("An asset without an issuer has no accounts to use");
+            };
+            assert_eq!(
+                error.to_string(),
+                "Asset USDC has no issuer or distributor account"
+            );
+        }
+    }
+}
@@ -0,0 +1,517 @@
+#![allow(clippy::module_inception)]
+
+/// Clawback module that recovers our assets after confirmed fraud or a legal order.
+///
+/// A clawback burns an amount a holder holds, or a whole claimable balance held in an
+/// escrow, and needs the issuer to have been registered with `AUTH_CLAWBACK_ENABLED`.
+/// Every clawback records its reason and the transaction it reverses, and the clawed
+/// back amount leaves the total supply once the clawback lands.
+pub mod clawback {
+    use anyhow::{Error, Ok};
+    use bigdecimal::BigDecimal;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::raw_transaction::{IssuerFlags, RawTransaction};
+    use helpers::stellar_chain::{decode_claimable_balance_id, StellarChain};
+    use models::common::establish_connection;
+    use models::models::{Account, Clawback, Escrow, NewClawback, Token};
+    use models::schema::{accounts, clawbacks, escrows, tokens, transactions};
+    use stellar_base::amount::Stroops;
+    use stellar_base::asset::CreditAsset;
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::asset::asset::to_stroops;
+    use crate::common::common::{
+        get_account_from_id, get_stellar_chain, record_pending_transaction,
+    };
+    use crate::outbox::outbox;
+    use crate::signer::signer::get_signer;
+
+    /// Reasons an amount can be clawed back for
+    pub const REASON_CODES: [&str; 5] = [
+        "fraud",
+        "court_order",
+        "sanctions",
+        "compromised_account",
+        "erroneous_issuance",
+    ];
+
+    /// A clawback of an amount a holder holds, which may be part of what the reversed
+    /// transaction sent.
+    pub struct ClawbackRequest {
+        pub token_id: String,
+        pub holder_address: String,
+        /// The units to claw back, with at most 7 decimals
+        pub amount: BigDecimal,
+        /// The transaction the clawback reverses, which sent the asset to the holder
+        pub original_transaction_id: String,
+        pub reason_code: String,
+        pub note: Option<String>,
+    }
+
+    /// Claws back an amount of one of our assets from a holder
+    ///
+    /// # Arguments
+    /// * `request` - The holder, amount, reason and transaction being reversed
+    ///
+    /// # Returns
+    /// * `Result<Clawback, Error>` - The clawback, `completed` once it landed
+    pub async fn clawback(request: ClawbackRequest) -> Result<Clawback, Error> {
+        validate_reason_code(&request.reason_code)?;
+
+        let token = get_token(Uuid::parse_str(&request.token_id)?).await?;
+        let issuer_account = clawback_issuer(&token).await?;
+        let holder_public_key = PublicKey::from_account_id(&request.holder_address)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let (original_transaction_id, destination_account_id, asset_code, original_amount) =
+            transactions::table
+                .find(Uuid::parse_str(&request.original_transaction_id)?)
+                .select((
+                    transactions::id,
+                    transactions::destination_account_id,
+                    transactions::asset_code,
+                    transactions::amount,
+                ))
+                .first::<(Uuid, Option<Uuid>, String, BigDecimal)>(&mut db_connection)
+                .await?;
+
+        if asset_code != token.asset_code {
+            return Err(anyhow::anyhow!(
+                "Transaction {} did not transfer {}",
+                original_transaction_id,
+                token.asset_code
+            ));
+        }
+
+        let holder_account_id = accounts::table
+            .filter(accounts::stellar_address.eq(&request.holder_address))
+            .select(accounts::id)
+            .first::<Uuid>(&mut db_connection)
+            .await
+            .optional()?;
+
+        // A transaction to one of our accounts names its recipient
+        let sent_elsewhere = matches!(
+            (holder_account_id, destination_account_id),
+            (Some(holder), Some(destination)) if holder != destination
+        );
+        if sent_elsewhere {
+            return Err(anyhow::anyhow!(
+                "Transaction {} was not sent to {}",
+                original_transaction_id,
+                request.holder_address
+            ));
+        }
+
+        let amount = clawback_amount(&request.amount, &original_amount, original_transaction_id)?;
+
+        let issuer_public_key = PublicKey::from_account_id(&issuer_account.stellar_address)?;
+        let stellar_chain = get_stellar_chain()?;
+        let transaction = stellar_chain.build_clawback_transaction(
+            &issuer_public_key,
+            &holder_public_key,
+            CreditAsset::new(token.asset_code.clone(), issuer_public_key.clone())?,
+            amount,
+        )?;
+
+        let new_clawback = NewClawback {
+            id: Uuid::new_v4(),
+            token_id: token.id,
+            holder_address: &request.holder_address,
+            account_id: holder_account_id,
+            escrow_id: None,
+            amount: request.amount.clone(),
+            reason_code: &request.reason_code,
+            note: request.note.as_deref(),
+            original_transaction_id,
+            // Set once the clawback transaction is recorded
+            clawback_transaction_id: Uuid::nil(),
+            status: "pending",
+        };
+
+        submit_clawback(
+            &stellar_chain,
+            transaction,
+            &issuer_account,
+            &token,
+            "clawback",
+            new_clawback,
+        )
+        .await
+    }
+
+    /// Claws back the claimable balance held in an escrow of one of our assets
+    ///
+    /// # Arguments
+    /// * `escrow_id` - The UUID of the escrow, which must still be held
+    /// * `reason_code` - Why the balance is clawed back
+    /// * `note` - Free text for the record
+    ///
+    /// # Returns
+    /// * `Result<Clawback, Error>` - The clawback, `completed` once it landed
+    pub async fn clawback_claimable_balance(
+        escrow_id: &str,
+        reason_code: &str,
+        note: Option<&str>,
+    ) -> Result<Clawback, Error> {
+        validate_reason_code(reason_code)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let escrow = escrows::table
+            .find(Uuid::parse_str(escrow_id)?)
+            .first::<Escrow>(&mut db_connection)
+            .await?;
+
+        if escrow.status != "held" {
+            return Err(anyhow::anyhow!("Escrow {} is {}", escrow.id, escrow.status));
+        }
+
+        let asset_issuer = escrow
+            .asset_issuer
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Escrow {} holds the native asset", escrow.id))?;
+
+        // The asset is ours if its issuer is one of our registered issuers
+        let token = tokens::table
+            .inner_join(accounts::table)
+            .filter(tokens::asset_code.eq(&escrow.asset_code))
+            .filter(accounts::stellar_address.eq(asset_issuer))
+            .select(tokens::all_columns)
+            .first::<Token>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| {
+                anyhow::anyhow!("Escrow {} does not hold one of our assets", escrow.id)
+            })?;
+
+        let issuer_account = clawback_issuer(&token).await?;
+        let sender_account = get_account_from_id(escrow.sender_account_id.to_string()).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let amount = stellar_chain
+            .claimable_balance_amount(&escrow.balance_id)
+            .await?
+            .parse::<BigDecimal>()?;
+
+        let transaction = stellar_chain.build_clawback_claimable_balance_transaction(
+            &PublicKey::from_account_id(&issuer_account.stellar_address)?,
+            decode_claimable_balance_id(&escrow.balance_id)?,
+        )?;
+
+        let new_clawback = NewClawback {
+            id: Uuid::new_v4(),
+            token_id: token.id,
+            holder_address: &sender_account.stellar_address,
+            account_id: Some(sender_account.id),
+            escrow_id: Some(escrow.id),
+            amount,
+            reason_code,
+            note,
+            original_transaction_id: escrow.create_transaction_id,
+            // Set once the clawback transaction is recorded
+            clawback_transaction_id: Uuid::nil(),
+            status: "pending",
+        };
+
+        submit_clawback(
+            &stellar_chain,
+            transaction,
+            &issuer_account,
+            &token,
+            "clawback_claimable_balance",
+            new_clawback,
+        )
+        .await
+    }
+
+    /// Lists the clawbacks of one of our assets, newest first
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    pub async fn get_clawbacks(token_id: &str) -> Result<Vec<Clawback>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let clawbacks = clawbacks::table
+            .filter(clawbacks::token_id.eq(Uuid::parse_str(token_id)?))
+            .order(clawbacks::created_at.desc())
+            .load::<Clawback>(&mut db_connection)
+            .await?;
+
+        Ok(clawbacks)
+    }
+
+    /// Returns an error unless the reason code is known
+    fn validate_reason_code(reason_code: &str) -> Result<(), Error> {
+        if !REASON_CODES.contains(&reason_code) {
+            return Err(anyhow::anyhow!(
+                "Unknown reason code {}, expected one of {}",
+                reason_code,
+                REASON_CODES.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the stroops to claw back, at most what the reversed transaction sent
+    fn clawback_amount(
+        amount: &BigDecimal,
+        original_amount: &BigDecimal,
+        original_transaction_id: Uuid,
+    ) -> Result<Stroops, Error> {
+        let stroops = to_stroops(amount)?;
+
+        if amount > original_amount {
+            return Err(anyhow::anyhow!(
+                "Cannot claw back more than transaction {} sent",
+                original_transaction_id
+            ));
+        }
+
+        Ok(stroops)
+    }
+
+    async fn get_token(token_id: Uuid) -> Result<Token, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .find(token_id)
+            .first::<Token>(&mut db_connection)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Returns the issuer account of an asset that can be clawed back
+    async fn clawback_issuer(token: &Token) -> Result<Account, Error> {
+        if token.status != "active" {
+            return Err(anyhow::anyhow!(
+                "Asset {} is {}",
+                token.asset_code,
+                token.status
+            ));
+        }
+
+        if !IssuerFlags::from_bits(token.issuer_flags as u32).auth_clawback_enabled {
+            return Err(anyhow::anyhow!(
+                "Asset {} was not registered with clawback enabled",
+                token.asset_code
+            ));
+        }
+
+        let issuer_account_id = token
+            .issuer_account_id
+            .ok_or_else(|| anyhow::anyhow!("Asset {} has no issuer", token.asset_code))?;
+
+        get_account_from_id(issuer_account_id.to_string()).await
+    }
+
+    /// Signs a clawback, records it and queues it in the outbox, then submits it
+    async fn submit_clawback(
+        stellar_chain: &StellarChain,
+        mut transaction: RawTransaction,
+        issuer_account: &Account,
+        token: &Token,
+        purpose: &str,
+        new_clawback: NewClawback<'_>,
+    ) -> Result<Clawback, Error> {
+        transaction
+            .sign(
+                stellar_chain.network(),
+                &get_signer()?,
+                &issuer_account.id.to_string(),
+            )
+            .await?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let transaction_ref = &transaction;
+        let clawback_id = new_clawback.id;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    if let Some(escrow_id) = new_clawback.escrow_id {
+                        lock_held_escrow(conn, escrow_id).await?;
+                    }
+
+                    let clawback_transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain,
+                        transaction_ref,
+                        new_clawback.holder_address,
+                        &issuer_account.stellar_address,
+                        &token.asset_code,
+                        new_clawback.amount.clone(),
+                    )
+                    .await?;
+
+                    diesel::insert_into(clawbacks::table)
+                        .values(&NewClawback {
+                            clawback_transaction_id,
+                            ..new_clawback
+                        })
+                        .execute(conn)
+                        .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain,
+                        transaction_ref,
+                        purpose,
+                        Some(clawback_id),
+                        clawback_transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The supply changes once the clawback lands
+        let entry = outbox::process_entry(stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        let clawback = clawbacks::table
+            .find(clawback_id)
+            .first::<Clawback>(&mut db_connection)
+            .await?;
+
+        Ok(clawback)
+    }
+
+    /// Locks an escrow, making sure it is still held and not being settled
+    async fn lock_held_escrow(conn: &mut AsyncPgConnection, escrow_id: Uuid) -> Result<(), Error> {
+        let status = escrows::table
+            .find(escrow_id)
+            .select(escrows::status)
+            .for_update()
+            .first::<String>(conn)
+            .await?;
+
+        if status != "held" {
+            return Err(anyhow::anyhow!("Escrow {} is {}", escrow_id, status));
+        }
+
+        let clawback_pending = clawbacks::table
+            .filter(clawbacks::escrow_id.eq(escrow_id))
+            .filter(clawbacks::status.eq("pending"))
+            .select(clawbacks::id)
+            .first::<Uuid>(conn)
+            .await
+            .optional()?;
+
+        if clawback_pending.is_some() {
+            return Err(anyhow::anyhow!(
+                "Escrow {} is already being clawed back",
+                escrow_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::str::FromStr;
+
+        fn token(issuer_flags: IssuerFlags) -> Token {
+            Token {
+                id: Uuid::new_v4(),
+                asset_code: "GRP".to_string(),
+                issuer_account_id: None,
+                total_supply: None,
+                created_at: None,
+                distributor_account_id: None,
+                status: "active".to_string(),
+                updated_at: None,
+                issuer_flags: issuer_flags.bits() as i32,
+                name: None,
+                description: None,
+                image: None,
+                display_decimals: 7,
+                anchor_asset_type: None,
+                anchor_asset: None,
+            }
+        }
+
+        fn flags(auth_clawback_enabled: bool) -> IssuerFlags {
+            IssuerFlags {
+                auth_required: true,
+                auth_revocable: true,
+                auth_clawback_enabled,
+                auth_immutable: false,
+            }
+        }
+
+        async fn issuer_error(token: &Token) -> String {
+            let Err(error) = clawback_issuer(token).await else {
+                panic!("Asset {} has no issuer to claw back with", token.asset_code);
+            };
+            error.to_string()
+        }
+
+        #[test]
+        fn test_validate_reason_code() {
+            for reason_code in REASON_CODES {
+                assert!(validate_reason_code(reason_code).is_ok());
+            }
+            assert!(validate_reason_code("").is_err());
+            assert!(validate_reason_code("Fraud").is_err());
+        }
+
+        #[tokio::test]
+        async fn test_clawback_needs_the_clawback_flag() {
+            assert_eq!(
+                issuer_error(&token(flags(false))).await,
+                "Asset GRP was not registered with clawback enabled"
+            );
+
+            // With the flag, only the missing issuer is left
+            assert_eq!(
+                issuer_error(&token(flags(true))).await,
+                "Asset GRP has no issuer"
+            );
+
+            let mut inactive = token(flags(true));
+            inactive.status = "pending".to_string();
+            assert_eq!(issuer_error(&inactive).await, "Asset GRP is pending");
+        }
+
+        #[test]
+        fn test_clawback_amount() {
+            let transaction_id = Uuid::new_v4();
+            let sent = BigDecimal::from(100);
+
+            // Part or all of what was sent
+            assert_eq!(
+                clawback_amount(
+                    &BigDecimal::from_str("40.5").unwrap(),
+                    &sent,
+                    transaction_id
+                )
+                .unwrap(),
+                Stroops::new(405_000_000)
+            );
+            assert_eq!(
+                clawback_amount(&sent, &sent, transaction_id).unwrap(),
+                Stroops::new(1_000_000_000)
+            );
+
+            assert!(clawback_amount(&BigDecimal::from(101), &sent, transaction_id).is_err());
+            assert!(clawback_amount(&BigDecimal::from(0), &sent, transaction_id).is_err());
+            assert!(clawback_amount(
+                &BigDecimal::from_str("0.00000001").unwrap(),
+                &sent,
+                transaction_id
+            )
+            .is_err());
+        }
+    }
+}
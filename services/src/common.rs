@@ -1,19 +1,20 @@
+#![allow(clippy::module_inception)]
+
 pub mod common {
 
     use anyhow::{Error, Ok};
     use bigdecimal::BigDecimal;
-    use diesel_async::RunQueryDsl;
-    use helpers::common::decrypt_private_key;
-    use models::models::AccountWithKey;
-    use models::{common::establish_connection, models::NewTransaction, schema};
-    use stellar_base::{Network, PublicKey};
-    use reqwest::Response;
-    use stellar_sdk::types::SubmitTransactionResponse;
-    use stellar_sdk::Keypair;
+    use diesel::ExpressionMethods;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::raw_transaction::SignedTransaction;
+    use helpers::stellar_chain::StellarChain;
+    use helpers::submitter::TransactionSubmitter;
+    use models::models::Account;
+    use models::{common::establish_connection, models::{NewTransaction, NewTransactionError}, schema};
+    use stellar_base::Network;
     use uuid::Uuid;
     use diesel::QueryDsl;
-    use diesel::ExpressionMethods;
-    use diesel::JoinOnDsl;
 
     /// Returns the Stellar network (testnet or public) based on the CHAIN_ENVIRONMENT environment variable
     /// 
@@ -25,84 +26,147 @@ pub mod common {
         match chain_environment {
             ref chain_env if chain_env == "testnet" => Ok(Network::new_test()),
             ref chain_env if chain_env == "public" => Ok(Network::new_public()),
-            _ => return Err(anyhow::anyhow!("Invalid chain environment")),
+            _ => Err(anyhow::anyhow!("Invalid chain environment")),
         }
     }
 
-    /// Saves a blockchain transaction to the database after it has been submitted to the network
-    /// 
+    /// Returns a StellarChain for the configured Horizon server and network
+    ///
+    /// Transactions it builds are valid for TRANSACTION_TIMEOUT_SECONDS, five minutes by
+    /// default, and each is submitted up to SUBMISSION_MAX_ATTEMPTS times.
+    pub fn get_stellar_chain() -> Result<StellarChain, Error> {
+        let mut stellar_chain = StellarChain::new(std::env::var("STELLAR_HORIZON_URL")?, get_chain_network()?);
+
+        if let std::result::Result::Ok(timeout) = std::env::var("TRANSACTION_TIMEOUT_SECONDS") {
+            let timeout = timeout.parse::<i64>()?;
+            if timeout <= 0 {
+                return Err(anyhow::anyhow!("TRANSACTION_TIMEOUT_SECONDS must be positive"));
+            }
+            stellar_chain = stellar_chain.with_transaction_timeout(chrono::Duration::seconds(timeout));
+        }
+
+        if let std::result::Result::Ok(max_attempts) = std::env::var("SUBMISSION_MAX_ATTEMPTS") {
+            let submitter = TransactionSubmitter::new(std::env::var("STELLAR_HORIZON_URL")?).with_max_attempts(max_attempts.parse::<u32>()?);
+            stellar_chain = stellar_chain.with_submitter(submitter);
+        }
+
+        Ok(stellar_chain)
+    }
+
+    /// Records a signed transaction as pending before it is submitted, so its outcome can
+    /// be resolved by hash even if the submission response is lost
+    ///
     /// # Arguments
-    /// * `response` - The HTTP response from submitting the transaction
-    /// * `source_account` - The public key of the sending account
-    /// * `destination_account` - The public key of the receiving account
+    /// * `db_connection` - The connection, usually inside the transaction that also
+    ///   writes the business state and the outbox entry
+    /// * `stellar_chain` - The chain the transaction is for
+    /// * `transaction` - The signed transaction
+    /// * `source_address` - The public key of the sending account
+    /// * `destination_address` - The public key of the receiving account
     /// * `asset_code` - The code/symbol of the asset being transferred
     /// * `amount` - The amount of the asset being transferred
-    /// 
+    ///
     /// # Returns
-    /// * `Ok(())` - If the transaction was successfully saved
-    /// * `Err(Error)` - If there was an error saving the transaction
-    pub async fn save_chain_transaction(response: Response, source_account: PublicKey, destination_account: PublicKey, asset_code: String, amount: BigDecimal) -> Result<(), Error> {
-        let transaction = response.json::<SubmitTransactionResponse>().await.unwrap();
-
-        let mut db_connection = establish_connection().await.unwrap();
+    /// * `Ok(Uuid)` - The id of the recorded transaction
+    /// * `Err(Error)` - If the transaction could not be recorded
+    pub async fn record_pending_transaction(db_connection: &mut AsyncPgConnection, stellar_chain: &StellarChain, transaction: &impl SignedTransaction, source_address: &str, destination_address: &str, asset_code: &str, amount: BigDecimal) -> Result<Uuid, Error> {
+        let transaction_hash = hex::encode(transaction.transaction_hash(stellar_chain.network())?);
 
-        let hash = transaction.hash.to_string();
-        let memo = transaction.memo.unwrap_or_default().to_string();
+        // Only accounts we manage are linked, payments may go to any address
+        let account_ids = schema::accounts::table
+            .filter(schema::accounts::stellar_address.eq_any([source_address, destination_address]))
+            .select((schema::accounts::id, schema::accounts::stellar_address))
+            .load::<(Uuid, String)>(db_connection)
+            .await?;
+        let account_id = |address: &str| account_ids.iter().find(|(_, stellar_address)| stellar_address == address).map(|(id, _)| *id);
 
         let new_transaction = NewTransaction {
             id: Uuid::new_v4(),
-            // id: Uuid::new_v4(),
-            source_account_id: Uuid::parse_str(source_account.to_string().as_str()).unwrap(),
-            destination_account_id: Uuid::parse_str(destination_account.to_string().as_str()).unwrap(),
-            transaction_hash: hash.as_str(),
+            source_account_id: account_id(source_address),
+            destination_account_id: account_id(destination_address),
+            transaction_hash: &transaction_hash,
             amount: Some(amount),
-            asset_code: asset_code.as_str(),
-            memo: Some(memo.as_str()),
-            status: if transaction.successful { "success" } else { "failed" },
-            created_at: {
-                let created_at = chrono::DateTime::parse_from_rfc3339(&transaction.created_at).unwrap();
-                Some(created_at.naive_utc())
-            },
+            asset_code,
+            memo: None,
+            created_at: Some(chrono::Utc::now().naive_utc()),
+            status: "pending",
+            expires_at: transaction.expiry().map(|expiry| expiry.naive_utc()),
+            batch_id: None,
+            batch_index: None,
+            operation_index: 0,
         };
 
         diesel::insert_into(schema::transactions::table)
             .values(&new_transaction)
-            .execute(&mut db_connection)
+            .execute(db_connection)
             .await?;
-        
-        Ok(())
+
+        Ok(new_transaction.id)
     }
 
-    pub async fn get_account_from_id(account_id: String) -> Result<(AccountWithKey, Keypair), Error> {
+    /// Moves a pending transaction to `completed` or `failed`, recording why it failed
+    ///
+    /// # Arguments
+    /// * `db_connection` - The connection to settle the transaction on
+    /// * `transaction_id` - The id of the recorded transaction
+    /// * `status` - The final status, `completed` or `failed`
+    /// * `error` - The error code and message when the transaction failed
+    ///
+    /// # Returns
+    /// * `Ok(bool)` - Whether the transaction was still pending and is now settled
+    pub async fn settle_transaction(db_connection: &mut AsyncPgConnection, transaction_id: Uuid, status: &str, error: Option<(&str, &str)>) -> Result<bool, Error> {
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let settled = diesel::update(schema::transactions::table.find(transaction_id))
+                        .filter(schema::transactions::status.eq("pending"))
+                        .set(schema::transactions::status.eq(status))
+                        .execute(conn)
+                        .await?;
+
+                    // Settled before, e.g. by the resolver
+                    if settled == 0 {
+                        return Ok(false);
+                    }
+
+                    if let Some((error_code, error_message)) = error {
+                        diesel::insert_into(schema::transaction_errors::table)
+                            .values(&NewTransactionError {
+                                transaction_id,
+                                error_code,
+                                error_message,
+                            })
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    Ok(true)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Retrieves an account by its id
+    ///
+    /// # Arguments
+    /// * `account_id` - The UUID of the account
+    ///
+    /// # Returns
+    /// * `Ok(Account)` - The account
+    /// * `Err(Error)` - If the account does not exist
+    pub async fn get_account_from_id(account_id: String) -> Result<Account, Error> {
 
         let mut db_connection = establish_connection().await.unwrap();
 
-        let account_uuid = Uuid::parse_str(account_id.as_str()).unwrap();
+        let account_uuid = Uuid::parse_str(account_id.as_str())?;
 
         // Get account
-        let account =
-            models::schema::accounts::table
-                .inner_join(models::schema::encrypted_keys::table.on(
-                    models::schema::encrypted_keys::account_id.eq(models::schema::accounts::id),
-                ))
-                .filter(models::schema::accounts::id.eq(account_uuid))
-                .select((
-                    models::schema::accounts::id,
-                    models::schema::accounts::stellar_address,
-                    models::schema::accounts::account_type,
-                    models::schema::accounts::created_at,
-                    models::schema::accounts::updated_at,
-                    models::schema::accounts::status,
-                    models::schema::encrypted_keys::encrypted_key,
-                ))
-                .first::<AccountWithKey>(&mut db_connection)
-                .await?;
-
-        let decrypted_key = decrypt_private_key(&account.encrypted_key).unwrap();
-
-        let account_keypair =
-            Keypair::from_secret_key(std::str::from_utf8(&decrypted_key).unwrap()).unwrap();
-
-        Ok((account, account_keypair))
+        let account = models::schema::accounts::table
+            .find(account_uuid)
+            .first::<Account>(&mut db_connection)
+            .await?;
+
+        Ok(account)
     }
 }
\ No newline at end of file
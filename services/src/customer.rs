@@ -0,0 +1,942 @@
+#![allow(clippy::module_inception)]
+
+/// Customer module that verifies the identity of our customers (SEP-0012).
+///
+/// Clients authenticated with SEP-0010 put a customer's SEP-0009 fields and documents,
+/// which are stored encrypted with the key ring, and read back which fields are still
+/// missing. Once every required field is there an operator reviews the customer and
+/// accepts or rejects it. Account holders need an accepted customer record to activate
+/// their account, have their trustlines authorized and send payments, within the
+/// limits of `KYC_ACCEPTED_PAYMENT_LIMIT` and `KYC_PROCESSING_PAYMENT_LIMIT`. Issuer
+/// and distributor accounts are ours and are not checked.
+pub mod customer {
+    use std::collections::BTreeMap;
+
+    use anyhow::{Error, Ok};
+    use chrono::NaiveDateTime;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::common::KeyRing;
+    use helpers::kyc::{
+        missing_fields, required_fields, validate_document, validate_field, CustomerStatus,
+        HolderReview, PaymentLimits, DEFAULT_TYPE,
+    };
+    use helpers::secret::redact_secrets;
+    use models::common::establish_connection;
+    use models::models::{Customer, CustomerDocument, NewCustomer, NewCustomerDocument};
+    use models::schema::{accounts, customer_documents, customers, kyc_legacy_accounts};
+    use serde::Serialize;
+    use uuid::Uuid;
+
+    use crate::remittance::remittance::receiver_reviewed;
+
+    /// Identifies a customer: by its id, or by the authenticated account, the memo
+    /// and the customer type.
+    pub struct CustomerQuery {
+        /// The Stellar account authenticated with SEP-0010
+        pub account: String,
+        pub id: Option<String>,
+        pub memo: Option<String>,
+        /// The customer type, `individual` if none
+        pub customer_type: Option<String>,
+    }
+
+    /// A document uploaded for a customer.
+    pub struct UploadedDocument {
+        /// The SEP-0009 binary field, e.g. `photo_id_front`
+        pub field: String,
+        pub content_type: String,
+        pub content: Vec<u8>,
+    }
+
+    /// Fields and documents provided for a customer.
+    pub struct CustomerUpdate {
+        pub customer: CustomerQuery,
+        /// SEP-0009 text fields by name
+        pub fields: BTreeMap<String, String>,
+        pub documents: Vec<UploadedDocument>,
+    }
+
+    /// A field as SEP-0012 describes it.
+    #[derive(Serialize)]
+    pub struct FieldView {
+        #[serde(rename = "type")]
+        pub field_type: &'static str,
+        pub description: &'static str,
+        /// The review status of a provided field
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub status: Option<&'static str>,
+    }
+
+    /// A customer in the shape SEP-0012 `GET /customer` returns.
+    #[derive(Serialize)]
+    pub struct CustomerView {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub id: Option<Uuid>,
+        /// `NEEDS_INFO`, `PROCESSING`, `ACCEPTED` or `REJECTED`
+        pub status: &'static str,
+        /// The required fields not provided yet
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        pub fields: BTreeMap<&'static str, FieldView>,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        pub provided_fields: BTreeMap<&'static str, FieldView>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub message: Option<String>,
+    }
+
+    /// A customer without its fields, as operators list them.
+    #[derive(Serialize)]
+    pub struct CustomerSummary {
+        pub id: Uuid,
+        pub stellar_account: String,
+        pub memo: Option<String>,
+        pub customer_type: String,
+        pub status: String,
+        pub status_message: Option<String>,
+        pub created_at: Option<NaiveDateTime>,
+        pub updated_at: Option<NaiveDateTime>,
+    }
+
+    /// A document, without its content.
+    #[derive(Serialize)]
+    pub struct DocumentSummary {
+        pub field: String,
+        pub content_type: String,
+        pub updated_at: Option<NaiveDateTime>,
+    }
+
+    /// A customer with its decrypted fields, as an operator reviews it.
+    #[derive(Serialize)]
+    pub struct CustomerReview {
+        #[serde(flatten)]
+        pub customer: CustomerSummary,
+        pub fields: BTreeMap<String, String>,
+        pub documents: Vec<DocumentSummary>,
+    }
+
+    /// Returns what we know of a customer and which fields are still missing
+    ///
+    /// A customer we have never seen needs every required field of its type.
+    ///
+    /// # Arguments
+    /// * `query` - The customer, and the type whose requirements apply
+    pub async fn get_customer(query: CustomerQuery) -> Result<CustomerView, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let customer = find_customer(&mut db_connection, &query).await?;
+        let customer_type = match (&query.customer_type, &customer) {
+            (Some(customer_type), _) => customer_type.clone(),
+            (None, Some(customer)) => customer.customer_type.clone(),
+            (None, None) => DEFAULT_TYPE.to_string(),
+        };
+
+        let Some(customer) = customer else {
+            return Ok(CustomerView {
+                id: None,
+                status: CustomerStatus::NeedsInfo.sep12_name(),
+                fields: field_views(&missing_fields(&customer_type, &[])?, None),
+                provided_fields: BTreeMap::new(),
+                message: None,
+            });
+        };
+
+        let key_ring = KeyRing::from_env()?;
+        let provided = provided_fields(&mut db_connection, &key_ring, &customer).await?;
+        let provided: Vec<&str> = provided.iter().map(String::as_str).collect();
+        let missing = missing_fields(&customer_type, &provided)?;
+
+        let status = view_status(&customer.status, &missing)?;
+        let provided = provided
+            .iter()
+            .filter_map(|name| helpers::kyc::field(name))
+            .collect::<Vec<_>>();
+
+        Ok(CustomerView {
+            id: Some(customer.id),
+            status: status.sep12_name(),
+            fields: field_views(&missing, None),
+            provided_fields: field_views(&provided, Some(provided_field_status(status))),
+            message: customer.status_message,
+        })
+    }
+
+    /// Stores fields and documents for a customer, creating it if needed
+    ///
+    /// The fields are merged with those provided before. A customer with every required
+    /// field is reviewed again, even if it was accepted before.
+    ///
+    /// # Arguments
+    /// * `update` - The customer, and the fields and documents provided
+    ///
+    /// # Returns
+    /// * `Result<Uuid, Error>` - The id of the customer
+    pub async fn put_customer(update: CustomerUpdate) -> Result<Uuid, Error> {
+        for (name, value) in &update.fields {
+            validate_field(name, value)?;
+        }
+        for document in &update.documents {
+            validate_document(
+                &document.field,
+                &document.content_type,
+                document.content.len(),
+            )?;
+        }
+
+        let key_ring = KeyRing::from_env()?;
+        let mut db_connection = establish_connection().await?;
+
+        let update_ref = &update;
+        let key_ring_ref = &key_ring;
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let query = &update_ref.customer;
+                    let customer = match find_customer(conn, query).await? {
+                        Some(customer) => customer,
+                        None => create_customer(conn, key_ring_ref, query).await?,
+                    };
+
+                    let mut fields = decrypt_fields(key_ring_ref, &customer)?;
+                    for (name, value) in &update_ref.fields {
+                        fields.insert(name.clone(), value.trim().to_string());
+                    }
+                    let (encrypted_fields, key_version) = key_ring_ref
+                        .encrypt(&serde_json::to_vec(&fields)?, customer.id.as_bytes())?;
+
+                    for document in &update_ref.documents {
+                        let (encrypted_content, key_version) = key_ring_ref.encrypt(
+                            &document.content,
+                            &document_aad(customer.id, &document.field),
+                        )?;
+
+                        diesel::insert_into(customer_documents::table)
+                            .values(&NewCustomerDocument {
+                                id: Uuid::new_v4(),
+                                customer_id: customer.id,
+                                field: &document.field,
+                                content_type: &document.content_type,
+                                encrypted_content: encrypted_content.clone(),
+                                key_version,
+                            })
+                            .on_conflict((
+                                customer_documents::customer_id,
+                                customer_documents::field,
+                            ))
+                            .do_update()
+                            .set((
+                                customer_documents::content_type.eq(&document.content_type),
+                                customer_documents::encrypted_content.eq(encrypted_content),
+                                customer_documents::key_version.eq(key_version),
+                                customer_documents::updated_at.eq(diesel::dsl::now),
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    let documents = customer_documents::table
+                        .filter(customer_documents::customer_id.eq(customer.id))
+                        .select(customer_documents::field)
+                        .load::<String>(conn)
+                        .await?;
+                    let provided: Vec<&str> = fields
+                        .keys()
+                        .chain(documents.iter())
+                        .map(String::as_str)
+                        .collect();
+
+                    let missing = !missing_fields(&customer.customer_type, &provided)?.is_empty();
+                    let changed = !update_ref.fields.is_empty() || !update_ref.documents.is_empty();
+                    let current = customer.status.parse::<CustomerStatus>()?;
+                    let status = current.after_update(missing, changed);
+
+                    // A message explains the last decision, which no longer stands
+                    let status_message = if status == current {
+                        customer.status_message
+                    } else {
+                        None
+                    };
+
+                    diesel::update(customers::table.find(customer.id))
+                        .set((
+                            customers::encrypted_fields.eq(encrypted_fields),
+                            customers::key_version.eq(key_version),
+                            customers::status.eq(status.as_str()),
+                            customers::status_message.eq(status_message),
+                            customers::updated_at.eq(diesel::dsl::now),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    Ok(customer.id)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Deletes a customer and its documents
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account authenticated with SEP-0010
+    /// * `memo` - The memo the customer was put with, if any
+    pub async fn delete_customer(account: &str, memo: Option<&str>) -> Result<(), Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let query = customers::table
+            .filter(customers::stellar_account.eq(account))
+            .into_boxed();
+        let query = match memo {
+            Some(memo) => query.filter(customers::memo.eq(memo)),
+            None => query.filter(customers::memo.is_null()),
+        };
+        let customer_ids = query
+            .select(customers::id)
+            .load::<Uuid>(&mut db_connection)
+            .await?;
+
+        if customer_ids.is_empty() {
+            return Err(anyhow::anyhow!("Customer not found"));
+        }
+
+        diesel::delete(customers::table.filter(customers::id.eq_any(customer_ids)))
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lists customers, oldest change first, so the review queue is worked in order
+    ///
+    /// # Arguments
+    /// * `status` - Only customers with this status, e.g. `processing`
+    pub async fn get_customers(status: Option<&str>) -> Result<Vec<CustomerSummary>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let mut query = customers::table
+            .order(customers::updated_at.asc())
+            .into_boxed();
+        if let Some(status) = status {
+            query = query.filter(customers::status.eq(status.parse::<CustomerStatus>()?.as_str()));
+        }
+
+        let customers = query.load::<Customer>(&mut db_connection).await?;
+
+        Ok(customers.into_iter().map(customer_summary).collect())
+    }
+
+    /// Returns a customer with its decrypted fields, for an operator to review
+    ///
+    /// # Arguments
+    /// * `customer_id` - The UUID of the customer
+    pub async fn get_customer_review(customer_id: &str) -> Result<CustomerReview, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let customer = get_customer_by_id(&mut db_connection, customer_id).await?;
+        let fields = decrypt_fields(&KeyRing::from_env()?, &customer)?;
+
+        let documents = customer_documents::table
+            .filter(customer_documents::customer_id.eq(customer.id))
+            .order(customer_documents::field.asc())
+            .load::<CustomerDocument>(&mut db_connection)
+            .await?
+            .into_iter()
+            .map(|document| DocumentSummary {
+                field: document.field,
+                content_type: document.content_type,
+                updated_at: document.updated_at,
+            })
+            .collect();
+
+        Ok(CustomerReview {
+            customer: customer_summary(customer),
+            fields,
+            documents,
+        })
+    }
+
+    /// Returns a decrypted document of a customer
+    ///
+    /// # Arguments
+    /// * `customer_id` - The UUID of the customer
+    /// * `field` - The SEP-0009 binary field, e.g. `photo_id_front`
+    ///
+    /// # Returns
+    /// * `Result<(String, Vec<u8>), Error>` - The content type and the content
+    pub async fn get_customer_document(
+        customer_id: &str,
+        field: &str,
+    ) -> Result<(String, Vec<u8>), Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let customer = get_customer_by_id(&mut db_connection, customer_id).await?;
+        let document = customer_documents::table
+            .filter(customer_documents::customer_id.eq(customer.id))
+            .filter(customer_documents::field.eq(field))
+            .first::<CustomerDocument>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Document {} not found", field))?;
+
+        let content = KeyRing::from_env()?.decrypt(
+            &document.encrypted_content,
+            &document_aad(customer.id, &document.field),
+            document.key_version,
+        )?;
+
+        Ok((document.content_type, content.expose_secret().to_vec()))
+    }
+
+    /// Records an operator's decision on a customer
+    ///
+    /// Payments waiting for a rejected receiver are held until its information is
+    /// corrected and accepted.
+    ///
+    /// # Arguments
+    /// * `customer_id` - The UUID of the customer
+    /// * `decision` - `accepted` or `rejected`
+    /// * `message` - Why, shown to the client
+    pub async fn review_customer(
+        customer_id: &str,
+        decision: &str,
+        message: Option<&str>,
+    ) -> Result<CustomerSummary, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let customer = get_customer_by_id(&mut db_connection, customer_id).await?;
+        let status = customer
+            .status
+            .parse::<CustomerStatus>()?
+            .review(decision.parse::<CustomerStatus>()?)?;
+
+        let customer = diesel::update(
+            customers::table
+                .find(customer.id)
+                .filter(customers::status.eq(&customer.status)),
+        )
+        .set((
+            customers::status.eq(status.as_str()),
+            customers::status_message.eq(message),
+            customers::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(customers::all_columns)
+        .get_result::<Customer>(&mut db_connection)
+        .await
+        .optional()?
+        .ok_or_else(|| anyhow::anyhow!("Customer {} changed meanwhile", customer_id))?;
+
+        receiver_reviewed(&customer, status).await?;
+
+        Ok(customer_summary(customer))
+    }
+
+    /// Returns the status of a customer of an account, if there is one
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account the customer belongs to
+    /// * `customer_id` - The UUID of the customer
+    /// * `customer_type` - The type the customer must have
+    pub(crate) async fn customer_status(
+        account: &str,
+        customer_id: &str,
+        customer_type: &str,
+    ) -> Result<Option<CustomerStatus>, Error> {
+        let std::result::Result::Ok(customer_id) = Uuid::parse_str(customer_id) else {
+            return Ok(None);
+        };
+
+        let mut db_connection = establish_connection().await?;
+
+        let status = customers::table
+            .find(customer_id)
+            .filter(customers::stellar_account.eq(account))
+            .filter(customers::customer_type.eq(customer_type))
+            .select(customers::status)
+            .first::<String>(&mut db_connection)
+            .await
+            .optional()?;
+
+        status.map(|status| status.parse()).transpose()
+    }
+
+    /// Checks that the holder of a Stellar account passed identity verification
+    ///
+    /// # Errors
+    /// Fails unless the account is ours, its customer was accepted, or it was opened
+    /// before identity verification and has no customer record yet
+    pub(crate) async fn check_verified(stellar_address: &str) -> Result<(), Error> {
+        let Some(review) = holder_status(stellar_address).await? else {
+            return Ok(());
+        };
+
+        if !review.is_verified() {
+            return Err(anyhow::anyhow!(
+                "Account {} has not passed identity verification",
+                stellar_address
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks a payment against the limit of the sending account holder
+    ///
+    /// # Errors
+    /// Fails if the amount is above the limit of the holder's review status
+    pub(crate) async fn check_payment_limit(
+        stellar_address: &str,
+        amount: u64,
+    ) -> Result<(), Error> {
+        let Some(review) = holder_status(stellar_address).await? else {
+            return Ok(());
+        };
+
+        PaymentLimits::from_env()?.check(review, amount)
+    }
+
+    /// Re-encrypts the fields and documents of every customer not yet under the
+    /// newest key version
+    ///
+    /// Records that cannot be decrypted are counted as failures and skipped.
+    ///
+    /// # Returns
+    /// * `Result<(i64, i64), Error>` - The number of records re-encrypted and failed
+    pub(crate) async fn reencrypt_customers(key_ring: &KeyRing) -> Result<(i64, i64), Error> {
+        let target_version = key_ring.current_version();
+        let mut db_connection = establish_connection().await?;
+        let (mut processed, mut failed) = (0i64, 0i64);
+
+        let customers = customers::table
+            .filter(customers::key_version.ne(target_version))
+            .select((
+                customers::id,
+                customers::encrypted_fields,
+                customers::key_version,
+            ))
+            .load::<(Uuid, Vec<u8>, i32)>(&mut db_connection)
+            .await?;
+
+        for (customer_id, encrypted_fields, key_version) in customers {
+            match key_ring.reencrypt(&encrypted_fields, customer_id.as_bytes(), key_version) {
+                std::result::Result::Ok((encrypted_fields, key_version)) => {
+                    diesel::update(customers::table.find(customer_id))
+                        .set((
+                            customers::encrypted_fields.eq(encrypted_fields),
+                            customers::key_version.eq(key_version),
+                        ))
+                        .execute(&mut db_connection)
+                        .await?;
+                    processed += 1;
+                }
+                Err(error) => {
+                    eprintln!(
+                        "Failed to re-encrypt customer {}: {}",
+                        customer_id,
+                        redact_secrets(&error.to_string())
+                    );
+                    failed += 1;
+                }
+            }
+        }
+
+        let documents = customer_documents::table
+            .filter(customer_documents::key_version.ne(target_version))
+            .load::<CustomerDocument>(&mut db_connection)
+            .await?;
+
+        for document in documents {
+            let aad = document_aad(document.customer_id, &document.field);
+            match key_ring.reencrypt(&document.encrypted_content, &aad, document.key_version) {
+                std::result::Result::Ok((encrypted_content, key_version)) => {
+                    diesel::update(customer_documents::table.find(document.id))
+                        .set((
+                            customer_documents::encrypted_content.eq(encrypted_content),
+                            customer_documents::key_version.eq(key_version),
+                        ))
+                        .execute(&mut db_connection)
+                        .await?;
+                    processed += 1;
+                }
+                Err(error) => {
+                    eprintln!(
+                        "Failed to re-encrypt document {}: {}",
+                        document.id,
+                        redact_secrets(&error.to_string())
+                    );
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok((processed, failed))
+    }
+
+    /// Counts the customer records encrypted under any of the given key versions
+    pub(crate) async fn count_key_version(key_versions: &[i32]) -> Result<i64, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let customers = customers::table
+            .filter(customers::key_version.eq_any(key_versions))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+        let documents = customer_documents::table
+            .filter(customer_documents::key_version.eq_any(key_versions))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+
+        Ok(customers + documents)
+    }
+
+    /// Returns what identity verification knows of the holder of a Stellar account
+    ///
+    /// # Returns
+    /// * `Result<Option<HolderReview>, Error>` - None for our own issuer and distributor
+    ///   accounts, which are not checked
+    async fn holder_status(stellar_address: &str) -> Result<Option<HolderReview>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let account_type = accounts::table
+            .filter(accounts::stellar_address.eq(stellar_address))
+            .select(accounts::account_type)
+            .first::<String>(&mut db_connection)
+            .await
+            .optional()?;
+
+        if account_type
+            .as_ref()
+            .is_some_and(|account_type| account_type != "user")
+        {
+            return Ok(None);
+        }
+
+        let status = customers::table
+            .filter(customers::stellar_account.eq(stellar_address))
+            .filter(customers::memo.is_null())
+            .filter(customers::customer_type.eq(DEFAULT_TYPE))
+            .select(customers::status)
+            .first::<String>(&mut db_connection)
+            .await
+            .optional()?;
+
+        let legacy = kyc_legacy_accounts::table
+            .find(stellar_address)
+            .select(kyc_legacy_accounts::stellar_address)
+            .first::<String>(&mut db_connection)
+            .await
+            .optional()?
+            .is_some();
+
+        holder_review(account_type.as_deref(), status.as_deref(), legacy)
+    }
+
+    /// Works out what identity verification knows of the holder of an account
+    ///
+    /// # Arguments
+    /// * `account_type` - The type of the account, if it is one of ours
+    /// * `customer_status` - The status of the holder's customer record, if it has one
+    /// * `legacy` - Whether the account was opened before identity verification
+    ///
+    /// # Returns
+    /// * `Result<Option<HolderReview>, Error>` - None for our own issuer and distributor
+    ///   accounts, which are not checked
+    fn holder_review(
+        account_type: Option<&str>,
+        customer_status: Option<&str>,
+        legacy: bool,
+    ) -> Result<Option<HolderReview>, Error> {
+        if account_type.is_some_and(|account_type| account_type != "user") {
+            return Ok(None);
+        }
+
+        // A customer record, once started, decides even for a legacy account
+        Ok(Some(match customer_status {
+            Some(status) => HolderReview::Customer(status.parse()?),
+            None if legacy => HolderReview::Legacy,
+            None => HolderReview::Unverified,
+        }))
+    }
+
+    /// Finds a customer by id, or by account, memo and type
+    async fn find_customer(
+        conn: &mut AsyncPgConnection,
+        query: &CustomerQuery,
+    ) -> Result<Option<Customer>, Error> {
+        if let Some(id) = &query.id {
+            let customer = customers::table
+                .find(
+                    Uuid::parse_str(id)
+                        .map_err(|_| anyhow::anyhow!("Customer {} not found", id))?,
+                )
+                .filter(customers::stellar_account.eq(&query.account))
+                .for_update()
+                .first::<Customer>(conn)
+                .await
+                .optional()?
+                .ok_or_else(|| anyhow::anyhow!("Customer {} not found", id))?;
+
+            return Ok(Some(customer));
+        }
+
+        let customer_type = query.customer_type.as_deref().unwrap_or(DEFAULT_TYPE);
+        required_fields(customer_type)?;
+
+        let mut customer_query = customers::table
+            .filter(customers::stellar_account.eq(&query.account))
+            .filter(customers::customer_type.eq(customer_type))
+            .into_boxed();
+        customer_query = match &query.memo {
+            Some(memo) => customer_query.filter(customers::memo.eq(memo)),
+            None => customer_query.filter(customers::memo.is_null()),
+        };
+
+        let Some(customer_id) = customer_query
+            .select(customers::id)
+            .first::<Uuid>(conn)
+            .await
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let customer = customers::table
+            .find(customer_id)
+            .for_update()
+            .first::<Customer>(conn)
+            .await?;
+
+        Ok(Some(customer))
+    }
+
+    /// Creates a customer with no fields yet
+    async fn create_customer(
+        conn: &mut AsyncPgConnection,
+        key_ring: &KeyRing,
+        query: &CustomerQuery,
+    ) -> Result<Customer, Error> {
+        let id = Uuid::new_v4();
+        let (encrypted_fields, key_version) = key_ring.encrypt(b"{}", id.as_bytes())?;
+
+        let customer = diesel::insert_into(customers::table)
+            .values(&NewCustomer {
+                id,
+                stellar_account: &query.account,
+                memo: query.memo.as_deref(),
+                customer_type: query.customer_type.as_deref().unwrap_or(DEFAULT_TYPE),
+                status: CustomerStatus::NeedsInfo.as_str(),
+                encrypted_fields,
+                key_version,
+            })
+            .returning(customers::all_columns)
+            .get_result::<Customer>(conn)
+            .await?;
+
+        Ok(customer)
+    }
+
+    async fn get_customer_by_id(
+        conn: &mut AsyncPgConnection,
+        customer_id: &str,
+    ) -> Result<Customer, Error> {
+        let customer = customers::table
+            .find(Uuid::parse_str(customer_id)?)
+            .first::<Customer>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Customer {} not found", customer_id))?;
+
+        Ok(customer)
+    }
+
+    /// Returns the names of the fields and documents provided for a customer
+    async fn provided_fields(
+        conn: &mut AsyncPgConnection,
+        key_ring: &KeyRing,
+        customer: &Customer,
+    ) -> Result<Vec<String>, Error> {
+        let mut provided: Vec<String> = decrypt_fields(key_ring, customer)?.into_keys().collect();
+
+        provided.extend(
+            customer_documents::table
+                .filter(customer_documents::customer_id.eq(customer.id))
+                .select(customer_documents::field)
+                .load::<String>(conn)
+                .await?,
+        );
+
+        Ok(provided)
+    }
+
+    fn decrypt_fields(
+        key_ring: &KeyRing,
+        customer: &Customer,
+    ) -> Result<BTreeMap<String, String>, Error> {
+        let fields = key_ring.decrypt(
+            &customer.encrypted_fields,
+            customer.id.as_bytes(),
+            customer.key_version,
+        )?;
+
+        Ok(serde_json::from_slice(fields.expose_secret())?)
+    }
+
+    /// The data a document is bound to, so it cannot be moved to another customer or field
+    fn document_aad(customer_id: Uuid, field: &str) -> Vec<u8> {
+        format!("{}:{}", customer_id, field).into_bytes()
+    }
+
+    /// The status a customer is shown with, given the fields its type still misses
+    fn view_status(
+        stored_status: &str,
+        missing: &[&'static helpers::kyc::KycField],
+    ) -> Result<CustomerStatus, Error> {
+        // A customer of another type may not have what this type requires yet
+        if missing.is_empty() {
+            Ok(stored_status.parse::<CustomerStatus>()?)
+        } else {
+            Ok(CustomerStatus::NeedsInfo)
+        }
+    }
+
+    /// The review status of the provided fields of a customer
+    fn provided_field_status(status: CustomerStatus) -> &'static str {
+        match status {
+            CustomerStatus::Accepted => "ACCEPTED",
+            CustomerStatus::Rejected => "REJECTED",
+            _ => "PROCESSING",
+        }
+    }
+
+    fn field_views(
+        fields: &[&'static helpers::kyc::KycField],
+        status: Option<&'static str>,
+    ) -> BTreeMap<&'static str, FieldView> {
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    field.name,
+                    FieldView {
+                        field_type: field.field_type,
+                        description: field.description,
+                        status,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn customer_summary(customer: Customer) -> CustomerSummary {
+        CustomerSummary {
+            id: customer.id,
+            stellar_account: customer.stellar_account,
+            memo: customer.memo,
+            customer_type: customer.customer_type,
+            status: customer.status,
+            status_message: customer.status_message,
+            created_at: customer.created_at,
+            updated_at: customer.updated_at,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use helpers::kyc::SENDER_TYPE;
+
+        #[test]
+        fn test_view_status() {
+            let missing = missing_fields(SENDER_TYPE, &["first_name"]).unwrap();
+            assert_eq!(
+                view_status(CustomerStatus::Accepted.as_str(), &missing).unwrap(),
+                CustomerStatus::NeedsInfo
+            );
+
+            let provided = ["first_name", "last_name", "country_code"];
+            let missing = missing_fields(SENDER_TYPE, &provided).unwrap();
+            for status in CustomerStatus::ALL {
+                assert_eq!(view_status(status.as_str(), &missing).unwrap(), status);
+            }
+            assert!(view_status("unknown", &missing).is_err());
+        }
+
+        #[test]
+        fn test_holder_review() {
+            assert_eq!(holder_review(Some("issuer"), None, false).unwrap(), None);
+            assert_eq!(
+                holder_review(Some("distributor"), Some("rejected"), false).unwrap(),
+                None
+            );
+
+            // Holders without a customer record
+            assert_eq!(
+                holder_review(Some("user"), None, false).unwrap(),
+                Some(HolderReview::Unverified)
+            );
+            assert_eq!(
+                holder_review(None, None, false).unwrap(),
+                Some(HolderReview::Unverified)
+            );
+            assert_eq!(
+                holder_review(Some("user"), None, true).unwrap(),
+                Some(HolderReview::Legacy)
+            );
+
+            assert_eq!(
+                holder_review(Some("user"), Some("processing"), true).unwrap(),
+                Some(HolderReview::Customer(CustomerStatus::Processing))
+            );
+            assert!(holder_review(Some("user"), Some("unknown"), false).is_err());
+        }
+
+        #[test]
+        fn test_no_customer_record_pays_only_on_a_legacy_account() {
+            let limits = PaymentLimits {
+                accepted: None,
+                processing: 0,
+                legacy: None,
+            };
+
+            let review = holder_review(Some("user"), None, false).unwrap().unwrap();
+            assert!(limits.check(review, 1).is_err());
+            assert!(!review.is_verified());
+
+            let review = holder_review(Some("user"), None, true).unwrap().unwrap();
+            assert!(limits.check(review, 1).is_ok());
+            assert!(review.is_verified());
+        }
+
+        #[test]
+        fn test_provided_field_status() {
+            assert_eq!(provided_field_status(CustomerStatus::Accepted), "ACCEPTED");
+            assert_eq!(provided_field_status(CustomerStatus::Rejected), "REJECTED");
+            assert_eq!(
+                provided_field_status(CustomerStatus::Processing),
+                "PROCESSING"
+            );
+            assert_eq!(
+                provided_field_status(CustomerStatus::NeedsInfo),
+                "PROCESSING"
+            );
+        }
+
+        #[test]
+        fn test_field_views() {
+            let missing = missing_fields(SENDER_TYPE, &["first_name"]).unwrap();
+            let views = field_views(&missing, Some("PROCESSING"));
+
+            assert_eq!(
+                views.keys().copied().collect::<Vec<_>>(),
+                ["country_code", "last_name"]
+            );
+            assert_eq!(views["last_name"].field_type, "string");
+            assert_eq!(views["last_name"].status, Some("PROCESSING"));
+            assert!(field_views(&missing, None)
+                .values()
+                .all(|view| view.status.is_none()));
+        }
+    }
+}
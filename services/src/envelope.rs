@@ -0,0 +1,240 @@
+#![allow(clippy::module_inception)]
+
+/// Envelope module for transactions that are built by the service but signed outside of
+/// it, e.g. by an issuer key kept on an offline machine.
+///
+/// Prepared envelopes are stored as pending transactions. Once signed they come back
+/// through [`envelope::submit_signed_envelope`], which checks the signatures against the
+/// source account and the configured network before anything reaches Horizon.
+pub mod envelope {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::multisig::{decode_transaction, verify_signatures, ThresholdLevel};
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::StellarChain;
+    use helpers::submitter::SubmissionOutcome;
+    use models::common::establish_connection;
+    use models::models::PendingTransaction;
+    use models::schema::{accounts, pending_transactions};
+    use stellar_base::time_bounds::TimeBounds;
+    use stellar_base::PublicKey;
+
+    use crate::common::common;
+    use crate::escrow::escrow;
+
+    /// How long prepared envelopes stay valid when PREPARED_ENVELOPE_TTL_SECONDS is unset
+    const DEFAULT_PREPARED_ENVELOPE_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+    /// Returns true when ISSUER_SIGNING_MODE keeps the issuer key out of the service
+    ///
+    /// `online` (the default) signs issuer transactions with `ISSUER_SECRET_KEY`,
+    /// `offline` only prepares them and waits for the signed envelope.
+    pub fn issuer_signing_offline() -> Result<bool, Error> {
+        let signing_mode =
+            std::env::var("ISSUER_SIGNING_MODE").unwrap_or_else(|_| "online".to_string());
+        match signing_mode.as_str() {
+            "online" => Ok(false),
+            "offline" => Ok(true),
+            _ => Err(anyhow::anyhow!("Invalid issuer signing mode")),
+        }
+    }
+
+    /// Returns the time bounds for a prepared envelope, giving offline signers
+    /// PREPARED_ENVELOPE_TTL_SECONDS (a day by default) to sign and return it
+    pub fn prepared_time_bounds() -> Result<TimeBounds, Error> {
+        let ttl_seconds = match std::env::var("PREPARED_ENVELOPE_TTL_SECONDS") {
+            std::result::Result::Ok(ttl_seconds) => ttl_seconds.parse::<i64>()?,
+            Err(_) => DEFAULT_PREPARED_ENVELOPE_TTL_SECONDS,
+        };
+
+        if ttl_seconds <= 0 {
+            return Err(anyhow::anyhow!(
+                "PREPARED_ENVELOPE_TTL_SECONDS must be positive"
+            ));
+        }
+
+        Ok(TimeBounds::valid_for(chrono::Duration::seconds(
+            ttl_seconds,
+        )))
+    }
+
+    /// Returns an error if a pending transaction is past its upper time bound
+    pub(crate) fn ensure_not_expired(
+        pending_transaction: &PendingTransaction,
+    ) -> Result<(), Error> {
+        match pending_transaction.expires_at {
+            Some(expires_at) if expires_at <= chrono::Utc::now().naive_utc() => {
+                Err(anyhow::anyhow!(
+                    "Transaction {} expired at {}",
+                    pending_transaction.transaction_hash,
+                    expires_at
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Submits an externally signed envelope for a prepared transaction
+    ///
+    /// The envelope is matched to its prepared transaction by the hash computed with the
+    /// configured network passphrase, so envelopes for another network or with altered
+    /// operations are never found. Every signature must come from a signer of the source
+    /// account, and together they must meet the threshold the transaction needs.
+    ///
+    /// # Arguments
+    /// * `envelope_xdr` - The base64 encoded, signed transaction envelope
+    ///
+    /// # Returns
+    /// * `Result<PendingTransaction, Error>` - The prepared transaction with the result
+    ///   of the submission
+    pub async fn submit_signed_envelope(envelope_xdr: &str) -> Result<PendingTransaction, Error> {
+        let transaction = decode_transaction(envelope_xdr)?;
+
+        let stellar_chain = common::get_stellar_chain()?;
+
+        let transaction_hash = hex::encode(transaction.hash(stellar_chain.network())?);
+
+        let mut db_connection = establish_connection().await?;
+
+        let pending_transaction = pending_transactions::table
+            .filter(pending_transactions::transaction_hash.eq(&transaction_hash))
+            .first::<PendingTransaction>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| {
+                anyhow::anyhow!("No prepared transaction matches this envelope on this network")
+            })?;
+
+        let authorization = stellar_chain.account_authorization(&PublicKey::from_account_id(
+            &pending_transaction.source_address,
+        )?)?;
+        let network = stellar_chain.network().clone();
+        let envelope_xdr = envelope_xdr.to_string();
+
+        let pending_transaction = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let pending_transaction = pending_transactions::table
+                        .find(pending_transaction.id)
+                        .for_update()
+                        .first::<PendingTransaction>(conn)
+                        .await?;
+
+                    if pending_transaction.status != "pending" {
+                        return Err(anyhow::anyhow!(
+                            "Transaction is already {}",
+                            pending_transaction.status
+                        ));
+                    }
+                    ensure_not_expired(&pending_transaction)?;
+
+                    let required_weight = pending_transaction
+                        .threshold_level
+                        .parse::<ThresholdLevel>()?
+                        .required_weight(&authorization);
+                    let collected_weight =
+                        verify_signatures(&transaction, &network, &authorization, required_weight)?;
+
+                    let pending_transaction =
+                        diesel::update(pending_transactions::table.find(pending_transaction.id))
+                            .set((
+                                pending_transactions::envelope_xdr.eq(envelope_xdr),
+                                pending_transactions::required_weight.eq(required_weight as i32),
+                                pending_transactions::collected_weight.eq(collected_weight as i32),
+                                pending_transactions::status.eq("submitted"),
+                                pending_transactions::updated_at
+                                    .eq(Some(chrono::Utc::now().naive_utc())),
+                            ))
+                            .returning(pending_transactions::all_columns)
+                            .get_result::<PendingTransaction>(conn)
+                            .await?;
+
+                    Ok(pending_transaction)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        submit_pending_transaction(&stellar_chain, pending_transaction).await
+    }
+
+    /// Submits a pending transaction that has collected enough signatures and records
+    /// the outcome. The caller marks it `submitted` first, so it is submitted only once;
+    /// it is `completed` once it lands, and if the outcome stays unknown it remains
+    /// `submitted` with the last error.
+    pub(crate) async fn submit_pending_transaction(
+        stellar_chain: &StellarChain,
+        pending_transaction: PendingTransaction,
+    ) -> Result<PendingTransaction, Error> {
+        let outcome = stellar_chain
+            .submitter()
+            .submit(
+                &pending_transaction.envelope_xdr,
+                &pending_transaction.transaction_hash,
+            )
+            .await;
+
+        let (status, result) = match &outcome {
+            SubmissionOutcome::Completed => ("completed", None),
+            SubmissionOutcome::Failed { error_message, .. } => ("failed", Some(error_message)),
+            SubmissionOutcome::Unknown { error_message } => ("submitted", Some(error_message)),
+        };
+
+        let mut db_connection = establish_connection().await?;
+
+        let pending_transaction =
+            diesel::update(pending_transactions::table.find(pending_transaction.id))
+                .set((
+                    pending_transactions::status.eq(status),
+                    pending_transactions::result.eq(result),
+                    pending_transactions::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+                ))
+                .returning(pending_transactions::all_columns)
+                .get_result::<PendingTransaction>(&mut db_connection)
+                .await?;
+
+        if outcome == SubmissionOutcome::Completed {
+            complete_pending_transaction(&mut db_connection, &pending_transaction).await?;
+        }
+
+        // Payments held until the account existed can be claimed now
+        if let (SubmissionOutcome::Completed, "activate_account", Some(account_id)) = (
+            &outcome,
+            pending_transaction.purpose.as_str(),
+            pending_transaction.reference_id,
+        ) {
+            let claimed = escrow::claim_held_payments(account_id).await;
+            if let Err(error) = claimed {
+                eprintln!(
+                    "Failed to claim held payments of {}: {}",
+                    account_id,
+                    redact_secrets(&error.to_string())
+                );
+            }
+        }
+
+        Ok(pending_transaction)
+    }
+
+    /// Applies the local effects of a transaction that landed on chain
+    async fn complete_pending_transaction(
+        conn: &mut AsyncPgConnection,
+        pending_transaction: &PendingTransaction,
+    ) -> Result<(), Error> {
+        if let ("activate_account", Some(account_id)) = (
+            pending_transaction.purpose.as_str(),
+            pending_transaction.reference_id,
+        ) {
+            diesel::update(accounts::table.find(account_id))
+                .set(accounts::status.eq("active"))
+                .execute(conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
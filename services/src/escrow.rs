@@ -0,0 +1,625 @@
+#![allow(clippy::module_inception)]
+
+/// Escrow module that holds funds in claimable balances.
+///
+/// Creating an escrow moves the funds from the sender into a claimable balance the
+/// recipient can claim, before the deadline if there is one. Once the deadline has
+/// passed only the sender can claim it, taking the funds back. Both claims are checked
+/// by the network against ledger close time, so neither side can claim out of turn.
+/// Every transaction goes through the outbox, which moves the escrow status along.
+///
+/// Payments to recipients that cannot receive them yet are held the same way, as
+/// escrows of kind `payment` without a deadline, and claimed for the recipient once
+/// its account is activated.
+pub mod escrow {
+    use anyhow::{Error, Ok};
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDateTime;
+    use diesel::BoolExpressionMethods;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::{
+        claimable_balance_id, decode_claimable_balance_id, encode_claimable_balance_id,
+        DestinationStatus,
+    };
+    use models::common::establish_connection;
+    use models::models::{Account, Escrow, NewEscrow};
+    use models::schema::{accounts, chain_outbox, escrows};
+    use stellar_base::claim::{ClaimPredicate, Claimant};
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::common::common::{
+        get_account_from_id, get_stellar_chain, record_pending_transaction,
+    };
+    use crate::customer::customer::check_payment_limit;
+    use crate::outbox::outbox;
+    use crate::payment::payment::payment_asset;
+    use crate::signer::signer::get_signer;
+
+    /// An escrow to create.
+    pub struct EscrowRequest {
+        pub sender_account_id: String,
+        pub recipient_public_key: String,
+        /// `XLM` with no issuer for native funds
+        pub asset_code: String,
+        pub asset_issuer: Option<String>,
+        pub amount: u64,
+        /// The recipient can claim until this time and the sender from then on. Without
+        /// it only the recipient can ever claim the funds.
+        pub deadline: Option<NaiveDateTime>,
+    }
+
+    /// Creates an escrow and funds its claimable balance
+    ///
+    /// # Arguments
+    /// * `request` - The funds to hold, for whom and until when
+    ///
+    /// # Returns
+    /// * `Result<Escrow, Error>` - The escrow, `held` once the balance exists on chain
+    pub async fn create_escrow(request: EscrowRequest) -> Result<Escrow, Error> {
+        hold_funds(request, "escrow").await
+    }
+
+    /// Holds a payment the recipient cannot receive yet in a claimable balance only
+    /// the recipient can claim
+    ///
+    /// # Arguments
+    /// * `request` - The payment to hold, without a deadline
+    pub(crate) async fn hold_payment(request: EscrowRequest) -> Result<Escrow, Error> {
+        hold_funds(request, "payment").await
+    }
+
+    /// Claims the payments held for an account that can receive them now, e.g. right
+    /// after it was activated
+    ///
+    /// A payment that cannot be claimed stays held and is tried again the next time.
+    ///
+    /// # Arguments
+    /// * `account_id` - The UUID of the recipient account
+    ///
+    /// # Returns
+    /// * `Result<Vec<Escrow>, Error>` - The payments claimed
+    pub async fn claim_held_payments(account_id: Uuid) -> Result<Vec<Escrow>, Error> {
+        let account = get_account_from_id(account_id.to_string()).await?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let held_payments = escrows::table
+            .filter(escrows::kind.eq("payment"))
+            .filter(escrows::status.eq("held"))
+            .filter(escrows::recipient_public_key.eq(&account.stellar_address))
+            .order(escrows::created_at.asc())
+            .load::<Escrow>(&mut db_connection)
+            .await?;
+
+        let mut claimed = Vec::with_capacity(held_payments.len());
+        for escrow in held_payments {
+            let escrow_id = escrow.id;
+            match claim_balance(escrow, &account, "release_escrow").await {
+                std::result::Result::Ok(escrow) => claimed.push(escrow),
+                Err(error) => {
+                    eprintln!(
+                        "Failed to claim held payment {}: {}",
+                        escrow_id,
+                        redact_secrets(&error.to_string())
+                    );
+                }
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    /// Creates a claimable balance and the escrow record of the given kind
+    ///
+    /// The sender holds funds within the limit of its identity verification.
+    async fn hold_funds(request: EscrowRequest, kind: &str) -> Result<Escrow, Error> {
+        check_hold(&request, chrono::Utc::now().naive_utc())?;
+
+        let stellar_chain = get_stellar_chain()?;
+
+        let sender_account = get_account_from_id(request.sender_account_id.clone()).await?;
+        check_payment_limit(&sender_account.stellar_address, request.amount).await?;
+        let sender_public_key = PublicKey::from_account_id(&sender_account.stellar_address)?;
+        let recipient_public_key = PublicKey::from_account_id(&request.recipient_public_key)?;
+        let asset = payment_asset(&request.asset_code, request.asset_issuer.as_deref())?;
+        let signer = get_signer()?;
+
+        let transaction = stellar_chain.build_create_claimable_balance_transaction(
+            &sender_public_key,
+            asset,
+            request.amount,
+            claimants(&sender_public_key, recipient_public_key, request.deadline),
+        )?;
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &signer, &sender_account.id.to_string())
+            .await?;
+
+        let balance_id = encode_claimable_balance_id(&claimable_balance_id(
+            &sender_public_key,
+            *transaction.sequence(),
+            0,
+        )?);
+
+        // Record the escrow and queue its transaction in one database transaction
+        let mut db_connection = establish_connection().await?;
+
+        let escrow_id = Uuid::new_v4();
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let request_ref = &request;
+        let sender_account_ref = &sender_account;
+        let balance_id_ref = &balance_id;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        &sender_account_ref.stellar_address,
+                        &request_ref.recipient_public_key,
+                        &request_ref.asset_code,
+                        BigDecimal::from(request_ref.amount),
+                    )
+                    .await?;
+
+                    // Only recipients we manage are linked
+                    let recipient_account_id = accounts::table
+                        .filter(accounts::stellar_address.eq(&request_ref.recipient_public_key))
+                        .select(accounts::id)
+                        .first::<Uuid>(conn)
+                        .await
+                        .optional()?;
+
+                    diesel::insert_into(escrows::table)
+                        .values(&NewEscrow {
+                            id: escrow_id,
+                            sender_account_id: sender_account_ref.id,
+                            recipient_public_key: &request_ref.recipient_public_key,
+                            recipient_account_id,
+                            asset_code: &request_ref.asset_code,
+                            asset_issuer: request_ref.asset_issuer.as_deref(),
+                            amount: i64::try_from(request_ref.amount)?,
+                            balance_id: balance_id_ref,
+                            deadline: request_ref.deadline,
+                            status: "pending",
+                            create_transaction_id: transaction_id,
+                            kind,
+                        })
+                        .execute(conn)
+                        .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        "create_escrow",
+                        Some(escrow_id),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The escrow is held once the transaction lands
+        let entry = outbox::process_entry(&stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        get_escrow(escrow_id).await
+    }
+
+    /// Lists the escrows an account sent or is the recipient of, newest first
+    ///
+    /// # Arguments
+    /// * `account_id` - The UUID of the account
+    pub async fn get_escrows(account_id: &str) -> Result<Vec<Escrow>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let account_uuid = Uuid::parse_str(account_id)?;
+
+        let escrows = escrows::table
+            .filter(
+                escrows::sender_account_id
+                    .eq(account_uuid)
+                    .or(escrows::recipient_account_id.eq(account_uuid)),
+            )
+            .order(escrows::created_at.desc())
+            .load::<Escrow>(&mut db_connection)
+            .await?;
+
+        Ok(escrows)
+    }
+
+    /// Releases a held escrow to its recipient, who claims the balance
+    ///
+    /// Only recipients managed by this service can be claimed for; any other
+    /// recipient claims the balance with its own key.
+    ///
+    /// # Arguments
+    /// * `escrow_id` - The UUID of the escrow
+    pub async fn claim_escrow(escrow_id: &str) -> Result<Escrow, Error> {
+        let escrow = get_escrow(Uuid::parse_str(escrow_id)?).await?;
+
+        let recipient_account_id = escrow.recipient_account_id.ok_or_else(|| {
+            anyhow::anyhow!("Recipient of escrow {} is not managed here", escrow.id)
+        })?;
+
+        // The network checks the deadline against ledger close time, not the local clock
+        if escrow.deadline.is_some() {
+            let stellar_chain = get_stellar_chain()?;
+            check_release(
+                &escrow,
+                stellar_chain.latest_ledger_close_time().await?.naive_utc(),
+            )?;
+        }
+
+        let recipient_account = get_account_from_id(recipient_account_id.to_string()).await?;
+        claim_balance(escrow, &recipient_account, "release_escrow").await
+    }
+
+    /// Returns the funds of an expired escrow to its sender
+    ///
+    /// # Arguments
+    /// * `escrow_id` - The UUID of the escrow
+    pub async fn reclaim_escrow(escrow_id: &str) -> Result<Escrow, Error> {
+        let escrow = get_escrow(Uuid::parse_str(escrow_id)?).await?;
+
+        let stellar_chain = get_stellar_chain()?;
+        check_reclaim(
+            &escrow,
+            stellar_chain.latest_ledger_close_time().await?.naive_utc(),
+        )?;
+
+        let sender_account = get_account_from_id(escrow.sender_account_id.to_string()).await?;
+        claim_balance(escrow, &sender_account, "reclaim_escrow").await
+    }
+
+    /// Returns an escrow by id
+    async fn get_escrow(escrow_id: Uuid) -> Result<Escrow, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let escrow = escrows::table
+            .find(escrow_id)
+            .first::<Escrow>(&mut db_connection)
+            .await?;
+
+        Ok(escrow)
+    }
+
+    /// Claims the balance of a held escrow for one of its claimants, establishing the
+    /// claimant's trustline for the asset first if it has none
+    async fn claim_balance(
+        escrow: Escrow,
+        claimant_account: &Account,
+        purpose: &str,
+    ) -> Result<Escrow, Error> {
+        check_held(&escrow)?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let signer = get_signer()?;
+
+        let asset = payment_asset(&escrow.asset_code, escrow.asset_issuer.as_deref())?;
+        let trust_asset = match stellar_chain
+            .destination_status(&claimant_account.stellar_address, &asset)
+            .await?
+        {
+            DestinationStatus::Ready => None,
+            DestinationStatus::NoTrustline => Some(asset),
+            DestinationStatus::NotFound => {
+                return Err(anyhow::anyhow!(
+                    "Account {} is not activated",
+                    claimant_account.id
+                ));
+            }
+            DestinationStatus::NotAuthorized => {
+                return Err(anyhow::anyhow!(
+                    "Account {} is not authorized to hold {}",
+                    claimant_account.id,
+                    escrow.asset_code
+                ));
+            }
+        };
+
+        let transaction = stellar_chain.build_claim_claimable_balance_transaction(
+            &PublicKey::from_account_id(&claimant_account.stellar_address)?,
+            decode_claimable_balance_id(&escrow.balance_id)?,
+            trust_asset,
+        )?;
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &signer, &claimant_account.id.to_string())
+            .await?;
+
+        // Record the claim and queue it in the outbox in one database transaction
+        let mut db_connection = establish_connection().await?;
+
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let claimant_address = &claimant_account.stellar_address;
+        let escrow_id = escrow.id;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let escrow = escrows::table
+                        .find(escrow_id)
+                        .for_update()
+                        .first::<Escrow>(conn)
+                        .await?;
+
+                    // Claimed meanwhile, so never released twice
+                    check_held(&escrow)?;
+
+                    let queued_claims = chain_outbox::table
+                        .filter(chain_outbox::purpose.eq_any(["release_escrow", "reclaim_escrow"]))
+                        .filter(chain_outbox::reference_id.eq(escrow_id))
+                        .filter(chain_outbox::status.eq("pending"))
+                        .count()
+                        .get_result::<i64>(conn)
+                        .await?;
+
+                    if queued_claims > 0 {
+                        return Err(anyhow::anyhow!(
+                            "Escrow {} is already being claimed",
+                            escrow_id
+                        ));
+                    }
+
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        claimant_address,
+                        claimant_address,
+                        &escrow.asset_code,
+                        BigDecimal::from(escrow.amount),
+                    )
+                    .await?;
+
+                    diesel::update(escrows::table.find(escrow_id))
+                        .set((
+                            escrows::settle_transaction_id.eq(Some(transaction_id)),
+                            escrows::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        purpose,
+                        Some(escrow_id),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The escrow is settled once the claim lands
+        let entry = outbox::process_entry(&stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        get_escrow(escrow_id).await
+    }
+
+    /// Returns the status an escrow moves from and to once a transaction of `purpose`
+    /// lands: `held` once created, then `released` to the recipient or `reclaimed` by
+    /// the sender
+    pub(crate) fn landed_status(purpose: &str) -> Option<(&'static str, &'static str)> {
+        match purpose {
+            "create_escrow" => Some(("pending", "held")),
+            "release_escrow" => Some(("held", "released")),
+            "reclaim_escrow" => Some(("held", "reclaimed")),
+            _ => None,
+        }
+    }
+
+    /// Returns an error unless funds can be held as requested at `now`
+    fn check_hold(request: &EscrowRequest, now: NaiveDateTime) -> Result<(), Error> {
+        if request.amount == 0 {
+            return Err(anyhow::anyhow!("Amount must be positive"));
+        }
+
+        if request.deadline.is_some_and(|deadline| deadline <= now) {
+            return Err(anyhow::anyhow!("Deadline must be in the future"));
+        }
+
+        Ok(())
+    }
+
+    /// Returns who may claim held funds: the recipient until the deadline and the
+    /// sender from then on, or the recipient alone without one
+    fn claimants(
+        sender_public_key: &PublicKey,
+        recipient_public_key: PublicKey,
+        deadline: Option<NaiveDateTime>,
+    ) -> Vec<Claimant> {
+        match deadline {
+            Some(deadline) => {
+                let before_deadline = ClaimPredicate::new_before_absolute_time(deadline.and_utc());
+                vec![
+                    Claimant::new(recipient_public_key, before_deadline.clone()),
+                    Claimant::new(
+                        sender_public_key.clone(),
+                        ClaimPredicate::new_not(before_deadline),
+                    ),
+                ]
+            }
+            None => vec![Claimant::new(
+                recipient_public_key,
+                ClaimPredicate::new_unconditional(),
+            )],
+        }
+    }
+
+    /// Returns an error unless an escrow is held, and so not settled or being settled
+    fn check_held(escrow: &Escrow) -> Result<(), Error> {
+        if escrow.status != "held" {
+            return Err(anyhow::anyhow!("Escrow {} is {}", escrow.id, escrow.status));
+        }
+        Ok(())
+    }
+
+    /// Returns an error unless the recipient may still claim an escrow when the latest
+    /// ledger closed at `close_time`
+    fn check_release(escrow: &Escrow, close_time: NaiveDateTime) -> Result<(), Error> {
+        if escrow
+            .deadline
+            .is_some_and(|deadline| close_time >= deadline)
+        {
+            return Err(anyhow::anyhow!(
+                "Escrow {} expired, only the sender can reclaim it",
+                escrow.id
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error unless the sender may take an escrow back when the latest
+    /// ledger closed at `close_time`
+    fn check_reclaim(escrow: &Escrow, close_time: NaiveDateTime) -> Result<(), Error> {
+        let deadline = escrow.deadline.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Escrow {} has no deadline, only the recipient can claim it",
+                escrow.id
+            )
+        })?;
+
+        if close_time < deadline {
+            return Err(anyhow::anyhow!(
+                "Escrow {} can be reclaimed after {}",
+                escrow.id,
+                deadline
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        const SENDER: &str = "GAHK7EEG2WWHVKDNT4CEQFZGKF2LGDSW2IVM4S5DP42RBW3K6BTODB4A";
+        const RECIPIENT: &str = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H";
+
+        fn at(day: u32, hour: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2025, 3, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+        }
+
+        fn escrow(status: &str, deadline: Option<NaiveDateTime>) -> Escrow {
+            Escrow {
+                id: Uuid::new_v4(),
+                sender_account_id: Uuid::new_v4(),
+                recipient_public_key: RECIPIENT.to_string(),
+                recipient_account_id: Some(Uuid::new_v4()),
+                asset_code: "XLM".to_string(),
+                asset_issuer: None,
+                amount: 10_000_000,
+                balance_id: String::new(),
+                deadline,
+                status: status.to_string(),
+                create_transaction_id: Uuid::new_v4(),
+                settle_transaction_id: None,
+                created_at: None,
+                updated_at: None,
+                kind: "escrow".to_string(),
+            }
+        }
+
+        fn request(amount: u64, deadline: Option<NaiveDateTime>) -> EscrowRequest {
+            EscrowRequest {
+                sender_account_id: Uuid::new_v4().to_string(),
+                recipient_public_key: RECIPIENT.to_string(),
+                asset_code: "XLM".to_string(),
+                asset_issuer: None,
+                amount,
+                deadline,
+            }
+        }
+
+        #[test]
+        fn test_landed_status() {
+            assert_eq!(landed_status("create_escrow"), Some(("pending", "held")));
+            assert_eq!(landed_status("release_escrow"), Some(("held", "released")));
+            assert_eq!(landed_status("reclaim_escrow"), Some(("held", "reclaimed")));
+            assert_eq!(landed_status("payment"), None);
+        }
+
+        #[test]
+        fn test_check_hold() {
+            let now = at(10, 12);
+            assert!(check_hold(&request(5, None), now).is_ok());
+            assert!(check_hold(&request(5, Some(at(11, 12))), now).is_ok());
+
+            assert!(check_hold(&request(0, None), now).is_err());
+            assert!(check_hold(&request(5, Some(now)), now).is_err());
+            assert!(check_hold(&request(5, Some(at(9, 12))), now).is_err());
+        }
+
+        #[test]
+        fn test_claimants() {
+            let sender = PublicKey::from_account_id(SENDER).unwrap();
+            let recipient = PublicKey::from_account_id(RECIPIENT).unwrap();
+
+            let unconditional = claimants(&sender, recipient.clone(), None);
+            assert_eq!(
+                unconditional,
+                vec![Claimant::new(
+                    recipient.clone(),
+                    ClaimPredicate::new_unconditional()
+                )]
+            );
+
+            // The recipient until the deadline, the sender from then on
+            let deadline = at(11, 12);
+            let before_deadline = ClaimPredicate::new_before_absolute_time(deadline.and_utc());
+            assert_eq!(
+                claimants(&sender, recipient.clone(), Some(deadline)),
+                vec![
+                    Claimant::new(recipient, before_deadline.clone()),
+                    Claimant::new(sender, ClaimPredicate::new_not(before_deadline)),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_release_and_reclaim_around_the_deadline() {
+            let held = escrow("held", Some(at(11, 12)));
+
+            assert!(check_release(&held, at(11, 11)).is_ok());
+            assert!(check_reclaim(&held, at(11, 11)).is_err());
+
+            // From the deadline on, only the sender can claim
+            assert!(check_release(&held, at(11, 12)).is_err());
+            assert!(check_reclaim(&held, at(11, 12)).is_ok());
+
+            // Without a deadline the funds are the recipient's for good
+            let open = escrow("held", None);
+            assert!(check_release(&open, at(30, 0)).is_ok());
+            assert!(check_reclaim(&open, at(30, 0)).is_err());
+        }
+
+        #[test]
+        fn test_settled_escrow_is_not_claimed_again() {
+            assert!(check_held(&escrow("held", None)).is_ok());
+
+            for status in ["pending", "released", "reclaimed", "clawed_back", "failed"] {
+                let error = check_held(&escrow(status, None)).unwrap_err();
+                assert!(error.to_string().ends_with(&format!(" is {}", status)));
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+#![allow(clippy::module_inception)]
+
+/// Federation module that maps names like `alice*greep.io` to Stellar accounts
+/// (SEP-0002).
+///
+/// Our own names are the handles recorded for our accounts under `HOME_DOMAIN`. Names
+/// on other domains are looked up on that domain's federation server, so payments can
+/// be sent to any federation address.
+pub mod federation {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::RunQueryDsl;
+    use helpers::federation::{
+        federation_memo, is_federation_address, normalize_handle, parse_federation_address,
+        resolve_federation_address, FederationRecord, FEDERATION_SEPARATOR,
+    };
+    use helpers::stellar_toml::home_domain_from_env;
+    use models::common::establish_connection;
+    use models::models::{FederationHandle, NewFederationHandle};
+    use models::schema::{accounts, federation_handles};
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::common::common::get_account_from_id;
+
+    /// A federation name to give one of our accounts
+    pub struct HandleRegistration {
+        pub account_id: String,
+        /// The name part of the address, before `*`
+        pub handle: String,
+        /// `text`, `id` or `hash` when payments to the name must carry a memo
+        pub memo_type: Option<String>,
+        pub memo: Option<String>,
+    }
+
+    /// Gives one of our accounts a federation name
+    ///
+    /// # Arguments
+    /// * `registration` - The account, the handle and the memo payments must carry
+    ///
+    /// # Returns
+    /// * `Result<FederationHandle, Error>` - The recorded handle
+    pub async fn register_handle(
+        registration: HandleRegistration,
+    ) -> Result<FederationHandle, Error> {
+        let handle = normalize_handle(&registration.handle)?;
+
+        match (
+            registration.memo_type.as_deref(),
+            registration.memo.as_deref(),
+        ) {
+            (None, None) => {}
+            (Some(memo_type), Some(memo)) => {
+                federation_memo(memo_type, memo)?;
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "A memo type and a memo must be given together"
+                ))
+            }
+        }
+
+        // Make sure the account is ours
+        let account = get_account_from_id(registration.account_id).await?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let taken = federation_handles::table
+            .filter(federation_handles::handle.eq(&handle))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+        if taken > 0 {
+            return Err(anyhow::anyhow!("Handle {} is already taken", handle));
+        }
+
+        let federation_handle = diesel::insert_into(federation_handles::table)
+            .values(&NewFederationHandle {
+                id: Uuid::new_v4(),
+                account_id: account.id,
+                handle: &handle,
+                memo_type: registration.memo_type.as_deref(),
+                memo: registration.memo.as_deref(),
+            })
+            .returning(federation_handles::all_columns)
+            .get_result::<FederationHandle>(&mut db_connection)
+            .await?;
+
+        Ok(federation_handle)
+    }
+
+    /// Looks up one of our federation names
+    ///
+    /// # Arguments
+    /// * `address` - The federation address, e.g. `alice*greep.io`
+    ///
+    /// # Returns
+    /// * `Result<Option<FederationRecord>, Error>` - The record, or `None` if the name is
+    ///   not ours or not taken
+    pub async fn resolve_name(address: &str) -> Result<Option<FederationRecord>, Error> {
+        let home_domain =
+            home_domain_from_env()?.ok_or_else(|| anyhow::anyhow!("HOME_DOMAIN is not set"))?;
+
+        let (name, domain) = parse_federation_address(address)?;
+        if domain != home_domain.to_ascii_lowercase() {
+            return Ok(None);
+        }
+
+        let mut db_connection = establish_connection().await?;
+
+        let handle = federation_handles::table
+            .inner_join(accounts::table)
+            .filter(federation_handles::handle.eq(name.to_lowercase()))
+            .select((federation_handles::all_columns, accounts::stellar_address))
+            .first::<(FederationHandle, String)>(&mut db_connection)
+            .await
+            .optional()?;
+
+        Ok(handle.map(|(handle, stellar_address)| {
+            federation_record(handle, stellar_address, &home_domain)
+        }))
+    }
+
+    /// Looks up the federation name of one of our accounts
+    ///
+    /// An account with several names is known by the oldest one.
+    ///
+    /// # Arguments
+    /// * `account_id` - The Stellar public key of the account
+    ///
+    /// # Returns
+    /// * `Result<Option<FederationRecord>, Error>` - The record, or `None` if the account
+    ///   has no name
+    pub async fn resolve_account_id(account_id: &str) -> Result<Option<FederationRecord>, Error> {
+        let home_domain =
+            home_domain_from_env()?.ok_or_else(|| anyhow::anyhow!("HOME_DOMAIN is not set"))?;
+
+        PublicKey::from_account_id(account_id)
+            .map_err(|_| anyhow::anyhow!("Account id must be a Stellar public key"))?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let handle = federation_handles::table
+            .inner_join(accounts::table)
+            .filter(accounts::stellar_address.eq(account_id))
+            .order(federation_handles::created_at.asc())
+            .select((federation_handles::all_columns, accounts::stellar_address))
+            .first::<(FederationHandle, String)>(&mut db_connection)
+            .await
+            .optional()?;
+
+        Ok(handle.map(|(handle, stellar_address)| {
+            federation_record(handle, stellar_address, &home_domain)
+        }))
+    }
+
+    /// Resolves the receiver of a payment, which may be a public key or a federation
+    /// address on any domain
+    ///
+    /// # Arguments
+    /// * `receiver` - A Stellar public key or a federation address
+    ///
+    /// # Returns
+    /// * `Result<FederationRecord, Error>` - The account to pay and the memo the payment
+    ///   must carry. A public key resolves to itself without a memo.
+    pub async fn resolve_destination(receiver: &str) -> Result<FederationRecord, Error> {
+        if !is_federation_address(receiver) {
+            return Ok(FederationRecord {
+                stellar_address: None,
+                account_id: receiver.to_string(),
+                memo_type: None,
+                memo: None,
+            });
+        }
+
+        let (_, domain) = parse_federation_address(receiver)?;
+        let is_ours = home_domain_from_env()?
+            .is_some_and(|home_domain| home_domain.eq_ignore_ascii_case(&domain));
+
+        if is_ours {
+            return resolve_name(receiver)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Federation address {} not found", receiver));
+        }
+
+        resolve_federation_address(receiver).await
+    }
+
+    /// Turns a handle into the record served for it
+    fn federation_record(
+        handle: FederationHandle,
+        stellar_address: String,
+        home_domain: &str,
+    ) -> FederationRecord {
+        FederationRecord {
+            stellar_address: Some(format!(
+                "{}{}{}",
+                handle.handle, FEDERATION_SEPARATOR, home_domain
+            )),
+            account_id: stellar_address,
+            memo_type: handle.memo_type,
+            memo: handle.memo,
+        }
+    }
+}
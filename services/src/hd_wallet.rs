@@ -0,0 +1,265 @@
+#![allow(clippy::module_inception)]
+
+/// HD wallet module that derives account keys from one encrypted BIP-39 master seed
+/// along SEP-0005 paths (`m/44'/148'/n'`), so accounts only store a derivation index.
+pub mod hd_wallet {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::JoinOnDsl;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::common::{decrypt_private_key, encrypt_private_key};
+    use helpers::hd_wallet::{derive_keypair, seed_from_mnemonic};
+    use helpers::secret::{redact_secrets, SecretBytes, SecretString};
+    use models::common::establish_connection;
+    use models::models::{Account, HdMasterSeed, NewHdMasterSeed};
+    use models::schema::{accounts, encrypted_keys, hd_master_seeds};
+    use stellar_base::{KeyPair, PublicKey};
+    use uuid::Uuid;
+
+    use crate::common::common;
+    use crate::signer::signer::get_signer;
+
+    /// Associated data binding the encrypted master seed to its purpose
+    pub const MASTER_SEED_AAD: &[u8] = b"hd-master-seed";
+
+    /// Outcome of migrating random-key accounts to derived keys.
+    pub struct MigrationSummary {
+        /// Accounts now controlled by their derived key
+        pub migrated: usize,
+        /// Accounts that could not be migrated and still use their random key
+        pub failed: usize,
+    }
+
+    /// Returns true when ACCOUNT_KEY_MODE selects derived keys for new accounts
+    ///
+    /// `random` (the default) generates and stores a random key per account, `hd` derives
+    /// account keys from the stored master seed.
+    pub fn derived_keys_enabled() -> Result<bool, Error> {
+        let key_mode = std::env::var("ACCOUNT_KEY_MODE").unwrap_or_else(|_| "random".to_string());
+        match key_mode.as_str() {
+            "random" => Ok(false),
+            "hd" => Ok(true),
+            _ => Err(anyhow::anyhow!("Invalid account key mode")),
+        }
+    }
+
+    /// Stores the master seed for a mnemonic, encrypted under the current key version
+    ///
+    /// # Arguments
+    /// * `mnemonic` - The BIP-39 mnemonic to derive account keys from
+    /// * `passphrase` - The optional BIP-39 passphrase, empty if unused
+    ///
+    /// # Errors
+    /// Returns an error if the mnemonic is invalid or a master seed is already stored
+    pub async fn store_master_seed(
+        mnemonic: &SecretString,
+        passphrase: &SecretString,
+    ) -> Result<(), Error> {
+        let seed = seed_from_mnemonic(mnemonic, passphrase)?;
+        let (encrypted_seed, key_version) =
+            encrypt_private_key(seed.expose_secret(), MASTER_SEED_AAD)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let existing = hd_master_seeds::table
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+
+        if existing > 0 {
+            return Err(anyhow::anyhow!("A master seed is already stored"));
+        }
+
+        diesel::insert_into(hd_master_seeds::table)
+            .values(&NewHdMasterSeed {
+                id: Uuid::new_v4(),
+                encrypted_seed,
+                key_version,
+            })
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Loads and decrypts the master seed
+    async fn load_master_seed(conn: &mut AsyncPgConnection) -> Result<SecretBytes, Error> {
+        let master_seed = hd_master_seeds::table
+            .first::<HdMasterSeed>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("No master seed is stored"))?;
+
+        decrypt_private_key(
+            &master_seed.encrypted_seed,
+            MASTER_SEED_AAD,
+            master_seed.key_version,
+        )
+    }
+
+    /// Reserves the next unused derivation index
+    ///
+    /// Run this inside the transaction that stores the index on an account, so an index
+    /// is never handed out twice.
+    pub async fn allocate_derivation_index(conn: &mut AsyncPgConnection) -> Result<i32, Error> {
+        let next_index = diesel::update(hd_master_seeds::table)
+            .set(hd_master_seeds::next_index.eq(hd_master_seeds::next_index + 1))
+            .returning(hd_master_seeds::next_index)
+            .get_result::<i32>(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("No master seed is stored"))?;
+
+        Ok(next_index - 1)
+    }
+
+    /// Derives the key pair at `m/44'/148'/derivation_index'` from the stored master seed
+    pub async fn derive_account_keypair(
+        conn: &mut AsyncPgConnection,
+        derivation_index: i32,
+    ) -> Result<KeyPair, Error> {
+        let seed = load_master_seed(conn).await?;
+        derive_keypair(&seed, u32::try_from(derivation_index)?)
+    }
+
+    /// Hands an existing random-key account over to a derived key
+    ///
+    /// The account is given a derivation index first, then the derived key replaces the
+    /// master key on chain and finally the stored random key is deleted. Each step is
+    /// skipped when already done, so an interrupted migration can simply be run again.
+    ///
+    /// # Arguments
+    /// * `account_id` - A string slice containing the UUID of the account to migrate
+    ///
+    /// # Errors
+    /// Returns an error if the account is not active on chain or the hand-over fails
+    pub async fn migrate_account_to_derived_key(account_id: &str) -> Result<(), Error> {
+        let account_uuid = Uuid::parse_str(account_id)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let account = accounts::table
+            .find(account_uuid)
+            .first::<Account>(&mut db_connection)
+            .await?;
+
+        let stored_keys = encrypted_keys::table
+            .filter(encrypted_keys::account_id.eq(account_uuid))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+
+        if stored_keys == 0 {
+            return match account.derivation_index {
+                Some(_) => Ok(()),
+                None => Err(anyhow::anyhow!(
+                    "No key is stored for account {}",
+                    account_id
+                )),
+            };
+        }
+
+        if account.status != "active" {
+            return Err(anyhow::anyhow!(
+                "Account {} must be active on chain to be migrated",
+                account_id
+            ));
+        }
+
+        // Record the index before touching the chain so a retry derives the same key
+        let derivation_index = match account.derivation_index {
+            Some(derivation_index) => derivation_index,
+            None => {
+                db_connection
+                    .transaction::<_, Error, _>(|conn| {
+                        async move {
+                            let derivation_index = allocate_derivation_index(conn).await?;
+
+                            diesel::update(accounts::table.find(account_uuid))
+                                .set(accounts::derivation_index.eq(Some(derivation_index)))
+                                .execute(conn)
+                                .await?;
+
+                            Ok(derivation_index)
+                        }
+                        .scope_boxed()
+                    })
+                    .await?
+            }
+        };
+
+        let derived_keypair = derive_account_keypair(&mut db_connection, derivation_index).await?;
+
+        let stellar_chain = common::get_stellar_chain()?;
+
+        let account_key = PublicKey::from_account_id(&account.stellar_address)?;
+        if stellar_chain.signer_weight(&account_key, derived_keypair.public_key())? == 0 {
+            let response = stellar_chain
+                .replace_master_key(
+                    &get_signer()?,
+                    account_id,
+                    derived_keypair.public_key().clone(),
+                )
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "Handing account {} over to its derived key failed: {}",
+                    account_id,
+                    response.text().await?
+                ));
+            }
+        }
+
+        // The random key can no longer sign for the account
+        diesel::delete(encrypted_keys::table.filter(encrypted_keys::account_id.eq(account_uuid)))
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Migrates active accounts that still sign with a stored random key
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of accounts to migrate in this run
+    ///
+    /// # Returns
+    /// * `Result<MigrationSummary, Error>` - Counts of migrated and failed accounts
+    pub async fn migrate_random_key_accounts(limit: i64) -> Result<MigrationSummary, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let account_ids = accounts::table
+            .inner_join(encrypted_keys::table.on(encrypted_keys::account_id.eq(accounts::id)))
+            .filter(accounts::status.eq("active"))
+            .select(accounts::id)
+            .order(accounts::id.asc())
+            .limit(limit)
+            .load::<Uuid>(&mut db_connection)
+            .await?;
+
+        let mut summary = MigrationSummary {
+            migrated: 0,
+            failed: 0,
+        };
+
+        for account_id in account_ids {
+            match migrate_account_to_derived_key(&account_id.to_string()).await {
+                std::result::Result::Ok(()) => summary.migrated += 1,
+                Err(error) => {
+                    eprintln!(
+                        "Failed to migrate account {}: {}",
+                        account_id,
+                        redact_secrets(&error.to_string())
+                    );
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
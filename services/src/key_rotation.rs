@@ -0,0 +1,282 @@
+#![allow(clippy::module_inception)]
+
+/// Key rotation module that re-encrypts every stored secret under the newest
+/// key-encryption key and guards the retirement of old key versions.
+pub mod key_rotation {
+    use anyhow::{Error, Ok};
+    use diesel::dsl::count_star;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::common::{versions_decrypted_with, KeyRing};
+    use helpers::secret::redact_secrets;
+    use models::common::establish_connection;
+    use models::models::{HdMasterSeed, KeyRotationJob, NewKeyRotationJob};
+    use models::schema::{encrypted_keys, hd_master_seeds, key_rotation_jobs};
+    use uuid::Uuid;
+
+    use crate::customer::customer::{count_key_version, reencrypt_customers};
+    use crate::hd_wallet::hd_wallet::MASTER_SEED_AAD;
+
+    /// Number of keys re-encrypted per database transaction when no size is given.
+    pub const DEFAULT_BATCH_SIZE: i64 = 100;
+
+    /// Re-encrypts all stored keys, the HD master seed and the customer records that are
+    /// not yet under the newest key version.
+    ///
+    /// Progress is persisted in `key_rotation_jobs` after every batch, so an interrupted
+    /// run picks up from the last processed key when started again. Rows that cannot be
+    /// decrypted are counted as failures and skipped, leaving them on their old version.
+    ///
+    /// # Arguments
+    /// * `batch_size` - Number of keys to re-encrypt per database transaction
+    ///
+    /// # Returns
+    /// * `Result<KeyRotationJob, Error>` - The finished job with its final counts
+    pub async fn rotate_encrypted_keys(batch_size: i64) -> Result<KeyRotationJob, Error> {
+        let key_ring = KeyRing::from_env()?;
+        let target_version = key_ring.current_version();
+
+        let mut db_connection = establish_connection().await?;
+
+        // Resume a running job for this key version, or start a new one
+        let existing_job = key_rotation_jobs::table
+            .filter(key_rotation_jobs::target_key_version.eq(target_version))
+            .filter(key_rotation_jobs::status.eq("running"))
+            .first::<KeyRotationJob>(&mut db_connection)
+            .await
+            .optional()?;
+
+        let mut job = match existing_job {
+            Some(job) => job,
+            None => {
+                diesel::insert_into(key_rotation_jobs::table)
+                    .values(&NewKeyRotationJob {
+                        id: Uuid::new_v4(),
+                        target_key_version: target_version,
+                        status: "running",
+                    })
+                    .returning(key_rotation_jobs::all_columns)
+                    .get_result::<KeyRotationJob>(&mut db_connection)
+                    .await?
+            }
+        };
+
+        loop {
+            let mut query = encrypted_keys::table
+                .filter(encrypted_keys::key_version.ne(target_version))
+                .select((
+                    encrypted_keys::id,
+                    encrypted_keys::account_id,
+                    encrypted_keys::encrypted_key,
+                    encrypted_keys::key_version,
+                ))
+                .order(encrypted_keys::id.asc())
+                .limit(batch_size)
+                .into_boxed();
+
+            if let Some(last_processed_key_id) = job.last_processed_key_id {
+                query = query.filter(encrypted_keys::id.gt(last_processed_key_id));
+            }
+
+            let batch = query
+                .load::<(Uuid, Uuid, Vec<u8>, i32)>(&mut db_connection)
+                .await?;
+
+            let Some((last_key_id, ..)) = batch.last() else {
+                break;
+            };
+            let last_key_id = *last_key_id;
+
+            let mut reencrypted = Vec::with_capacity(batch.len());
+            let mut failed = 0i64;
+
+            for (key_id, account_id, encrypted_key, key_version) in &batch {
+                match key_ring.reencrypt(encrypted_key, account_id.as_bytes(), *key_version) {
+                    std::result::Result::Ok((new_encrypted_key, new_version)) => {
+                        reencrypted.push((*key_id, new_encrypted_key, new_version))
+                    }
+                    Err(error) => {
+                        eprintln!(
+                            "Failed to re-encrypt key {}: {}",
+                            key_id,
+                            redact_secrets(&error.to_string())
+                        );
+                        failed += 1;
+                    }
+                }
+            }
+
+            let processed = reencrypted.len() as i64;
+            let job_id = job.id;
+
+            // Persist the batch and the job cursor together so a crash never loses progress
+            job = db_connection
+                .transaction::<_, Error, _>(|conn| {
+                    async move {
+                        for (key_id, new_encrypted_key, new_version) in reencrypted {
+                            diesel::update(encrypted_keys::table.find(key_id))
+                                .set((
+                                    encrypted_keys::encrypted_key.eq(new_encrypted_key),
+                                    encrypted_keys::key_version.eq(new_version),
+                                ))
+                                .execute(conn)
+                                .await?;
+                        }
+
+                        let job = diesel::update(key_rotation_jobs::table.find(job_id))
+                            .set((
+                                key_rotation_jobs::last_processed_key_id.eq(Some(last_key_id)),
+                                key_rotation_jobs::processed_count
+                                    .eq(key_rotation_jobs::processed_count + processed),
+                                key_rotation_jobs::failed_count
+                                    .eq(key_rotation_jobs::failed_count + failed),
+                                key_rotation_jobs::updated_at
+                                    .eq(Some(chrono::Utc::now().naive_utc())),
+                            ))
+                            .returning(key_rotation_jobs::all_columns)
+                            .get_result::<KeyRotationJob>(conn)
+                            .await?;
+
+                        Ok(job)
+                    }
+                    .scope_boxed()
+                })
+                .await?;
+
+            println!(
+                "Key rotation {}: {} re-encrypted, {} failed",
+                job.id, job.processed_count, job.failed_count
+            );
+        }
+
+        // The master seed is encrypted under the same key ring as the stored keys
+        let master_seed = hd_master_seeds::table
+            .first::<HdMasterSeed>(&mut db_connection)
+            .await
+            .optional()?;
+
+        if let Some(master_seed) = master_seed.filter(|seed| seed.key_version != target_version) {
+            let (encrypted_seed, key_version) = key_ring.reencrypt(
+                &master_seed.encrypted_seed,
+                MASTER_SEED_AAD,
+                master_seed.key_version,
+            )?;
+
+            diesel::update(hd_master_seeds::table.find(master_seed.id))
+                .set((
+                    hd_master_seeds::encrypted_seed.eq(encrypted_seed),
+                    hd_master_seeds::key_version.eq(key_version),
+                ))
+                .execute(&mut db_connection)
+                .await?;
+        }
+
+        // So are the fields and documents of our customers
+        let (customers_processed, customers_failed) = reencrypt_customers(&key_ring).await?;
+
+        let final_status = if job.failed_count + customers_failed > 0 {
+            "failed"
+        } else {
+            "completed"
+        };
+
+        let job = diesel::update(key_rotation_jobs::table.find(job.id))
+            .set((
+                key_rotation_jobs::status.eq(final_status),
+                key_rotation_jobs::processed_count
+                    .eq(key_rotation_jobs::processed_count + customers_processed),
+                key_rotation_jobs::failed_count
+                    .eq(key_rotation_jobs::failed_count + customers_failed),
+                key_rotation_jobs::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+                key_rotation_jobs::completed_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(key_rotation_jobs::all_columns)
+            .get_result::<KeyRotationJob>(&mut db_connection)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Counts stored keys per key version.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(i32, i64)>, Error>` - Pairs of key version and number of rows using it
+    pub async fn get_key_version_usage() -> Result<Vec<(i32, i64)>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let usage = encrypted_keys::table
+            .group_by(encrypted_keys::key_version)
+            .select((encrypted_keys::key_version, count_star()))
+            .order(encrypted_keys::key_version.asc())
+            .load::<(i32, i64)>(&mut db_connection)
+            .await?;
+
+        Ok(usage)
+    }
+
+    /// Checks that a key version can be removed from the key ring.
+    ///
+    /// # Arguments
+    /// * `key_version` - The key version to retire
+    ///
+    /// # Errors
+    /// Returns an error if it is the current key version or any stored key or customer
+    /// record still needs it, including legacy rows decrypted with it
+    pub async fn retire_key_version(key_version: i32) -> Result<(), Error> {
+        let key_ring = KeyRing::from_env()?;
+
+        if key_version == key_ring.current_version() {
+            return Err(anyhow::anyhow!(
+                "Key version {} is the current key and cannot be retired",
+                key_version
+            ));
+        }
+
+        let mut db_connection = establish_connection().await?;
+
+        // Legacy rows are decrypted with version 1, so they hold up its retirement too
+        let versions = versions_decrypted_with(key_version);
+
+        let remaining = encrypted_keys::table
+            .filter(encrypted_keys::key_version.eq_any(&versions))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+
+        let remaining_master_seeds = hd_master_seeds::table
+            .filter(hd_master_seeds::key_version.eq_any(&versions))
+            .count()
+            .get_result::<i64>(&mut db_connection)
+            .await?;
+
+        if remaining_master_seeds > 0 {
+            return Err(anyhow::anyhow!(
+                "Key version {} is still used by the HD master seed",
+                key_version
+            ));
+        }
+
+        if remaining > 0 {
+            return Err(anyhow::anyhow!(
+                "Key version {} is still used by {} stored keys",
+                key_version,
+                remaining
+            ));
+        }
+
+        let remaining_customer_records = count_key_version(&versions).await?;
+
+        if remaining_customer_records > 0 {
+            return Err(anyhow::anyhow!(
+                "Key version {} is still used by {} customer records",
+                key_version,
+                remaining_customer_records
+            ));
+        }
+
+        Ok(())
+    }
+}
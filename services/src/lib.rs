@@ -1,4 +1,24 @@
 pub mod common;
 pub mod account;
+pub mod anchor;
+pub mod asset;
+pub mod clawback;
+pub mod customer;
+pub mod envelope;
+pub mod escrow;
+pub mod federation;
+pub mod hd_wallet;
+pub mod key_rotation;
+pub mod multisig;
+pub mod outbox;
 pub mod payment;
-pub mod types;
\ No newline at end of file
+pub mod payment_request;
+pub mod recovery;
+pub mod remittance;
+pub mod resolver;
+pub mod scheduler;
+pub mod signer;
+pub mod stellar_toml;
+pub mod trustline;
+pub mod types;
+pub mod web_auth;
\ No newline at end of file
@@ -0,0 +1,542 @@
+#![allow(clippy::module_inception)]
+
+/// Multisig module that applies declarative signer policies to accounts and collects
+/// co-signer signatures for transactions that need more than one signer.
+pub mod multisig {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::multisig::{
+        add_decorated_signature, add_signature, collected_weight, decode_transaction,
+        encode_transaction, merge_signatures, required_threshold_level, transaction_expiry,
+        ThresholdLevel,
+    };
+    use helpers::signer::{Signer, DISTRIBUTOR_ACCOUNT_ID, ISSUER_ACCOUNT_ID};
+    use helpers::stellar_chain::{AccountAuthorization, AccountOptions, StellarChain};
+    use models::common::establish_connection;
+    use models::models::{NewPendingTransaction, PendingTransaction};
+    use models::schema::pending_transactions;
+    use stellar_base::{Network, PublicKey, Transaction};
+    use uuid::Uuid;
+
+    use crate::common::common;
+    use crate::envelope::envelope::{ensure_not_expired, submit_pending_transaction};
+    use crate::signer::signer::get_signer;
+
+    /// Highest weight or threshold Stellar accepts
+    const MAX_WEIGHT: u32 = 255;
+
+    /// Who asks the service to sign with a key it holds
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Caller {
+        /// An operator authenticated with the admin key
+        Admin,
+        /// The holder of the account the key belongs to
+        AccountHolder,
+    }
+
+    /// A signer an account should have, with its weight
+    pub struct PolicySigner {
+        pub public_key: String,
+        pub weight: u32,
+    }
+
+    /// The complete signer configuration an account should end up with. Signers on the
+    /// account that are not listed are removed.
+    pub struct SignerPolicy {
+        pub master_weight: u32,
+        pub low_threshold: u32,
+        pub medium_threshold: u32,
+        pub high_threshold: u32,
+        pub home_domain: Option<String>,
+        pub signers: Vec<PolicySigner>,
+    }
+
+    /// Checks that a caller may have the service sign for an account
+    ///
+    /// The issuer and distributor keys control the assets we issue and the funds we hold,
+    /// so only an admin may have them sign.
+    fn check_may_sign_for(caller: Caller, account_id: &str) -> Result<(), Error> {
+        if caller != Caller::Admin
+            && [ISSUER_ACCOUNT_ID, DISTRIBUTOR_ACCOUNT_ID].contains(&account_id)
+        {
+            return Err(anyhow::anyhow!(
+                "Only an admin may have the {} account sign",
+                account_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Works out the SetOptions changes that bring an account in line with a policy
+    ///
+    /// # Arguments
+    /// * `account` - The public key of the account
+    /// * `current` - The signers and thresholds the account has now
+    /// * `policy` - The signers and thresholds the account should have
+    ///
+    /// # Errors
+    /// Returns an error if a weight is out of range, the master key is listed as a
+    /// signer, or the policy would leave the account unable to meet its high threshold
+    pub fn policy_options(
+        account: &PublicKey,
+        current: &AccountAuthorization,
+        policy: &SignerPolicy,
+    ) -> Result<AccountOptions, Error> {
+        let weights = [
+            policy.master_weight,
+            policy.low_threshold,
+            policy.medium_threshold,
+            policy.high_threshold,
+        ];
+        if weights
+            .iter()
+            .chain(policy.signers.iter().map(|signer| &signer.weight))
+            .any(|weight| *weight > MAX_WEIGHT)
+        {
+            return Err(anyhow::anyhow!(
+                "Weights and thresholds must be at most {}",
+                MAX_WEIGHT
+            ));
+        }
+
+        let mut policy_signers = Vec::with_capacity(policy.signers.len());
+        for signer in &policy.signers {
+            let key = PublicKey::from_account_id(&signer.public_key)?;
+            if key == *account {
+                return Err(anyhow::anyhow!(
+                    "The master key is configured through master_weight"
+                ));
+            }
+            policy_signers.push((key, signer.weight));
+        }
+
+        let total_weight: u32 =
+            policy.master_weight + policy_signers.iter().map(|(_, weight)| weight).sum::<u32>();
+        if total_weight == 0 || total_weight < policy.high_threshold {
+            return Err(anyhow::anyhow!(
+                "Signer weights must add up to at least the high threshold"
+            ));
+        }
+
+        // Only send the signers that change, and remove the ones the policy leaves out
+        let mut signers = policy_signers
+            .iter()
+            .filter(|(key, weight)| current.weight_of(key) != *weight)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        signers.extend(
+            current
+                .signers
+                .iter()
+                .filter(|(key, _)| key != account)
+                .filter(|(key, _)| !policy_signers.iter().any(|(signer, _)| signer == key))
+                .map(|(key, _)| (key.clone(), 0)),
+        );
+
+        Ok(AccountOptions {
+            master_weight: Some(policy.master_weight),
+            low_threshold: Some(policy.low_threshold),
+            medium_threshold: Some(policy.medium_threshold),
+            high_threshold: Some(policy.high_threshold),
+            home_domain: policy.home_domain.clone(),
+            signers,
+            ..Default::default()
+        })
+    }
+
+    /// Applies a signer policy to an account
+    ///
+    /// The SetOptions transaction is stored as a pending transaction and signed with the
+    /// account key the service holds. It is submitted straight away when that signature
+    /// meets the account's high threshold, otherwise it waits for co-signers.
+    ///
+    /// # Arguments
+    /// * `caller` - Who applies the policy
+    /// * `account_id` - The id of the account to configure
+    /// * `policy` - The signers and thresholds the account should have
+    ///
+    /// # Returns
+    /// * `Result<PendingTransaction, Error>` - The pending transaction for the change
+    pub async fn apply_signer_policy(
+        caller: Caller,
+        account_id: &str,
+        policy: SignerPolicy,
+    ) -> Result<PendingTransaction, Error> {
+        check_may_sign_for(caller, account_id)?;
+
+        let stellar_chain = common::get_stellar_chain()?;
+        let signer = get_signer()?;
+
+        let account = signer.public_key(account_id).await?;
+        let authorization = stellar_chain.account_authorization(&account)?;
+
+        let options = policy_options(&account, &authorization, &policy)?;
+        let transaction = stellar_chain.build_set_options_transaction(&account, &options)?;
+
+        let pending_transaction = create_pending_transaction(
+            &stellar_chain,
+            &transaction,
+            "signer_policy",
+            Uuid::parse_str(account_id).ok(),
+            Some("Apply signer policy"),
+        )
+        .await?;
+
+        // The key the service holds may already have been removed by an earlier policy
+        let hash = transaction.hash(stellar_chain.network())?;
+        let signature = signer.sign_hash(account_id, &hash).await?;
+        if authorization
+            .signers
+            .iter()
+            .all(|(key, _)| !signature.signature().verify(key, &hash))
+        {
+            return Ok(pending_transaction);
+        }
+
+        collect_signatures(
+            &pending_transaction.id.to_string(),
+            |transaction, network, authorization| {
+                add_decorated_signature(transaction, network, authorization, signature)?;
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Stores a transaction so signatures can be collected for it
+    ///
+    /// # Arguments
+    /// * `stellar_chain` - The chain the transaction is for
+    /// * `transaction` - The transaction, with any signatures it already has
+    /// * `purpose` - What to finish locally once the transaction lands
+    /// * `reference_id` - The record the purpose applies to, e.g. the account to activate
+    /// * `description` - What the transaction does, for co-signers reviewing it
+    ///
+    /// # Returns
+    /// * `Result<PendingTransaction, Error>` - The stored pending transaction, expiring
+    ///   with the upper time bound of the transaction
+    pub async fn create_pending_transaction(
+        stellar_chain: &StellarChain,
+        transaction: &Transaction,
+        purpose: &str,
+        reference_id: Option<Uuid>,
+        description: Option<&str>,
+    ) -> Result<PendingTransaction, Error> {
+        let source_address = transaction.source_account().account_id();
+        let authorization =
+            stellar_chain.account_authorization(&PublicKey::from_account_id(&source_address)?)?;
+
+        let network = stellar_chain.network();
+        let threshold_level = required_threshold_level(transaction);
+
+        let mut db_connection = establish_connection().await?;
+
+        let pending_transaction = diesel::insert_into(pending_transactions::table)
+            .values(&NewPendingTransaction {
+                id: Uuid::new_v4(),
+                source_address: &source_address,
+                envelope_xdr: &encode_transaction(transaction)?,
+                transaction_hash: &hex::encode(transaction.hash(network)?),
+                threshold_level: threshold_level.as_str(),
+                required_weight: threshold_level.required_weight(&authorization) as i32,
+                collected_weight: collected_weight(transaction, network, &authorization)? as i32,
+                description,
+                status: "pending",
+                purpose,
+                reference_id,
+                expires_at: transaction_expiry(transaction).map(|expiry| expiry.naive_utc()),
+            })
+            .returning(pending_transactions::all_columns)
+            .get_result::<PendingTransaction>(&mut db_connection)
+            .await?;
+
+        Ok(pending_transaction)
+    }
+
+    /// Retrieves a pending transaction by its id
+    pub async fn get_pending_transaction(
+        pending_transaction_id: &str,
+    ) -> Result<PendingTransaction, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let pending_transaction = pending_transactions::table
+            .find(Uuid::parse_str(pending_transaction_id)?)
+            .first::<PendingTransaction>(&mut db_connection)
+            .await?;
+
+        Ok(pending_transaction)
+    }
+
+    /// Signs a pending transaction with a key the service holds, e.g. a service account
+    /// that is a co-signer of the source account
+    ///
+    /// # Arguments
+    /// * `caller` - Who asks for the signature
+    /// * `pending_transaction_id` - The id of the pending transaction
+    /// * `account_id` - The id of the account whose key signs
+    pub async fn sign_pending_transaction(
+        caller: Caller,
+        pending_transaction_id: &str,
+        account_id: &str,
+    ) -> Result<PendingTransaction, Error> {
+        check_may_sign_for(caller, account_id)?;
+
+        let pending_transaction = get_pending_transaction(pending_transaction_id).await?;
+        let stellar_chain = common::get_stellar_chain()?;
+
+        let hash =
+            decode_transaction(&pending_transaction.envelope_xdr)?.hash(stellar_chain.network())?;
+        let signature = get_signer()?.sign_hash(account_id, &hash).await?;
+
+        collect_signatures(
+            pending_transaction_id,
+            |transaction, network, authorization| {
+                add_decorated_signature(transaction, network, authorization, signature)?;
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Adds a co-signer's signature to a pending transaction
+    ///
+    /// # Arguments
+    /// * `pending_transaction_id` - The id of the pending transaction
+    /// * `public_key` - The public key of the co-signer
+    /// * `signature` - The hex encoded ed25519 signature of the transaction hash
+    pub async fn add_pending_signature(
+        pending_transaction_id: &str,
+        public_key: &str,
+        signature: &str,
+    ) -> Result<PendingTransaction, Error> {
+        let signer_key = PublicKey::from_account_id(public_key)?;
+        let signature = hex::decode(signature)?;
+
+        collect_signatures(
+            pending_transaction_id,
+            |transaction, network, authorization| {
+                add_signature(transaction, network, authorization, &signer_key, &signature)
+            },
+        )
+        .await
+    }
+
+    /// Adds the signatures of a co-signed copy of a pending transaction's envelope
+    ///
+    /// # Arguments
+    /// * `pending_transaction_id` - The id of the pending transaction
+    /// * `envelope_xdr` - The base64 envelope signed by one or more co-signers
+    pub async fn add_pending_envelope(
+        pending_transaction_id: &str,
+        envelope_xdr: &str,
+    ) -> Result<PendingTransaction, Error> {
+        let signed = decode_transaction(envelope_xdr)?;
+
+        collect_signatures(
+            pending_transaction_id,
+            |transaction, network, authorization| {
+                if merge_signatures(transaction, network, authorization, &signed)? == 0 {
+                    return Err(anyhow::anyhow!("Envelope has no new signatures"));
+                }
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Adds signatures to a pending transaction and submits it once the source account's
+    /// threshold is met
+    ///
+    /// The row is locked while signatures are added, so concurrent co-signers never
+    /// overwrite each other, and only the signer that completes the threshold submits.
+    async fn collect_signatures<F>(
+        pending_transaction_id: &str,
+        add_signatures: F,
+    ) -> Result<PendingTransaction, Error>
+    where
+        F: FnOnce(&mut Transaction, &Network, &AccountAuthorization) -> Result<(), Error> + Send,
+    {
+        let pending_transaction_uuid = Uuid::parse_str(pending_transaction_id)?;
+        let pending_transaction = get_pending_transaction(pending_transaction_id).await?;
+
+        let stellar_chain = common::get_stellar_chain()?;
+        let network = stellar_chain.network().clone();
+        let authorization = stellar_chain.account_authorization(&PublicKey::from_account_id(
+            &pending_transaction.source_address,
+        )?)?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let (pending_transaction, ready) = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let pending_transaction = pending_transactions::table
+                        .find(pending_transaction_uuid)
+                        .for_update()
+                        .first::<PendingTransaction>(conn)
+                        .await?;
+
+                    if pending_transaction.status != "pending" {
+                        return Err(anyhow::anyhow!(
+                            "Transaction is already {}",
+                            pending_transaction.status
+                        ));
+                    }
+
+                    ensure_not_expired(&pending_transaction)?;
+
+                    let mut transaction = decode_transaction(&pending_transaction.envelope_xdr)?;
+                    add_signatures(&mut transaction, &network, &authorization)?;
+
+                    let required_weight = pending_transaction
+                        .threshold_level
+                        .parse::<ThresholdLevel>()?
+                        .required_weight(&authorization);
+                    let collected_weight =
+                        collected_weight(&transaction, &network, &authorization)?;
+
+                    // A transaction always needs at least one signature, even at threshold 0
+                    let ready = collected_weight >= required_weight.max(1);
+
+                    let pending_transaction =
+                        diesel::update(pending_transactions::table.find(pending_transaction_uuid))
+                            .set((
+                                pending_transactions::envelope_xdr
+                                    .eq(encode_transaction(&transaction)?),
+                                pending_transactions::required_weight.eq(required_weight as i32),
+                                pending_transactions::collected_weight.eq(collected_weight as i32),
+                                pending_transactions::status.eq(if ready {
+                                    "submitted"
+                                } else {
+                                    "pending"
+                                }),
+                                pending_transactions::updated_at
+                                    .eq(Some(chrono::Utc::now().naive_utc())),
+                            ))
+                            .returning(pending_transactions::all_columns)
+                            .get_result::<PendingTransaction>(conn)
+                            .await?;
+
+                    Ok((pending_transaction, ready))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        if !ready {
+            return Ok(pending_transaction);
+        }
+
+        submit_pending_transaction(&stellar_chain, pending_transaction).await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use stellar_base::KeyPair;
+
+        fn policy(
+            master_weight: u32,
+            high_threshold: u32,
+            signers: &[(&KeyPair, u32)],
+        ) -> SignerPolicy {
+            SignerPolicy {
+                master_weight,
+                low_threshold: 1,
+                medium_threshold: 1,
+                high_threshold,
+                home_domain: None,
+                signers: signers
+                    .iter()
+                    .map(|(keypair, weight)| PolicySigner {
+                        public_key: keypair.public_key().account_id(),
+                        weight: *weight,
+                    })
+                    .collect(),
+            }
+        }
+
+        #[test]
+        fn test_only_an_admin_may_sign_for_service_accounts() {
+            for account_id in [ISSUER_ACCOUNT_ID, DISTRIBUTOR_ACCOUNT_ID] {
+                assert!(check_may_sign_for(Caller::Admin, account_id).is_ok());
+                assert!(check_may_sign_for(Caller::AccountHolder, account_id).is_err());
+            }
+
+            let account_id = Uuid::new_v4().to_string();
+            assert!(check_may_sign_for(Caller::AccountHolder, &account_id).is_ok());
+            assert!(check_may_sign_for(Caller::Admin, &account_id).is_ok());
+        }
+
+        #[test]
+        fn test_policy_options_adds_changes_and_removes_signers() {
+            let master = KeyPair::random().unwrap();
+            let kept = KeyPair::random().unwrap();
+            let reweighted = KeyPair::random().unwrap();
+            let removed = KeyPair::random().unwrap();
+            let added = KeyPair::random().unwrap();
+
+            let current = AccountAuthorization {
+                signers: vec![
+                    (master.public_key().clone(), 1),
+                    (kept.public_key().clone(), 1),
+                    (reweighted.public_key().clone(), 1),
+                    (removed.public_key().clone(), 1),
+                ],
+                low_threshold: 0,
+                medium_threshold: 0,
+                high_threshold: 0,
+            };
+
+            let options = policy_options(
+                master.public_key(),
+                &current,
+                &policy(1, 3, &[(&kept, 1), (&reweighted, 2), (&added, 1)]),
+            )
+            .unwrap();
+
+            assert_eq!(options.master_weight, Some(1));
+            assert_eq!(options.high_threshold, Some(3));
+            assert_eq!(
+                options.signers,
+                vec![
+                    (reweighted.public_key().clone(), 2),
+                    (added.public_key().clone(), 1),
+                    (removed.public_key().clone(), 0),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_policy_options_rejects_lockout_and_master_signer() {
+            let master = KeyPair::random().unwrap();
+            let cosigner = KeyPair::random().unwrap();
+            let current = AccountAuthorization {
+                signers: vec![(master.public_key().clone(), 1)],
+                low_threshold: 0,
+                medium_threshold: 0,
+                high_threshold: 0,
+            };
+
+            assert!(policy_options(
+                master.public_key(),
+                &current,
+                &policy(1, 3, &[(&cosigner, 1)])
+            )
+            .is_err());
+            assert!(policy_options(master.public_key(), &current, &policy(0, 0, &[])).is_err());
+            assert!(policy_options(
+                master.public_key(),
+                &current,
+                &policy(1, 1, &[(&master, 1)])
+            )
+            .is_err());
+            assert!(policy_options(master.public_key(), &current, &policy(256, 1, &[])).is_err());
+        }
+    }
+}
@@ -0,0 +1,744 @@
+#![allow(clippy::module_inception)]
+
+/// Outbox module that keeps the database and the ledger consistent.
+///
+/// A chain operation is written to `chain_outbox` as a signed envelope in the same
+/// database transaction as the business state it belongs to, so either both exist or
+/// neither does. The envelope is then submitted right away by the caller, and later by
+/// the outbox worker for anything a crash or an unknown outcome left behind. Every
+/// retry sends the identical envelope after looking it up by hash, so processing an
+/// entry more than once can never apply it twice.
+pub mod outbox {
+    use anyhow::{Error, Ok};
+    use bigdecimal::BigDecimal;
+    use diesel::BoolExpressionMethods;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::raw_transaction::{IssuerFlags, SignedTransaction};
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::StellarChain;
+    use helpers::submitter::SubmissionOutcome;
+    use models::common::establish_connection;
+    use models::models::{ChainOutboxEntry, NewChainOutboxEntry};
+    use models::schema::{
+        accounts, chain_outbox, clawbacks, escrows, payment_requests, tokens, transactions,
+        trustlines,
+    };
+    use uuid::Uuid;
+
+    use crate::common::common;
+    use crate::escrow::escrow::landed_status;
+
+    /// How long a claimed entry is reserved for the process submitting it. Long enough
+    /// for the submitter to run out of attempts, so two processes never race on it.
+    const OUTBOX_LEASE_SECONDS: i64 = 300;
+
+    /// Outcome of an outbox worker run.
+    pub struct OutboxSummary {
+        /// Entries that landed on chain and had their effects applied
+        pub completed: usize,
+        /// Entries rejected or failed on chain
+        pub failed: usize,
+        /// Entries whose outcome is still unknown and are tried again on the next run
+        pub retrying: usize,
+    }
+
+    /// Writes a signed transaction to the outbox
+    ///
+    /// Call it inside the database transaction that writes the business state, after
+    /// recording the transaction with [`common::record_pending_transaction`].
+    ///
+    /// # Arguments
+    /// * `db_connection` - The connection of the surrounding database transaction
+    /// * `stellar_chain` - The chain the transaction is for
+    /// * `transaction` - The signed transaction
+    /// * `purpose` - What the transaction does, `activate_account`, `payment`,
+    ///   `create_escrow`, `release_escrow`, `reclaim_escrow`, `register_asset`,
+    ///   `mint_asset`, `burn_asset`, `authorize_trustline`, `freeze_trustline`,
+    ///   `revoke_trustline`, `clawback`, `clawback_claimable_balance`,
+    ///   `set_home_domain` or `payment_request`
+    /// * `reference_id` - The record the effects apply to, e.g. the account to activate
+    /// * `transaction_id` - The id of the recorded transaction
+    ///
+    /// # Returns
+    /// * `Result<ChainOutboxEntry, Error>` - The outbox entry waiting to be submitted
+    pub async fn enqueue(
+        db_connection: &mut AsyncPgConnection,
+        stellar_chain: &StellarChain,
+        transaction: &impl SignedTransaction,
+        purpose: &str,
+        reference_id: Option<Uuid>,
+        transaction_id: Uuid,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let envelope_xdr = transaction.envelope_xdr()?;
+        let transaction_hash = hex::encode(transaction.transaction_hash(stellar_chain.network())?);
+
+        let entry = diesel::insert_into(chain_outbox::table)
+            .values(&NewChainOutboxEntry {
+                id: Uuid::new_v4(),
+                transaction_id,
+                purpose,
+                reference_id,
+                envelope_xdr: &envelope_xdr,
+                transaction_hash: &transaction_hash,
+                status: "pending",
+            })
+            .returning(chain_outbox::all_columns)
+            .get_result::<ChainOutboxEntry>(db_connection)
+            .await?;
+
+        Ok(entry)
+    }
+
+    /// Returns an error unless an outbox entry completed
+    ///
+    /// # Arguments
+    /// * `entry` - The processed outbox entry
+    pub fn ensure_completed(entry: &ChainOutboxEntry) -> Result<(), Error> {
+        let last_error = entry.last_error.as_deref().unwrap_or_default();
+        match entry.status.as_str() {
+            "completed" => Ok(()),
+            "failed" => Err(anyhow::anyhow!(
+                "Transaction {} failed: {}",
+                entry.transaction_id,
+                last_error
+            )),
+            _ => Err(anyhow::anyhow!(
+                "Outcome of transaction {} is not known yet; the outbox worker will retry it: {}",
+                entry.transaction_id,
+                last_error
+            )),
+        }
+    }
+
+    /// Submits a single outbox entry, unless another process holds it
+    ///
+    /// # Arguments
+    /// * `stellar_chain` - The chain to submit to
+    /// * `entry_id` - The id of the outbox entry
+    ///
+    /// # Returns
+    /// * `Result<ChainOutboxEntry, Error>` - The entry with its new status
+    pub async fn process_entry(
+        stellar_chain: &StellarChain,
+        entry_id: Uuid,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let mut db_connection = establish_connection().await?;
+        let now = chrono::Utc::now().naive_utc();
+
+        let entry = diesel::update(chain_outbox::table.find(entry_id))
+            .filter(chain_outbox::status.eq("pending"))
+            .filter(
+                chain_outbox::locked_until
+                    .is_null()
+                    .or(chain_outbox::locked_until.lt(now)),
+            )
+            .set((
+                chain_outbox::locked_until.eq(Some(lease_expiry())),
+                chain_outbox::attempts.eq(chain_outbox::attempts + 1),
+            ))
+            .returning(chain_outbox::all_columns)
+            .get_results::<ChainOutboxEntry>(&mut db_connection)
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                anyhow::anyhow!("Outbox entry {} is settled or being processed", entry_id)
+            })?;
+
+        process_claimed_entry(stellar_chain, entry).await
+    }
+
+    /// Submits the oldest pending outbox entries that no other process holds
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of entries to process in this run
+    ///
+    /// # Returns
+    /// * `Result<OutboxSummary, Error>` - Counts of settled and retried entries
+    pub async fn process_pending_entries(limit: i64) -> Result<OutboxSummary, Error> {
+        let stellar_chain = common::get_stellar_chain()?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let entries = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+
+                    // Skip entries other workers are claiming at the same time
+                    let entry_ids = chain_outbox::table
+                        .filter(chain_outbox::status.eq("pending"))
+                        .filter(
+                            chain_outbox::locked_until
+                                .is_null()
+                                .or(chain_outbox::locked_until.lt(now)),
+                        )
+                        .order(chain_outbox::created_at.asc())
+                        .limit(limit)
+                        .select(chain_outbox::id)
+                        .for_update()
+                        .skip_locked()
+                        .load::<Uuid>(conn)
+                        .await?;
+
+                    let entries = diesel::update(chain_outbox::table)
+                        .filter(chain_outbox::id.eq_any(entry_ids))
+                        .set((
+                            chain_outbox::locked_until.eq(Some(lease_expiry())),
+                            chain_outbox::attempts.eq(chain_outbox::attempts + 1),
+                        ))
+                        .returning(chain_outbox::all_columns)
+                        .get_results::<ChainOutboxEntry>(conn)
+                        .await?;
+
+                    Ok(entries)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let mut summary = OutboxSummary {
+            completed: 0,
+            failed: 0,
+            retrying: 0,
+        };
+
+        for entry in entries {
+            let entry_id = entry.id;
+            match process_claimed_entry(&stellar_chain, entry).await {
+                std::result::Result::Ok(entry) => match entry.status.as_str() {
+                    "completed" => summary.completed += 1,
+                    "failed" => summary.failed += 1,
+                    _ => summary.retrying += 1,
+                },
+                Err(error) => {
+                    // The lease runs out and the next run picks the entry up again
+                    eprintln!(
+                        "Failed to process outbox entry {}: {}",
+                        entry_id,
+                        redact_secrets(&error.to_string())
+                    );
+                    summary.retrying += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Returns the end of the lease for an entry claimed now
+    fn lease_expiry() -> chrono::NaiveDateTime {
+        (chrono::Utc::now() + chrono::Duration::seconds(OUTBOX_LEASE_SECONDS)).naive_utc()
+    }
+
+    /// Submits a claimed entry and records the outcome
+    async fn process_claimed_entry(
+        stellar_chain: &StellarChain,
+        entry: ChainOutboxEntry,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let submitter = stellar_chain.submitter();
+
+        // An earlier attempt may have landed without its outcome being recorded
+        let landed = if entry.attempts > 1 {
+            submitter.find(&entry.transaction_hash).await.ok().flatten()
+        } else {
+            None
+        };
+
+        let outcome = match landed {
+            Some(outcome) => outcome,
+            None => {
+                submitter
+                    .submit(&entry.envelope_xdr, &entry.transaction_hash)
+                    .await
+            }
+        };
+
+        finalize_entry(entry, outcome).await
+    }
+
+    /// Records the outcome of an entry, together with the transaction status and the
+    /// effects of a landed transaction, in one database transaction
+    ///
+    /// Nothing is recorded once the entry is no longer pending under the lease it was
+    /// claimed with: after the lease ran out another process may have claimed and
+    /// settled it, and its effects must only be applied once.
+    async fn finalize_entry(
+        entry: ChainOutboxEntry,
+        outcome: SubmissionOutcome,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+                    let (status, last_error) = outcome_status(&outcome);
+
+                    // A pending entry is released so the next worker run retries it
+                    let finalized = diesel::update(chain_outbox::table.find(entry.id))
+                        .filter(chain_outbox::status.eq("pending"))
+                        .filter(chain_outbox::locked_until.eq(entry.locked_until))
+                        .set((
+                            chain_outbox::status.eq(status),
+                            chain_outbox::last_error.eq(last_error),
+                            chain_outbox::locked_until.eq(None::<chrono::NaiveDateTime>),
+                            chain_outbox::processed_at.eq((status != "pending").then_some(now)),
+                        ))
+                        .returning(chain_outbox::all_columns)
+                        .get_results::<ChainOutboxEntry>(conn)
+                        .await?
+                        .pop();
+
+                    // Another process claimed the entry since, and records its outcome
+                    let Some(finalized) = finalized else {
+                        let current = chain_outbox::table
+                            .find(entry.id)
+                            .first::<ChainOutboxEntry>(conn)
+                            .await?;
+                        return Ok(current);
+                    };
+
+                    match &outcome {
+                        SubmissionOutcome::Completed => {
+                            settle_entry_transactions(conn, &entry, "completed", None).await?;
+                            apply_effects(conn, &entry).await?;
+                        }
+                        SubmissionOutcome::Failed { .. } => {
+                            settle_entry_transactions(conn, &entry, "failed", Some(&outcome))
+                                .await?;
+                            apply_failure(conn, &entry).await?;
+                        }
+                        SubmissionOutcome::Unknown { .. } => {}
+                    }
+
+                    Ok(finalized)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Returns the status an entry moves to after a submission, and the error kept with it
+    fn outcome_status(outcome: &SubmissionOutcome) -> (&'static str, Option<String>) {
+        match outcome {
+            SubmissionOutcome::Completed => ("completed", None),
+            SubmissionOutcome::Failed { error_code, .. } => ("failed", Some(error_code.clone())),
+            SubmissionOutcome::Unknown { error_message } => {
+                ("pending", Some(error_message.clone()))
+            }
+        }
+    }
+
+    /// Settles every transaction row recorded for the entry's envelope, one per
+    /// operation, giving each failed row the result code of its own operation
+    async fn settle_entry_transactions(
+        conn: &mut AsyncPgConnection,
+        entry: &ChainOutboxEntry,
+        status: &str,
+        failure: Option<&SubmissionOutcome>,
+    ) -> Result<(), Error> {
+        let rows = transactions::table
+            .filter(transactions::transaction_hash.eq(&entry.transaction_hash))
+            .select((transactions::id, transactions::operation_index))
+            .load::<(Uuid, i32)>(conn)
+            .await?;
+
+        for (transaction_id, operation_index) in rows {
+            let error = match failure {
+                Some(SubmissionOutcome::Failed {
+                    error_code,
+                    operation_codes,
+                    error_message,
+                }) => {
+                    // Operations that would have succeeded failed with the transaction
+                    let error_code = operation_codes
+                        .get(operation_index as usize)
+                        .filter(|operation_code| operation_code.as_str() != "op_success")
+                        .unwrap_or(error_code);
+                    Some((error_code.as_str(), error_message.as_str()))
+                }
+                _ => None,
+            };
+
+            common::settle_transaction(conn, transaction_id, status, error).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the local effects of an outbox entry that landed on chain
+    async fn apply_effects(
+        conn: &mut AsyncPgConnection,
+        entry: &ChainOutboxEntry,
+    ) -> Result<(), Error> {
+        let Some(reference_id) = entry.reference_id else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        match entry.purpose.as_str() {
+            "activate_account" => {
+                diesel::update(accounts::table.find(reference_id))
+                    .set((
+                        accounts::status.eq("active"),
+                        accounts::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            purpose @ ("create_escrow" | "release_escrow" | "reclaim_escrow") => {
+                if let Some((from_status, to_status)) = landed_status(purpose) {
+                    diesel::update(escrows::table.find(reference_id))
+                        .filter(escrows::status.eq(from_status))
+                        .set((
+                            escrows::status.eq(to_status),
+                            escrows::updated_at.eq(Some(now)),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+            }
+            "register_asset" => {
+                let token = diesel::update(tokens::table.find(reference_id))
+                    .filter(tokens::status.eq("pending"))
+                    .set((
+                        tokens::status.eq("active"),
+                        tokens::updated_at.eq(Some(now)),
+                    ))
+                    .returning((
+                        tokens::asset_code,
+                        tokens::distributor_account_id,
+                        tokens::issuer_flags,
+                    ))
+                    .get_result::<(String, Option<Uuid>, i32)>(conn)
+                    .await
+                    .optional()?;
+
+                // The distributor was authorized in the same transaction if it had to be
+                if let Some((asset_code, Some(distributor_account_id), issuer_flags)) = token {
+                    let status = if IssuerFlags::from_bits(issuer_flags as u32).auth_required {
+                        "authorized"
+                    } else {
+                        "active"
+                    };
+
+                    diesel::update(trustlines::table)
+                        .filter(trustlines::account_id.eq(distributor_account_id))
+                        .filter(trustlines::asset_code.eq(asset_code))
+                        .filter(trustlines::status.eq("pending"))
+                        .set((
+                            trustlines::status.eq(status),
+                            trustlines::updated_at.eq(Some(now)),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+            }
+            "authorize_trustline" | "freeze_trustline" | "revoke_trustline" => {
+                let status = match entry.purpose.as_str() {
+                    "authorize_trustline" => "authorized",
+                    "freeze_trustline" => "frozen",
+                    _ => "revoked",
+                };
+
+                diesel::update(trustlines::table.find(reference_id))
+                    .set((
+                        trustlines::status.eq(status),
+                        trustlines::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            "mint_asset" | "burn_asset" => {
+                let amount = transactions::table
+                    .find(entry.transaction_id)
+                    .select(transactions::amount)
+                    .first::<BigDecimal>(conn)
+                    .await?;
+
+                if let Some(change) = supply_change(&entry.purpose, amount) {
+                    change_total_supply(conn, reference_id, change).await?;
+                }
+            }
+            "clawback" | "clawback_claimable_balance" => {
+                let clawback = diesel::update(clawbacks::table.find(reference_id))
+                    .filter(clawbacks::status.eq("pending"))
+                    .set((
+                        clawbacks::status.eq("completed"),
+                        clawbacks::updated_at.eq(Some(now)),
+                    ))
+                    .returning((clawbacks::token_id, clawbacks::amount, clawbacks::escrow_id))
+                    .get_result::<(Uuid, BigDecimal, Option<Uuid>)>(conn)
+                    .await
+                    .optional()?;
+
+                // Clawed back units are burned
+                if let Some((token_id, amount, escrow_id)) = clawback {
+                    if let Some(change) = supply_change(&entry.purpose, amount) {
+                        change_total_supply(conn, token_id, change).await?;
+                    }
+
+                    if let Some(escrow_id) = escrow_id {
+                        diesel::update(escrows::table.find(escrow_id))
+                            .filter(escrows::status.eq("held"))
+                            .set((
+                                escrows::status.eq("clawed_back"),
+                                escrows::updated_at.eq(Some(now)),
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+                }
+            }
+            "payment_request" => {
+                diesel::update(payment_requests::table.find(reference_id))
+                    .filter(payment_requests::status.eq("submitted"))
+                    .set((
+                        payment_requests::status.eq("paid"),
+                        payment_requests::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Applies the local effects of an outbox entry that failed on chain
+    async fn apply_failure(
+        conn: &mut AsyncPgConnection,
+        entry: &ChainOutboxEntry,
+    ) -> Result<(), Error> {
+        let Some(reference_id) = entry.reference_id else {
+            return Ok(());
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        if matches!(
+            entry.purpose.as_str(),
+            "authorize_trustline" | "freeze_trustline" | "revoke_trustline"
+        ) {
+            diesel::delete(trustlines::table.find(reference_id))
+                .filter(trustlines::status.eq("pending"))
+                .execute(conn)
+                .await?;
+            return Ok(());
+        }
+
+        let Some((from_status, to_status)) = failure_status(&entry.purpose) else {
+            return Ok(());
+        };
+
+        match entry.purpose.as_str() {
+            "create_escrow" => {
+                diesel::update(escrows::table.find(reference_id))
+                    .filter(escrows::status.eq(from_status))
+                    .set((
+                        escrows::status.eq(to_status),
+                        escrows::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            "register_asset" => {
+                let token = diesel::update(tokens::table.find(reference_id))
+                    .filter(tokens::status.eq(from_status))
+                    .set((
+                        tokens::status.eq(to_status),
+                        tokens::updated_at.eq(Some(now)),
+                    ))
+                    .returning((tokens::asset_code, tokens::distributor_account_id))
+                    .get_result::<(String, Option<Uuid>)>(conn)
+                    .await
+                    .optional()?;
+
+                if let Some((asset_code, Some(distributor_account_id))) = token {
+                    diesel::delete(trustlines::table)
+                        .filter(trustlines::account_id.eq(distributor_account_id))
+                        .filter(trustlines::asset_code.eq(asset_code))
+                        .filter(trustlines::status.eq("pending"))
+                        .execute(conn)
+                        .await?;
+                }
+            }
+            "clawback" | "clawback_claimable_balance" => {
+                diesel::update(clawbacks::table.find(reference_id))
+                    .filter(clawbacks::status.eq(from_status))
+                    .set((
+                        clawbacks::status.eq(to_status),
+                        clawbacks::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            "payment_request" => {
+                diesel::update(payment_requests::table.find(reference_id))
+                    .filter(payment_requests::status.eq(from_status))
+                    .set((
+                        payment_requests::status.eq(to_status),
+                        payment_requests::updated_at.eq(Some(now)),
+                    ))
+                    .execute(conn)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns the status a failed entry moves the record it references from and to
+    ///
+    /// A failed claim leaves the escrow held, so it can be claimed again. A failed mint
+    /// or burn leaves the supply as it was, and a failed trustline change the status it
+    /// had; only trustlines it would have created are removed.
+    fn failure_status(purpose: &str) -> Option<(&'static str, &'static str)> {
+        match purpose {
+            "create_escrow" | "register_asset" | "clawback" | "clawback_claimable_balance" => {
+                Some(("pending", "failed"))
+            }
+            "payment_request" => Some(("submitted", "failed")),
+            _ => None,
+        }
+    }
+
+    /// Returns the change a landed entry makes to the total supply of its asset
+    fn supply_change(purpose: &str, amount: BigDecimal) -> Option<BigDecimal> {
+        match purpose {
+            "mint_asset" => Some(amount),
+            // Burned and clawed back units leave circulation
+            "burn_asset" | "clawback" | "clawback_claimable_balance" => Some(-amount),
+            _ => None,
+        }
+    }
+
+    /// Returns the total supply after a change, counting from zero for an asset that
+    /// has none recorded yet
+    fn changed_supply(total_supply: Option<BigDecimal>, change: BigDecimal) -> BigDecimal {
+        total_supply.unwrap_or_default() + change
+    }
+
+    /// Adds a change to the total supply of an asset
+    async fn change_total_supply(
+        conn: &mut AsyncPgConnection,
+        token_id: Uuid,
+        change: BigDecimal,
+    ) -> Result<(), Error> {
+        let total_supply = tokens::table
+            .find(token_id)
+            .select(tokens::total_supply)
+            .for_update()
+            .first::<Option<BigDecimal>>(conn)
+            .await?;
+
+        diesel::update(tokens::table.find(token_id))
+            .set((
+                tokens::total_supply.eq(Some(changed_supply(total_supply, change))),
+                tokens::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::str::FromStr;
+
+        #[test]
+        fn test_outcome_status() {
+            assert_eq!(
+                outcome_status(&SubmissionOutcome::Completed),
+                ("completed", None)
+            );
+            assert_eq!(
+                outcome_status(&SubmissionOutcome::Failed {
+                    error_code: "tx_failed".to_string(),
+                    operation_codes: vec!["op_underfunded".to_string()],
+                    error_message: "Transaction failed".to_string(),
+                }),
+                ("failed", Some("tx_failed".to_string()))
+            );
+
+            // An unknown outcome keeps the entry pending for the next run
+            assert_eq!(
+                outcome_status(&SubmissionOutcome::Unknown {
+                    error_message: "timeout".to_string(),
+                }),
+                ("pending", Some("timeout".to_string()))
+            );
+        }
+
+        #[test]
+        fn test_failure_status() {
+            for purpose in [
+                "create_escrow",
+                "register_asset",
+                "clawback",
+                "clawback_claimable_balance",
+            ] {
+                assert_eq!(failure_status(purpose), Some(("pending", "failed")));
+            }
+            assert_eq!(
+                failure_status("payment_request"),
+                Some(("submitted", "failed"))
+            );
+
+            // Held escrows stay claimable and supplies and trustlines stay as they were
+            for purpose in [
+                "activate_account",
+                "payment",
+                "release_escrow",
+                "reclaim_escrow",
+                "mint_asset",
+                "burn_asset",
+                "authorize_trustline",
+                "freeze_trustline",
+                "revoke_trustline",
+                "set_home_domain",
+            ] {
+                assert_eq!(failure_status(purpose), None);
+            }
+        }
+
+        #[test]
+        fn test_supply_change() {
+            let amount = BigDecimal::from(25);
+
+            assert_eq!(
+                supply_change("mint_asset", amount.clone()),
+                Some(amount.clone())
+            );
+            for purpose in ["burn_asset", "clawback", "clawback_claimable_balance"] {
+                assert_eq!(
+                    supply_change(purpose, amount.clone()),
+                    Some(-amount.clone())
+                );
+            }
+            assert_eq!(supply_change("payment", amount), None);
+        }
+
+        #[test]
+        fn test_changed_supply() {
+            assert_eq!(
+                changed_supply(None, BigDecimal::from(100)),
+                BigDecimal::from(100)
+            );
+            assert_eq!(
+                changed_supply(
+                    Some(BigDecimal::from_str("100.5").unwrap()),
+                    BigDecimal::from(-40)
+                ),
+                BigDecimal::from_str("60.5").unwrap()
+            );
+        }
+    }
+}
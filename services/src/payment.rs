@@ -1,12 +1,89 @@
+#![allow(clippy::module_inception)]
+
 pub mod payment {
     use crate::common::common::get_account_from_id;
-    use crate::common::common::get_chain_network;
-    use crate::common::common::save_chain_transaction;
+    use crate::common::common::get_stellar_chain;
+    use crate::common::common::record_pending_transaction;
+    use crate::customer::customer::check_payment_limit;
+    use crate::escrow::escrow::{hold_payment, EscrowRequest};
+    use crate::federation::federation::resolve_destination;
+    use crate::outbox::outbox;
+    use crate::signer::signer::get_signer;
     use anyhow::Error;
     use bigdecimal::BigDecimal;
-    use helpers::stellar_chain::StellarChain;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::RunQueryDsl;
+    use diesel_async::{AsyncConnection, AsyncPgConnection};
+    use helpers::federation::{is_federation_address, memo_value};
+    use helpers::multisig::transaction_expiry;
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::{
+        DestinationStatus, PaymentInstruction, StellarChain, MAX_OPERATIONS_PER_TRANSACTION,
+    };
+    use models::common::establish_connection;
+    use models::models::{ChainOutboxEntry, NewPaymentBatch, NewTransaction};
+    use models::schema::{accounts, payment_batches, transaction_errors};
+    use serde::Serialize;
+    use std::collections::HashMap;
     use stellar_base::asset::{Asset, CreditAsset};
-    use stellar_base::PublicKey;
+    use stellar_base::{Memo, PublicKey, Transaction};
+    use uuid::Uuid;
+
+    /// Most items accepted in one batch
+    pub const MAX_BATCH_ITEMS: usize = 1000;
+
+    /// A single payment in a batch. Native payments use asset code `XLM` and no issuer.
+    /// The receiver may be a federation address, which must not ask for a memo.
+    pub struct BatchPaymentItem {
+        pub receiver_public_key: String,
+        pub asset_code: String,
+        pub asset_issuer: Option<String>,
+        pub amount: u64,
+    }
+
+    /// A batch rejected before anything was signed, with one message per invalid item.
+    #[derive(Debug, thiserror::Error)]
+    #[error("Batch rejected: {}", .errors.join("; "))]
+    pub struct BatchValidationError {
+        pub errors: Vec<String>,
+    }
+
+    /// Outcome of a single batch item.
+    #[derive(Serialize)]
+    pub struct BatchPaymentItemResult {
+        /// Position of the item in the request
+        pub index: usize,
+        /// The transaction row recorded for the item, none if it was not sent
+        pub transaction_id: Option<Uuid>,
+        pub receiver_public_key: String,
+        pub asset_code: String,
+        pub amount: u64,
+        /// `completed`, `failed`, `pending` while the outcome is not known yet, or
+        /// `not_sent` when a transaction before it has an unknown outcome
+        pub status: String,
+        /// The result code of the item's operation, or of its transaction, if it failed
+        pub error_code: Option<String>,
+    }
+
+    /// Outcome of a batch payout.
+    #[derive(Serialize)]
+    pub struct PaymentBatchResult {
+        pub batch_id: Uuid,
+        pub items: Vec<BatchPaymentItemResult>,
+    }
+
+    /// Outcome of a single payment.
+    #[derive(Serialize)]
+    pub struct PaymentResult {
+        /// The transaction recorded for the payment, or for its claimable balance
+        pub transaction_id: Uuid,
+        /// Set when the recipient could not receive the payment yet and it is held in
+        /// a claimable balance for them instead
+        pub escrow_id: Option<Uuid>,
+        pub balance_id: Option<String>,
+    }
 
     /// Establish a trustline for a non-native asset.
     /// This function only works for custom assets (non-native).
@@ -15,11 +92,11 @@ pub mod payment {
         asset_code: &str,
         asset_issuer: &str,
     ) -> Result<bool, Error> {
-        let network = get_chain_network()?;
-        let stellar_chain = StellarChain::new(std::env::var("STELLAR_HORIZON_URL")?, network);
+        let stellar_chain = get_stellar_chain()?;
 
-        // Retrieve the account and keypair from the database
-        let (_account, keypair) = get_account_from_id(account_id).await?;
+        // Make sure the account exists before signing for it
+        let account = get_account_from_id(account_id).await?;
+        let signer = get_signer()?;
 
         // Create the custom asset
         let credit_asset = CreditAsset::new(
@@ -29,87 +106,612 @@ pub mod payment {
 
         // Establish the trustline for the custom asset
         stellar_chain
-            .establish_trustline_for_asset(keypair, Asset::Credit(credit_asset))
+            .establish_trustline_for_asset(
+                &signer,
+                &account.id.to_string(),
+                Asset::Credit(credit_asset),
+            )
             .await?;
 
         Ok(true)
     }
 
     /// Sends a native payment (XLM) and saves the transaction to the database.
+    ///
+    /// The receiver may be a public key or a federation address such as
+    /// `alice*greep.io`, whose memo the payment then carries.
+    ///
+    /// With `claimable_fallback`, a payment to an account that does not exist yet is
+    /// held in a claimable balance for the recipient instead of failing.
     pub async fn send_native_payment(
         sender_account_id: String,
         receiver_public_key: &str,
         amount: u64,
-    ) -> Result<bool, Error> {
-        send_payment(
+        claimable_fallback: bool,
+    ) -> Result<PaymentResult, Error> {
+        send_or_hold_payment(
             sender_account_id,
             receiver_public_key,
-            Asset::Native, // Native asset (XLM)
-            "XLM".to_string(),
+            "XLM",
+            None,
             amount,
+            claimable_fallback,
         )
         .await
     }
 
     /// Sends a non-native payment and saves the transaction to the database.
+    ///
+    /// The receiver may be a public key or a federation address such as
+    /// `alice*greep.io`, whose memo the payment then carries.
+    ///
+    /// With `claimable_fallback`, a payment to an account that does not exist yet or
+    /// has no trustline for the asset is held in a claimable balance for the recipient
+    /// instead of failing.
     pub async fn send_non_native_payment(
         sender_account_id: String,
         receiver_public_key: &str,
         asset_code: &str,
         asset_issuer: &str,
         amount: u64,
-    ) -> Result<bool, Error> {
-        // Create the custom asset
-        let asset = Asset::Credit(CreditAsset::new(
-            asset_code.to_string(),
-            PublicKey::from_account_id(asset_issuer)?,
-        )?);
+        claimable_fallback: bool,
+    ) -> Result<PaymentResult, Error> {
+        send_or_hold_payment(
+            sender_account_id,
+            receiver_public_key,
+            asset_code,
+            Some(asset_issuer),
+            amount,
+            claimable_fallback,
+        )
+        .await
+    }
+
+    /// Sends a payment, or holds it in a claimable balance if the fallback is enabled
+    /// and the recipient cannot receive it yet
+    async fn send_or_hold_payment(
+        sender_account_id: String,
+        receiver_public_key: &str,
+        asset_code: &str,
+        asset_issuer: Option<&str>,
+        amount: u64,
+        claimable_fallback: bool,
+    ) -> Result<PaymentResult, Error> {
+        let asset = payment_asset(asset_code, asset_issuer)?;
+
+        // Federation addresses name the account to pay and the memo it needs
+        let destination = resolve_destination(receiver_public_key).await?;
+        let memo = destination.payment_memo()?;
+        let receiver_public_key = destination.account_id.as_str();
 
-        send_payment(
+        let destination_status = if claimable_fallback {
+            get_stellar_chain()?
+                .destination_status(receiver_public_key, &asset)
+                .await?
+        } else {
+            DestinationStatus::Ready
+        };
+
+        if matches!(
+            destination_status,
+            DestinationStatus::NotFound | DestinationStatus::NoTrustline
+        ) {
+            // A claimable balance cannot pass the memo on to the recipient
+            if memo.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Payments to {} need a memo and cannot be held in a claimable balance",
+                    receiver_public_key
+                ));
+            }
+
+            let escrow = hold_payment(EscrowRequest {
+                sender_account_id,
+                recipient_public_key: receiver_public_key.to_string(),
+                asset_code: asset_code.to_string(),
+                asset_issuer: asset_issuer.map(str::to_string),
+                amount,
+                deadline: None,
+            })
+            .await?;
+
+            return Ok(PaymentResult {
+                transaction_id: escrow.create_transaction_id,
+                escrow_id: Some(escrow.id),
+                balance_id: Some(escrow.balance_id),
+            });
+        }
+
+        let entry = send_payment(
             sender_account_id,
             receiver_public_key,
             asset,
             asset_code.to_string(),
             amount,
+            memo,
         )
-        .await
+        .await?;
+        outbox::ensure_completed(&entry)?;
+
+        Ok(PaymentResult {
+            transaction_id: entry.transaction_id,
+            escrow_id: None,
+            balance_id: None,
+        })
+    }
+
+    /// A payment signed by its sender, ready to be recorded and queued in the outbox.
+    pub(crate) struct SignedPayment {
+        transaction: Transaction,
+        sender_address: String,
+        receiver_public_key: String,
+        asset_code: String,
+        amount: u64,
+        memo_value: Option<String>,
     }
 
     /// Helper function to send a payment and save the transaction to the database.
-    /// This function handles both native and non-native assets.
-    async fn send_payment(
+    /// This function handles both native and non-native assets, and takes the memo
+    /// the receiver needs, if any. The sender pays within the limit of its identity
+    /// verification.
+    ///
+    /// Returns the processed outbox entry: `completed`, `failed`, or `pending` if the
+    /// outcome is not known yet and the outbox worker will retry it.
+    pub(crate) async fn send_payment(
         sender_account_id: String,
         receiver_public_key: &str,
         asset: Asset, // Can be Native or Credit
         asset_code: String,
         amount: u64,
-    ) -> Result<bool, Error> {
-        let network = get_chain_network()?;
-        let stellar_chain = StellarChain::new(std::env::var("STELLAR_HORIZON_URL")?, network);
-
-        // Retrieve the sender account and keypair from the database
-        let (sender_account, sender_keypair) = get_account_from_id(sender_account_id).await?;
-
-        // Send the payment
-        let response = stellar_chain
-            .send_asset(
-                sender_keypair,
-                receiver_public_key.to_string(),
-                asset,
-                amount,
-            )
+        memo: Option<Memo>,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let stellar_chain = get_stellar_chain()?;
+
+        let payment = sign_payment(
+            &stellar_chain,
+            sender_account_id,
+            receiver_public_key,
+            asset,
+            asset_code,
+            amount,
+            memo,
+        )
+        .await?;
+
+        // Record the payment and queue it in the outbox in one database transaction
+        let mut db_connection = establish_connection().await?;
+
+        let stellar_chain_ref = &stellar_chain;
+        let payment_ref = &payment;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move { record_payment(conn, stellar_chain_ref, payment_ref).await }
+                    .scope_boxed()
+            })
+            .await?;
+
+        outbox::process_entry(&stellar_chain, entry.id).await
+    }
+
+    /// Builds and signs a payment after checking it against the sender's limit
+    pub(crate) async fn sign_payment(
+        stellar_chain: &StellarChain,
+        sender_account_id: String,
+        receiver_public_key: &str,
+        asset: Asset,
+        asset_code: String,
+        amount: u64,
+        memo: Option<Memo>,
+    ) -> Result<SignedPayment, Error> {
+        // Retrieve the sender account from the database
+        let sender_account = get_account_from_id(sender_account_id).await?;
+        check_payment_limit(&sender_account.stellar_address, amount).await?;
+        let signer = get_signer()?;
+
+        // Build and sign the payment
+        let mut transaction = stellar_chain.build_payment_transaction(
+            &PublicKey::from_account_id(&sender_account.stellar_address)?,
+            receiver_public_key,
+            asset,
+            amount,
+        )?;
+        let memo_value = memo.as_ref().and_then(memo_value);
+        if let Some(memo) = memo {
+            *transaction.memo_mut() = memo;
+        }
+        let transaction = stellar_chain
+            .sign_transaction(transaction, &signer, &sender_account.id.to_string())
             .await?;
 
-        // Save the transaction to the database
-        save_chain_transaction(
-            response,
-            PublicKey::from_account_id(&sender_account.stellar_address)?,
-            PublicKey::from_account_id(receiver_public_key)?,
+        Ok(SignedPayment {
+            transaction,
+            sender_address: sender_account.stellar_address,
+            receiver_public_key: receiver_public_key.to_string(),
             asset_code,
-            BigDecimal::from(amount),
+            amount,
+            memo_value,
+        })
+    }
+
+    /// Records a signed payment and queues it in the outbox, within the caller's
+    /// database transaction
+    ///
+    /// # Returns
+    /// * `Result<ChainOutboxEntry, Error>` - The queued entry, not submitted yet
+    pub(crate) async fn record_payment(
+        conn: &mut AsyncPgConnection,
+        stellar_chain: &StellarChain,
+        payment: &SignedPayment,
+    ) -> Result<ChainOutboxEntry, Error> {
+        let transaction_id = record_pending_transaction(
+            conn,
+            stellar_chain,
+            &payment.transaction,
+            &payment.sender_address,
+            &payment.receiver_public_key,
+            &payment.asset_code,
+            BigDecimal::from(payment.amount),
         )
         .await?;
 
-        Ok(true)
+        if payment.memo_value.is_some() {
+            diesel::update(models::schema::transactions::table.find(transaction_id))
+                .set(models::schema::transactions::memo.eq(&payment.memo_value))
+                .execute(conn)
+                .await?;
+        }
+
+        outbox::enqueue(
+            conn,
+            stellar_chain,
+            &payment.transaction,
+            "payment",
+            None,
+            transaction_id,
+        )
+        .await
+    }
+
+    /// Returns the asset for a code and issuer. Native payments use `XLM` and no issuer.
+    pub(crate) fn payment_asset(
+        asset_code: &str,
+        asset_issuer: Option<&str>,
+    ) -> Result<Asset, Error> {
+        match asset_issuer {
+            Some(asset_issuer) => Ok(Asset::Credit(CreditAsset::new(
+                asset_code.to_string(),
+                PublicKey::from_account_id(asset_issuer)?,
+            )?)),
+            None if asset_code == "XLM" => Ok(Asset::Native),
+            None => Err(anyhow::anyhow!(
+                "Asset issuer is required for non-native assets"
+            )),
+        }
+    }
+
+    /// Sends a batch of payments, packing up to 100 payments into each transaction.
+    ///
+    /// Every destination and trustline is checked before anything is signed, so one bad
+    /// item rejects the whole batch. Each item is recorded as its own transaction row;
+    /// a failed chain transaction fails all of its items, each with the result code of
+    /// its own operation. The transactions are sent one after another, and the items of
+    /// any after one whose outcome is unknown are not sent.
+    pub async fn send_batch_payment(
+        sender_account_id: String,
+        items: Vec<BatchPaymentItem>,
+    ) -> Result<PaymentBatchResult, Error> {
+        if items.is_empty() || items.len() > MAX_BATCH_ITEMS {
+            return Err(anyhow::anyhow!(
+                "A batch takes between 1 and {} items",
+                MAX_BATCH_ITEMS
+            ));
+        }
+
+        let stellar_chain = get_stellar_chain()?;
+
+        let sender_account = get_account_from_id(sender_account_id).await?;
+        let sender_public_key = PublicKey::from_account_id(&sender_account.stellar_address)?;
+        let signer = get_signer()?;
+
+        // The limit applies to all the batch sends in an asset, so splitting a payout
+        // into small items does not get around it
+        for total in batch_totals(&items).into_values() {
+            check_payment_limit(&sender_account.stellar_address, total).await?;
+        }
+
+        // Federation addresses are replaced by the accounts they name. A transaction has
+        // one memo for all its payments, so names that need a memo are rejected.
+        let mut items = items;
+        let mut item_errors = Vec::new();
+        for (index, item) in items.iter_mut().enumerate() {
+            if !is_federation_address(&item.receiver_public_key) {
+                continue;
+            }
+
+            match resolve_destination(&item.receiver_public_key).await {
+                std::result::Result::Ok(destination) if destination.memo.is_some() => item_errors
+                    .push(format!(
+                        "item {}: {} needs a memo, send it as a single payment",
+                        index, item.receiver_public_key
+                    )),
+                std::result::Result::Ok(destination) => {
+                    item.receiver_public_key = destination.account_id
+                }
+                Err(error) => item_errors.push(format!("item {}: {}", index, error)),
+            }
+        }
+
+        // Validate every item up front
+        let mut payments = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            // Names that did not resolve are reported already
+            if is_federation_address(&item.receiver_public_key) {
+                continue;
+            }
+
+            match batch_payment_instruction(&stellar_chain, item) {
+                std::result::Result::Ok(payment) => payments.push(payment),
+                Err(error) => item_errors.push(format!("item {}: {}", index, error)),
+            }
+        }
+
+        if !item_errors.is_empty() {
+            return Err(BatchValidationError {
+                errors: item_errors,
+            }
+            .into());
+        }
+
+        let mut db_connection = establish_connection().await?;
+
+        // Only destinations we manage are linked
+        let account_ids = accounts::table
+            .filter(
+                accounts::stellar_address
+                    .eq_any(items.iter().map(|item| &item.receiver_public_key)),
+            )
+            .select((accounts::stellar_address, accounts::id))
+            .load::<(String, Uuid)>(&mut db_connection)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        // Each transaction takes its sequence number once the one before it settled, so
+        // one rejected before reaching the ledger does not fail the ones after it. Once
+        // an outcome is unknown nothing more is sent, as that transaction may still use
+        // the sequence number.
+        let batch_id = Uuid::new_v4();
+        let mut item_ids = Vec::with_capacity(items.len());
+        for (chunk_index, chunk) in payments.chunks(MAX_OPERATIONS_PER_TRANSACTION).enumerate() {
+            let first_item = chunk_index * MAX_OPERATIONS_PER_TRANSACTION;
+            let chunk_items = &items[first_item..first_item + chunk.len()];
+
+            let recorded = async {
+                let sequence_number = stellar_chain.next_sequence_number(&sender_public_key)?;
+                let transaction = stellar_chain.build_payments_transaction(
+                    &sender_public_key,
+                    sequence_number,
+                    chunk,
+                )?;
+                let transaction = stellar_chain
+                    .sign_transaction(transaction, &signer, &sender_account.id.to_string())
+                    .await?;
+
+                // Record the items and the outbox entry in one database transaction,
+                // together with the batch itself for the first one
+                let stellar_chain_ref = &stellar_chain;
+                let transaction_ref = &transaction;
+                let sender_account_ref = &sender_account;
+                let account_ids_ref = &account_ids;
+                let item_count = items.len();
+                db_connection
+                    .transaction::<_, Error, _>(|conn| {
+                        async move {
+                            if first_item == 0 {
+                                diesel::insert_into(payment_batches::table)
+                                    .values(&NewPaymentBatch {
+                                        id: batch_id,
+                                        sender_account_id: sender_account_ref.id,
+                                        item_count: item_count as i32,
+                                    })
+                                    .execute(conn)
+                                    .await?;
+                            }
+
+                            let transaction_hash =
+                                hex::encode(transaction_ref.hash(stellar_chain_ref.network())?);
+
+                            let mut chunk_item_ids = Vec::with_capacity(chunk_items.len());
+                            for (operation_index, item) in chunk_items.iter().enumerate() {
+                                let new_transaction = NewTransaction {
+                                    id: Uuid::new_v4(),
+                                    source_account_id: Some(sender_account_ref.id),
+                                    destination_account_id: account_ids_ref
+                                        .get(&item.receiver_public_key)
+                                        .copied(),
+                                    transaction_hash: &transaction_hash,
+                                    amount: Some(BigDecimal::from(item.amount)),
+                                    asset_code: &item.asset_code,
+                                    memo: None,
+                                    created_at: Some(chrono::Utc::now().naive_utc()),
+                                    status: "pending",
+                                    expires_at: transaction_expiry(transaction_ref)
+                                        .map(|expiry| expiry.naive_utc()),
+                                    batch_id: Some(batch_id),
+                                    batch_index: Some((first_item + operation_index) as i32),
+                                    operation_index: operation_index as i32,
+                                };
+
+                                diesel::insert_into(models::schema::transactions::table)
+                                    .values(&new_transaction)
+                                    .execute(conn)
+                                    .await?;
+
+                                chunk_item_ids.push(new_transaction.id);
+                            }
+
+                            let entry = outbox::enqueue(
+                                conn,
+                                stellar_chain_ref,
+                                transaction_ref,
+                                "payment",
+                                Some(batch_id),
+                                chunk_item_ids[0],
+                            )
+                            .await?;
+
+                            Ok((chunk_item_ids, entry.id))
+                        }
+                        .scope_boxed()
+                    })
+                    .await
+            }
+            .await;
+
+            let (chunk_item_ids, entry_id) = match recorded {
+                std::result::Result::Ok(recorded) => recorded,
+                // Nothing of the batch was recorded yet
+                Err(error) if first_item == 0 => return Err(error),
+                Err(error) => {
+                    eprintln!(
+                        "Failed to send batch {} from item {}: {}",
+                        batch_id,
+                        first_item,
+                        redact_secrets(&error.to_string())
+                    );
+                    break;
+                }
+            };
+            item_ids.extend(chunk_item_ids);
+
+            // Entries left pending are retried by the outbox worker
+            let settled = match outbox::process_entry(&stellar_chain, entry_id).await {
+                std::result::Result::Ok(entry) => entry.status != "pending",
+                Err(error) => {
+                    eprintln!(
+                        "Failed to process outbox entry {}: {}",
+                        entry_id,
+                        redact_secrets(&error.to_string())
+                    );
+                    false
+                }
+            };
+            if !settled {
+                break;
+            }
+        }
+
+        // Report every item with the outcome of its own operation
+        let statuses = models::schema::transactions::table
+            .filter(models::schema::transactions::batch_id.eq(batch_id))
+            .select((
+                models::schema::transactions::id,
+                models::schema::transactions::status,
+            ))
+            .load::<(Uuid, String)>(&mut db_connection)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let error_codes = transaction_errors::table
+            .filter(transaction_errors::transaction_id.eq_any(&item_ids))
+            .select((
+                transaction_errors::transaction_id,
+                transaction_errors::error_code,
+            ))
+            .load::<(Option<Uuid>, String)>(&mut db_connection)
+            .await?
+            .into_iter()
+            .filter_map(|(transaction_id, error_code)| transaction_id.map(|id| (id, error_code)))
+            .collect::<HashMap<_, _>>();
+
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let transaction_id = item_ids.get(index).copied();
+                BatchPaymentItemResult {
+                    index,
+                    transaction_id,
+                    receiver_public_key: item.receiver_public_key,
+                    asset_code: item.asset_code,
+                    amount: item.amount,
+                    status: match transaction_id {
+                        Some(transaction_id) => statuses
+                            .get(&transaction_id)
+                            .cloned()
+                            .unwrap_or_else(|| "pending".to_string()),
+                        None => "not_sent".to_string(),
+                    },
+                    error_code: transaction_id
+                        .and_then(|transaction_id| error_codes.get(&transaction_id).cloned()),
+                }
+            })
+            .collect();
+
+        Ok(PaymentBatchResult { batch_id, items })
+    }
+
+    /// Sums the amounts of a batch per asset code and issuer
+    fn batch_totals(items: &[BatchPaymentItem]) -> HashMap<(&str, Option<&str>), u64> {
+        let mut totals = HashMap::new();
+        for item in items {
+            let total = totals
+                .entry((item.asset_code.as_str(), item.asset_issuer.as_deref()))
+                .or_insert(0u64);
+            *total = total.saturating_add(item.amount);
+        }
+        totals
+    }
+
+    /// Turns a batch item into a payment after checking its destination can receive it
+    fn batch_payment_instruction(
+        stellar_chain: &StellarChain,
+        item: &BatchPaymentItem,
+    ) -> Result<PaymentInstruction, Error> {
+        if item.amount == 0 {
+            return Err(anyhow::anyhow!("Amount must be positive"));
+        }
+
+        let asset = payment_asset(&item.asset_code, item.asset_issuer.as_deref())?;
+
+        stellar_chain.check_destination(&item.receiver_public_key, &asset)?;
+
+        Ok(PaymentInstruction {
+            receiver_pub_key: item.receiver_public_key.clone(),
+            asset,
+            amount: item.amount,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn item(asset_code: &str, asset_issuer: Option<&str>, amount: u64) -> BatchPaymentItem {
+            BatchPaymentItem {
+                receiver_public_key: "GDEST".to_string(),
+                asset_code: asset_code.to_string(),
+                asset_issuer: asset_issuer.map(str::to_string),
+                amount,
+            }
+        }
+
+        #[test]
+        fn test_batch_totals() {
+            let items = vec![
+                item("XLM", None, 40),
+                item("USDC", Some("GISSUER"), 25),
+                item("XLM", None, 70),
+                item("USDC", Some("GOTHER"), 5),
+                item("USDC", Some("GISSUER"), u64::MAX),
+            ];
+
+            let totals = batch_totals(&items);
+
+            assert_eq!(totals.len(), 3);
+            assert_eq!(totals[&("XLM", None)], 110);
+            assert_eq!(totals[&("USDC", Some("GOTHER"))], 5);
+            assert_eq!(totals[&("USDC", Some("GISSUER"))], u64::MAX);
+        }
     }
 }
@@ -0,0 +1,664 @@
+#![allow(clippy::module_inception)]
+
+/// Payment request module that lets merchants ask customers' wallets for payments
+/// (SEP-0007).
+///
+/// A request is handed out as a `web+stellar:pay` URI, leaving the wallet to build the
+/// payment, or as a `web+stellar:tx` URI carrying the payment we built for the payer's
+/// account. URIs are signed with `URI_REQUEST_SIGNING_SEED`, whose public key is our
+/// `URI_REQUEST_SIGNING_KEY`, name `HOME_DOMAIN` as their origin and stay valid for
+/// `PAYMENT_REQUEST_TTL_SECONDS`, fifteen minutes by default. Wallets post the signed
+/// transaction to the request's callback, and it is submitted through the outbox like
+/// any other payment.
+pub mod payment_request {
+    use anyhow::{Error, Ok};
+    use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+    use chrono::NaiveDateTime;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::multisig::{
+        decode_transaction, encode_transaction, verify_signatures, ThresholdLevel,
+    };
+    use helpers::qr_code::QrCode;
+    use helpers::secret::SecretString;
+    use helpers::stellar_toml::home_domain_from_env;
+    use helpers::stellar_uri::{sign_uri, PayRequest, TransactionRequest};
+    use models::common::establish_connection;
+    use models::models::{NewPaymentRequest, PaymentRequest};
+    use models::schema::{payment_requests, transactions};
+    use serde::Serialize;
+    use stellar_base::amount::Stroops;
+    use stellar_base::time_bounds::TimeBounds;
+    use stellar_base::{KeyPair, Memo, Network, Operation, PublicKey, Transaction};
+    use uuid::Uuid;
+
+    use crate::anchor::anchor::withdraw_memo;
+    use crate::asset::asset::to_stroops;
+    use crate::common::common::{
+        get_account_from_id, get_stellar_chain, record_pending_transaction,
+    };
+    use crate::customer::customer::check_payment_limit;
+    use crate::outbox::outbox;
+    use crate::payment::payment::payment_asset;
+
+    /// How long a request stays valid when PAYMENT_REQUEST_TTL_SECONDS is unset
+    const DEFAULT_PAYMENT_REQUEST_TTL_SECONDS: i64 = 15 * 60;
+
+    /// Pixels per module of PNG QR codes
+    const QR_PNG_SCALE: usize = 8;
+
+    /// Decimal places of Stellar amounts
+    const AMOUNT_SCALE: i64 = 7;
+
+    /// A payment a merchant requests from a customer.
+    pub struct NewPaymentRequestInput {
+        /// The merchant's account, which is paid
+        pub account_id: String,
+        /// `pay` or `tx`
+        pub kind: String,
+        /// `XLM` without an issuer for lumens
+        pub asset_code: String,
+        pub asset_issuer: Option<String>,
+        /// The amount in units, required for `tx` requests
+        pub amount: Option<BigDecimal>,
+        /// The account a `tx` request is built for
+        pub payer: Option<String>,
+        /// Shown to the customer by the wallet
+        pub message: Option<String>,
+    }
+
+    /// A payment request and the URI to hand to the customer's wallet.
+    #[derive(Serialize)]
+    pub struct PaymentRequestView {
+        pub id: Uuid,
+        pub kind: String,
+        pub account_id: Uuid,
+        pub destination: String,
+        pub asset_code: String,
+        pub asset_issuer: Option<String>,
+        pub amount: Option<String>,
+        pub memo_type: String,
+        pub memo: String,
+        pub message: Option<String>,
+        pub payer: Option<String>,
+        pub uri: String,
+        /// `pending`, `submitted` once the wallet posted the signed transaction, then
+        /// `paid` or `failed`
+        pub status: String,
+        /// The payment recorded once the signed transaction was submitted
+        pub transaction_id: Option<Uuid>,
+        pub expires_at: NaiveDateTime,
+    }
+
+    /// Creates a payment request and its signed URI
+    ///
+    /// # Arguments
+    /// * `input` - The merchant's account, the kind of request, the asset, the amount
+    ///   and, for `tx` requests, the paying account
+    ///
+    /// # Returns
+    /// * `Result<PaymentRequestView, Error>` - The request with its URI
+    ///
+    /// # Errors
+    /// Fails if the request is invalid, or the signing key or home domain are not
+    /// configured
+    pub async fn create_payment_request(
+        input: NewPaymentRequestInput,
+    ) -> Result<PaymentRequestView, Error> {
+        let account = get_account_from_id(input.account_id.clone()).await?;
+        if account.status != "active" {
+            return Err(anyhow::anyhow!("Account {} is not active", account.id));
+        }
+
+        let asset = payment_asset(&input.asset_code, input.asset_issuer.as_deref())?;
+        let amount = input
+            .amount
+            .map(|amount| -> Result<(BigDecimal, Stroops), Error> {
+                let stroops = to_stroops(&amount)?;
+                Ok((amount.normalized(), stroops))
+            })
+            .transpose()?;
+
+        let signing_key = uri_request_signing_key()?;
+        let home_domain =
+            home_domain_from_env()?.ok_or_else(|| anyhow::anyhow!("HOME_DOMAIN is not set"))?;
+        let stellar_chain = get_stellar_chain()?;
+        let network_passphrase = network_passphrase(stellar_chain.network());
+
+        let id = Uuid::new_v4();
+        let memo = withdraw_memo(id);
+        let payment_memo = Memo::new_id(memo.parse::<u64>()?);
+        let callback = format!(
+            "https://{}/v1/payment-requests/{}/callback",
+            home_domain, id
+        );
+        let ttl = payment_request_ttl()?;
+        let expires_at = chrono::Utc::now() + ttl;
+
+        let (uri, transaction_hash) = match input.kind.as_str() {
+            "pay" => {
+                let amount = amount.as_ref().map(|(amount, _)| amount.to_plain_string());
+                let uri = PayRequest {
+                    destination: &account.stellar_address,
+                    amount: amount.as_deref(),
+                    asset_code: input
+                        .asset_issuer
+                        .as_ref()
+                        .map(|_| input.asset_code.as_str()),
+                    asset_issuer: input.asset_issuer.as_deref(),
+                    memo: Some(&payment_memo),
+                    callback: Some(&callback),
+                    msg: input.message.as_deref(),
+                    network_passphrase,
+                    origin_domain: Some(&home_domain),
+                }
+                .to_uri()?;
+                (uri, None)
+            }
+            "tx" => {
+                let (_, stroops) = amount
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("Amount is required for tx requests"))?;
+                let payer = input
+                    .payer
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Payer is required for tx requests"))?;
+                let payer_key = PublicKey::from_account_id(payer)
+                    .map_err(|_| anyhow::anyhow!("Payer must be a Stellar public key"))?;
+
+                let payment = Operation::new_payment()
+                    .with_destination(PublicKey::from_account_id(&account.stellar_address)?)
+                    .with_asset(asset)
+                    .with_amount(*stroops)?
+                    .build()?;
+                let mut transaction = Transaction::builder(
+                    payer_key.clone(),
+                    stellar_chain.next_sequence_number(&payer_key)?,
+                    Stroops::new(100),
+                )
+                .add_operation(payment)
+                .with_time_bounds(TimeBounds::valid_for(ttl))
+                .into_transaction()?;
+                *transaction.memo_mut() = payment_memo;
+
+                let xdr = encode_transaction(&transaction)?;
+                let uri = TransactionRequest {
+                    xdr: &xdr,
+                    callback: Some(&callback),
+                    pubkey: Some(payer),
+                    msg: input.message.as_deref(),
+                    network_passphrase,
+                    origin_domain: Some(&home_domain),
+                }
+                .to_uri()?;
+                (
+                    uri,
+                    Some(hex::encode(transaction.hash(stellar_chain.network())?)),
+                )
+            }
+            _ => return Err(anyhow::anyhow!("Kind must be pay or tx")),
+        };
+        let uri = sign_uri(&uri, &signing_key)?;
+
+        let mut db_connection = establish_connection().await?;
+        let payment_request = diesel::insert_into(payment_requests::table)
+            .values(&NewPaymentRequest {
+                id,
+                kind: &input.kind,
+                account_id: account.id,
+                destination: &account.stellar_address,
+                asset_code: &input.asset_code,
+                asset_issuer: input.asset_issuer.as_deref(),
+                amount: amount.map(|(amount, _)| amount),
+                memo: &memo,
+                message: input.message.as_deref(),
+                payer: input.payer.as_deref().filter(|_| input.kind == "tx"),
+                uri: &uri,
+                transaction_hash: transaction_hash.as_deref(),
+                expires_at: expires_at.naive_utc(),
+            })
+            .returning(payment_requests::all_columns)
+            .get_result::<PaymentRequest>(&mut db_connection)
+            .await?;
+
+        Ok(payment_request_view(payment_request))
+    }
+
+    /// Returns a payment request by its id
+    pub async fn get_payment_request(request_id: &str) -> Result<PaymentRequestView, Error> {
+        Ok(payment_request_view(
+            find_payment_request(request_id).await?,
+        ))
+    }
+
+    /// Renders the URI of a payment request as a QR code
+    ///
+    /// # Arguments
+    /// * `request_id` - The UUID of the request
+    /// * `format` - `png` or `svg`
+    ///
+    /// # Returns
+    /// * `Result<(String, Vec<u8>), Error>` - The content type and the image
+    pub async fn get_payment_request_qr_code(
+        request_id: &str,
+        format: &str,
+    ) -> Result<(String, Vec<u8>), Error> {
+        let payment_request = find_payment_request(request_id).await?;
+        let qr_code = QrCode::encode(payment_request.uri.as_bytes())?;
+
+        match format {
+            "png" => Ok(("image/png".to_string(), qr_code.to_png(QR_PNG_SCALE)?)),
+            "svg" => Ok(("image/svg+xml".to_string(), qr_code.to_svg().into_bytes())),
+            _ => Err(anyhow::anyhow!("Format must be png or svg")),
+        }
+    }
+
+    /// Submits the transaction a wallet signed for a payment request
+    ///
+    /// The transaction of a `tx` request must be the one we built. A `pay` request
+    /// accepts a single payment of the requested asset and amount to the merchant,
+    /// carrying the request's memo. Either way the signatures must meet the medium
+    /// threshold of the paying account before the payment is recorded and queued in
+    /// the outbox.
+    ///
+    /// # Arguments
+    /// * `request_id` - The UUID of the request
+    /// * `envelope_xdr` - The base64 encoded, signed transaction envelope
+    ///
+    /// # Returns
+    /// * `Result<PaymentRequestView, Error>` - The request, `submitted` until the
+    ///   outcome is known, then `paid` or `failed`
+    pub async fn submit_payment_request(
+        request_id: &str,
+        envelope_xdr: &str,
+    ) -> Result<PaymentRequestView, Error> {
+        let payment_request = find_payment_request(request_id).await?;
+        check_pending(&payment_request)?;
+
+        let transaction = decode_transaction(envelope_xdr)?;
+        let stellar_chain = get_stellar_chain()?;
+        let network = stellar_chain.network();
+        let transaction_hash = hex::encode(transaction.hash(network)?);
+
+        if payment_request.transaction_hash.is_some()
+            && payment_request.transaction_hash.as_deref() != Some(transaction_hash.as_str())
+        {
+            return Err(anyhow::anyhow!(
+                "Transaction is not the one built for this payment request"
+            ));
+        }
+        let (source, stroops) = check_payment(&payment_request, &transaction)?;
+        let amount = BigDecimal::new(stroops.to_i64().into(), AMOUNT_SCALE).normalized();
+
+        // Account holders pay within the limit of their identity verification
+        let limit_amount = amount
+            .with_scale_round(0, RoundingMode::Ceiling)
+            .to_u64()
+            .ok_or_else(|| anyhow::anyhow!("Amount is too large"))?;
+        check_payment_limit(&source, limit_amount).await?;
+
+        let authorization =
+            stellar_chain.account_authorization(&PublicKey::from_account_id(&source)?)?;
+        verify_signatures(
+            &transaction,
+            network,
+            &authorization,
+            ThresholdLevel::Medium.required_weight(&authorization),
+        )?;
+
+        // Record the payment and queue it in the outbox in one database transaction
+        let mut db_connection = establish_connection().await?;
+
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let payment_request_ref = &payment_request;
+        let source_ref = &source;
+        let entry = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    // Lock the request, so a wallet posting twice submits once
+                    let payment_request = payment_requests::table
+                        .find(payment_request_ref.id)
+                        .for_update()
+                        .first::<PaymentRequest>(conn)
+                        .await?;
+                    check_pending(&payment_request)?;
+
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        source_ref,
+                        &payment_request.destination,
+                        &payment_request.asset_code,
+                        amount,
+                    )
+                    .await?;
+
+                    diesel::update(transactions::table.find(transaction_id))
+                        .set(transactions::memo.eq(&payment_request.memo))
+                        .execute(conn)
+                        .await?;
+
+                    diesel::update(payment_requests::table.find(payment_request.id))
+                        .set((
+                            payment_requests::status.eq("submitted"),
+                            payment_requests::transaction_hash.eq(&transaction_hash),
+                            payment_requests::transaction_id.eq(transaction_id),
+                            payment_requests::updated_at.eq(diesel::dsl::now),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        "payment_request",
+                        Some(payment_request.id),
+                        transaction_id,
+                    )
+                    .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        outbox::process_entry(&stellar_chain, entry.id).await?;
+
+        get_payment_request(request_id).await
+    }
+
+    /// Returns a payment request by its id
+    async fn find_payment_request(request_id: &str) -> Result<PaymentRequest, Error> {
+        let request_uuid = Uuid::parse_str(request_id)
+            .map_err(|_| anyhow::anyhow!("Payment request {} not found", request_id))?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let payment_request = payment_requests::table
+            .find(request_uuid)
+            .first::<PaymentRequest>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Payment request {} not found", request_id))?;
+
+        Ok(payment_request)
+    }
+
+    /// Returns an error unless a payment request still waits for its transaction
+    fn check_pending(payment_request: &PaymentRequest) -> Result<(), Error> {
+        if payment_request.status != "pending" {
+            return Err(anyhow::anyhow!(
+                "Payment request is already {}",
+                payment_request.status
+            ));
+        }
+        if payment_request.expires_at <= chrono::Utc::now().naive_utc() {
+            return Err(anyhow::anyhow!(
+                "Payment request expired at {}",
+                payment_request.expires_at
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a transaction makes the payment a request asks for
+    ///
+    /// # Returns
+    /// * `Result<(String, Stroops), Error>` - The paying account and the amount paid
+    fn check_payment(
+        payment_request: &PaymentRequest,
+        transaction: &Transaction,
+    ) -> Result<(String, Stroops), Error> {
+        let [operation] = transaction.operations().as_slice() else {
+            return Err(anyhow::anyhow!("Transaction must make exactly one payment"));
+        };
+        let payment = operation
+            .as_payment()
+            .ok_or_else(|| anyhow::anyhow!("Transaction must make exactly one payment"))?;
+
+        if payment.destination().account_id() != payment_request.destination {
+            return Err(anyhow::anyhow!("Payment must go to the merchant's account"));
+        }
+        let asset = payment_asset(
+            &payment_request.asset_code,
+            payment_request.asset_issuer.as_deref(),
+        )?;
+        if *payment.asset() != asset {
+            return Err(anyhow::anyhow!(
+                "Payment must be in {}",
+                payment_request.asset_code
+            ));
+        }
+        let amount = payment_request
+            .amount
+            .as_ref()
+            .map(to_stroops)
+            .transpose()?;
+        if amount.is_some_and(|amount| *payment.amount() != amount) {
+            return Err(anyhow::anyhow!("Payment must be of the requested amount"));
+        }
+
+        let memo = Memo::new_id(payment_request.memo.parse::<u64>()?);
+        if *transaction.memo() != memo {
+            return Err(anyhow::anyhow!(
+                "Payment must carry the id memo {}",
+                payment_request.memo
+            ));
+        }
+
+        let source = payment
+            .source_account()
+            .as_ref()
+            .unwrap_or(transaction.source_account())
+            .account_id();
+        if payment_request
+            .payer
+            .as_deref()
+            .is_some_and(|payer| payer != source)
+        {
+            return Err(anyhow::anyhow!(
+                "Payment must come from the payer's account"
+            ));
+        }
+
+        Ok((source, *payment.amount()))
+    }
+
+    /// Returns the key URIs are signed with, which must be the published
+    /// `URI_REQUEST_SIGNING_KEY`
+    fn uri_request_signing_key() -> Result<KeyPair, Error> {
+        let signing_seed = SecretString::new(
+            std::env::var("URI_REQUEST_SIGNING_SEED")
+                .map_err(|_| anyhow::anyhow!("URI_REQUEST_SIGNING_SEED is not set"))?,
+        );
+        let signing_key = KeyPair::from_secret_seed(signing_seed.expose_secret())
+            .map_err(|_| anyhow::anyhow!("URI_REQUEST_SIGNING_SEED is not a secret seed"))?;
+
+        // Wallets check the signature against the key in our stellar.toml
+        let published_key = std::env::var("URI_REQUEST_SIGNING_KEY")
+            .map_err(|_| anyhow::anyhow!("URI_REQUEST_SIGNING_KEY is not set"))?;
+        if signing_key.public_key().account_id() != published_key {
+            return Err(anyhow::anyhow!(
+                "URI_REQUEST_SIGNING_SEED does not match URI_REQUEST_SIGNING_KEY"
+            ));
+        }
+
+        Ok(signing_key)
+    }
+
+    /// Returns how long a payment request stays valid
+    fn payment_request_ttl() -> Result<chrono::Duration, Error> {
+        let ttl_seconds = match std::env::var("PAYMENT_REQUEST_TTL_SECONDS") {
+            std::result::Result::Ok(ttl_seconds) => ttl_seconds.parse::<i64>()?,
+            Err(_) => DEFAULT_PAYMENT_REQUEST_TTL_SECONDS,
+        };
+
+        if ttl_seconds <= 0 {
+            return Err(anyhow::anyhow!(
+                "PAYMENT_REQUEST_TTL_SECONDS must be positive"
+            ));
+        }
+
+        Ok(chrono::Duration::seconds(ttl_seconds))
+    }
+
+    /// Returns the passphrase URIs name, which SEP-0007 leaves out for the public network
+    fn network_passphrase(network: &Network) -> Option<&str> {
+        Some(network.passphrase())
+            .filter(|passphrase| *passphrase != Network::new_public().passphrase())
+    }
+
+    fn payment_request_view(payment_request: PaymentRequest) -> PaymentRequestView {
+        PaymentRequestView {
+            id: payment_request.id,
+            kind: payment_request.kind,
+            account_id: payment_request.account_id,
+            destination: payment_request.destination,
+            asset_code: payment_request.asset_code,
+            asset_issuer: payment_request.asset_issuer,
+            amount: payment_request
+                .amount
+                .map(|amount| amount.normalized().to_plain_string()),
+            memo_type: "id".to_string(),
+            memo: payment_request.memo,
+            message: payment_request.message,
+            payer: payment_request.payer,
+            uri: payment_request.uri,
+            status: payment_request.status,
+            transaction_id: payment_request.transaction_id,
+            expires_at: payment_request.expires_at,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use stellar_base::operations::PaymentOperationBuilder;
+        use stellar_base::Asset;
+
+        struct Payment<'a> {
+            payer: &'a KeyPair,
+            destination: PublicKey,
+            asset: Asset,
+            amount: Stroops,
+            memo: Memo,
+        }
+
+        fn transaction(payment: Payment) -> Transaction {
+            let operation = PaymentOperationBuilder::new()
+                .with_destination(payment.destination)
+                .with_asset(payment.asset)
+                .with_amount(payment.amount)
+                .unwrap()
+                .build()
+                .unwrap();
+
+            Transaction::builder(payment.payer.public_key().clone(), 1, Stroops::new(100))
+                .add_operation(operation)
+                .with_memo(payment.memo)
+                .with_time_bounds(TimeBounds::always_valid())
+                .into_transaction()
+                .unwrap()
+        }
+
+        fn payment_request(
+            destination: &KeyPair,
+            issuer: &KeyPair,
+            payer: Option<&KeyPair>,
+        ) -> PaymentRequest {
+            PaymentRequest {
+                id: Uuid::new_v4(),
+                kind: "pay".to_string(),
+                account_id: Uuid::new_v4(),
+                destination: destination.public_key().account_id(),
+                asset_code: "USDC".to_string(),
+                asset_issuer: Some(issuer.public_key().account_id()),
+                amount: Some("12.5".parse::<BigDecimal>().unwrap()),
+                memo: "42".to_string(),
+                message: None,
+                payer: payer.map(|payer| payer.public_key().account_id()),
+                uri: String::new(),
+                status: "pending".to_string(),
+                transaction_hash: None,
+                transaction_id: None,
+                expires_at: chrono::Utc::now().naive_utc(),
+                created_at: None,
+                updated_at: None,
+            }
+        }
+
+        #[test]
+        fn test_check_payment() {
+            let destination = KeyPair::random().unwrap();
+            let issuer = KeyPair::random().unwrap();
+            let payer = KeyPair::random().unwrap();
+            let request = payment_request(&destination, &issuer, Some(&payer));
+            let usdc = payment_asset("USDC", Some(&issuer.public_key().account_id())).unwrap();
+            let payment = || Payment {
+                payer: &payer,
+                destination: destination.public_key().clone(),
+                asset: usdc.clone(),
+                amount: Stroops::new(125_000_000),
+                memo: Memo::new_id(42),
+            };
+
+            let (source, amount) = check_payment(&request, &transaction(payment())).unwrap();
+            assert_eq!(source, payer.public_key().account_id());
+            assert_eq!(amount, Stroops::new(125_000_000));
+
+            let other = KeyPair::random().unwrap();
+            let wrong_payments = [
+                Payment {
+                    destination: other.public_key().clone(),
+                    ..payment()
+                },
+                Payment {
+                    asset: Asset::new_native(),
+                    ..payment()
+                },
+                Payment {
+                    amount: Stroops::new(125_000_001),
+                    ..payment()
+                },
+                Payment {
+                    memo: Memo::new_id(43),
+                    ..payment()
+                },
+                Payment {
+                    payer: &other,
+                    ..payment()
+                },
+            ];
+            for wrong_payment in wrong_payments {
+                assert!(check_payment(&request, &transaction(wrong_payment)).is_err());
+            }
+        }
+
+        #[test]
+        fn test_check_payment_of_any_amount_from_anyone() {
+            let destination = KeyPair::random().unwrap();
+            let issuer = KeyPair::random().unwrap();
+            let payer = KeyPair::random().unwrap();
+            let mut request = payment_request(&destination, &issuer, None);
+            request.amount = None;
+
+            let payment = Payment {
+                payer: &payer,
+                destination: destination.public_key().clone(),
+                asset: payment_asset("USDC", Some(&issuer.public_key().account_id())).unwrap(),
+                amount: Stroops::new(7),
+                memo: Memo::new_id(42),
+            };
+
+            let (source, amount) = check_payment(&request, &transaction(payment)).unwrap();
+            assert_eq!(source, payer.public_key().account_id());
+            assert_eq!(amount, Stroops::new(7));
+        }
+    }
+}
@@ -0,0 +1,194 @@
+#![allow(clippy::module_inception)]
+
+/// Recovery module that exports custodial account seeds into an offline recovery bundle
+/// and restores them from one after the database has been lost.
+pub mod recovery {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::JoinOnDsl;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::common::KeyRing;
+    use helpers::recovery::{
+        open_bundle, seal_bundle, BundleProtection, BundleUnlock, RecoveryBundle, RecoveryEntry,
+        SealedBundle,
+    };
+    use models::common::establish_connection;
+    use models::models::{Account, EncryptedKey};
+    use models::schema::{accounts, encrypted_keys};
+    use uuid::Uuid;
+
+    /// Outcome of importing a recovery bundle.
+    pub struct RecoveryImportSummary {
+        /// Accounts that were missing and have been recreated
+        pub restored_accounts: usize,
+        /// Keys that were missing and have been stored again
+        pub restored_keys: usize,
+        /// Accounts whose key was already present and left untouched
+        pub skipped: usize,
+    }
+
+    /// Exports every custodial account seed into an encrypted recovery bundle.
+    ///
+    /// Accounts with derived keys have no stored seed and are not included; they are
+    /// recovered from the HD wallet mnemonic and their derivation index instead.
+    ///
+    /// # Arguments
+    /// * `protection` - How the bundle key should be protected
+    ///
+    /// # Returns
+    /// * `Result<SealedBundle, Error>` - The bundle and, for Shamir protection, the custodian shares
+    pub async fn export_recovery_bundle(
+        protection: BundleProtection,
+    ) -> Result<SealedBundle, Error> {
+        let key_ring = KeyRing::from_env()?;
+        let mut db_connection = establish_connection().await?;
+
+        let rows = accounts::table
+            .inner_join(encrypted_keys::table.on(encrypted_keys::account_id.eq(accounts::id)))
+            .select((
+                accounts::id,
+                accounts::stellar_address,
+                accounts::account_type,
+                accounts::status,
+                encrypted_keys::encrypted_key,
+                encrypted_keys::key_version,
+            ))
+            .order(accounts::id.asc())
+            .load::<(Uuid, String, String, String, Vec<u8>, i32)>(&mut db_connection)
+            .await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for (account_id, stellar_address, account_type, status, encrypted_key, key_version) in rows
+        {
+            let secret_key = key_ring
+                .decrypt(&encrypted_key, account_id.as_bytes(), key_version)?
+                .to_secret_string()?;
+
+            entries.push(RecoveryEntry {
+                account_id: account_id.to_string(),
+                stellar_address,
+                account_type,
+                status,
+                secret_key,
+            });
+        }
+
+        seal_bundle(&entries, protection)
+    }
+
+    /// Opens a recovery bundle and checks every seed against its recorded address,
+    /// without touching the database.
+    ///
+    /// # Returns
+    /// * `Result<usize, Error>` - The number of accounts in the bundle
+    pub fn verify_recovery_bundle(
+        bundle: &RecoveryBundle,
+        unlock: BundleUnlock,
+    ) -> Result<usize, Error> {
+        Ok(open_bundle(bundle, unlock)?.len())
+    }
+
+    /// Restores accounts and encrypted keys from a recovery bundle.
+    ///
+    /// Missing accounts are recreated and missing keys are stored again under the current
+    /// key version. Accounts that still have a stored key are left as they are. Nothing is
+    /// imported if an existing account has another address than its entry.
+    ///
+    /// # Returns
+    /// * `Result<RecoveryImportSummary, Error>` - Counts of what was restored
+    pub async fn import_recovery_bundle(
+        bundle: &RecoveryBundle,
+        unlock: BundleUnlock,
+    ) -> Result<RecoveryImportSummary, Error> {
+        let entries = open_bundle(bundle, unlock)?;
+        let key_ring = KeyRing::from_env()?;
+
+        let mut db_connection = establish_connection().await?;
+
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let mut summary = RecoveryImportSummary {
+                        restored_accounts: 0,
+                        restored_keys: 0,
+                        skipped: 0,
+                    };
+
+                    for entry in &entries {
+                        let account_id = Uuid::parse_str(&entry.account_id)?;
+
+                        let existing_account = accounts::table
+                            .find(account_id)
+                            .first::<Account>(conn)
+                            .await
+                            .optional()?;
+
+                        // A key for another address must never be attached to the account
+                        if let Some(existing_account) = existing_account
+                            .as_ref()
+                            .filter(|account| account.stellar_address != entry.stellar_address)
+                        {
+                            return Err(anyhow::anyhow!(
+                                "Account {} has address {}, but the bundle has {}",
+                                account_id,
+                                existing_account.stellar_address,
+                                entry.stellar_address
+                            ));
+                        }
+
+                        if existing_account.is_none() {
+                            diesel::insert_into(accounts::table)
+                                .values(&Account {
+                                    id: account_id,
+                                    stellar_address: entry.stellar_address.clone(),
+                                    account_type: entry.account_type.clone(),
+                                    created_at: Some(chrono::Utc::now().naive_utc()),
+                                    updated_at: Some(chrono::Utc::now().naive_utc()),
+                                    status: entry.status.clone(),
+                                    derivation_index: None,
+                                })
+                                .execute(conn)
+                                .await?;
+                            summary.restored_accounts += 1;
+                        }
+
+                        let existing_key = encrypted_keys::table
+                            .filter(encrypted_keys::account_id.eq(account_id))
+                            .select(encrypted_keys::id)
+                            .first::<Uuid>(conn)
+                            .await
+                            .optional()?;
+
+                        if existing_key.is_some() {
+                            summary.skipped += 1;
+                            continue;
+                        }
+
+                        let (encrypted_key, key_version) = key_ring.encrypt(
+                            entry.secret_key.expose_secret().as_bytes(),
+                            account_id.as_bytes(),
+                        )?;
+
+                        diesel::insert_into(encrypted_keys::table)
+                            .values(&EncryptedKey {
+                                id: Uuid::new_v4(),
+                                account_id,
+                                encrypted_key,
+                                created_at: Some(chrono::Utc::now().naive_utc()),
+                                key_version,
+                            })
+                            .execute(conn)
+                            .await?;
+                        summary.restored_keys += 1;
+                    }
+
+                    Ok(summary)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+}
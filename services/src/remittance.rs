@@ -0,0 +1,500 @@
+#![allow(clippy::module_inception)]
+
+/// Remittance module that receives cross-border payments from sending anchors
+/// (SEP-0031).
+///
+/// A sending anchor authenticates with SEP-0010, names the customers sending and
+/// receiving the payment by their SEP-0012 ids, and gets the distributor account and
+/// an id memo to pay. The anchor worker matches the payment by memo, records it in
+/// `transactions` and pays the receiver in fiat through the rail. Each status change is
+/// posted to the callback URL the sending anchor registered, signed with our
+/// `SIGNING_KEY`.
+pub mod remittance {
+    use std::time::Duration;
+
+    use anyhow::{Error, Ok};
+    use bigdecimal::BigDecimal;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::RunQueryDsl;
+    use helpers::anchor::{callback_host, callback_signature, AnchorStatus};
+    use helpers::kyc::{CustomerStatus, RECEIVER_TYPE, SENDER_TYPE};
+    use helpers::submitter::http_client;
+    use models::common::establish_connection;
+    use models::models::{AnchorTransaction, Customer, NewAnchorTransaction};
+    use models::schema::{anchor_transactions, tokens};
+    use serde::Serialize;
+    use serde_json::{json, Map, Value};
+    use uuid::Uuid;
+
+    use crate::anchor::anchor::{
+        anchored_asset, check_account, check_amount, get_anchor_transaction, next_status, notify,
+        transaction_view, withdraw_memo, AnchorTransactionView,
+    };
+    use crate::customer::customer::customer_status;
+    use crate::web_auth::web_auth::signing_key;
+
+    /// How long a sending anchor's callback may take
+    const CALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// A payment the sending anchor wants to make, rejected because a customer is not
+    /// known or not accepted yet. The sending anchor puts the customer with SEP-0012
+    /// and retries.
+    #[derive(Debug, thiserror::Error)]
+    #[error("Customer information needed for {customer_type}")]
+    pub struct CustomerInfoNeeded {
+        /// `sep31-sender` or `sep31-receiver`
+        pub customer_type: String,
+    }
+
+    /// A payment a sending anchor wants to make.
+    pub struct ReceiveRequest {
+        /// The Stellar account the sending anchor authenticated as
+        pub account: String,
+        pub asset_code: String,
+        /// The amount in units the sending anchor will pay
+        pub amount: BigDecimal,
+        pub sender_id: Option<String>,
+        pub receiver_id: Option<String>,
+    }
+
+    /// Where the sending anchor pays, in the shape SEP-0031 defines.
+    #[derive(Serialize)]
+    pub struct ReceiveInstructions {
+        pub id: Uuid,
+        pub stellar_account_id: String,
+        pub stellar_memo_type: String,
+        pub stellar_memo: String,
+    }
+
+    /// Lists the assets we receive, in the SEP-0031 `/info` shape
+    ///
+    /// Sending anchors register both customers with SEP-0012 using the types listed
+    /// here before creating a transaction.
+    pub async fn get_info() -> Result<Value, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let asset_codes = tokens::table
+            .filter(tokens::status.eq("active"))
+            .filter(tokens::distributor_account_id.is_not_null())
+            .filter(tokens::issuer_account_id.is_not_null())
+            .order(tokens::asset_code.asc())
+            .select(tokens::asset_code)
+            .load::<String>(&mut db_connection)
+            .await?;
+
+        let mut receive = Map::new();
+        for asset_code in asset_codes {
+            receive.insert(
+                asset_code,
+                json!({
+                    "enabled": true,
+                    "quotes_supported": false,
+                    "quotes_required": false,
+                    "sep12": {
+                        "sender": {
+                            "types": {
+                                SENDER_TYPE: { "description": "The customer sending the payment" }
+                            }
+                        },
+                        "receiver": {
+                            "types": {
+                                RECEIVER_TYPE: {
+                                    "description": "The customer receiving the payment, with the bank account to pay out to"
+                                }
+                            }
+                        }
+                    }
+                }),
+            );
+        }
+
+        Ok(json!({ "receive": receive }))
+    }
+
+    /// Creates a payment for a sending anchor to make
+    ///
+    /// # Arguments
+    /// * `request` - The sending anchor, the asset, the amount and both customers
+    ///
+    /// # Returns
+    /// * `Result<ReceiveInstructions, Error>` - The transaction, and the account and memo
+    ///   to pay. Fails with [`CustomerInfoNeeded`] if a customer is missing.
+    pub async fn start_receive(request: ReceiveRequest) -> Result<ReceiveInstructions, Error> {
+        check_amount(Some(&request.amount))?;
+        check_account(&request.account)?;
+        let (token, _, distributor) = anchored_asset(&request.asset_code).await?;
+
+        let sender_id =
+            check_customer(&request.account, request.sender_id.as_deref(), SENDER_TYPE).await?;
+        let receiver_id = check_customer(
+            &request.account,
+            request.receiver_id.as_deref(),
+            RECEIVER_TYPE,
+        )
+        .await?;
+
+        let id = Uuid::new_v4();
+        let memo = withdraw_memo(id);
+
+        let mut db_connection = establish_connection().await?;
+        diesel::insert_into(anchor_transactions::table)
+            .values(&NewAnchorTransaction {
+                id,
+                kind: "receive",
+                protocol: "sep31",
+                status: AnchorStatus::PendingSender.as_str(),
+                token_id: token.id,
+                stellar_account: &request.account,
+                amount_in: Some(request.amount),
+                withdraw_anchor_account: Some(&distributor.stellar_address),
+                withdraw_memo: Some(&memo),
+                fiat_destination: None,
+                sender_id: Some(sender_id),
+                receiver_id: Some(receiver_id),
+            })
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(ReceiveInstructions {
+            id,
+            stellar_account_id: distributor.stellar_address,
+            stellar_memo_type: "id".to_string(),
+            stellar_memo: memo,
+        })
+    }
+
+    /// Returns a payment a sending anchor created
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account the sending anchor authenticated as
+    /// * `transaction_id` - The UUID of the transaction
+    pub async fn get_receive_transaction(
+        account: &str,
+        transaction_id: &str,
+    ) -> Result<AnchorTransactionView, Error> {
+        let transaction = find_receive_transaction(account, transaction_id).await?;
+
+        Ok(transaction_view(transaction))
+    }
+
+    /// Registers the URL the status of a payment is posted to
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar account the sending anchor authenticated as
+    /// * `transaction_id` - The UUID of the transaction
+    /// * `url` - An HTTPS URL
+    pub async fn set_callback(account: &str, transaction_id: &str, url: &str) -> Result<(), Error> {
+        callback_host(url)?;
+        let transaction = find_receive_transaction(account, transaction_id).await?;
+
+        let mut db_connection = establish_connection().await?;
+        diesel::update(anchor_transactions::table.find(transaction.id))
+            .set((
+                anchor_transactions::callback_url.eq(url),
+                anchor_transactions::updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Posts the current status of a payment to its callback URL
+    ///
+    /// The body is the transaction as `GET /transactions/:id` returns it, and the
+    /// `Signature` header signs it with our `SIGNING_KEY`.
+    ///
+    /// # Arguments
+    /// * `transaction` - The transaction, which must have a callback URL
+    pub async fn send_status_callback(transaction: &AnchorTransaction) -> Result<(), Error> {
+        let url = transaction
+            .callback_url
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Transaction {} has no callback", transaction.id))?;
+        let host = callback_host(url)?;
+
+        // Read back the transaction, so the callback always carries its latest state
+        let view = transaction_view(get_anchor_transaction(transaction.id).await?);
+        let body = serde_json::to_string(&json!({ "transaction": view }))?;
+        let signature = callback_signature(
+            &signing_key()?,
+            chrono::Utc::now().timestamp(),
+            &host,
+            &body,
+        );
+
+        http_client()
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("Signature", signature)
+            .body(body)
+            .timeout(CALLBACK_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Returns a payment created by a sending anchor
+    async fn find_receive_transaction(
+        account: &str,
+        transaction_id: &str,
+    ) -> Result<AnchorTransaction, Error> {
+        let transaction_id = Uuid::parse_str(transaction_id)
+            .map_err(|_| anyhow::anyhow!("Transaction {} not found", transaction_id))?;
+
+        let mut db_connection = establish_connection().await?;
+
+        let transaction = anchor_transactions::table
+            .find(transaction_id)
+            .filter(anchor_transactions::kind.eq("receive"))
+            .filter(anchor_transactions::stellar_account.eq(account))
+            .first::<AnchorTransaction>(&mut db_connection)
+            .await
+            .optional()?
+            .ok_or_else(|| anyhow::anyhow!("Transaction {} not found", transaction_id))?;
+
+        Ok(transaction)
+    }
+
+    /// Holds or resumes the payments to a receiver an operator reviewed
+    ///
+    /// Payments waiting for a rejected receiver wait for the sending anchor to correct
+    /// its information, and go on once the receiver is accepted again.
+    ///
+    /// # Arguments
+    /// * `customer` - The reviewed customer
+    /// * `status` - The decision
+    pub(crate) async fn receiver_reviewed(
+        customer: &Customer,
+        status: CustomerStatus,
+    ) -> Result<(), Error> {
+        if customer.customer_type != RECEIVER_TYPE {
+            return Ok(());
+        }
+
+        let Some((from, to)) = receiver_review_move(status) else {
+            return Ok(());
+        };
+
+        let mut db_connection = establish_connection().await?;
+
+        let transactions = anchor_transactions::table
+            .filter(anchor_transactions::kind.eq("receive"))
+            .filter(anchor_transactions::receiver_id.eq(customer.id.to_string()))
+            .filter(anchor_transactions::status.eq(from.as_str()))
+            .load::<AnchorTransaction>(&mut db_connection)
+            .await?;
+
+        for transaction in transactions {
+            let next = next_status(&transaction, to)?;
+
+            // A transaction the worker moved meanwhile is left alone
+            let transaction = diesel::update(
+                anchor_transactions::table
+                    .find(transaction.id)
+                    .filter(anchor_transactions::status.eq(&transaction.status)),
+            )
+            .set((
+                anchor_transactions::status.eq(next),
+                anchor_transactions::message.eq(&customer.status_message),
+                anchor_transactions::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(anchor_transactions::all_columns)
+            .get_result::<AnchorTransaction>(&mut db_connection)
+            .await
+            .optional()?;
+
+            if let Some(transaction) = transaction {
+                notify(&transaction).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the id of an accepted customer of the sending anchor, or the error asking
+    /// it to put the customer
+    async fn check_customer<'a>(
+        account: &str,
+        customer_id: Option<&'a str>,
+        customer_type: &str,
+    ) -> Result<&'a str, Error> {
+        let customer_id = required_customer_id(customer_id, customer_type)?;
+
+        match customer_status(account, customer_id, customer_type).await? {
+            Some(CustomerStatus::Accepted) => Ok(customer_id),
+            _ => Err(customer_info_needed(customer_type).into()),
+        }
+    }
+
+    /// Returns the customer id a sending anchor gave, or the error asking for one
+    fn required_customer_id<'a>(
+        customer_id: Option<&'a str>,
+        customer_type: &str,
+    ) -> Result<&'a str, CustomerInfoNeeded> {
+        customer_id
+            .map(str::trim)
+            .filter(|customer_id| !customer_id.is_empty())
+            .ok_or_else(|| customer_info_needed(customer_type))
+    }
+
+    /// The error asking the sending anchor to put a customer
+    fn customer_info_needed(customer_type: &str) -> CustomerInfoNeeded {
+        CustomerInfoNeeded {
+            customer_type: customer_type.to_string(),
+        }
+    }
+
+    /// Returns the status payments to a reviewed receiver move from and to, if any
+    fn receiver_review_move(status: CustomerStatus) -> Option<(AnchorStatus, AnchorStatus)> {
+        match status {
+            CustomerStatus::Rejected => Some((
+                AnchorStatus::PendingReceiver,
+                AnchorStatus::PendingCustomerInfoUpdate,
+            )),
+            CustomerStatus::Accepted => Some((
+                AnchorStatus::PendingCustomerInfoUpdate,
+                AnchorStatus::PendingReceiver,
+            )),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::str::FromStr;
+
+        const SENDING_ANCHOR: &str = "GAHK7EEG2WWHVKDNT4CEQFZGKF2LGDSW2IVM4S5DP42RBW3K6BTODB4A";
+
+        fn receive(status: &str) -> AnchorTransaction {
+            AnchorTransaction {
+                id: Uuid::new_v4(),
+                kind: "receive".to_string(),
+                protocol: "sep31".to_string(),
+                status: status.to_string(),
+                token_id: Uuid::new_v4(),
+                stellar_account: SENDING_ANCHOR.to_string(),
+                amount_in: None,
+                amount_out: None,
+                amount_fee: None,
+                withdraw_anchor_account: None,
+                withdraw_memo: None,
+                fiat_destination: None,
+                external_reference: None,
+                stellar_transaction_id: None,
+                stellar_transaction_hash: None,
+                message: None,
+                created_at: None,
+                updated_at: None,
+                completed_at: None,
+                sender_id: Some("sender-1".to_string()),
+                receiver_id: Some("receiver-1".to_string()),
+                callback_url: None,
+            }
+        }
+
+        #[test]
+        fn test_receive_expires_only_before_payment() {
+            let waiting = receive("pending_sender");
+            assert_eq!(
+                next_status(&waiting, AnchorStatus::Expired).unwrap(),
+                "expired"
+            );
+
+            // Once the payment arrived, it is paid out or refunded instead
+            let received = receive("pending_receiver");
+            assert!(next_status(&received, AnchorStatus::Expired).is_err());
+            assert_eq!(
+                next_status(&received, AnchorStatus::PendingExternal).unwrap(),
+                "pending_external"
+            );
+        }
+
+        #[test]
+        fn test_receive_view_amounts() {
+            let mut transaction = receive("pending_receiver");
+            transaction.amount_in = Some(BigDecimal::from_str("12.5000000").unwrap());
+            transaction.amount_out = Some(BigDecimal::from_str("12.5000000").unwrap());
+            transaction.amount_fee = Some(BigDecimal::from(0));
+            transaction.withdraw_anchor_account = Some(SENDING_ANCHOR.to_string());
+            transaction.withdraw_memo = Some("42".to_string());
+
+            let view = transaction_view(transaction);
+            // Trailing zeros of the stored amount are dropped, no fee is charged
+            assert_eq!(view.amount_in.as_deref(), Some("12.5"));
+            assert_eq!(view.amount_out.as_deref(), Some("12.5"));
+            assert_eq!(view.amount_fee.as_deref(), Some("0"));
+
+            // SEP-0031 names the account and memo to pay differently
+            assert_eq!(view.stellar_account_id.as_deref(), Some(SENDING_ANCHOR));
+            assert_eq!(view.stellar_memo.as_deref(), Some("42"));
+            assert_eq!(view.stellar_memo_type.as_deref(), Some("id"));
+            assert!(view.withdraw_anchor_account.is_none());
+            assert!(view.withdraw_memo.is_none());
+            assert!(view.from.is_none());
+        }
+
+        #[test]
+        fn test_receiver_review_move() {
+            assert_eq!(
+                receiver_review_move(CustomerStatus::Rejected),
+                Some((
+                    AnchorStatus::PendingReceiver,
+                    AnchorStatus::PendingCustomerInfoUpdate
+                ))
+            );
+            assert_eq!(
+                receiver_review_move(CustomerStatus::Accepted),
+                Some((
+                    AnchorStatus::PendingCustomerInfoUpdate,
+                    AnchorStatus::PendingReceiver
+                ))
+            );
+            assert_eq!(receiver_review_move(CustomerStatus::Processing), None);
+        }
+
+        #[test]
+        fn test_failed_receive_can_be_refunded() {
+            // A held payment goes back to the receiver before it is refunded
+            let held = receive("pending_customer_info_update");
+            assert!(next_status(&held, AnchorStatus::Refunded).is_err());
+            assert_eq!(next_status(&held, AnchorStatus::Error).unwrap(), "error");
+
+            let received = receive("pending_receiver");
+            assert_eq!(
+                next_status(&received, AnchorStatus::Refunded).unwrap(),
+                "refunded"
+            );
+
+            // Nothing is refunded once the fiat was paid out
+            let paid = receive("completed");
+            assert!(next_status(&paid, AnchorStatus::Refunded).is_err());
+        }
+
+        #[test]
+        fn test_required_customer_id() {
+            assert_eq!(
+                required_customer_id(Some(" sender-1 "), SENDER_TYPE).unwrap(),
+                "sender-1"
+            );
+
+            for customer_id in [None, Some(""), Some("  ")] {
+                let error = required_customer_id(customer_id, RECEIVER_TYPE).unwrap_err();
+                assert_eq!(error.customer_type, RECEIVER_TYPE);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_status_callback_needs_a_url() {
+            let error = send_status_callback(&receive("pending_receiver"))
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("has no callback"));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+#![allow(clippy::module_inception)]
+
+/// Resolver module that settles transactions whose submission outcome was never learned.
+///
+/// Every transaction has a max time, after which no ledger can include it. Once the
+/// latest ledger closed past that time, the transaction is either on chain or never
+/// will be, so its record can be settled for good and callers can safely retry.
+pub mod resolver {
+    use anyhow::{Error, Ok};
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel_async::RunQueryDsl;
+    use helpers::secret::redact_secrets;
+    use helpers::stellar_chain::TransactionOutcome;
+    use models::common::establish_connection;
+    use models::schema::{pending_transactions, transactions};
+    use uuid::Uuid;
+
+    use crate::common::common;
+
+    /// Outcome of a resolver run.
+    pub struct ResolutionSummary {
+        /// Transactions found applied on chain
+        pub completed: usize,
+        /// Transactions that failed on chain or expired without being included
+        pub failed: usize,
+        /// Transactions that could not be looked up and stay pending
+        pub unresolved: usize,
+        /// Prepared envelopes that expired before collecting their signatures
+        pub expired_envelopes: usize,
+    }
+
+    /// Settles pending transactions that expired before the latest ledger closed
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of transactions to look up in this run
+    ///
+    /// # Returns
+    /// * `Result<ResolutionSummary, Error>` - Counts of settled and unresolved transactions
+    pub async fn resolve_expired_transactions(limit: i64) -> Result<ResolutionSummary, Error> {
+        let stellar_chain = common::get_stellar_chain()?;
+
+        // Time bounds are checked against ledger close time, not the local clock
+        let ledger_close_time = stellar_chain.latest_ledger_close_time().await?.naive_utc();
+
+        let mut db_connection = establish_connection().await?;
+
+        let expired_transactions = transactions::table
+            .filter(transactions::status.eq("pending"))
+            .filter(transactions::expires_at.lt(ledger_close_time))
+            .select((transactions::id, transactions::transaction_hash))
+            .order(transactions::expires_at.asc())
+            .limit(limit)
+            .load::<(Uuid, String)>(&mut db_connection)
+            .await?;
+
+        let mut summary = ResolutionSummary {
+            completed: 0,
+            failed: 0,
+            unresolved: 0,
+            expired_envelopes: 0,
+        };
+
+        for (transaction_id, transaction_hash) in expired_transactions {
+            let outcome = match stellar_chain.find_transaction(&transaction_hash).await {
+                std::result::Result::Ok(outcome) => outcome,
+                Err(error) => {
+                    eprintln!(
+                        "Failed to look up transaction {}: {}",
+                        transaction_id,
+                        redact_secrets(&error.to_string())
+                    );
+                    summary.unresolved += 1;
+                    continue;
+                }
+            };
+
+            match outcome {
+                Some(TransactionOutcome::Succeeded) => {
+                    common::settle_transaction(&mut db_connection, transaction_id, "completed", None).await?;
+                    summary.completed += 1;
+                }
+                Some(TransactionOutcome::Failed) => {
+                    common::settle_transaction(
+                        &mut db_connection,
+                        transaction_id,
+                        "failed",
+                        Some(("tx_failed", "Transaction failed on chain")),
+                    )
+                    .await?;
+                    summary.failed += 1;
+                }
+                None => {
+                    common::settle_transaction(
+                        &mut db_connection,
+                        transaction_id,
+                        "failed",
+                        Some((
+                            "tx_too_late",
+                            "Transaction expired without being included in a ledger",
+                        )),
+                    )
+                    .await?;
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        // Envelopes still waiting for signatures can no longer be submitted
+        summary.expired_envelopes = diesel::update(pending_transactions::table)
+            .filter(pending_transactions::status.eq("pending"))
+            .filter(pending_transactions::expires_at.lt(ledger_close_time))
+            .set((
+                pending_transactions::status.eq("expired"),
+                pending_transactions::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(&mut db_connection)
+            .await?;
+
+        Ok(summary)
+    }
+}
@@ -0,0 +1,924 @@
+#![allow(clippy::module_inception)]
+
+/// Scheduler module for future dated payouts and standing orders.
+///
+/// A schedule either runs once at its start time or recurs on a cron expression until
+/// its end date or maximum number of runs. Every due occurrence is recorded in
+/// `payment_schedule_runs` before it is paid, keyed by schedule and occurrence time, so
+/// overlapping workers or a retry after a crash never pay an occurrence twice. A run
+/// abandoned before its payment was recorded is paid again after a lease, and a run
+/// submitted to the chain is resolved once the outbox settles its payment. A schedule
+/// whose payments keep failing is paused until it is resumed.
+pub mod scheduler {
+    use anyhow::{Error, Ok};
+    use chrono::NaiveDateTime;
+    use diesel::ExpressionMethods;
+    use diesel::NullableExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+    use helpers::cron::CronExpression;
+    use helpers::secret::redact_secrets;
+    use models::common::establish_connection;
+    use models::models::{
+        ChainOutboxEntry, NewPaymentSchedule, NewPaymentScheduleRun, PaymentSchedule,
+        PaymentScheduleRun,
+    };
+    use models::schema::{chain_outbox, payment_schedule_runs, payment_schedules};
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::common::common::{get_account_from_id, get_stellar_chain};
+    use crate::customer::customer::check_payment_limit;
+    use crate::outbox::outbox;
+    use crate::payment::payment::{payment_asset, record_payment, sign_payment};
+
+    /// Failed runs in a row after which a schedule is paused
+    const MAX_CONSECUTIVE_FAILURES: i32 = 3;
+
+    /// Seconds after which a run still `running` counts as abandoned by its worker
+    const RUN_LEASE_SECONDS: i64 = 600;
+
+    /// A payment schedule to create.
+    pub struct PaymentScheduleRequest {
+        pub sender_account_id: String,
+        pub receiver_public_key: String,
+        /// `XLM` with no issuer for native payments
+        pub asset_code: String,
+        pub asset_issuer: Option<String>,
+        pub amount: u64,
+        /// When the first payment is due, now if not given
+        pub start_at: Option<NaiveDateTime>,
+        /// A cron expression in UTC, e.g. `0 9 * * 1` for Mondays at 09:00. Without it
+        /// the schedule runs once.
+        pub recurrence: Option<String>,
+        /// No payment is made after this time
+        pub end_at: Option<NaiveDateTime>,
+        /// No more payments are made after this many runs
+        pub max_runs: Option<u32>,
+    }
+
+    /// Outcome of a scheduler run.
+    pub struct ScheduleRunSummary {
+        /// Occurrences paid
+        pub completed: usize,
+        /// Occurrences whose payment failed
+        pub failed: usize,
+        /// Occurrences submitted whose outcome is left to the outbox worker
+        pub submitted: usize,
+        /// Schedules paused after failing repeatedly
+        pub paused: usize,
+    }
+
+    /// Creates a payment schedule
+    ///
+    /// # Arguments
+    /// * `request` - The payment and when to make it
+    ///
+    /// # Returns
+    /// * `Result<PaymentSchedule, Error>` - The schedule with its first run time
+    pub async fn create_payment_schedule(
+        request: PaymentScheduleRequest,
+    ) -> Result<PaymentSchedule, Error> {
+        if request.amount == 0 {
+            return Err(anyhow::anyhow!("Amount must be positive"));
+        }
+
+        let sender_account = get_account_from_id(request.sender_account_id.clone()).await?;
+        // Each payment is checked again when it is made, as the sender's review may change
+        check_payment_limit(&sender_account.stellar_address, request.amount).await?;
+        PublicKey::from_account_id(&request.receiver_public_key)?;
+        payment_asset(&request.asset_code, request.asset_issuer.as_deref())?;
+
+        let start_at = request
+            .start_at
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let first_run_at = match &request.recurrence {
+            // Include an occurrence falling exactly on the start time
+            Some(recurrence) => recurrence
+                .parse::<CronExpression>()?
+                .next_after((start_at - chrono::Duration::seconds(1)).and_utc())
+                .map(|first_run_at| first_run_at.naive_utc())
+                .ok_or_else(|| anyhow::anyhow!("Recurrence never matches"))?,
+            None => start_at,
+        };
+
+        if request.end_at.is_some_and(|end_at| first_run_at > end_at) {
+            return Err(anyhow::anyhow!("Schedule ends before its first run"));
+        }
+
+        let max_runs = request.max_runs.map(i32::try_from).transpose()?;
+        if max_runs == Some(0) {
+            return Err(anyhow::anyhow!("Max runs must be positive"));
+        }
+
+        let mut db_connection = establish_connection().await?;
+
+        let schedule = diesel::insert_into(payment_schedules::table)
+            .values(&NewPaymentSchedule {
+                id: Uuid::new_v4(),
+                sender_account_id: sender_account.id,
+                receiver_public_key: &request.receiver_public_key,
+                asset_code: &request.asset_code,
+                asset_issuer: request.asset_issuer.as_deref(),
+                amount: i64::try_from(request.amount)?,
+                recurrence: request.recurrence.as_deref(),
+                next_run_at: Some(first_run_at),
+                end_at: request.end_at,
+                max_runs,
+                status: "active",
+            })
+            .returning(payment_schedules::all_columns)
+            .get_result::<PaymentSchedule>(&mut db_connection)
+            .await?;
+
+        Ok(schedule)
+    }
+
+    /// Lists the payment schedules of a sending account, newest first
+    ///
+    /// # Arguments
+    /// * `account_id` - The UUID of the sending account
+    pub async fn get_payment_schedules(account_id: &str) -> Result<Vec<PaymentSchedule>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let account_uuid = Uuid::parse_str(account_id)?;
+
+        let schedules = payment_schedules::table
+            .filter(payment_schedules::sender_account_id.eq(account_uuid))
+            .order(payment_schedules::created_at.desc())
+            .load::<PaymentSchedule>(&mut db_connection)
+            .await?;
+
+        Ok(schedules)
+    }
+
+    /// Lists the runs of a payment schedule, newest first
+    ///
+    /// # Arguments
+    /// * `schedule_id` - The UUID of the schedule
+    pub async fn get_payment_schedule_runs(
+        schedule_id: &str,
+    ) -> Result<Vec<PaymentScheduleRun>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let schedule_uuid = Uuid::parse_str(schedule_id)?;
+
+        let runs = payment_schedule_runs::table
+            .filter(payment_schedule_runs::schedule_id.eq(schedule_uuid))
+            .order(payment_schedule_runs::occurrence_at.desc())
+            .load::<PaymentScheduleRun>(&mut db_connection)
+            .await?;
+
+        Ok(runs)
+    }
+
+    /// Pauses an active schedule, no payments are made until it is resumed
+    ///
+    /// # Arguments
+    /// * `schedule_id` - The UUID of the schedule
+    pub async fn pause_payment_schedule(schedule_id: &str) -> Result<PaymentSchedule, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let schedule_uuid = Uuid::parse_str(schedule_id)?;
+
+        diesel::update(payment_schedules::table.find(schedule_uuid))
+            .filter(payment_schedules::status.eq("active"))
+            .set((
+                payment_schedules::status.eq("paused"),
+                payment_schedules::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(payment_schedules::all_columns)
+            .get_results::<PaymentSchedule>(&mut db_connection)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Schedule {} is not active", schedule_id))
+    }
+
+    /// Resumes a paused schedule
+    ///
+    /// Occurrences missed while paused are skipped; a one-off payment that was due
+    /// runs on the next scheduler run.
+    ///
+    /// # Arguments
+    /// * `schedule_id` - The UUID of the schedule
+    pub async fn resume_payment_schedule(schedule_id: &str) -> Result<PaymentSchedule, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let schedule_uuid = Uuid::parse_str(schedule_id)?;
+
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let schedule = payment_schedules::table
+                        .find(schedule_uuid)
+                        .for_update()
+                        .first::<PaymentSchedule>(conn)
+                        .await?;
+
+                    if schedule.status != "paused" {
+                        return Err(anyhow::anyhow!("Schedule {} is not paused", schedule.id));
+                    }
+
+                    let now = chrono::Utc::now().naive_utc();
+                    let next_run_at = match schedule.next_run_at {
+                        Some(next_run_at) if next_run_at < now && schedule.recurrence.is_some() => {
+                            next_occurrence(&schedule, now, schedule.run_count)?
+                        }
+                        next_run_at => next_run_at,
+                    };
+
+                    let schedule = diesel::update(payment_schedules::table.find(schedule.id))
+                        .set((
+                            payment_schedules::status.eq(if next_run_at.is_some() {
+                                "active"
+                            } else {
+                                "completed"
+                            }),
+                            payment_schedules::next_run_at.eq(next_run_at),
+                            payment_schedules::consecutive_failures.eq(0),
+                            payment_schedules::updated_at.eq(Some(now)),
+                        ))
+                        .returning(payment_schedules::all_columns)
+                        .get_result::<PaymentSchedule>(conn)
+                        .await?;
+
+                    Ok(schedule)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Cancels a schedule for good
+    ///
+    /// # Arguments
+    /// * `schedule_id` - The UUID of the schedule
+    pub async fn cancel_payment_schedule(schedule_id: &str) -> Result<PaymentSchedule, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let schedule_uuid = Uuid::parse_str(schedule_id)?;
+
+        diesel::update(payment_schedules::table.find(schedule_uuid))
+            .filter(payment_schedules::status.eq_any(["active", "paused"]))
+            .set((
+                payment_schedules::status.eq("cancelled"),
+                payment_schedules::next_run_at.eq(None::<NaiveDateTime>),
+                payment_schedules::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .returning(payment_schedules::all_columns)
+            .get_results::<PaymentSchedule>(&mut db_connection)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Schedule {} is already finished", schedule_id))
+    }
+
+    /// Pays every occurrence that is due
+    ///
+    /// A recurring schedule that fell behind, e.g. while the worker was down, pays its
+    /// latest due occurrence once and continues from the next future one. Runs submitted
+    /// earlier are resolved once the outbox settles their payment. A run is only marked
+    /// `submitted` together with recording its payment, so runs a stopped worker left
+    /// `running` for longer than `RUN_LEASE_SECONDS` were never paid and are paid again.
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of schedules to run in this run
+    ///
+    /// # Returns
+    /// * `Result<ScheduleRunSummary, Error>` - Counts of paid, failed and paused runs
+    pub async fn run_due_schedules(limit: i64) -> Result<ScheduleRunSummary, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let mut summary = ScheduleRunSummary {
+            completed: 0,
+            failed: 0,
+            submitted: 0,
+            paused: 0,
+        };
+
+        for (schedule_id, lease, entry) in settled_runs(&mut db_connection, limit).await? {
+            let status = run_status(&entry.status);
+            let outcome = record_run_outcome(
+                &mut db_connection,
+                schedule_id,
+                &lease,
+                status,
+                Some(entry.transaction_id),
+                entry.last_error,
+            )
+            .await?;
+            summary.count(status, outcome);
+        }
+
+        let mut runs = reclaim_abandoned_runs(&mut db_connection, limit).await?;
+        runs.extend(claim_due_runs(&mut db_connection, limit).await?);
+
+        for (schedule, lease) in runs {
+            let outcome = match pay_occurrence(&mut db_connection, &schedule, &lease).await {
+                std::result::Result::Ok((entry, submitted)) => {
+                    let status = run_status(&entry.status);
+                    if status == "submitted" {
+                        // Resolved by a later run once the outbox settles it
+                        summary.count(status, Some(false));
+                        continue;
+                    }
+
+                    let outcome = record_run_outcome(
+                        &mut db_connection,
+                        schedule.id,
+                        &submitted,
+                        status,
+                        Some(entry.transaction_id),
+                        entry.last_error,
+                    )
+                    .await?;
+                    (status, outcome)
+                }
+                Err(error) => {
+                    let outcome = record_run_outcome(
+                        &mut db_connection,
+                        schedule.id,
+                        &lease,
+                        "failed",
+                        None,
+                        Some(error.to_string()),
+                    )
+                    .await?;
+                    ("failed", outcome)
+                }
+            };
+            summary.count(outcome.0, outcome.1);
+        }
+
+        Ok(summary)
+    }
+
+    impl ScheduleRunSummary {
+        /// Counts a run recorded with `status`. A run another worker moved on first,
+        /// with no outcome, is left to that worker.
+        fn count(&mut self, status: &str, outcome: Option<bool>) {
+            let Some(paused) = outcome else {
+                return;
+            };
+
+            match status {
+                "completed" => self.completed += 1,
+                "failed" => self.failed += 1,
+                _ => self.submitted += 1,
+            }
+            if paused {
+                self.paused += 1;
+            }
+        }
+    }
+
+    /// A run as a worker last saw it. Moving the run on fails once another worker has
+    /// moved it since.
+    struct RunLease {
+        run_id: Uuid,
+        status: &'static str,
+        updated_at: Option<NaiveDateTime>,
+    }
+
+    /// Returns the run status for the status of its outbox entry
+    fn run_status(entry_status: &str) -> &'static str {
+        match entry_status {
+            "completed" => "completed",
+            "failed" => "failed",
+            _ => "submitted",
+        }
+    }
+
+    /// Returns the time before which a `running` run counts as abandoned
+    fn lease_cutoff(now: NaiveDateTime) -> NaiveDateTime {
+        now - chrono::Duration::seconds(RUN_LEASE_SECONDS)
+    }
+
+    /// Claims the due occurrences and moves their schedules on in one transaction
+    ///
+    /// # Returns
+    /// * `Result<Vec<(PaymentSchedule, RunLease)>, Error>` - The claimed runs
+    async fn claim_due_runs(
+        db_connection: &mut AsyncPgConnection,
+        limit: i64,
+    ) -> Result<Vec<(PaymentSchedule, RunLease)>, Error> {
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+
+                    let due_schedules = payment_schedules::table
+                        .filter(payment_schedules::status.eq("active"))
+                        .filter(payment_schedules::next_run_at.le(now))
+                        .order(payment_schedules::next_run_at.asc())
+                        .limit(limit)
+                        .for_update()
+                        .skip_locked()
+                        .load::<PaymentSchedule>(conn)
+                        .await?;
+
+                    let mut runs = Vec::with_capacity(due_schedules.len());
+                    for schedule in due_schedules {
+                        let Some(occurrence_at) = schedule.next_run_at else {
+                            continue;
+                        };
+
+                        let run_id = Uuid::new_v4();
+                        let claimed = diesel::insert_into(payment_schedule_runs::table)
+                            .values(&NewPaymentScheduleRun {
+                                id: run_id,
+                                schedule_id: schedule.id,
+                                occurrence_at,
+                                status: "running",
+                            })
+                            .on_conflict_do_nothing()
+                            .returning(payment_schedule_runs::updated_at)
+                            .get_results::<Option<NaiveDateTime>>(conn)
+                            .await?
+                            .pop();
+
+                        let run_count = schedule.run_count + claimed.is_some() as i32;
+                        let next_run_at =
+                            next_occurrence(&schedule, occurrence_at.max(now), run_count)?;
+
+                        diesel::update(payment_schedules::table.find(schedule.id))
+                            .set((
+                                payment_schedules::next_run_at.eq(next_run_at),
+                                payment_schedules::run_count.eq(run_count),
+                                payment_schedules::status.eq(if next_run_at.is_some() {
+                                    "active"
+                                } else {
+                                    "completed"
+                                }),
+                                payment_schedules::updated_at.eq(Some(now)),
+                            ))
+                            .execute(conn)
+                            .await?;
+
+                        // Already run by someone else
+                        if let Some(updated_at) = claimed {
+                            let lease = RunLease {
+                                run_id,
+                                status: "running",
+                                updated_at,
+                            };
+                            runs.push((schedule, lease));
+                        }
+                    }
+
+                    Ok(runs)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Takes over the runs a worker left `running` for longer than `RUN_LEASE_SECONDS`.
+    /// Those of a cancelled schedule fail instead.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(PaymentSchedule, RunLease)>, Error>` - The runs to pay again
+    async fn reclaim_abandoned_runs(
+        db_connection: &mut AsyncPgConnection,
+        limit: i64,
+    ) -> Result<Vec<(PaymentSchedule, RunLease)>, Error> {
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let abandoned = payment_schedule_runs::table
+                        .filter(payment_schedule_runs::status.eq("running"))
+                        .filter(
+                            payment_schedule_runs::updated_at
+                                .lt(lease_cutoff(chrono::Utc::now().naive_utc())),
+                        )
+                        .order(payment_schedule_runs::updated_at.asc())
+                        .limit(limit)
+                        .for_update()
+                        .skip_locked()
+                        .load::<PaymentScheduleRun>(conn)
+                        .await?;
+
+                    let mut runs = Vec::with_capacity(abandoned.len());
+                    for run in abandoned {
+                        let lease = RunLease {
+                            run_id: run.id,
+                            status: "running",
+                            updated_at: run.updated_at,
+                        };
+                        let schedule = payment_schedules::table
+                            .find(run.schedule_id)
+                            .first::<PaymentSchedule>(conn)
+                            .await?;
+
+                        if schedule.status == "cancelled" {
+                            move_run(
+                                conn,
+                                &lease,
+                                "failed",
+                                None,
+                                Some("Schedule was cancelled before the run was paid"),
+                            )
+                            .await?;
+                            continue;
+                        }
+
+                        // Holding the run again restarts its lease
+                        if let Some(lease) = move_run(conn, &lease, "running", None, None).await? {
+                            runs.push((schedule, lease));
+                        }
+                    }
+
+                    Ok(runs)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    /// Loads the `submitted` runs whose payment the outbox has settled since
+    ///
+    /// # Returns
+    /// * `Result<Vec<(Uuid, RunLease, ChainOutboxEntry)>, Error>` - The schedule id,
+    ///   run and settled outbox entry of each
+    async fn settled_runs(
+        db_connection: &mut AsyncPgConnection,
+        limit: i64,
+    ) -> Result<Vec<(Uuid, RunLease, ChainOutboxEntry)>, Error> {
+        let runs = payment_schedule_runs::table
+            .filter(payment_schedule_runs::status.eq("submitted"))
+            .filter(
+                payment_schedule_runs::transaction_id.eq_any(
+                    chain_outbox::table
+                        .filter(chain_outbox::status.ne("pending"))
+                        .select(chain_outbox::transaction_id.nullable()),
+                ),
+            )
+            .order(payment_schedule_runs::updated_at.asc())
+            .limit(limit)
+            .load::<PaymentScheduleRun>(db_connection)
+            .await?;
+
+        let transaction_ids = runs
+            .iter()
+            .filter_map(|run| run.transaction_id)
+            .collect::<Vec<_>>();
+        let mut entries = chain_outbox::table
+            .filter(chain_outbox::transaction_id.eq_any(&transaction_ids))
+            .load::<ChainOutboxEntry>(db_connection)
+            .await?;
+
+        Ok(runs
+            .into_iter()
+            .filter_map(|run| {
+                let index = entries
+                    .iter()
+                    .position(|entry| Some(entry.transaction_id) == run.transaction_id)?;
+                let lease = RunLease {
+                    run_id: run.id,
+                    status: "submitted",
+                    updated_at: run.updated_at,
+                };
+                Some((run.schedule_id, lease, entries.swap_remove(index)))
+            })
+            .collect())
+    }
+
+    /// Returns the first occurrence after `after`, or `None` once the schedule is over
+    fn next_occurrence(
+        schedule: &PaymentSchedule,
+        after: NaiveDateTime,
+        run_count: i32,
+    ) -> Result<Option<NaiveDateTime>, Error> {
+        let Some(recurrence) = &schedule.recurrence else {
+            return Ok(None);
+        };
+
+        if schedule
+            .max_runs
+            .is_some_and(|max_runs| run_count >= max_runs)
+        {
+            return Ok(None);
+        }
+
+        let next_run_at = recurrence
+            .parse::<CronExpression>()?
+            .next_after(after.and_utc())
+            .map(|next_run_at| next_run_at.naive_utc());
+
+        Ok(next_run_at
+            .filter(|next_run_at| schedule.end_at.is_none_or(|end_at| *next_run_at <= end_at)))
+    }
+
+    /// Pays one occurrence of a schedule
+    ///
+    /// The run is marked `submitted` in the database transaction that records the
+    /// payment, and only while the worker still holds it.
+    ///
+    /// # Returns
+    /// * `Result<(ChainOutboxEntry, RunLease), Error>` - The processed outbox entry, and
+    ///   the submitted run
+    async fn pay_occurrence(
+        db_connection: &mut AsyncPgConnection,
+        schedule: &PaymentSchedule,
+        lease: &RunLease,
+    ) -> Result<(ChainOutboxEntry, RunLease), Error> {
+        let stellar_chain = get_stellar_chain()?;
+        let asset = payment_asset(&schedule.asset_code, schedule.asset_issuer.as_deref())?;
+
+        let payment = sign_payment(
+            &stellar_chain,
+            schedule.sender_account_id.to_string(),
+            &schedule.receiver_public_key,
+            asset,
+            schedule.asset_code.clone(),
+            u64::try_from(schedule.amount)?,
+            None,
+        )
+        .await?;
+
+        let stellar_chain_ref = &stellar_chain;
+        let payment_ref = &payment;
+        let (entry, submitted) = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let entry = record_payment(conn, stellar_chain_ref, payment_ref).await?;
+                    let submitted =
+                        move_run(conn, lease, "submitted", Some(entry.transaction_id), None)
+                            .await?
+                            .ok_or_else(|| {
+                                anyhow::anyhow!(
+                                    "Run {} was taken over by another worker",
+                                    lease.run_id
+                                )
+                            })?;
+
+                    Ok((entry, submitted))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The payment is recorded, so an entry left pending is up to the outbox worker
+        let entry = match outbox::process_entry(&stellar_chain, entry.id).await {
+            std::result::Result::Ok(entry) => entry,
+            Err(error) => {
+                eprintln!(
+                    "Failed to process outbox entry {}: {}",
+                    entry.id,
+                    redact_secrets(&error.to_string())
+                );
+                entry
+            }
+        };
+
+        Ok((entry, submitted))
+    }
+
+    /// Moves a run on to `status`, unless another worker has moved it since `lease`
+    ///
+    /// # Returns
+    /// * `Result<Option<RunLease>, Error>` - The moved run, or `None` if it was taken over
+    async fn move_run(
+        conn: &mut AsyncPgConnection,
+        lease: &RunLease,
+        status: &'static str,
+        transaction_id: Option<Uuid>,
+        error_message: Option<&str>,
+    ) -> Result<Option<RunLease>, Error> {
+        let updated_at = diesel::update(
+            payment_schedule_runs::table
+                .find(lease.run_id)
+                .filter(payment_schedule_runs::status.eq(lease.status))
+                .filter(payment_schedule_runs::updated_at.eq(lease.updated_at)),
+        )
+        .set((
+            payment_schedule_runs::status.eq(status),
+            payment_schedule_runs::transaction_id.eq(transaction_id),
+            payment_schedule_runs::error_message.eq(error_message),
+            payment_schedule_runs::updated_at.eq(Some(chrono::Utc::now().naive_utc())),
+        ))
+        .returning(payment_schedule_runs::updated_at)
+        .get_results::<Option<NaiveDateTime>>(conn)
+        .await?
+        .pop();
+
+        // The time is read back as stored, so that it matches the next time
+        Ok(updated_at.map(|updated_at| RunLease {
+            run_id: lease.run_id,
+            status,
+            updated_at,
+        }))
+    }
+
+    /// Counts the consecutive failures of a schedule after a run with `status`
+    ///
+    /// # Returns
+    /// * `(i32, bool)` - The failure count, and whether the schedule must be paused
+    fn failures_after_run(schedule: &PaymentSchedule, status: &str) -> (i32, bool) {
+        // An unknown outcome neither counts as a failure nor a success
+        let consecutive_failures = match status {
+            "failed" => schedule.consecutive_failures + 1,
+            "completed" => 0,
+            _ => schedule.consecutive_failures,
+        };
+        let pause = schedule.status == "active" && consecutive_failures >= MAX_CONSECUTIVE_FAILURES;
+
+        (consecutive_failures, pause)
+    }
+
+    /// Records the outcome of a run and pauses its schedule after repeated failures
+    ///
+    /// # Returns
+    /// * `Result<Option<bool>, Error>` - Whether the schedule was paused, or `None` if
+    ///   another worker moved the run on first
+    async fn record_run_outcome(
+        db_connection: &mut AsyncPgConnection,
+        schedule_id: Uuid,
+        lease: &RunLease,
+        status: &'static str,
+        transaction_id: Option<Uuid>,
+        error_message: Option<String>,
+    ) -> Result<Option<bool>, Error> {
+        db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+
+                    let moved = move_run(
+                        conn,
+                        lease,
+                        status,
+                        transaction_id,
+                        error_message.as_deref(),
+                    )
+                    .await?;
+                    if moved.is_none() {
+                        return Ok(None);
+                    }
+
+                    let schedule = payment_schedules::table
+                        .find(schedule_id)
+                        .for_update()
+                        .first::<PaymentSchedule>(conn)
+                        .await?;
+
+                    let (consecutive_failures, pause) = failures_after_run(&schedule, status);
+
+                    diesel::update(payment_schedules::table.find(schedule_id))
+                        .set((
+                            payment_schedules::consecutive_failures.eq(consecutive_failures),
+                            payment_schedules::last_error.eq(if status == "failed" {
+                                error_message
+                            } else {
+                                schedule.last_error
+                            }),
+                            payment_schedules::status.eq(if pause {
+                                "paused"
+                            } else {
+                                schedule.status.as_str()
+                            }),
+                            payment_schedules::updated_at.eq(Some(now)),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    Ok(Some(pause))
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::NaiveDate;
+
+        fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2025, 3, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        }
+
+        fn schedule(recurrence: Option<&str>) -> PaymentSchedule {
+            PaymentSchedule {
+                id: Uuid::new_v4(),
+                sender_account_id: Uuid::new_v4(),
+                receiver_public_key: "GDEST".to_string(),
+                asset_code: "XLM".to_string(),
+                asset_issuer: None,
+                amount: 100,
+                recurrence: recurrence.map(str::to_string),
+                next_run_at: None,
+                end_at: None,
+                max_runs: None,
+                run_count: 0,
+                consecutive_failures: 0,
+                last_error: None,
+                status: "active".to_string(),
+                created_at: None,
+                updated_at: None,
+            }
+        }
+
+        #[test]
+        fn test_next_occurrence() {
+            // Daily at 09:00
+            let mut schedule = schedule(Some("0 9 * * *"));
+            assert_eq!(
+                next_occurrence(&schedule, at(20, 9, 0), 1).unwrap(),
+                Some(at(21, 9, 0))
+            );
+
+            // Not past the end
+            schedule.end_at = Some(at(21, 9, 0));
+            assert_eq!(
+                next_occurrence(&schedule, at(20, 12, 0), 1).unwrap(),
+                Some(at(21, 9, 0))
+            );
+            assert_eq!(next_occurrence(&schedule, at(21, 9, 0), 2).unwrap(), None);
+
+            // Nor after the last run
+            schedule.end_at = None;
+            schedule.max_runs = Some(3);
+            assert_eq!(
+                next_occurrence(&schedule, at(20, 9, 0), 2).unwrap(),
+                Some(at(21, 9, 0))
+            );
+            assert_eq!(next_occurrence(&schedule, at(20, 9, 0), 3).unwrap(), None);
+        }
+
+        #[test]
+        fn test_next_occurrence_of_one_off_and_invalid_schedules() {
+            assert_eq!(
+                next_occurrence(&schedule(None), at(20, 9, 0), 0).unwrap(),
+                None
+            );
+            assert!(next_occurrence(&schedule(Some("60 * * * *")), at(20, 9, 0), 0).is_err());
+        }
+
+        #[test]
+        fn test_failures_after_run() {
+            let mut schedule = schedule(Some("0 9 * * *"));
+            schedule.consecutive_failures = 1;
+            assert_eq!(failures_after_run(&schedule, "failed"), (2, false));
+            assert_eq!(failures_after_run(&schedule, "completed"), (0, false));
+            assert_eq!(failures_after_run(&schedule, "submitted"), (1, false));
+
+            // Paused on reaching the limit
+            schedule.consecutive_failures = MAX_CONSECUTIVE_FAILURES - 1;
+            assert_eq!(
+                failures_after_run(&schedule, "failed"),
+                (MAX_CONSECUTIVE_FAILURES, true)
+            );
+            // An unknown outcome does not pause it
+            assert_eq!(
+                failures_after_run(&schedule, "submitted"),
+                (MAX_CONSECUTIVE_FAILURES - 1, false)
+            );
+
+            // Only an active schedule is paused
+            schedule.status = "cancelled".to_string();
+            assert_eq!(
+                failures_after_run(&schedule, "failed"),
+                (MAX_CONSECUTIVE_FAILURES, false)
+            );
+        }
+
+        #[test]
+        fn test_run_status() {
+            assert_eq!(run_status("completed"), "completed");
+            assert_eq!(run_status("failed"), "failed");
+            // Left to the outbox worker until it settles
+            assert_eq!(run_status("pending"), "submitted");
+        }
+
+        #[test]
+        fn test_lease_cutoff() {
+            assert_eq!(lease_cutoff(at(3, 9, 10)), at(3, 9, 0));
+            assert_eq!(lease_cutoff(at(3, 0, 5)), at(2, 23, 55));
+        }
+
+        #[test]
+        fn test_summary_counts_only_recorded_runs() {
+            let mut summary = ScheduleRunSummary {
+                completed: 0,
+                failed: 0,
+                submitted: 0,
+                paused: 0,
+            };
+            summary.count("completed", Some(false));
+            summary.count("failed", Some(true));
+            summary.count("submitted", Some(false));
+            // Moved on by another worker first
+            summary.count("failed", None);
+
+            assert_eq!(summary.completed, 1);
+            assert_eq!(summary.failed, 1);
+            assert_eq!(summary.submitted, 1);
+            assert_eq!(summary.paused, 1);
+        }
+    }
+}
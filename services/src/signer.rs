@@ -0,0 +1,221 @@
+#![allow(clippy::module_inception)]
+
+/// Signer module providing the signers services use to authorise chain transactions.
+/// Private keys stay behind the signer: services only ever see public keys and signatures.
+pub mod signer {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use anyhow::Error;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::RunQueryDsl;
+    use helpers::common::decrypt_private_key;
+    use helpers::signer::{
+        KeyPairSigner, RemoteSigner, Signer, DEFAULT_REMOTE_SIGNER_CONNECT_TIMEOUT,
+        DEFAULT_REMOTE_SIGNER_TIMEOUT, ISSUER_ACCOUNT_ID,
+    };
+    use models::common::establish_connection;
+    use models::schema::{accounts, encrypted_keys};
+    use stellar_base::signature::DecoratedSignature;
+    use stellar_base::{KeyPair, PublicKey};
+    use uuid::Uuid;
+
+    use crate::envelope::envelope::issuer_signing_offline;
+    use crate::hd_wallet::hd_wallet::derive_account_keypair;
+
+    /// Signs with envelope-encrypted keys from `encrypted_keys`, or with the key derived
+    /// from the master seed for accounts that have a derivation index instead.
+    ///
+    /// The account key is decrypted only for the duration of a single signature. Service
+    /// accounts configured in the environment (issuer, distributor) are signed for directly.
+    ///
+    /// When `ISSUER_SIGNING_MODE` is `offline` the issuer key is not held at all: only its
+    /// public key (`ISSUER_PUBLIC_KEY`) is known, and issuer transactions are prepared as
+    /// envelopes for signing outside the service.
+    pub struct LocalSigner {
+        service_accounts: KeyPairSigner,
+        offline_issuer: Option<PublicKey>,
+    }
+
+    impl LocalSigner {
+        /// Creates a local signer with the service accounts configured in the environment
+        pub fn from_env() -> Result<Self, Error> {
+            let service_accounts = KeyPairSigner::from_env()?;
+
+            let offline_issuer = if issuer_signing_offline()? {
+                if service_accounts.has_account(ISSUER_ACCOUNT_ID) {
+                    return Err(anyhow::anyhow!(
+                        "ISSUER_SECRET_KEY must not be set when the issuer signs offline"
+                    ));
+                }
+                Some(PublicKey::from_account_id(&std::env::var(
+                    "ISSUER_PUBLIC_KEY",
+                )?)?)
+            } else {
+                if !service_accounts.has_account(ISSUER_ACCOUNT_ID) {
+                    return Err(anyhow::anyhow!("ISSUER_SECRET_KEY is not set"));
+                }
+                None
+            };
+
+            Ok(Self {
+                service_accounts,
+                offline_issuer,
+            })
+        }
+    }
+
+    impl Signer for LocalSigner {
+        async fn public_key(&self, account_id: &str) -> Result<PublicKey, Error> {
+            if self.service_accounts.has_account(account_id) {
+                return self.service_accounts.public_key(account_id).await;
+            }
+
+            if let (ISSUER_ACCOUNT_ID, Some(issuer)) = (account_id, &self.offline_issuer) {
+                return Ok(issuer.clone());
+            }
+
+            let mut db_connection = establish_connection().await?;
+
+            let stellar_address = accounts::table
+                .find(Uuid::parse_str(account_id)?)
+                .select(accounts::stellar_address)
+                .first::<String>(&mut db_connection)
+                .await?;
+
+            Ok(PublicKey::from_account_id(&stellar_address)?)
+        }
+
+        async fn sign_hash(
+            &self,
+            account_id: &str,
+            hash: &[u8],
+        ) -> Result<DecoratedSignature, Error> {
+            if self.service_accounts.has_account(account_id) {
+                return self.service_accounts.sign_hash(account_id, hash).await;
+            }
+
+            if account_id == ISSUER_ACCOUNT_ID && self.offline_issuer.is_some() {
+                return Err(anyhow::anyhow!(
+                    "The issuer signs offline; prepare an envelope for it instead"
+                ));
+            }
+
+            let mut db_connection = establish_connection().await?;
+
+            let account_uuid = Uuid::parse_str(account_id)?;
+
+            let stored_key = encrypted_keys::table
+                .filter(encrypted_keys::account_id.eq(account_uuid))
+                .select((encrypted_keys::encrypted_key, encrypted_keys::key_version))
+                .first::<(Vec<u8>, i32)>(&mut db_connection)
+                .await
+                .optional()?;
+
+            // Accounts without a stored key sign with the key derived from the master seed
+            let keypair = match stored_key {
+                Some((encrypted_key, key_version)) => {
+                    let secret_key =
+                        decrypt_private_key(&encrypted_key, account_uuid.as_bytes(), key_version)?
+                            .to_secret_string()?;
+
+                    KeyPair::from_str(secret_key.expose_secret()).map_err(|_| {
+                        anyhow::anyhow!("Stored key for account {} is invalid", account_id)
+                    })?
+                }
+                None => {
+                    let derivation_index = accounts::table
+                        .find(account_uuid)
+                        .select(accounts::derivation_index)
+                        .first::<Option<i32>>(&mut db_connection)
+                        .await?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("No key is stored for account {}", account_id)
+                        })?;
+
+                    derive_account_keypair(&mut db_connection, derivation_index).await?
+                }
+            };
+
+            Ok(keypair.sign_decorated(hash))
+        }
+    }
+
+    /// The signer selected for this deployment through `SIGNER_MODE`.
+    pub enum ServiceSigner {
+        Local(LocalSigner),
+        Remote(RemoteSigner),
+    }
+
+    impl Signer for ServiceSigner {
+        async fn public_key(&self, account_id: &str) -> Result<PublicKey, Error> {
+            match self {
+                ServiceSigner::Local(signer) => signer.public_key(account_id).await,
+                ServiceSigner::Remote(signer) => signer.public_key(account_id).await,
+            }
+        }
+
+        async fn sign_hash(
+            &self,
+            account_id: &str,
+            hash: &[u8],
+        ) -> Result<DecoratedSignature, Error> {
+            match self {
+                ServiceSigner::Local(signer) => signer.sign_hash(account_id, hash).await,
+                ServiceSigner::Remote(signer) => signer.sign_hash(account_id, hash).await,
+            }
+        }
+    }
+
+    /// Returns the signer configured by the SIGNER_MODE environment variable
+    ///
+    /// `local` (the default) signs with envelope-encrypted keys from the database,
+    /// `remote` delegates to the signing service at `REMOTE_SIGNER_URL`, authenticating
+    /// with `REMOTE_SIGNER_TOKEN` when set. Connecting to it may take
+    /// `REMOTE_SIGNER_CONNECT_TIMEOUT_SECONDS` (5 by default) and each request
+    /// `REMOTE_SIGNER_TIMEOUT_SECONDS` (30 by default).
+    ///
+    /// # Returns
+    /// * `Ok(ServiceSigner)` - The configured signer
+    /// * `Err(Error)` - If the signer mode is invalid or misconfigured
+    pub fn get_signer() -> Result<ServiceSigner, Error> {
+        let signer_mode = std::env::var("SIGNER_MODE").unwrap_or_else(|_| "local".to_string());
+        match signer_mode.as_str() {
+            "local" => Ok(ServiceSigner::Local(LocalSigner::from_env()?)),
+            "remote" => {
+                let signer = RemoteSigner::new(
+                    std::env::var("REMOTE_SIGNER_URL")?,
+                    std::env::var("REMOTE_SIGNER_TOKEN").ok(),
+                )?
+                .with_timeouts(
+                    timeout_from_env(
+                        "REMOTE_SIGNER_CONNECT_TIMEOUT_SECONDS",
+                        DEFAULT_REMOTE_SIGNER_CONNECT_TIMEOUT,
+                    )?,
+                    timeout_from_env(
+                        "REMOTE_SIGNER_TIMEOUT_SECONDS",
+                        DEFAULT_REMOTE_SIGNER_TIMEOUT,
+                    )?,
+                )?;
+                Ok(ServiceSigner::Remote(signer))
+            }
+            _ => Err(anyhow::anyhow!("Invalid signer mode")),
+        }
+    }
+
+    /// Reads a timeout in seconds from the environment, or returns `default` when unset
+    fn timeout_from_env(name: &str, default: Duration) -> Result<Duration, Error> {
+        match std::env::var(name) {
+            std::result::Result::Ok(seconds) => {
+                let seconds = seconds.parse::<u64>()?;
+                if seconds == 0 {
+                    return Err(anyhow::anyhow!("{} must be positive", name));
+                }
+                Ok(Duration::from_secs(seconds))
+            }
+            Err(_) => Ok(default),
+        }
+    }
+}
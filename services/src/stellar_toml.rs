@@ -0,0 +1,67 @@
+#![allow(clippy::module_inception)]
+
+/// Stellar toml module that generates our `stellar.toml` (SEP-0001) from the asset
+/// registry.
+///
+/// Every active asset is listed as a currency with the metadata recorded for it, and
+/// the issuer and distributor accounts as accounts we control. The organization and
+/// the SEP endpoints come from the environment.
+pub mod stellar_toml {
+    use anyhow::Error;
+    use diesel::ExpressionMethods;
+    use diesel::QueryDsl;
+    use diesel_async::RunQueryDsl;
+    use helpers::stellar_toml::{Currency, StellarToml};
+    use models::common::establish_connection;
+    use models::models::Token;
+    use models::schema::{accounts, tokens};
+
+    use crate::common::common::get_chain_network;
+
+    /// Returns the contents of `/.well-known/stellar.toml`
+    pub async fn get_stellar_toml() -> Result<String, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let assets = tokens::table
+            .inner_join(accounts::table)
+            .filter(tokens::status.eq("active"))
+            .order(tokens::created_at.asc())
+            .select((tokens::all_columns, accounts::stellar_address))
+            .load::<(Token, String)>(&mut db_connection)
+            .await?;
+
+        let distributor_ids = assets
+            .iter()
+            .filter_map(|(token, _)| token.distributor_account_id)
+            .collect::<Vec<_>>();
+        let distributor_addresses = accounts::table
+            .filter(accounts::id.eq_any(distributor_ids))
+            .select(accounts::stellar_address)
+            .load::<String>(&mut db_connection)
+            .await?;
+
+        let mut stellar_toml = StellarToml::from_env(get_chain_network()?.passphrase())?;
+
+        for (token, issuer_address) in assets {
+            stellar_toml.accounts.push(issuer_address.clone());
+            stellar_toml.currencies.push(Currency {
+                code: token.asset_code,
+                issuer: issuer_address,
+                status: "live".to_string(),
+                display_decimals: token.display_decimals as u32,
+                name: token.name,
+                desc: token.description,
+                image: token.image,
+                is_asset_anchored: token.anchor_asset_type.is_some(),
+                anchor_asset_type: token.anchor_asset_type,
+                anchor_asset: token.anchor_asset,
+            });
+        }
+
+        stellar_toml.accounts.extend(distributor_addresses);
+        stellar_toml.accounts.sort();
+        stellar_toml.accounts.dedup();
+
+        stellar_toml.to_toml_string()
+    }
+}
@@ -0,0 +1,219 @@
+#![allow(clippy::module_inception)]
+
+/// Trustline module that authorizes, freezes and revokes holders of the assets we issue.
+///
+/// Issuers registered with `AUTH_REQUIRED` must authorize every holder, which happens
+/// once the holder passed our KYC check. Issuers with `AUTH_REVOCABLE` can later freeze
+/// a holder, leaving it only able to maintain its liabilities, or revoke it. Each change
+/// is a `SetTrustLineFlags` transaction through the outbox, and `trustlines.status`
+/// follows it once it lands.
+pub mod trustline {
+    use anyhow::{Error, Ok};
+    use bigdecimal::BigDecimal;
+    use diesel::ExpressionMethods;
+    use diesel::OptionalExtension;
+    use diesel::QueryDsl;
+    use diesel_async::scoped_futures::ScopedFutureExt;
+    use diesel_async::{AsyncConnection, RunQueryDsl};
+    use helpers::raw_transaction::{IssuerFlags, TrustlineAuthorization};
+    use models::common::establish_connection;
+    use models::models::{NewTrustline, Token, Trustline};
+    use models::schema::{accounts, tokens, trustlines};
+    use stellar_base::asset::CreditAsset;
+    use stellar_base::PublicKey;
+    use uuid::Uuid;
+
+    use crate::common::common::{
+        get_account_from_id, get_stellar_chain, record_pending_transaction,
+    };
+    use crate::customer::customer::check_verified;
+    use crate::outbox::outbox;
+    use crate::signer::signer::get_signer;
+
+    /// Returns the outbox purpose of a trustline authorization change
+    pub fn authorization_purpose(authorization: TrustlineAuthorization) -> &'static str {
+        match authorization {
+            TrustlineAuthorization::Authorized => "authorize_trustline",
+            TrustlineAuthorization::MaintainLiabilities => "freeze_trustline",
+            TrustlineAuthorization::Deauthorized => "revoke_trustline",
+        }
+    }
+
+    /// Sets the authorization of a holder's trustline for one of our assets
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    /// * `holder_address` - The Stellar address of the holder
+    /// * `authorization` - The authorization to give the trustline
+    ///
+    /// # Returns
+    /// * `Result<Trustline, Error>` - The trustline with its new status
+    pub async fn set_trustline_authorization(
+        token_id: &str,
+        holder_address: &str,
+        authorization: TrustlineAuthorization,
+    ) -> Result<Trustline, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .find(Uuid::parse_str(token_id)?)
+            .first::<Token>(&mut db_connection)
+            .await?;
+
+        if token.status != "active" {
+            return Err(anyhow::anyhow!(
+                "Asset {} is {}",
+                token.asset_code,
+                token.status
+            ));
+        }
+
+        let issuer_flags = IssuerFlags::from_bits(token.issuer_flags as u32);
+        if authorization != TrustlineAuthorization::Authorized && !issuer_flags.auth_revocable {
+            return Err(anyhow::anyhow!(
+                "Asset {} was not registered as revocable",
+                token.asset_code
+            ));
+        }
+
+        // Only holders who passed identity verification are authorized
+        if authorization == TrustlineAuthorization::Authorized {
+            check_verified(holder_address).await?;
+        }
+
+        let issuer_account_id = token
+            .issuer_account_id
+            .ok_or_else(|| anyhow::anyhow!("Asset {} has no issuer", token.asset_code))?;
+        let issuer_account = get_account_from_id(issuer_account_id.to_string()).await?;
+        let issuer_public_key = PublicKey::from_account_id(&issuer_account.stellar_address)?;
+        let holder_public_key = PublicKey::from_account_id(holder_address)?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let mut transaction = stellar_chain.build_set_trustline_flags_transaction(
+            &issuer_public_key,
+            &holder_public_key,
+            CreditAsset::new(token.asset_code.clone(), issuer_public_key.clone())?,
+            authorization,
+        )?;
+        transaction
+            .sign(
+                stellar_chain.network(),
+                &get_signer()?,
+                &issuer_account.id.to_string(),
+            )
+            .await?;
+
+        // Record the change and queue it in one database transaction
+        let stellar_chain_ref = &stellar_chain;
+        let transaction_ref = &transaction;
+        let token_ref = &token;
+        let issuer_address = issuer_account.stellar_address.as_str();
+        let (trustline_id, entry) = db_connection
+            .transaction::<_, Error, _>(|conn| {
+                async move {
+                    let trustline_id = trustlines::table
+                        .filter(trustlines::stellar_address.eq(holder_address))
+                        .filter(trustlines::asset_code.eq(&token_ref.asset_code))
+                        .filter(trustlines::asset_issuer.eq(issuer_address))
+                        .select(trustlines::id)
+                        .for_update()
+                        .first::<Uuid>(conn)
+                        .await
+                        .optional()?;
+
+                    // A holder seen for the first time is pending until the change lands
+                    let trustline_id = match trustline_id {
+                        Some(trustline_id) => trustline_id,
+                        None => {
+                            let account_id = accounts::table
+                                .filter(accounts::stellar_address.eq(holder_address))
+                                .select(accounts::id)
+                                .first::<Uuid>(conn)
+                                .await
+                                .optional()?;
+
+                            diesel::insert_into(trustlines::table)
+                                .values(&NewTrustline {
+                                    account_id,
+                                    asset_code: &token_ref.asset_code,
+                                    asset_issuer: issuer_address,
+                                    trust_limit: None,
+                                    status: "pending",
+                                    stellar_address: holder_address,
+                                })
+                                .returning(trustlines::id)
+                                .get_result::<Uuid>(conn)
+                                .await?
+                        }
+                    };
+
+                    let transaction_id = record_pending_transaction(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        issuer_address,
+                        holder_address,
+                        &token_ref.asset_code,
+                        BigDecimal::from(0),
+                    )
+                    .await?;
+
+                    let entry = outbox::enqueue(
+                        conn,
+                        stellar_chain_ref,
+                        transaction_ref,
+                        authorization_purpose(authorization),
+                        Some(trustline_id),
+                        transaction_id,
+                    )
+                    .await?;
+
+                    Ok((trustline_id, entry))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // The status changes once the transaction lands
+        let entry = outbox::process_entry(&stellar_chain, entry.id).await?;
+        outbox::ensure_completed(&entry)?;
+
+        let trustline = trustlines::table
+            .find(trustline_id)
+            .first::<Trustline>(&mut db_connection)
+            .await?;
+
+        Ok(trustline)
+    }
+
+    /// Lists the trustlines recorded for one of our assets
+    ///
+    /// # Arguments
+    /// * `token_id` - The UUID of the asset
+    pub async fn get_trustlines(token_id: &str) -> Result<Vec<Trustline>, Error> {
+        let mut db_connection = establish_connection().await?;
+
+        let token = tokens::table
+            .find(Uuid::parse_str(token_id)?)
+            .first::<Token>(&mut db_connection)
+            .await?;
+
+        let issuer_account_id = token
+            .issuer_account_id
+            .ok_or_else(|| anyhow::anyhow!("Asset {} has no issuer", token.asset_code))?;
+        let issuer_address = accounts::table
+            .find(issuer_account_id)
+            .select(accounts::stellar_address)
+            .first::<String>(&mut db_connection)
+            .await?;
+
+        let trustlines = trustlines::table
+            .filter(trustlines::asset_code.eq(&token.asset_code))
+            .filter(trustlines::asset_issuer.eq(issuer_address))
+            .order(trustlines::created_at.asc())
+            .load::<Trustline>(&mut db_connection)
+            .await?;
+
+        Ok(trustlines)
+    }
+}
@@ -0,0 +1,200 @@
+#![allow(clippy::module_inception)]
+
+/// Web auth module that authenticates wallet users by their Stellar account (SEP-0010).
+///
+/// The challenge is signed with the key in `WEB_AUTH_SIGNING_SEED`, whose public key is
+/// our `SIGNING_KEY`, and is built for `HOME_DOMAIN`. Tokens are signed with
+/// `WEB_AUTH_JWT_SECRET` and stay valid for `WEB_AUTH_TOKEN_TTL_SECONDS`, a day by
+/// default. They name `WEB_AUTH_ENDPOINT` as issuer, which defaults to `/auth` on
+/// `WEB_AUTH_DOMAIN`, itself defaulting to the home domain.
+pub mod web_auth {
+    use anyhow::{Error, Ok};
+    use helpers::secret::SecretString;
+    use helpers::stellar_chain::DestinationStatus;
+    use helpers::stellar_toml::home_domain_from_env;
+    use helpers::web_auth::{
+        build_challenge, issue_token, read_challenge, verify_challenge_signatures, WebAuthClaims,
+    };
+    use serde::Serialize;
+    use stellar_base::xdr::XDRSerialize;
+    use stellar_base::{Asset, KeyPair, PublicKey};
+
+    use crate::common::common::{get_chain_network, get_stellar_chain};
+
+    /// How long a token stays valid when no TTL is configured
+    const DEFAULT_TOKEN_TTL_SECONDS: i64 = 86_400;
+
+    /// A challenge for the client to sign, in the shape SEP-0010 defines.
+    #[derive(Serialize)]
+    pub struct ChallengeResponse {
+        pub transaction: String,
+        pub network_passphrase: String,
+    }
+
+    /// The configuration of web authentication
+    struct WebAuthConfig {
+        signing_key: KeyPair,
+        jwt_secret: SecretString,
+        home_domain: String,
+        web_auth_domain: String,
+        issuer: String,
+        token_ttl_seconds: i64,
+    }
+
+    /// Builds a challenge for an account to sign
+    ///
+    /// # Arguments
+    /// * `account` - The Stellar address the client wants to authenticate as
+    /// * `home_domain` - The home domain the client expects, ours if not given
+    ///
+    /// # Returns
+    /// * `Result<ChallengeResponse, Error>` - The challenge signed by our server key
+    pub fn get_challenge(
+        account: &str,
+        home_domain: Option<&str>,
+    ) -> Result<ChallengeResponse, Error> {
+        let config = web_auth_config()?;
+        let network = get_chain_network()?;
+
+        let client_account = PublicKey::from_account_id(account)
+            .map_err(|_| anyhow::anyhow!("Account must be a Stellar public key"))?;
+
+        if home_domain.is_some_and(|home_domain| home_domain != config.home_domain) {
+            return Err(anyhow::anyhow!(
+                "Home domain must be {}",
+                config.home_domain
+            ));
+        }
+
+        let transaction = build_challenge(
+            &config.signing_key,
+            &client_account,
+            &config.home_domain,
+            &config.web_auth_domain,
+            &network,
+        )?;
+
+        Ok(ChallengeResponse {
+            transaction: transaction.to_envelope().xdr_base64()?,
+            network_passphrase: network.passphrase().to_string(),
+        })
+    }
+
+    /// Verifies a signed challenge and issues a token for its account
+    ///
+    /// The signers and thresholds of the account are loaded from Horizon. An account
+    /// that does not exist yet must have signed with its own key.
+    ///
+    /// # Arguments
+    /// * `envelope_xdr` - The base64 envelope of the challenge, signed by the client
+    ///
+    /// # Returns
+    /// * `Result<String, Error>` - The token
+    pub async fn authenticate(envelope_xdr: &str) -> Result<String, Error> {
+        let config = web_auth_config()?;
+        let network = get_chain_network()?;
+        let server_account = config.signing_key.public_key();
+
+        let challenge = read_challenge(
+            envelope_xdr,
+            server_account,
+            &[config.home_domain.as_str()],
+            &config.web_auth_domain,
+            &network,
+        )?;
+
+        let stellar_chain = get_stellar_chain()?;
+        let client_address = challenge.client_account.account_id();
+        let authorization = match stellar_chain
+            .destination_status(&client_address, &Asset::new_native())
+            .await?
+        {
+            DestinationStatus::NotFound => None,
+            _ => Some(stellar_chain.account_authorization(&challenge.client_account)?),
+        };
+
+        verify_challenge_signatures(&challenge, server_account, &network, authorization.as_ref())?;
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = WebAuthClaims {
+            iss: config.issuer,
+            sub: client_address,
+            iat: now,
+            exp: now + config.token_ttl_seconds,
+            jti: hex::encode(challenge.transaction.hash(&network)?),
+        };
+
+        issue_token(&claims, config.jwt_secret.expose_secret().as_bytes())
+    }
+
+    /// Verifies a token we issued and returns its claims
+    ///
+    /// # Arguments
+    /// * `token` - The token, without the `Bearer` prefix
+    pub fn verify_token(token: &str) -> Result<WebAuthClaims, Error> {
+        let config = web_auth_config()?;
+
+        helpers::web_auth::verify_token(
+            token,
+            config.jwt_secret.expose_secret().as_bytes(),
+            &config.issuer,
+            chrono::Utc::now(),
+        )
+    }
+
+    /// Returns the key published as our `SIGNING_KEY`, from `WEB_AUTH_SIGNING_SEED`
+    pub(crate) fn signing_key() -> Result<KeyPair, Error> {
+        let signing_seed = SecretString::new(
+            std::env::var("WEB_AUTH_SIGNING_SEED")
+                .map_err(|_| anyhow::anyhow!("WEB_AUTH_SIGNING_SEED is not set"))?,
+        );
+
+        KeyPair::from_secret_seed(signing_seed.expose_secret())
+            .map_err(|_| anyhow::anyhow!("WEB_AUTH_SIGNING_SEED is not a secret seed"))
+    }
+
+    /// Reads the configuration of web authentication from the environment
+    fn web_auth_config() -> Result<WebAuthConfig, Error> {
+        let signing_key = signing_key()?;
+
+        let jwt_secret = SecretString::new(
+            std::env::var("WEB_AUTH_JWT_SECRET")
+                .map_err(|_| anyhow::anyhow!("WEB_AUTH_JWT_SECRET is not set"))?,
+        );
+        if jwt_secret.expose_secret().len() < 32 {
+            return Err(anyhow::anyhow!(
+                "WEB_AUTH_JWT_SECRET must be at least 32 characters"
+            ));
+        }
+
+        let home_domain =
+            home_domain_from_env()?.ok_or_else(|| anyhow::anyhow!("HOME_DOMAIN is not set"))?;
+        let web_auth_domain = std::env::var("WEB_AUTH_DOMAIN")
+            .ok()
+            .filter(|domain| !domain.is_empty())
+            .unwrap_or_else(|| home_domain.clone());
+        let issuer = std::env::var("WEB_AUTH_ENDPOINT")
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty())
+            .unwrap_or_else(|| format!("https://{}/auth", web_auth_domain));
+
+        let token_ttl_seconds = match std::env::var("WEB_AUTH_TOKEN_TTL_SECONDS") {
+            std::result::Result::Ok(ttl) => ttl.parse::<i64>()?,
+            Err(_) => DEFAULT_TOKEN_TTL_SECONDS,
+        };
+        if token_ttl_seconds <= 0 {
+            return Err(anyhow::anyhow!(
+                "WEB_AUTH_TOKEN_TTL_SECONDS must be positive"
+            ));
+        }
+
+        Ok(WebAuthConfig {
+            signing_key,
+            jwt_secret,
+            home_domain,
+            web_auth_domain,
+            issuer,
+            token_ttl_seconds,
+        })
+    }
+}